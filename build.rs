@@ -23,6 +23,10 @@ fn main() {
       "x86_64" => "TRACEXEC_TARGET_X86_64",
       "riscv64" => "TRACEXEC_TARGET_RISCV64",
       "aarch64" => "TRACEXEC_TARGET_AARCH64",
+      "s390x" => "TRACEXEC_TARGET_S390X",
+      // The eBPF program attaches to the syscalls with BPF trampolines, which are not
+      // implemented by the BPF JIT of 32-bit ARM.
+      "arm" => panic!("eBPF backend is not supported on 32-bit ARM, please disable the ebpf feature"),
       _ => panic!("Arch {arch} is not supported for now"),
     });
     let max_cpus = 64;
@@ -0,0 +1,25 @@
+//! Benchmark fixture that execs itself 100000 times, or the number of times given by the first argument.
+//!
+//! ```bash
+//! cargo build --release
+//! time target/release/tracexec log -o /dev/null -- target/release/exec-loop
+//! ```
+
+use std::ffi::CString;
+
+use nix::unistd::execv;
+
+fn main() {
+  let remaining: u64 = std::env::args()
+    .nth(1)
+    .and_then(|arg| arg.parse().ok())
+    .unwrap_or(100_000);
+  if remaining == 0 {
+    return;
+  }
+  let argv = [
+    CString::new("exec-loop").unwrap(),
+    CString::new((remaining - 1).to_string()).unwrap(),
+  ];
+  execv(c"/proc/self/exe", &argv).unwrap();
+}
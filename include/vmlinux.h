@@ -7,6 +7,8 @@
 #include "aarch64/vmlinux.h"
 #elif TRACEXEC_TARGET_RISCV64
 #include "riscv64/vmlinux.h"
+#elif TRACEXEC_TARGET_S390X
+#include "s390x/vmlinux.h"
 #endif
 
 #endif
@@ -1,13 +1,21 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use crossterm::event::KeyEvent;
+use nix::{sys::signal::Signal, unistd::Pid};
 use ratatui::{layout::Size, text::Line};
+use strum::Display;
 
 use crate::{
   event::TracerEventDetails,
   tui::{
-    copy_popup::CopyPopupState, details_popup::DetailsPopupState, error_popup::InfoPopupState,
+    confirm_popup::ConfirmPopupState,
+    copy_popup::CopyPopupState,
+    details_popup::DetailsPopupState,
+    error_popup::InfoPopupState,
+    export_prompt::{ExportFormat, ExportScope},
+    keybindings_popup::KeyBindingsPopupState,
     query::Query,
+    signal_popup::SignalPopupState,
   },
 };
 
@@ -35,7 +43,26 @@ pub enum Action {
   ToggleFollow,
   ToggleEnvDisplay,
   ToggleCwdDisplay,
+  ToggleArgvDisplay,
+  ToggleFilenameDisplay,
+  ToggleCommDisplay,
   StopFollow,
+  JumpToParent,
+  JumpBack,
+  ToggleGrouping,
+  ToggleGroupCollapse,
+  CycleResultFilter,
+  ToggleDedup,
+  ToggleTable,
+  CycleColorBy,
+  ToggleScope,
+  CycleSortColumn,
+  ReverseSortOrder,
+  ToggleBookmark,
+  NextBookmark,
+  PrevBookmark,
+  TogglePause,
+  ToggleWrap,
   // Sizing
   ShrinkPane,
   GrowPane,
@@ -43,6 +70,7 @@ pub enum Action {
   SwitchLayout,
   // Pane
   SwitchActivePane,
+  ToggleZoom,
   // Popup
   SetActivePopup(ActivePopup),
   CancelCurrentPopup,
@@ -65,6 +93,33 @@ pub enum Action {
   CloseBreakpointManager,
   ShowHitManager,
   HideHitManager,
+  /// Approve or deny the exec of a process that hit an ask breakpoint
+  AnswerExecApproval {
+    hid: u64,
+    approved: bool,
+  },
+  // Session
+  ShowCommandPrompt,
+  CloseCommandPrompt,
+  RunCommand(Vec<String>),
+  // Export
+  ShowExportPrompt,
+  CloseExportPrompt,
+  ExportEvents {
+    path: PathBuf,
+    scope: ExportScope,
+    format: ExportFormat,
+  },
+  // Process control
+  SignalRootProcesses {
+    signal: Signal,
+    /// Signal the process groups of the root processes instead
+    group: bool,
+  },
+  SignalProcess {
+    pid: Pid,
+    signal: Signal,
+  },
 }
 
 impl Action {
@@ -84,22 +139,54 @@ pub enum CopyTarget {
   CommandlineWithFds(SupportedShell),
   Env,
   Argv,
+  /// The arguments as a JSON array
+  ArgvJson,
   Filename,
   SyscallResult,
   EnvDiff,
+  Pid,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
 pub enum SupportedShell {
   Bash,
   Sh,
   Fish,
 }
 
+impl SupportedShell {
+  pub fn next(self) -> Self {
+    match self {
+      Self::Bash => Self::Sh,
+      Self::Sh => Self::Fish,
+      Self::Fish => Self::Bash,
+    }
+  }
+
+  /// Quote the string for this shell
+  pub fn quote(self, s: &str) -> String {
+    match self {
+      Self::Bash => shell_quote::QuoteRefExt::quoted(s, shell_quote::Bash),
+      // Sh only quotes into bytes
+      Self::Sh => String::from_utf8_lossy(&shell_quote::QuoteRefExt::<Vec<u8>>::quoted(
+        s,
+        shell_quote::Sh,
+      ))
+      .into_owned(),
+      Self::Fish => shell_quote::QuoteRefExt::quoted(s, shell_quote::Fish),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum ActivePopup {
   Help,
   ViewDetails(DetailsPopupState),
   CopyTargetSelection(CopyPopupState),
   InfoPopup(InfoPopupState),
+  Confirm(ConfirmPopupState),
+  SignalSelection(SignalPopupState),
+  KeyBindings(KeyBindingsPopupState),
+  Statistics,
 }
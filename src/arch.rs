@@ -10,6 +10,12 @@ cfg_if! {
     } else if #[cfg(target_arch = "riscv64")] {
         pub mod riscv64;
         pub use riscv64::*;
+    } else if #[cfg(target_arch = "arm")] {
+        pub mod arm;
+        pub use arm::*;
+    } else if #[cfg(target_arch = "s390x")] {
+        pub mod s390x;
+        pub use s390x::*;
     } else {
         compile_error!("unsupported architecture");
     }
@@ -24,4 +30,10 @@ pub use audit::*;
 
 pub trait RegsExt {
   fn syscall_arg(&self, idx: usize, is_32bit: bool) -> usize;
+  /// Change the syscall number at syscall entry. On aarch64 and arm, the syscall number
+  /// is not in the general purpose registers and is changed by other ptrace requests.
+  #[cfg(not(any(target_arch = "aarch64", target_arch = "arm")))]
+  fn set_syscall_nr(&mut self, nr: isize);
+  /// Change the return value at syscall exit.
+  fn set_syscall_result(&mut self, result: isize);
 }
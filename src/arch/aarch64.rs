@@ -23,4 +23,8 @@ impl RegsExt for Regs {
       _ => unimplemented!(),
     } as usize)
   }
+
+  fn set_syscall_result(&mut self, result: isize) {
+    self.regs[0] = result as u64;
+  }
 }
@@ -0,0 +1,30 @@
+use super::RegsExt;
+use nix::libc::{c_ulong, user_regs};
+
+pub const NATIVE_AUDIT_ARCH: u32 = super::AUDIT_ARCH_ARM;
+pub const HAS_32BIT: bool = false;
+
+pub type Regs = user_regs;
+pub type RegsPayload = Regs;
+#[repr(transparent)]
+pub struct RegsRepr {
+  pub payload: RegsPayload,
+}
+
+impl RegsExt for Regs {
+  fn syscall_arg(&self, idx: usize, _is_32bit: bool) -> usize {
+    (match idx {
+      0 => self.arm_r0,
+      1 => self.arm_r1,
+      2 => self.arm_r2,
+      3 => self.arm_r3,
+      4 => self.arm_r4,
+      5 => self.arm_r5,
+      _ => unimplemented!(),
+    } as usize)
+  }
+
+  fn set_syscall_result(&mut self, result: isize) {
+    self.arm_r0 = result as c_ulong;
+  }
+}
@@ -23,4 +23,12 @@ impl RegsExt for Regs {
       _ => unimplemented!(),
     } as usize)
   }
+
+  fn set_syscall_nr(&mut self, nr: isize) {
+    self.a7 = nr as u64;
+  }
+
+  fn set_syscall_result(&mut self, result: isize) {
+    self.a0 = result as u64;
+  }
 }
@@ -0,0 +1,47 @@
+use super::RegsExt;
+
+pub const NATIVE_AUDIT_ARCH: u32 = super::AUDIT_ARCH_S390X;
+pub const HAS_32BIT: bool = false;
+
+/// The NT_PRSTATUS register set of s390x, which is not provided by the libc crate.
+///
+/// https://github.com/torvalds/linux/blob/v6.9/arch/s390/include/uapi/asm/ptrace.h
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PtraceRegisters {
+  pub psw_mask: u64,
+  pub psw_addr: u64,
+  pub gprs: [u64; 16],
+  pub acrs: [u32; 16],
+  pub orig_gpr2: u64,
+}
+
+pub type Regs = PtraceRegisters;
+pub type RegsPayload = Regs;
+#[repr(transparent)]
+pub struct RegsRepr {
+  pub payload: RegsPayload,
+}
+
+impl RegsExt for Regs {
+  fn syscall_arg(&self, idx: usize, _is_32bit: bool) -> usize {
+    (match idx {
+      // gpr2 is overwritten by the return value
+      0 => self.orig_gpr2,
+      1 => self.gprs[3],
+      2 => self.gprs[4],
+      3 => self.gprs[5],
+      4 => self.gprs[6],
+      5 => self.gprs[7],
+      _ => unimplemented!(),
+    } as usize)
+  }
+
+  fn set_syscall_nr(&mut self, nr: isize) {
+    self.gprs[2] = nr as u64;
+  }
+
+  fn set_syscall_result(&mut self, result: isize) {
+    self.gprs[2] = result as u64;
+  }
+}
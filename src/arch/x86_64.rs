@@ -3,6 +3,9 @@ use nix::libc::user_regs_struct;
 pub const NATIVE_AUDIT_ARCH: u32 = super::AUDIT_ARCH_X86_64;
 pub const SYS_EXECVE_32: i32 = 11;
 pub const SYS_EXECVEAT_32: i32 = 358;
+pub const X32_SYSCALL_BIT: i64 = 0x40000000;
+pub const SYS_EXECVE_X32: i64 = X32_SYSCALL_BIT | 520;
+pub const SYS_EXECVEAT_X32: i64 = X32_SYSCALL_BIT | 545;
 pub const HAS_32BIT: bool = true;
 
 // https://github.com/rust-lang/rfcs/blob/master/text/2195-really-tagged-unions.md
@@ -98,4 +101,18 @@ impl RegsExt for Regs {
       }
     }
   }
+
+  fn set_syscall_nr(&mut self, nr: isize) {
+    match self {
+      Self::X86(regs) => regs.orig_eax = nr as u32,
+      Self::X64(regs) => regs.orig_rax = nr as u64,
+    }
+  }
+
+  fn set_syscall_result(&mut self, result: isize) {
+    match self {
+      Self::X86(regs) => regs.eax = result as u32,
+      Self::X64(regs) => regs.rax = result as u64,
+    }
+  }
 }
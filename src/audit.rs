@@ -0,0 +1,180 @@
+//! Find the suspicious patterns of the execs in `--audit` mode.
+
+use std::{collections::BTreeMap, fmt::Display, os::unix::fs::MetadataExt, path::Path};
+
+use arcstr::literal;
+use serde::{Deserialize, Serialize};
+
+use crate::{event::OutputMsg, tracer::state::ExecData};
+
+/// The directories that anyone could drop a binary into
+const TEMP_DIRS: [&str; 3] = ["/tmp/", "/var/tmp/", "/dev/shm/"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditRule {
+  #[serde(rename = "TX001")]
+  TempDirExec,
+  #[serde(rename = "TX002")]
+  WorldWritableDirExec,
+  #[serde(rename = "TX003")]
+  DeletedExe,
+  #[serde(rename = "TX004")]
+  InlineCode,
+  #[serde(rename = "TX005")]
+  LdPreload,
+}
+
+impl AuditRule {
+  pub fn id(self) -> &'static str {
+    match self {
+      Self::TempDirExec => "TX001",
+      Self::WorldWritableDirExec => "TX002",
+      Self::DeletedExe => "TX003",
+      Self::InlineCode => "TX004",
+      Self::LdPreload => "TX005",
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::TempDirExec => "temp-dir-exec",
+      Self::WorldWritableDirExec => "world-writable-dir-exec",
+      Self::DeletedExe => "deleted-exe",
+      Self::InlineCode => "inline-code",
+      Self::LdPreload => "ld-preload",
+    }
+  }
+
+  pub fn description(self) -> &'static str {
+    match self {
+      Self::TempDirExec => "The executable is in a temporary directory",
+      Self::WorldWritableDirExec => "The executable is in a world-writable directory",
+      Self::DeletedExe => "The executable is deleted after the exec",
+      Self::InlineCode => "An interpreter is fed code on the commandline",
+      Self::LdPreload => "LD_PRELOAD is set or changed",
+    }
+  }
+}
+
+impl Display for AuditRule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}:{}", self.id(), self.name())
+  }
+}
+
+/// The flags of the interpreters that take code as their argument
+fn inline_code_flags(interpreter: &str) -> &'static [&'static str] {
+  match interpreter {
+    "sh" | "bash" | "dash" | "zsh" | "ksh" | "mksh" | "ash" | "fish" => &["c"],
+    "perl" | "ruby" => &["e", "E"],
+    "node" | "nodejs" => &["e", "p", "-eval", "-print"],
+    "php" => &["r"],
+    "lua" | "luajit" => &["e"],
+    interp if interp.starts_with("python") => &["c"],
+    _ => &[],
+  }
+}
+
+fn has_inline_code(filename: &str, argv: &[OutputMsg]) -> bool {
+  let interpreter = filename.rsplit('/').next().unwrap_or(filename);
+  let flags = inline_code_flags(interpreter);
+  if flags.is_empty() {
+    return false;
+  }
+  argv
+    .iter()
+    .skip(1)
+    .map(|arg| arg.as_ref())
+    // Only the options before the script or the code are looked at
+    .take_while(|arg| arg.starts_with('-') && *arg != "--")
+    .any(|arg| {
+      let arg = &arg[1..];
+      if arg.starts_with('-') {
+        flags.contains(&arg)
+      } else {
+        // Short options could be combined, e.g. `sh -ec`
+        flags
+          .iter()
+          .filter(|flag| flag.len() == 1)
+          .any(|flag| arg.contains(*flag))
+      }
+    })
+}
+
+/// Find the suspicious patterns of the exec
+pub fn audit_exec(
+  exec_data: &ExecData,
+  baseline_env: &BTreeMap<OutputMsg, OutputMsg>,
+) -> Vec<AuditRule> {
+  let mut findings = Vec::new();
+  // The resolved executable is more accurate than the filename, which could be relative
+  let (path, deleted) = match exec_data.exe.as_ref() {
+    Some(OutputMsg::Ok(exe)) => match exe.strip_suffix(" (deleted)") {
+      Some(exe) => (exe.to_owned(), true),
+      None => (exe.to_string(), false),
+    },
+    _ => {
+      let filename = exec_data.filename.as_ref();
+      if filename.starts_with('/') {
+        (filename.to_owned(), false)
+      } else {
+        (format!("{}/{filename}", exec_data.cwd.as_ref()), false)
+      }
+    }
+  };
+  if TEMP_DIRS.iter().any(|dir| path.starts_with(dir)) {
+    findings.push(AuditRule::TempDirExec);
+  } else if Path::new(&path)
+    .parent()
+    .and_then(|dir| dir.metadata().ok())
+    .is_some_and(|meta| meta.mode() & 0o002 != 0)
+  {
+    findings.push(AuditRule::WorldWritableDirExec);
+  }
+  if deleted {
+    findings.push(AuditRule::DeletedExe);
+  }
+  if let Ok(argv) = exec_data.argv.as_ref() {
+    if has_inline_code(exec_data.filename.as_ref(), argv) {
+      findings.push(AuditRule::InlineCode);
+    }
+  }
+  if let Ok(envp) = exec_data.envp.as_ref() {
+    let key = OutputMsg::Ok(literal!("LD_PRELOAD"));
+    if envp
+      .get(&key)
+      .is_some_and(|v| baseline_env.get(&key) != Some(v))
+    {
+      findings.push(AuditRule::LdPreload);
+    }
+  }
+  findings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn argv(args: &[&str]) -> Vec<OutputMsg> {
+    args.iter().map(|&arg| OutputMsg::Ok(arg.into())).collect()
+  }
+
+  #[test]
+  fn inline_code_is_detected() {
+    assert!(has_inline_code("/bin/sh", &argv(&["sh", "-c", "id"])));
+    assert!(has_inline_code("/bin/bash", &argv(&["bash", "-ec", "id"])));
+    assert!(has_inline_code(
+      "/usr/bin/python3",
+      &argv(&["python3", "-c", "print()"])
+    ));
+    assert!(has_inline_code(
+      "/usr/bin/node",
+      &argv(&["node", "--eval", "1"])
+    ));
+    assert!(!has_inline_code(
+      "/bin/sh",
+      &argv(&["sh", "script.sh", "-c"])
+    ));
+    assert!(!has_inline_code("/usr/bin/cc", &argv(&["cc", "-c", "a.c"])));
+  }
+}
@@ -0,0 +1,218 @@
+//! Measure the overhead of tracing a command with each backend.
+//!
+//! The command is run without tracing first to get the baseline, then with each backend.
+//! Like in log mode, the exec events are formatted by the printer, but the output is
+//! discarded so that the terminal does not slow the tracer down.
+
+use std::{
+  io,
+  num::NonZeroUsize,
+  process::{Command, Stdio},
+  sync::Arc,
+  time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::Context;
+use owo_colors::OwoColorize;
+use strum::Display;
+use tokio::sync::mpsc;
+
+#[cfg(feature = "seccomp-bpf")]
+use crate::cli::options::SeccompBpf;
+use crate::{
+  cli::{
+    args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+    options::Redirect,
+  },
+  proc::BaselineInfo,
+  tracer::{Tracer, TracerMode},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Backend {
+  /// ptrace without the seccomp-bpf optimization
+  Ptrace,
+  /// ptrace with the seccomp-bpf optimization
+  #[cfg(feature = "seccomp-bpf")]
+  PtraceSeccompBpf,
+  /// seccomp user notification, like `tracexec notify`
+  #[cfg(feature = "seccomp-notify")]
+  Notify,
+  /// eBPF, like `tracexec ebpf log`
+  #[cfg(feature = "ebpf")]
+  Ebpf,
+}
+
+/// The timings of the runs of a command
+struct Timings(Vec<Duration>);
+
+impl Timings {
+  fn median(&self) -> Duration {
+    let mut sorted = self.0.clone();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+  }
+
+  fn min(&self) -> Duration {
+    self.0.iter().min().copied().unwrap_or_default()
+  }
+}
+
+/// The tracees read from `/dev/null` and their output is discarded
+fn quiet_log_args() -> LogModeArgs {
+  let dev_null = || Redirect::File("/dev/null".into());
+  LogModeArgs {
+    stdin: dev_null(),
+    stdout: dev_null(),
+    stderr: dev_null(),
+    ..Default::default()
+  }
+}
+
+fn run_untraced(cmd: &[String]) -> color_eyre::Result<()> {
+  Command::new(&cmd[0])
+    .args(&cmd[1..])
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .with_context(|| format!("Failed to run {}", cmd[0]))?;
+  Ok(())
+}
+
+async fn run_ptrace(cmd: &[String], ptrace_args: PtraceArgs) -> color_eyre::Result<()> {
+  let baseline = BaselineInfo::new()?;
+  let (tracer_tx, _tracer_rx) = TracerEventArgs::all().channel(Arc::new(baseline.clone()));
+  let (req_tx, req_rx) = mpsc::unbounded_channel();
+  let tracer = Arc::new(Tracer::new(
+    TracerMode::Log { foreground: false },
+    quiet_log_args(),
+    ModifierArgs::default().processed(),
+    ptrace_args,
+    TracerEventArgs::all(),
+    baseline,
+    tracer_tx,
+    None,
+    req_tx,
+  )?);
+  tracer
+    .spawn(cmd.to_vec(), Some(Box::new(io::sink())), req_rx)
+    .await??;
+  Ok(())
+}
+
+async fn run_backend(
+  backend: Backend,
+  cmd: &[String],
+  ptrace_args: &PtraceArgs,
+) -> color_eyre::Result<()> {
+  match backend {
+    #[cfg(not(feature = "seccomp-bpf"))]
+    Backend::Ptrace => run_ptrace(cmd, ptrace_args.clone()).await,
+    #[cfg(feature = "seccomp-bpf")]
+    Backend::Ptrace => {
+      run_ptrace(
+        cmd,
+        PtraceArgs {
+          seccomp_bpf: SeccompBpf::Off,
+          ..ptrace_args.clone()
+        },
+      )
+      .await
+    }
+    #[cfg(feature = "seccomp-bpf")]
+    Backend::PtraceSeccompBpf => {
+      run_ptrace(
+        cmd,
+        PtraceArgs {
+          seccomp_bpf: SeccompBpf::On,
+          ..ptrace_args.clone()
+        },
+      )
+      .await
+    }
+    #[cfg(feature = "seccomp-notify")]
+    Backend::Notify => {
+      crate::notify::run_silently(cmd.to_vec(), &quiet_log_args())?;
+      Ok(())
+    }
+    #[cfg(feature = "ebpf")]
+    Backend::Ebpf => {
+      let cmd = cmd.to_vec();
+      tokio::task::spawn_blocking(move || {
+        crate::bpf::run_silently(cmd, quiet_log_args().tracee_stdio())
+      })
+      .await?
+    }
+  }
+}
+
+/// Run the command `runs` times with `backend`, or without tracing if it is `None`.
+async fn measure(
+  backend: Option<Backend>,
+  cmd: &[String],
+  ptrace_args: &PtraceArgs,
+  runs: NonZeroUsize,
+) -> color_eyre::Result<Timings> {
+  let mut timings = Vec::with_capacity(runs.get());
+  for _ in 0..runs.get() {
+    let start = Instant::now();
+    match backend {
+      None => run_untraced(cmd)?,
+      Some(backend) => run_backend(backend, cmd, ptrace_args).await?,
+    }
+    timings.push(start.elapsed());
+  }
+  Ok(Timings(timings))
+}
+
+fn format_duration(duration: Duration) -> String {
+  format!("{:.3}ms", duration.as_secs_f64() * 1000.)
+}
+
+pub async fn run(
+  cmd: Vec<String>,
+  mut backends: Vec<Backend>,
+  runs: NonZeroUsize,
+  ptrace_args: PtraceArgs,
+) -> color_eyre::Result<()> {
+  if backends.is_empty() {
+    backends = Backend::value_variants().to_vec();
+  }
+  // Warm up the page cache so that the first backend is not penalized
+  run_untraced(&cmd)?;
+  let baseline = measure(None, &cmd, &ptrace_args, runs).await?;
+  println!(
+    "{:<20} {:>12} {:>12} {:>10}",
+    "backend".bold(),
+    "median".bold(),
+    "min".bold(),
+    "overhead".bold()
+  );
+  println!(
+    "{:<20} {:>12} {:>12} {:>10}",
+    "untraced",
+    format_duration(baseline.median()),
+    format_duration(baseline.min()),
+    "-"
+  );
+  for backend in backends {
+    match measure(Some(backend), &cmd, &ptrace_args, runs).await {
+      Ok(timings) => {
+        let overhead =
+          (timings.median().as_secs_f64() / baseline.median().as_secs_f64() - 1.) * 100.;
+        println!(
+          "{:<20} {:>12} {:>12} {:>10}",
+          backend.to_string(),
+          format_duration(timings.median()),
+          format_duration(timings.min()),
+          format!("{overhead:+.1}%")
+        );
+      }
+      Err(e) => println!("{:<20} {}: {e}", backend.to_string(), "FAIL".red().bold()),
+    }
+  }
+  Ok(())
+}
@@ -1,32 +1,38 @@
 use std::{
   borrow::Cow,
-  cell::RefCell,
+  cell::{Cell, RefCell},
   collections::{HashMap, HashSet},
   ffi::{CStr, CString},
+  fs::File,
   io::{self, stdin},
   iter::repeat,
   mem::MaybeUninit,
+  num::NonZeroUsize,
   os::{
     fd::{AsRawFd, RawFd},
     unix::{fs::MetadataExt, process::CommandExt},
   },
+  path::{Path, PathBuf},
   process,
   sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc, OnceLock, RwLock,
   },
-  time::Duration,
+  time::{Duration, Instant},
 };
 
 use arcstr::ArcStr;
-use color_eyre::{eyre::eyre, Section};
+use color_eyre::{
+  eyre::{bail, eyre},
+  Section,
+};
 use enumflags2::{BitFlag, BitFlags};
 use event::EventStorage;
 use interface::BpfEventFlags;
 use libbpf_rs::{
   num_possible_cpus,
   skel::{OpenSkel, Skel, SkelBuilder},
-  ErrorKind, OpenObject, RingBuffer, RingBufferBuilder,
+  ErrorKind, MapCore, MapFlags, OpenObject, RingBuffer, RingBufferBuilder,
 };
 use nix::{
   errno::Errno,
@@ -49,30 +55,36 @@ use skel::{
   },
   TracexecSystemSkel,
 };
-use tokio::{
-  sync::mpsc::{self, UnboundedSender},
-  task::spawn_blocking,
-};
+use tokio::task::spawn_blocking;
 use tracing::{debug, warn};
 
 use crate::{
+  audit::audit_exec,
   cache::StringCache,
+  caps::read_exec_capabilities,
+  channel::MessageSender,
   cli::{
-    args::{LogModeArgs, ModifierArgs},
-    options::{Color, ExportFormat},
+    args::{LogModeArgs, ModifierArgs, TracerEventArgs},
+    options::{Color, ExportFormat, MaxEventsAction, TraceeStdio},
     Cli, EbpfCommand,
   },
   cmdbuilder::CommandBuilder,
+  collect::{collect, CollectOptions},
+  container::read_cgroup,
   event::{
     filterable_event, ExecEvent, FilterableTracerEventDetails, FriendlyError, OutputMsg,
     ProcessStateUpdate, ProcessStateUpdateEvent, TracerEvent, TracerEventDetails,
-    TracerEventDetailsKind, TracerMessage,
+    TracerEventDetailsKind,
   },
-  export::{self, JsonExecEvent, JsonMetaData},
+  export::JsonMetaData,
+  path_map,
   printer::{Printer, PrinterArgs, PrinterOut},
-  proc::{cached_string, diff_env, parse_failiable_envp, BaselineInfo, FileDescriptorInfo},
+  proc::{
+    cached_string, diff_env, parse_failiable_envp, read_credentials, read_ppid,
+    read_security_label, BaselineInfo, FileDescriptorInfo, ProcessHandle,
+  },
   pty::{self, native_pty_system, PtySize, PtySystem},
-  serialize_json_to_output,
+  redact,
   tracer::{
     state::{ExecData, ProcessExit},
     TracerMode,
@@ -114,12 +126,23 @@ fn bump_memlock_rlimit() -> color_eyre::Result<()> {
 pub struct EbpfTracer {
   cmd: Vec<String>,
   user: Option<User>,
-  modifier: ModifierArgs,
   printer: Arc<Printer>,
   baseline: Arc<BaselineInfo>,
-  tx: Option<UnboundedSender<TracerMessage>>,
+  tx: Option<MessageSender>,
   filter: BitFlags<TracerEventDetailsKind>,
   mode: TracerMode,
+  /// Only trace the processes in this cgroup in system-wide mode
+  cgroup: Option<PathBuf>,
+  /// The limits of the argv captured per exec, the kernel-side defaults are used if unset
+  max_argv_entries: Option<u32>,
+  max_argv_bytes: Option<u32>,
+  /// The size of the ring buffer in bytes, the kernel-side default is used if unset
+  ringbuf_size: Option<u32>,
+  /// Where the standard streams of the command go in log mode
+  stdio: TraceeStdio,
+  /// Stop tracing after this many exec events
+  max_events: Option<NonZeroUsize>,
+  on_max_events: MaxEventsAction,
 }
 
 impl EbpfTracer {
@@ -131,12 +154,15 @@ impl EbpfTracer {
   ) -> color_eyre::Result<RunningEbpfTracer<'obj>> {
     let (skel, child) = self.spawn_command(obj)?;
     let follow_forks = !self.cmd.is_empty();
+    // stderr is taken by the TUI
+    let warn_lost_events = !matches!(self.mode, TracerMode::Tui(_));
     let mut tracker = ProcessTracker::default();
     child.inspect(|p| tracker.add(*p));
     let mut builder = RingBufferBuilder::new();
     let event_storage: RefCell<HashMap<u64, EventStorage>> = RefCell::new(HashMap::new());
     let lost_events: RefCell<HashSet<u64>> = RefCell::new(HashSet::new());
     let mut eid = 0;
+    let mut exec_events = 0;
     let printer_clone = self.printer.clone();
     let should_exit = Arc::new(AtomicBool::new(false));
     builder.add(&skel.maps.events, {
@@ -182,15 +208,43 @@ impl EbpfTracer {
             }
             assert_eq!(data.len(), size_of::<exec_event>());
             let event: exec_event = unsafe { std::ptr::read(data.as_ptr() as *const _) };
-            if event.ret != 0 && self.modifier.successful_only {
+            if (event.ret != 0 && self.printer.args.successful_only)
+              || (event.ret == 0 && self.printer.args.failed_only)
+            {
+              // Drop the strings of the filtered out exec
+              event_storage.borrow_mut().remove(&header.eid);
               return 0;
             }
+            if let Some(max_events) = self.max_events {
+              exec_events += 1;
+              if exec_events > max_events.get() {
+                event_storage.borrow_mut().remove(&header.eid);
+                return 0;
+              }
+              if exec_events == max_events.get() {
+                if !follow_forks {
+                  // There is no command to wait for in system-wide mode
+                  should_exit.store(true, Ordering::Relaxed);
+                } else if self.on_max_events == MaxEventsAction::Kill {
+                  for pid in tracker.pids() {
+                    match kill(pid, Signal::SIGKILL) {
+                      Ok(_) | Err(Errno::ESRCH) => (),
+                      Err(e) => warn!("Failed to kill {pid}: {e}"),
+                    }
+                  }
+                }
+                // Otherwise, the following execs are dropped until the command exits
+              }
+            }
             let mut storage = event_storage.borrow_mut();
             let mut storage = storage.remove(&header.eid).unwrap();
+            let eflags = BpfEventFlags::from_bits_truncate(header.flags);
             let envp = storage.strings.split_off(event.count[0] as usize);
-            let argv = storage.strings;
+            let mut argv = storage.strings;
+            if eflags.contains(BpfEventFlags::ARGV_TRUNCATED) {
+              argv.push(OutputMsg::Err(BpfError::ArgvTruncated.into()));
+            }
             let cwd: OutputMsg = storage.paths.remove(&AT_FDCWD).unwrap().into();
-            let eflags = BpfEventFlags::from_bits_truncate(header.flags);
             // TODO: How should we handle possible truncation?
             let base_filename = if eflags.contains(BpfEventFlags::FILENAME_READ_ERR) {
               OutputMsg::Err(FriendlyError::Bpf(BpfError::Flags))
@@ -218,21 +272,30 @@ impl EbpfTracer {
                 }
               }
             };
-            let exec_data = ExecData::new(
+            let mut exec_data = ExecData::new(
               filename,
               Ok(argv),
               Ok(parse_failiable_envp(envp)),
               cwd,
               None,
               storage.fdinfo_map,
+              read_cgroup(Pid::from_raw(header.pid)),
             );
+            if self.printer.args.audit {
+              exec_data.audit = audit_exec(&exec_data, &self.baseline.env);
+            }
             let pid = Pid::from_raw(header.pid);
+            exec_data.ppid = read_ppid(pid).ok();
+            exec_data.credentials = read_credentials(pid).ok();
+            exec_data.capabilities = read_exec_capabilities(pid, exec_data.ppid);
+            exec_data.security_label = read_security_label(pid);
             let comm = cached_cow(utf8_lossy_cow_from_bytes_with_nul(&event.comm));
             self
               .printer
               .print_exec_trace(
                 pid,
                 comm.clone(),
+                None,
                 event.ret,
                 &exec_data,
                 &self.baseline.env,
@@ -242,12 +305,23 @@ impl EbpfTracer {
             if self.filter.intersects(TracerEventDetailsKind::Exec) {
               let event = TracerEvent::from(TracerEventDetails::Exec(Box::new(ExecEvent {
                 pid,
+                ppid: exec_data.ppid,
                 cwd: exec_data.cwd.clone(),
                 comm,
                 filename: exec_data.filename.clone(),
                 argv: exec_data.argv.clone(),
                 envp: exec_data.envp.clone(),
                 interpreter: exec_data.interpreters.clone(),
+                container: exec_data.container.clone(),
+                cgroup: exec_data.cgroup.clone(),
+                exe: exec_data.exe.clone(),
+                exe_hash: exec_data.exe_hash.clone(),
+                privileges: exec_data.privileges,
+                credentials: exec_data.credentials,
+                capabilities: exec_data.capabilities,
+                security_label: exec_data.security_label.clone(),
+                security_label_before: None,
+                audit: exec_data.audit.clone(),
                 env_diff: exec_data
                   .envp
                   .as_ref()
@@ -256,6 +330,7 @@ impl EbpfTracer {
                   .map_err(|e| *e),
                 result: event.ret,
                 fdinfo: exec_data.fdinfo.clone(),
+                process: None,
               })));
               if follow_forks {
                 tracker.associate_events(pid, [event.id])
@@ -352,19 +427,34 @@ impl EbpfTracer {
             let pid = Pid::from_raw(header.pid);
             if let Some(associated) = tracker.maybe_associated_events(pid) {
               if !associated.is_empty() {
+                let exit = match (event.sig, event.code) {
+                  (0, code) => ProcessExit::Code(code),
+                  (sig, _) => {
+                    // 0x80 bit indicates coredump
+                    ProcessExit::Signal(Signal::try_from(sig as i32 & 0x7f).unwrap())
+                  }
+                };
+                let comm = cached_cow(utf8_lossy_cow_from_bytes_with_nul(&event.comm));
+                self
+                  .printer
+                  .print_exit(pid, comm.clone(), None, exit)
+                  .unwrap();
+                self
+                  .tx
+                  .as_ref()
+                  .map(|tx| {
+                    filterable_event!(ProcessExit { pid, comm, exit })
+                      .send_if_match(tx, self.filter)
+                  })
+                  .transpose()
+                  .unwrap();
                 self
                   .tx
                   .as_ref()
                   .map(|tx| {
                     tx.send(
                       ProcessStateUpdateEvent {
-                        update: ProcessStateUpdate::Exit(match (event.sig, event.code) {
-                          (0, code) => ProcessExit::Code(code),
-                          (sig, _) => {
-                            // 0x80 bit indicates coredump
-                            ProcessExit::Signal(Signal::try_from(sig as i32 & 0x7f).unwrap())
-                          }
-                        }),
+                        update: ProcessStateUpdate::Exit(exit),
                         pid,
                         ids: associated.to_owned(),
                       }
@@ -421,6 +511,10 @@ impl EbpfTracer {
     Ok(RunningEbpfTracer {
       rb,
       should_exit,
+      lost_events: Arc::new(AtomicU64::new(0)),
+      warn_lost_events,
+      reported_lost_events: Cell::new(0),
+      last_lost_events_report: Cell::new(None),
       skel,
     })
   }
@@ -435,6 +529,15 @@ impl EbpfTracer {
     let ncpu = num_possible_cpus()?.try_into().expect("Too many cores!");
     open_skel.maps.rodata_data.tracexec_config.max_num_cpus = ncpu;
     open_skel.maps.cache.set_max_entries(ncpu)?;
+    if let Some(ringbuf_size) = self.ringbuf_size {
+      open_skel.maps.events.set_max_entries(ringbuf_size)?;
+    }
+    if let Some(max_argv_entries) = self.max_argv_entries {
+      open_skel.maps.rodata_data.tracexec_config.max_argv_entries = max_argv_entries;
+    }
+    if let Some(max_argv_bytes) = self.max_argv_bytes {
+      open_skel.maps.rodata_data.tracexec_config.max_argv_bytes = max_argv_bytes;
+    }
     // tracexec runs in the same pid namespace with the tracee
     let pid_ns_ino = std::fs::metadata("/proc/self/ns/pid")?.ino();
     let (skel, child) = if !self.cmd.is_empty() {
@@ -442,12 +545,22 @@ impl EbpfTracer {
       cmd.args(self.cmd.iter().skip(1));
       cmd.cwd(std::env::current_dir()?);
       let mut cmd = cmd.as_command()?;
+      self.stdio.apply(&mut cmd)?;
       match unsafe { fork()? } {
         ForkResult::Parent { child } => {
           self
             .tx
             .as_ref()
-            .map(|tx| filterable_event!(TraceeSpawn(child)).send_if_match(tx, self.filter))
+            .map(|tx| {
+              // The child is not reaped before it is waited for, so the pidfd refers to it
+              let process = matches!(self.mode, TracerMode::Tui(_))
+                .then(|| Arc::new(ProcessHandle::new(child)));
+              filterable_event!(TraceeSpawn {
+                pid: child,
+                process,
+              })
+              .send_if_match(tx, self.filter)
+            })
             .transpose()?;
           if matches!(&self.mode, TracerMode::Log { foreground: true }) {
             match tcsetpgrp(stdin(), child) {
@@ -488,14 +601,7 @@ impl EbpfTracer {
             if unsafe { libc::ioctl(0, libc::TIOCSCTTY as _, 0) } == -1 {
               Err(io::Error::last_os_error())?;
             }
-          } else if matches!(self.mode, TracerMode::Tui(_)) {
-            unsafe {
-              let dev_null = std::fs::File::open("/dev/null")?;
-              dup2(dev_null.as_raw_fd(), 0);
-              dup2(dev_null.as_raw_fd(), 1);
-              dup2(dev_null.as_raw_fd(), 2);
-            }
-          } else {
+          } else if !matches!(self.mode, TracerMode::Tui(_)) {
             let me = getpid();
             setpgid(me, me)?;
           }
@@ -535,7 +641,19 @@ impl EbpfTracer {
         }
       }
     } else {
+      let cgroup = self.cgroup.as_deref().map(open_cgroup).transpose()?;
+      if cgroup.is_some() {
+        open_skel.maps.rodata_data.tracexec_config.filter_cgroup = MaybeUninit::new(true);
+      }
       let mut skel = open_skel.load()?;
+      if let Some(cgroup) = cgroup {
+        // The map keeps a reference to the cgroup so the file could be closed afterwards
+        skel.maps.cgroup_filter.update(
+          &0u32.to_ne_bytes(),
+          &cgroup.as_raw_fd().to_ne_bytes(),
+          MapFlags::ANY,
+        )?;
+      }
       skel.attach()?;
       (skel, None)
     };
@@ -543,13 +661,30 @@ impl EbpfTracer {
   }
 }
 
+/// Open the cgroup directory. The path is relative to the cgroup v2 mount point
+/// unless it is already under it.
+fn open_cgroup(path: &Path) -> color_eyre::Result<File> {
+  const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+  let path = if path.starts_with(CGROUP_ROOT) {
+    path.to_owned()
+  } else {
+    Path::new(CGROUP_ROOT).join(path.strip_prefix("/").unwrap_or(path))
+  };
+  File::open(&path).map_err(|e| eyre!("Failed to open cgroup {}: {e}", path.display()))
+}
+
 // TODO: we should start polling the ringbuffer before program load
 
 pub struct RunningEbpfTracer<'obj> {
   rb: RingBuffer<'obj>,
   should_exit: Arc<AtomicBool>,
+  /// The number of events that the eBPF program could not send through the ring buffer
+  pub lost_events: Arc<AtomicU64>,
+  /// Whether to warn on stderr about the lost events
+  warn_lost_events: bool,
+  reported_lost_events: Cell<u64>,
+  last_lost_events_report: Cell<Option<Instant>>,
   // The eBPF program gets unloaded on skel drop
-  #[allow(unused)]
   skel: TracexecSystemSkel<'obj>,
 }
 
@@ -559,6 +694,7 @@ impl<'rb> RunningEbpfTracer<'rb> {
       if self.should_exit.load(Ordering::Relaxed) {
         break;
       }
+      self.update_lost_events(false);
       match self.rb.poll(Duration::from_millis(100)) {
         Ok(_) => continue,
         Err(e) => {
@@ -570,9 +706,74 @@ impl<'rb> RunningEbpfTracer<'rb> {
         }
       }
     }
+    self.update_lost_events(true);
+  }
+
+  /// Read the counter of lost events from the eBPF program.
+  ///
+  /// Outside of the TUI, the newly lost events are reported on stderr at most once per second
+  /// unless it is the final report.
+  fn update_lost_events(&self, final_report: bool) {
+    // The counter is updated by the eBPF program concurrently
+    let lost = unsafe { std::ptr::read_volatile(&self.skel.maps.bss_data.lost_event_counter) };
+    self.lost_events.store(lost, Ordering::Relaxed);
+    let reported = self.reported_lost_events.get();
+    if !self.warn_lost_events || lost == reported {
+      return;
+    }
+    let now = Instant::now();
+    if !final_report
+      && self
+        .last_lost_events_report
+        .get()
+        .is_some_and(|t| now - t < Duration::from_secs(1))
+    {
+      return;
+    }
+    warn!(
+      "{} events lost because the ring buffer is full",
+      lost - reported
+    );
+    eprintln!(
+      "Warning: {} events lost because the ring buffer is full, {lost} in total. The trace is incomplete, use --ebpf-ringbuf-size to enlarge the ring buffer.",
+      lost - reported
+    );
+    self.reported_lost_events.set(lost);
+    self.last_lost_events_report.set(Some(now));
   }
 }
 
+/// Trace the command without printing anything. Used by `tracexec bench`.
+pub fn run_silently(cmd: Vec<String>, stdio: TraceeStdio) -> color_eyre::Result<()> {
+  // The skeleton borrows the object until it is dropped
+  let obj = Box::leak(Box::new(MaybeUninit::uninit()));
+  let modifier_args = ModifierArgs::default().processed();
+  let baseline = Arc::new(BaselineInfo::new()?);
+  let printer = Arc::new(Printer::new(
+    PrinterArgs::from_cli(&LogModeArgs::default(), &modifier_args),
+    baseline.clone(),
+  ));
+  let tracer = EbpfTracer {
+    cmd,
+    user: None,
+    printer,
+    baseline,
+    tx: None,
+    filter: TracerEventDetailsKind::empty(),
+    mode: TracerMode::Log { foreground: false },
+    cgroup: None,
+    max_argv_entries: None,
+    max_argv_bytes: None,
+    ringbuf_size: None,
+    stdio,
+    max_events: None,
+    on_max_events: MaxEventsAction::default(),
+  };
+  let running_tracer = tracer.spawn(obj, Some(Box::new(io::sink())))?;
+  running_tracer.run_until_exit();
+  Ok(())
+}
+
 pub async fn run(
   command: EbpfCommand,
   user: Option<User>,
@@ -585,8 +786,11 @@ pub async fn run(
       output,
       modifier_args,
       log_args,
+      ebpf_args,
     } => {
       let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
       let baseline = Arc::new(BaselineInfo::new()?);
       let output = Cli::get_output(output, color)?;
       let printer = Arc::new(Printer::new(
@@ -596,17 +800,27 @@ pub async fn run(
       let tracer = EbpfTracer {
         cmd,
         user,
-        modifier: modifier_args,
-        printer,
+        printer: printer.clone(),
         baseline,
         tx: None,
         filter: TracerEventDetailsKind::empty(), // FIXME
         mode: TracerMode::Log {
           foreground: log_args.foreground(),
         },
+        cgroup: ebpf_args.cgroup,
+        max_argv_entries: ebpf_args.max_argv_entries,
+        max_argv_bytes: ebpf_args.max_argv_bytes,
+        ringbuf_size: ebpf_args.ebpf_ringbuf_size,
+        stdio: log_args.tracee_stdio(),
+        max_events: modifier_args.max_events,
+        on_max_events: modifier_args.on_max_events,
       };
       let running_tracer = tracer.spawn(obj, Some(output))?;
       running_tracer.run_until_exit();
+      if printer.args.dedup {
+        printer.print_dedup_summary()?;
+      }
+      printer.flush()?;
       Ok(())
     }
     EbpfCommand::Tui {
@@ -614,6 +828,7 @@ pub async fn run(
       modifier_args,
       tracer_event_args,
       tui_args,
+      ebpf_args,
     } => {
       let follow_forks = !cmd.is_empty();
       if tui_args.tty && !follow_forks {
@@ -624,6 +839,8 @@ pub async fn run(
         );
       }
       let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
       // Disable owo-colors when running TUI
       owo_colors::control::set_should_colorize(false);
       let (baseline, tracer_mode, pty_master) = if tui_args.tty {
@@ -642,6 +859,7 @@ pub async fn run(
       } else {
         (BaselineInfo::new()?, TracerMode::Tui(None), None)
       };
+      let stdio = tui_args.tracee_stdio();
       let baseline = Arc::new(baseline);
       let frame_rate = tui_args.frame_rate.unwrap_or(60.);
       let log_args = LogModeArgs {
@@ -666,19 +884,27 @@ pub async fn run(
         PrinterArgs::from_cli(&log_args, &modifier_args),
         baseline.clone(),
       ));
-      let (tracer_tx, tracer_rx) = mpsc::unbounded_channel();
+      let (tracer_tx, tracer_rx) = tracer_event_args.channel(baseline.clone());
+      app.overflow_stats = Some(tracer_rx.overflow_stats());
       // let (req_tx, req_rx) = mpsc::unbounded_channel();
       let tracer = EbpfTracer {
         cmd,
         user,
-        modifier: modifier_args,
         printer,
         baseline,
         filter: tracer_event_args.filter()?,
         tx: Some(tracer_tx),
         mode: tracer_mode,
+        cgroup: ebpf_args.cgroup,
+        max_argv_entries: ebpf_args.max_argv_entries,
+        max_argv_bytes: ebpf_args.max_argv_bytes,
+        ringbuf_size: ebpf_args.ebpf_ringbuf_size,
+        stdio,
+        max_events: modifier_args.max_events,
+        on_max_events: modifier_args.on_max_events,
       };
       let running_tracer = tracer.spawn(obj, None)?;
+      app.lost_events = Some(running_tracer.lost_events.clone());
       let should_exit = running_tracer.should_exit.clone();
       let tracer_thread = spawn_blocking(move || {
         running_tracer.run_until_exit();
@@ -704,13 +930,23 @@ pub async fn run(
       modifier_args,
       format,
       pretty,
+      notes_args,
+      ebpf_args,
+      otlp_endpoint,
       output,
+      flush,
       foreground,
       no_foreground,
     } => {
       let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
       let baseline = Arc::new(BaselineInfo::new()?);
+      let metadata = JsonMetaData::new(baseline.as_ref().to_owned()).with_notes(notes_args.into());
       let mut output = Cli::get_output(output, color)?;
+      if otlp_endpoint.is_some() && format != ExportFormat::Otlp {
+        bail!("--otlp-endpoint is only available with the otlp format");
+      }
       let log_args = LogModeArgs {
         show_cmdline: false,
         show_argv: true,
@@ -726,11 +962,10 @@ pub async fn run(
         PrinterArgs::from_cli(&log_args, &modifier_args),
         baseline.clone(),
       ));
-      let (tx, mut rx) = mpsc::unbounded_channel();
+      let (tx, mut rx) = TracerEventArgs::all().channel(baseline.clone());
       let tracer = EbpfTracer {
         cmd,
         user,
-        modifier: modifier_args,
         printer,
         baseline: baseline.clone(),
         tx: Some(tx),
@@ -738,82 +973,33 @@ pub async fn run(
         mode: TracerMode::Log {
           foreground: log_args.foreground(),
         },
+        cgroup: ebpf_args.cgroup,
+        max_argv_entries: ebpf_args.max_argv_entries,
+        max_argv_bytes: ebpf_args.max_argv_bytes,
+        ringbuf_size: ebpf_args.ebpf_ringbuf_size,
+        stdio: TraceeStdio::default(),
+        max_events: modifier_args.max_events,
+        on_max_events: modifier_args.on_max_events,
       };
       let running_tracer = tracer.spawn(obj, None)?;
       let tracer_thread = spawn_blocking(move || {
         running_tracer.run_until_exit();
       });
-      match format {
-        ExportFormat::Json => {
-          let mut json = export::Json {
-            meta: JsonMetaData::new(baseline.as_ref().to_owned()),
-            events: Vec::new(),
-          };
-          loop {
-            match rx.recv().await {
-              Some(TracerMessage::Event(TracerEvent {
-                details: TracerEventDetails::TraceeExit { exit_code, .. },
-                ..
-              })) => {
-                tracing::debug!("Waiting for tracer thread to exit");
-                tracer_thread.await?;
-                serialize_json_to_output(&mut output, &json, pretty)?;
-                output.write_all(b"\n")?;
-                output.flush()?;
-                process::exit(exit_code);
-              }
-              Some(TracerMessage::Event(TracerEvent {
-                details: TracerEventDetails::Exec(exec),
-                id,
-              })) => {
-                json.events.push(JsonExecEvent::new(id, *exec));
-              }
-              // channel closed abnormally.
-              None | Some(TracerMessage::FatalError(_)) => {
-                tracing::debug!("Waiting for tracer thread to exit");
-                tracer_thread.await?;
-                process::exit(1);
-              }
-              _ => (),
-            }
-          }
-        }
-        ExportFormat::JsonStream => {
-          serialize_json_to_output(
-            &mut output,
-            &JsonMetaData::new(baseline.as_ref().to_owned()),
-            pretty,
-          )?;
-          loop {
-            match rx.recv().await {
-              Some(TracerMessage::Event(TracerEvent {
-                details: TracerEventDetails::TraceeExit { exit_code, .. },
-                ..
-              })) => {
-                tracing::debug!("Waiting for tracer thread to exit");
-                tracer_thread.await?;
-                process::exit(exit_code);
-              }
-              Some(TracerMessage::Event(TracerEvent {
-                details: TracerEventDetails::Exec(exec),
-                id,
-              })) => {
-                let json_event = JsonExecEvent::new(id, *exec);
-                serialize_json_to_output(&mut output, &json_event, pretty)?;
-                output.write_all(b"\n")?;
-                output.flush()?;
-              }
-              // channel closed abnormally.
-              None | Some(TracerMessage::FatalError(_)) => {
-                tracing::debug!("Waiting for tracer thread to exit");
-                tracer_thread.await?;
-                process::exit(1);
-              }
-              _ => (),
-            }
-          }
-        }
-      }
+      let options = CollectOptions {
+        pretty,
+        flush,
+        otlp_endpoint,
+      };
+      let exit_code = collect(
+        format,
+        options,
+        metadata,
+        &mut output,
+        &mut rx,
+        async move { Ok(tracer_thread.await?) },
+      )
+      .await?;
+      process::exit(exit_code);
     }
   }
 }
@@ -37,6 +37,8 @@
 #define SYSCALL_PREFIX "arm64"
 #elif TRACEXEC_TARGET_RISCV64
 #define SYSCALL_PREFIX "riscv"
+#elif TRACEXEC_TARGET_S390X
+#define SYSCALL_PREFIX "s390x"
 #endif
 
 #ifdef TRACEXEC_TARGET_X86_64
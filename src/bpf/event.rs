@@ -59,4 +59,6 @@ impl From<Path> for OutputMsg {
 pub enum BpfError {
   Dropped,
   Flags,
+  /// argv is cut by `--max-argv-entries` or `--max-argv-bytes`
+  ArgvTruncated,
 }
@@ -76,7 +76,9 @@ enum exec_event_flags {
   // filename read failure
   FILENAME_READ_ERR = 65536,
   // file->pos read failure
-  POS_READ_ERR = 131072
+  POS_READ_ERR = 131072,
+  // argv is cut by the configured entry or byte limit
+  ARGV_TRUNCATED = 262144
 };
 
 enum event_type {
@@ -159,6 +161,7 @@ struct exit_event {
   int code;
   u32 sig;
   bool is_root_tracee;
+  u8 comm[TASK_COMM_LEN];
 };
 
 union cache_item {
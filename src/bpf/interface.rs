@@ -1,5 +1,12 @@
 use enumflags2::bitflags;
 
+// Copy of the limits in interface.h
+
+/// The maximum possible value of argc and num of env
+pub const ARGC_MAX: u32 = 233017;
+/// The limit for argc + argv + envp
+pub const ARG_MAX: u32 = 2097152;
+
 #[bitflags]
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -35,4 +42,8 @@ pub enum BpfEventFlags {
   MNTID_READ_ERR = 32768,
   // filename read failure
   FILENAME_READ_ERR = 65536,
+  // file->pos read failure
+  POS_READ_ERR = 131072,
+  // argv is cut by the configured entry or byte limit
+  ARGV_TRUNCATED = 262144,
 }
@@ -23,6 +23,10 @@ impl ProcessTracker {
     assert!(ret.is_some())
   }
 
+  pub fn pids(&self) -> impl Iterator<Item = Pid> + '_ {
+    self.processes.keys().copied()
+  }
+
   pub fn maybe_remove(&mut self, pid: Pid) {
     let _ = self.processes.remove(&pid);
   }
@@ -10,6 +10,9 @@ char LICENSE[] SEC("license") = "GPL";
 static const struct exec_event empty_event = {};
 static u64 event_counter = 0;
 static u32 drop_counter = 0;
+// The number of events that could not be sent to userspace, e.g. because the
+// ring buffer is full. It is read by userspace.
+u64 lost_event_counter = 0;
 // The tgid of the root tracee in global namespace.
 // This field is used to check whether we should signal
 // userspace tracexec to exit.
@@ -20,17 +23,33 @@ const volatile struct {
   u32 max_num_cpus;
   u32 nofile;
   bool follow_fork;
+  // Only trace the tasks in the cgroup in cgroup_filter
+  bool filter_cgroup;
   pid_t tracee_pid;
   unsigned int tracee_pidns_inum;
+  // The limits of the captured argv per exec
+  u32 max_argv_entries;
+  u32 max_argv_bytes;
 } tracexec_config = {
     .max_num_cpus = MAX_CPUS,
     // https://www.kxxt.dev/blog/max-possible-value-of-rlimit-nofile/
     .nofile = 2147483584,
     .follow_fork = false,
+    .filter_cgroup = false,
     .tracee_pid = 0,
     .tracee_pidns_inum = 0,
+    .max_argv_entries = ARGC_MAX,
+    .max_argv_bytes = _SC_ARG_MAX,
 };
 
+// The cgroup to trace in system-wide mode, set by userspace at index 0
+struct {
+  __uint(type, BPF_MAP_TYPE_CGROUP_ARRAY);
+  __uint(max_entries, 1);
+  __type(key, u32);
+  __type(value, u32);
+} cgroup_filter SEC(".maps");
+
 struct {
   __uint(type, BPF_MAP_TYPE_HASH);
   __uint(max_entries, 1024);
@@ -78,10 +97,19 @@ struct {
   // considering the space taken by path segments) so on a machine with 64
   // cores, there can be at most 64 execs happening in parallel, taking at most
   // 128MiB space in a burst. We haven't considered the rate at which the
-  // userspace code consumes event, 256MiB is used as a heruistic for now
+  // userspace code consumes event, 256MiB is used as a heruistic for now.
+  // It could be changed by userspace with --ebpf-ringbuf-size.
   __uint(max_entries, 268435456);
 } events SEC(".maps");
 
+// Send the event to userspace and count it if it is lost
+static long __always_inline output_event(void *data, u64 size, u64 flags) {
+  long ret = bpf_ringbuf_output(&events, data, size, flags);
+  if (ret < 0)
+    __sync_fetch_and_add(&lost_event_counter, 1);
+  return ret;
+}
+
 struct reader_context {
   struct exec_event *event;
   // index:
@@ -91,6 +119,9 @@ struct reader_context {
   bool is_compat;
   // ptr is a userspace pointer to an array of cstring pointers
   const u8 *const *ptr;
+  // The maximum number of entries and the remaining bytes to read
+  u32 max_entries;
+  u32 bytes_left;
 };
 
 struct fdset_reader_context {
@@ -126,10 +157,17 @@ static int read_send_path(const struct path *path,
 #define debug(...) bpf_printk("");
 #endif
 
+// Whether the current task is in the cgroup to trace or its descendants
+static bool in_traced_cgroup() {
+  if (!tracexec_config.filter_cgroup)
+    return true;
+  return bpf_current_task_under_cgroup(&cgroup_filter, 0) == 1;
+}
+
 bool should_trace(pid_t old_tgid) {
   // Trace all if not following forks
   if (!tracexec_config.follow_fork)
-    return true;
+    return in_traced_cgroup();
   // Check if it is in the closure
   void *ptr = bpf_map_lookup_elem(&tgid_closure, &old_tgid);
   if (ptr != NULL)
@@ -278,11 +316,17 @@ int trace_exec_common(struct sys_enter_exec_args *ctx) {
   reader_ctx.ptr = ctx->argv;
   reader_ctx.index = 0;
   reader_ctx.is_compat = ctx->is_compat;
-  // bpf_loop allows 1 << 23 (~8 million) loops, otherwise we cannot achieve it
-  bpf_loop(ARGC_MAX, read_strings, &reader_ctx, 0);
+  reader_ctx.max_entries = tracexec_config.max_argv_entries;
+  reader_ctx.bytes_left = tracexec_config.max_argv_bytes;
+  // bpf_loop allows 1 << 23 (~8 million) loops, otherwise we cannot achieve it.
+  // One more loop is needed to find out whether argv is truncated.
+  bpf_loop(reader_ctx.max_entries + 1, read_strings, &reader_ctx, 0);
   // Read envp
   reader_ctx.ptr = ctx->envp;
   reader_ctx.index = 1;
+  // envp is not limited
+  reader_ctx.max_entries = ARGC_MAX;
+  reader_ctx.bytes_left = (u32)-1;
   bpf_loop(ARGC_MAX, read_strings, &reader_ctx, 0);
   // Read file descriptors
   read_fds(event);
@@ -342,8 +386,7 @@ int trace_fork(u64 *ctx) {
     entry->header.flags = 0;
     entry->header.pid = pid;
     entry->parent_tgid = parent_tgid;
-    ret =
-        bpf_ringbuf_output(&events, entry, sizeof(*entry), BPF_RB_FORCE_WAKEUP);
+    ret = output_event(entry, sizeof(*entry), BPF_RB_FORCE_WAKEUP);
     if (ret < 0) {
       // TODO: find a better way to ensure userspace receives fork event
       debug("Failed to send fork event!");
@@ -366,6 +409,8 @@ int handle_exit(struct trace_event_raw_sched_process_template *ctx) {
   void *ptr = bpf_map_lookup_elem(&tgid_closure, &tgid);
   if (ptr == NULL && tracexec_config.follow_fork)
     return 0;
+  if (!tracexec_config.follow_fork && !in_traced_cgroup())
+    return 0;
   struct task_struct *current = (void *)bpf_get_current_task();
   int ret = -1;
   if (tracexec_config.follow_fork) {
@@ -406,7 +451,11 @@ int handle_exit(struct trace_event_raw_sched_process_template *ctx) {
   }
   entry->code = exit_code >> 8;
   entry->sig = exit_code & 0xFF;
-  ret = bpf_ringbuf_output(&events, entry, sizeof(*entry), BPF_RB_FORCE_WAKEUP);
+  if (0 != bpf_get_current_comm(entry->comm, sizeof(entry->comm))) {
+    // Failed to read comm
+    entry->comm[0] = '\0';
+  }
+  ret = output_event(entry, sizeof(*entry), BPF_RB_FORCE_WAKEUP);
   if (ret < 0) {
     // TODO: find a better way to ensure userspace receives exit event
     debug("Failed to send exit event!");
@@ -482,7 +531,7 @@ int __always_inline tp_sys_exit_exec(int sysret) {
   event->ret = sysret;
   event->header.type = SYSEXIT_EVENT;
   debug("execve result: %d PID %d\n", sysret, pid);
-  long ret = bpf_ringbuf_output(&events, event, sizeof(struct exec_event), 0);
+  long ret = output_event(event, sizeof(struct exec_event), 0);
   if (ret != 0) {
 #ifdef EBPF_DEBUG
     u64 avail = bpf_ringbuf_query(&events, BPF_RB_AVAIL_DATA);
@@ -792,12 +841,12 @@ static int _read_fd(unsigned int fd_num, struct file **fd_array,
   }
   // debug("open fd: %u -> %u with flags %u", fd_num, entry->path_id,
   //       entry->flags);
-  bpf_ringbuf_output(&events, entry, sizeof(struct fd_event), 0);
+  output_event(entry, sizeof(struct fd_event), 0);
   return 0;
 ptr_err:
   entry->header.flags |= PTR_READ_FAILURE;
   entry->path_id = -1;
-  bpf_ringbuf_output(&events, entry, sizeof(struct fd_event), 0);
+  output_event(entry, sizeof(struct fd_event), 0);
   return 1;
 }
 
@@ -820,6 +869,11 @@ static int read_strings(u32 index, struct reader_context *ctx) {
     event->count[ctx->index] = index;
     return 1;
   }
+  if (index >= ctx->max_entries || ctx->bytes_left == 0) {
+    // There are more entries than the configured limits allow
+    event->header.flags |= ARGV_TRUNCATED;
+    return 1;
+  }
   // Read the str into a temporary buffer
   u32 entry_index = bpf_get_smp_processor_id();
   if (entry_index > tracexec_config.max_num_cpus) {
@@ -835,8 +889,10 @@ static int read_strings(u32 index, struct reader_context *ctx) {
   entry->header.pid = event->header.pid;
   entry->header.eid = event->header.eid;
   entry->header.id = index + ctx->index * event->count[0];
-  s64 bytes_read =
-      bpf_probe_read_user_str(entry->data, sizeof(entry->data), argp);
+  u32 size = ctx->bytes_left;
+  if (size > sizeof(entry->data))
+    size = sizeof(entry->data);
+  s64 bytes_read = bpf_probe_read_user_str(entry->data, size, argp);
   if (bytes_read < 0) {
     debug("failed to read arg %d(addr:%x) from userspace", index, argp);
     entry->header.flags |= STR_READ_FAILURE;
@@ -848,9 +904,13 @@ static int read_strings(u32 index, struct reader_context *ctx) {
     bytes_read = 1;
   } else if (bytes_read == sizeof(entry->data)) {
     entry->header.flags |= POSSIBLE_TRUNCATION;
+  } else if (bytes_read == size) {
+    // The string is possibly cut by the byte limit, we cannot determine
+    event->header.flags |= ARGV_TRUNCATED;
   }
-  ret = bpf_ringbuf_output(
-      &events, entry, sizeof(struct tracexec_event_header) + bytes_read, 0);
+  ctx->bytes_left -= bytes_read;
+  ret = output_event(entry, sizeof(struct tracexec_event_header) + bytes_read,
+                     0);
   if (ret < 0) {
     event->header.flags |= OUTPUT_FAILURE;
   }
@@ -917,6 +977,7 @@ static int read_send_dentry_segment(u32 index, struct path_segment_ctx *ctx) {
       bpf_ringbuf_reserve(&events, sizeof(struct path_segment_event), 0);
   if (event == NULL) {
     ctx->path_event->header.flags |= OUTPUT_FAILURE;
+    __sync_fetch_and_add(&lost_event_counter, 1);
     return ret;
   }
   // Check if we reached mount point or root
@@ -1139,7 +1200,7 @@ static int read_send_path(const struct path *path,
   }
   // Send path event to userspace
   event->segment_count = ctx.base_index;
-  ret = bpf_ringbuf_output(&events, event, sizeof(*event), 0);
+  ret = output_event(event, sizeof(*event), 0);
   if (ret < 0) {
     debug("Failed to output path_event to ringbuf");
     return -1;
@@ -1153,7 +1214,7 @@ loop_err:
   goto err_out;
 err_out:
   event->segment_count = 0;
-  ret = bpf_ringbuf_output(&events, event, sizeof(*event), 0);
+  ret = output_event(event, sizeof(*event), 0);
   if (ret < 0) {
     debug("Failed to output path_event to ringbuf");
     return -1;
@@ -1,6 +1,8 @@
 use std::{
-  collections::HashSet,
+  collections::{hash_map::DefaultHasher, HashMap, HashSet},
+  hash::{Hash, Hasher},
   ops::{Deref, DerefMut},
+  sync::{Arc, Weak},
 };
 
 use arcstr::ArcStr;
@@ -50,3 +52,81 @@ impl DerefMut for StringCache {
     &mut self.cache
   }
 }
+
+/// Shares identical values among the events, e.g. the argv or the environment
+/// of the execs in a build that only differ in a few arguments.
+///
+/// Only weak references are kept, so a value is freed once no event refers to it anymore.
+/// Errors are not shared.
+pub struct ArcCache<T, E> {
+  cache: HashMap<u64, Vec<Weak<Result<T, E>>>>,
+  /// The number of entries after the last sweep of dead entries
+  swept_len: usize,
+}
+
+impl<T: Hash + Eq, E> ArcCache<T, E> {
+  pub fn new() -> Self {
+    Self {
+      cache: HashMap::new(),
+      swept_len: 0,
+    }
+  }
+
+  pub fn get_or_insert(&mut self, value: Result<T, E>) -> Arc<Result<T, E>> {
+    let Ok(inner) = &value else {
+      return Arc::new(value);
+    };
+    let hash = {
+      let mut hasher = DefaultHasher::new();
+      inner.hash(&mut hasher);
+      hasher.finish()
+    };
+    let bucket = self.cache.entry(hash).or_default();
+    bucket.retain(|weak| weak.strong_count() > 0);
+    if let Some(arc) = bucket
+      .iter()
+      .filter_map(Weak::upgrade)
+      .find(|arc| arc.as_ref().as_ref().is_ok_and(|v| v == inner))
+    {
+      return arc;
+    }
+    let arc = Arc::new(value);
+    bucket.push(Arc::downgrade(&arc));
+    if self.cache.len() > 2 * self.swept_len.max(1024) {
+      self.sweep();
+    }
+    arc
+  }
+
+  /// Remove the entries whose values have been freed
+  fn sweep(&mut self) {
+    self.cache.retain(|_, bucket| {
+      bucket.retain(|weak| weak.strong_count() > 0);
+      !bucket.is_empty()
+    });
+    self.swept_len = self.cache.len();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use super::ArcCache;
+
+  #[test]
+  fn arc_cache_shares_ok_values_and_forgets_freed_ones() {
+    let mut cache: ArcCache<String, i32> = ArcCache::new();
+    let a = cache.get_or_insert(Ok("a".to_string()));
+    let b = cache.get_or_insert(Ok("a".to_string()));
+    assert!(Arc::ptr_eq(&a, &b));
+    let e1 = cache.get_or_insert(Err(1));
+    let e2 = cache.get_or_insert(Err(1));
+    assert!(!Arc::ptr_eq(&e1, &e2));
+    let weak = Arc::downgrade(&a);
+    drop((a, b));
+    assert!(weak.upgrade().is_none());
+    let c = cache.get_or_insert(Ok("a".to_string()));
+    assert_eq!(c.as_deref(), Ok("a"));
+  }
+}
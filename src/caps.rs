@@ -0,0 +1,190 @@
+//! The capability sets of the processes, read from `/proc/<pid>/status` at exec.
+
+use std::fmt::Display;
+
+use nix::unistd::Pid;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The names of the capabilities, indexed by their numbers
+const CAP_NAMES: [&str; 41] = [
+  "cap_chown",
+  "cap_dac_override",
+  "cap_dac_read_search",
+  "cap_fowner",
+  "cap_fsetid",
+  "cap_kill",
+  "cap_setgid",
+  "cap_setuid",
+  "cap_setpcap",
+  "cap_linux_immutable",
+  "cap_net_bind_service",
+  "cap_net_broadcast",
+  "cap_net_admin",
+  "cap_net_raw",
+  "cap_ipc_lock",
+  "cap_ipc_owner",
+  "cap_sys_module",
+  "cap_sys_rawio",
+  "cap_sys_chroot",
+  "cap_sys_ptrace",
+  "cap_sys_pacct",
+  "cap_sys_admin",
+  "cap_sys_boot",
+  "cap_sys_nice",
+  "cap_sys_resource",
+  "cap_sys_time",
+  "cap_sys_tty_config",
+  "cap_mknod",
+  "cap_lease",
+  "cap_audit_write",
+  "cap_audit_control",
+  "cap_setfcap",
+  "cap_mac_override",
+  "cap_mac_admin",
+  "cap_syslog",
+  "cap_wake_alarm",
+  "cap_block_suspend",
+  "cap_audit_read",
+  "cap_perfmon",
+  "cap_bpf",
+  "cap_checkpoint_restore",
+];
+
+/// A set of capabilities as a bit mask of their numbers.
+///
+/// It is serialized as the list of the capability names.
+/// Capabilities unknown to tracexec are named `cap_<number>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapSet(pub u64);
+
+impl CapSet {
+  pub fn is_empty(&self) -> bool {
+    self.0 == 0
+  }
+
+  pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+    (0..64)
+      .filter(|bit| self.0 & (1 << bit) != 0)
+      .map(|bit| match CAP_NAMES.get(bit) {
+        Some(name) => (*name).to_owned(),
+        None => format!("cap_{bit}"),
+      })
+  }
+
+  fn from_name(name: &str) -> Option<Self> {
+    let bit = match CAP_NAMES.iter().position(|&n| n == name) {
+      Some(bit) => bit as u32,
+      None => name
+        .strip_prefix("cap_")?
+        .parse()
+        .ok()
+        .filter(|&bit| bit < 64)?,
+    };
+    Some(Self(1 << bit))
+  }
+}
+
+impl Display for CapSet {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (i, name) in self.names().enumerate() {
+      if i > 0 {
+        write!(f, ",")?;
+      }
+      write!(f, "{name}")?;
+    }
+    Ok(())
+  }
+}
+
+impl Serialize for CapSet {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self.names())
+  }
+}
+
+impl<'de> Deserialize<'de> for CapSet {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    names.iter().try_fold(Self::default(), |set, name| {
+      Self::from_name(name)
+        .map(|cap| Self(set.0 | cap.0))
+        .ok_or_else(|| D::Error::custom(format!("Unknown capability {name}")))
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+  pub effective: CapSet,
+  pub permitted: CapSet,
+  /// The capabilities that the process has but its parent does not
+  #[serde(default, skip_serializing_if = "CapSet::is_empty")]
+  pub gained: CapSet,
+}
+
+impl Capabilities {
+  pub fn is_empty(&self) -> bool {
+    self.effective.is_empty() && self.permitted.is_empty()
+  }
+
+  pub fn compare_with_parent(&mut self, parent: &Self) {
+    self.gained = CapSet((self.effective.0 | self.permitted.0) & !parent.permitted.0);
+  }
+}
+
+/// Read the effective and permitted capability sets of a process
+pub fn read_capabilities(pid: Pid) -> color_eyre::Result<Capabilities> {
+  let filename = format!("/proc/{pid}/status");
+  let status = std::fs::read_to_string(filename)?;
+  let set = |key: &str| -> color_eyre::Result<CapSet> {
+    let hex = status
+      .lines()
+      .find_map(|line| line.strip_prefix(key))
+      .ok_or_else(|| color_eyre::eyre::eyre!("No {key} field in /proc/{pid}/status"))?;
+    Ok(CapSet(u64::from_str_radix(hex.trim(), 16)?))
+  };
+  Ok(Capabilities {
+    effective: set("CapEff:")?,
+    permitted: set("CapPrm:")?,
+    gained: CapSet::default(),
+  })
+}
+
+/// Read the capabilities of a process after the exec and compare them with its parent
+pub fn read_exec_capabilities(pid: Pid, ppid: Option<Pid>) -> Option<Capabilities> {
+  let mut caps = read_capabilities(pid).ok()?;
+  if let Some(parent) = ppid.and_then(|ppid| read_capabilities(ppid).ok()) {
+    caps.compare_with_parent(&parent);
+  }
+  Some(caps)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn capability_sets_are_named() {
+    let set = CapSet((1 << 12) | (1 << 21) | (1 << 63));
+    assert_eq!(set.to_string(), "cap_net_admin,cap_sys_admin,cap_63");
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json, r#"["cap_net_admin","cap_sys_admin","cap_63"]"#);
+    assert_eq!(serde_json::from_str::<CapSet>(&json).unwrap(), set);
+  }
+
+  #[test]
+  fn gained_capabilities_are_relative_to_the_parent() {
+    let parent = Capabilities {
+      effective: CapSet(0),
+      permitted: CapSet(1 << 12),
+      gained: CapSet(0),
+    };
+    let mut caps = Capabilities {
+      effective: CapSet((1 << 12) | (1 << 13)),
+      permitted: CapSet((1 << 12) | (1 << 13)),
+      gained: CapSet(0),
+    };
+    caps.compare_with_parent(&parent);
+    assert_eq!(caps.gained, CapSet(1 << 13));
+  }
+}
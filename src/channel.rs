@@ -0,0 +1,350 @@
+//! The channel that carries tracer messages from the tracer to the consumer.
+//!
+//! A tracee that execs in a tight loop could produce messages faster than the consumer
+//! handles them. The channel only queues a bounded number of messages in memory.
+//! The overflow spills to a file in the record format and is read back in order.
+
+use std::{
+  collections::VecDeque,
+  fs::{File, OpenOptions},
+  io::{self, BufRead, BufReader, BufWriter, Write},
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use futures::Stream;
+use tokio::sync::mpsc::{
+  self,
+  error::{SendError, TrySendError},
+};
+use tracing::warn;
+
+use crate::{
+  event::{ProcessStateUpdate, ProcessStateUpdateEvent, TracerMessage},
+  export::RecordEntry,
+  plugin::PluginChain,
+  proc::BaselineInfo,
+};
+
+/// The number of messages queued in memory by default
+pub const DEFAULT_CAPACITY: usize = 16384;
+
+/// Counters of the messages that did not fit in the in-memory queue
+#[derive(Debug, Default)]
+pub struct OverflowStats {
+  spilled: AtomicU64,
+  kept_in_memory: AtomicU64,
+}
+
+impl OverflowStats {
+  /// Number of messages written to the spill file
+  pub fn spilled(&self) -> u64 {
+    self.spilled.load(Ordering::Relaxed)
+  }
+
+  /// Number of overflowing messages that could not be spilled and were kept in memory
+  pub fn kept_in_memory(&self) -> u64 {
+    self.kept_in_memory.load(Ordering::Relaxed)
+  }
+
+  /// Report the overflow on stderr at the end of a session outside of the TUI
+  pub fn report(&self) {
+    let (spilled, kept_in_memory) = (self.spilled(), self.kept_in_memory());
+    if spilled + kept_in_memory > 0 {
+      eprintln!(
+        "Warning: the tracer message queue overflowed, {spilled} messages spilled to disk and {kept_in_memory} kept in memory."
+      );
+    }
+  }
+}
+
+enum Spilled {
+  /// The given number of messages in the spill file
+  Disk(u64),
+  /// A message that is not representable in the record format or failed to spill
+  Memory(TracerMessage),
+}
+
+struct SpillFile {
+  writer: BufWriter<File>,
+  reader: BufReader<File>,
+}
+
+impl SpillFile {
+  /// The file is unlinked right after creation so that it goes away with us.
+  fn create(dir: &std::path::Path) -> io::Result<Self> {
+    let path = dir.join(format!("tracexec-spill-{}.jsonl", std::process::id()));
+    let writer = OpenOptions::new()
+      .write(true)
+      .create_new(true)
+      .open(&path)?;
+    let reader = File::open(&path);
+    std::fs::remove_file(&path)?;
+    Ok(Self {
+      writer: BufWriter::new(writer),
+      reader: BufReader::new(reader?),
+    })
+  }
+}
+
+struct Spill {
+  dir: PathBuf,
+  file: Option<SpillFile>,
+  /// Failed to create or write the spill file. Don't try again.
+  broken: bool,
+  /// The spilled messages in order
+  queue: VecDeque<Spilled>,
+}
+
+impl Spill {
+  fn push(&mut self, msg: TracerMessage, stats: &OverflowStats) {
+    // Only events and exit updates are recorded, see `RecordEntry::from_message`
+    let recorded = matches!(
+      msg,
+      TracerMessage::Event(_)
+        | TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+          update: ProcessStateUpdate::Exit(_),
+          ..
+        })
+    );
+    if !recorded || self.broken {
+      self.queue.push_back(Spilled::Memory(msg));
+      stats.kept_in_memory.fetch_add(1, Ordering::Relaxed);
+      return;
+    }
+    if let Err(e) = self.write(msg.clone()) {
+      warn!(
+        "Failed to spill tracer messages to {}, keeping them in memory: {e}",
+        self.dir.display()
+      );
+      self.broken = true;
+      self.queue.push_back(Spilled::Memory(msg));
+      stats.kept_in_memory.fetch_add(1, Ordering::Relaxed);
+      return;
+    }
+    stats.spilled.fetch_add(1, Ordering::Relaxed);
+    match self.queue.back_mut() {
+      Some(Spilled::Disk(count)) => *count += 1,
+      _ => self.queue.push_back(Spilled::Disk(1)),
+    }
+  }
+
+  fn write(&mut self, msg: TracerMessage) -> io::Result<()> {
+    if self.file.is_none() {
+      warn!(
+        "The tracer message queue is full, spilling messages to {}",
+        self.dir.display()
+      );
+      self.file = Some(SpillFile::create(&self.dir)?);
+    }
+    let file = self.file.as_mut().unwrap();
+    let entry = RecordEntry::from_message(msg).expect("message should be recorded");
+    serde_json::to_writer(&mut file.writer, &entry)?;
+    file.writer.write_all(b"\n")?;
+    Ok(())
+  }
+
+  fn pop(&mut self, baseline: &BaselineInfo) -> Option<TracerMessage> {
+    match self.queue.front_mut()? {
+      Spilled::Memory(_) => {
+        let Some(Spilled::Memory(msg)) = self.queue.pop_front() else {
+          unreachable!()
+        };
+        Some(msg)
+      }
+      Spilled::Disk(count) => {
+        *count -= 1;
+        if *count == 0 {
+          self.queue.pop_front();
+        }
+        let file = self
+          .file
+          .as_mut()
+          .expect("spilled messages should be in the file");
+        let entry = file.writer.flush().and_then(|_| {
+          let mut line = String::new();
+          file.reader.read_line(&mut line)?;
+          Ok(serde_json::from_str::<RecordEntry>(&line)?)
+        });
+        match entry {
+          Ok(entry) => entry.into_message(baseline),
+          Err(e) => Some(TracerMessage::FatalError(format!(
+            "Failed to read spilled tracer messages: {e}"
+          ))),
+        }
+      }
+    }
+  }
+}
+
+struct Shared {
+  spill: Mutex<Spill>,
+  stats: Arc<OverflowStats>,
+}
+
+#[derive(Clone)]
+pub struct MessageSender {
+  tx: mpsc::Sender<TracerMessage>,
+  shared: Arc<Shared>,
+}
+
+pub struct MessageReceiver {
+  rx: mpsc::Receiver<TracerMessage>,
+  shared: Arc<Shared>,
+  baseline: Arc<BaselineInfo>,
+  plugins: Option<PluginChain>,
+  /// The messages that came out of the plugins but are not received yet
+  processed: VecDeque<TracerMessage>,
+}
+
+/// Create a channel that queues at most `capacity` messages in memory
+/// and spills the overflow to a file in `spill_dir`.
+///
+/// The baseline is used to restore the exec events read back from the spill file.
+pub fn channel(
+  capacity: usize,
+  spill_dir: PathBuf,
+  baseline: Arc<BaselineInfo>,
+) -> (MessageSender, MessageReceiver) {
+  let (tx, rx) = mpsc::channel(capacity.max(1));
+  let shared = Arc::new(Shared {
+    spill: Mutex::new(Spill {
+      dir: spill_dir,
+      file: None,
+      broken: false,
+      queue: VecDeque::new(),
+    }),
+    stats: Default::default(),
+  });
+  (
+    MessageSender {
+      tx,
+      shared: shared.clone(),
+    },
+    MessageReceiver {
+      rx,
+      shared,
+      baseline,
+      plugins: None,
+      processed: VecDeque::new(),
+    },
+  )
+}
+
+impl MessageSender {
+  /// Send a message without blocking. It spills if the in-memory queue is full.
+  pub fn send(&self, msg: TracerMessage) -> Result<(), SendError<TracerMessage>> {
+    let mut spill = self.shared.spill.lock().unwrap();
+    // Keep the order: once spilled, the following messages spill until the spill is drained.
+    if spill.queue.is_empty() {
+      match self.tx.try_send(msg) {
+        Ok(()) => return Ok(()),
+        Err(TrySendError::Closed(msg)) => return Err(SendError(msg)),
+        Err(TrySendError::Full(msg)) => spill.push(msg, &self.shared.stats),
+      }
+    } else if self.tx.is_closed() {
+      return Err(SendError(msg));
+    } else {
+      spill.push(msg, &self.shared.stats);
+    }
+    Ok(())
+  }
+}
+
+impl MessageReceiver {
+  pub fn overflow_stats(&self) -> Arc<OverflowStats> {
+    self.shared.stats.clone()
+  }
+
+  /// Pass the received messages through the plugins run by these shell commands.
+  /// See [`crate::plugin`] for how the plugins work.
+  pub fn attach_plugins(&mut self, cmds: &[String], quiet: bool) -> color_eyre::Result<()> {
+    if !cmds.is_empty() {
+      self.plugins = Some(PluginChain::spawn(cmds, quiet, self.baseline.clone())?);
+    }
+    Ok(())
+  }
+
+  /// Receive the next message in the order they were sent, after the plugins if any.
+  ///
+  /// Returns [`None`] when all the senders are dropped and all the messages are received.
+  pub async fn recv(&mut self) -> Option<TracerMessage> {
+    loop {
+      if let Some(msg) = self.processed.pop_front() {
+        return Some(msg);
+      }
+      let msg = self.recv_unprocessed().await?;
+      let Some(plugins) = self.plugins.as_mut() else {
+        return Some(msg);
+      };
+      self.processed.extend(plugins.process(msg).await);
+    }
+  }
+
+  /// Turn the receiver into a [`Stream`] of the messages
+  pub fn into_stream(self) -> impl Stream<Item = TracerMessage> + Send {
+    futures::stream::unfold(self, |mut rx| async move {
+      rx.recv().await.map(|msg| (msg, rx))
+    })
+  }
+
+  async fn recv_unprocessed(&mut self) -> Option<TracerMessage> {
+    {
+      // Under the lock, nothing is sent or spilled so the state is consistent.
+      let mut spill = self.shared.spill.lock().unwrap();
+      // The in-memory queue is older than the spill.
+      if let Ok(msg) = self.rx.try_recv() {
+        return Some(msg);
+      }
+      if let Some(msg) = spill.pop(&self.baseline) {
+        return Some(msg);
+      }
+    }
+    // Nothing spills until the in-memory queue is full, which wakes us up.
+    match self.rx.recv().await {
+      Some(msg) => Some(msg),
+      None => self.shared.spill.lock().unwrap().pop(&self.baseline),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nix::unistd::Pid;
+
+  use super::*;
+  use crate::event::{TracerEvent, TracerEventDetails};
+
+  #[tokio::test]
+  async fn overflow_is_received_in_order() {
+    let baseline = Arc::new(BaselineInfo::new().unwrap());
+    let (tx, mut rx) = channel(2, std::env::temp_dir(), baseline);
+    let mut sent = (0..10)
+      .map(|id| {
+        TracerMessage::Event(TracerEvent {
+          details: TracerEventDetails::TraceeSpawn {
+            pid: Pid::from_raw(id as i32 + 1),
+            process: None,
+          },
+          id,
+        })
+      })
+      .collect::<Vec<_>>();
+    sent.insert(5, TracerMessage::FatalError("not recorded".to_owned()));
+    for msg in sent.iter().cloned() {
+      tx.send(msg).unwrap();
+    }
+    drop(tx);
+    let stats = rx.overflow_stats();
+    assert_eq!(stats.spilled(), 8);
+    assert_eq!(stats.kept_in_memory(), 1);
+    let mut received = vec![];
+    while let Some(msg) = rx.recv().await {
+      received.push(msg);
+    }
+    assert_eq!(received, sent);
+  }
+}
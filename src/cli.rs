@@ -1,15 +1,20 @@
 use std::{
   io::{stderr, stdout, BufWriter},
+  num::NonZeroUsize,
   path::PathBuf,
 };
 
-use args::{DebuggerArgs, PtraceArgs, TuiModeArgs};
+#[cfg(feature = "ebpf")]
+use args::EbpfArgs;
+use args::{DebuggerArgs, ExecHookArgs, PluginArgs, PtraceArgs, TraceNotesArgs, TuiModeArgs};
 use clap::{CommandFactory, Parser, Subcommand};
 use config::Config;
-use options::ExportFormat;
+use options::{ExportFormat, FlushPolicy};
 use tracing::debug;
 
-use crate::printer::PrinterOut;
+#[cfg(feature = "seccomp-notify")]
+use crate::tracer::state::BreakPointPattern;
+use crate::{bench::Backend, printer::PrinterOut, selftest::Fixture};
 
 use self::{
   args::{LogModeArgs, ModifierArgs, TracerEventArgs},
@@ -19,6 +24,7 @@ use self::{
 pub mod args;
 pub mod config;
 pub mod options;
+pub mod run;
 #[cfg(test)]
 mod test;
 pub mod theme;
@@ -59,6 +65,12 @@ pub enum CliCommand {
   Log {
     #[arg(last = true, required = true, help = "command to be executed")]
     cmd: Vec<String>,
+    #[clap(
+      long,
+      value_name = "CMD",
+      help = "Also trace another command concurrently with the same baseline. The output is prefixed with the index of the command. Can be specified multiple times. Quote the command like in a shell"
+    )]
+    also: Vec<String>,
     #[clap(flatten)]
     tracing_args: LogModeArgs,
     #[clap(flatten)]
@@ -67,17 +79,42 @@ pub enum CliCommand {
     ptrace_args: PtraceArgs,
     #[clap(flatten)]
     tracer_event_args: TracerEventArgs,
+    #[clap(flatten)]
+    exec_hook_args: ExecHookArgs,
+    #[clap(flatten)]
+    plugin_args: PluginArgs,
     #[clap(
       short,
       long,
       help = "Output, stderr by default. A single hyphen '-' represents stdout."
     )]
     output: Option<PathBuf>,
+    #[clap(
+      long,
+      value_name = "FILE",
+      help = "Write a JSON summary of the session to this file on exit, including the numbers of processes and execs, failures by errno, wall time and exit code. A single hyphen '-' represents stdout."
+    )]
+    summary: Option<PathBuf>,
   },
   #[clap(about = "Run tracexec in TUI mode, stdin/out/err are redirected to /dev/null by default")]
   Tui {
-    #[arg(last = true, required = true, help = "command to be executed")]
+    #[arg(
+      last = true,
+      help = "command to be executed. Leave it empty to start an empty session and launch commands later from the TUI or by `tracexec ctl run`"
+    )]
     cmd: Vec<String>,
+    #[clap(
+      long,
+      help = "Load the output of `tracexec collect` instead of tracing a command. Only the record format preserves non-exec events",
+      conflicts_with = "cmd"
+    )]
+    load: Option<PathBuf>,
+    #[clap(
+      long,
+      value_name = "FILE",
+      help = "Save the session in the record format when exiting, including the bookmarks. Load it again with --load"
+    )]
+    save: Option<PathBuf>,
     #[clap(flatten)]
     modifier_args: ModifierArgs,
     #[clap(flatten)]
@@ -88,6 +125,10 @@ pub enum CliCommand {
     tui_args: TuiModeArgs,
     #[clap(flatten)]
     debugger_args: DebuggerArgs,
+    #[clap(flatten)]
+    exec_hook_args: ExecHookArgs,
+    #[clap(flatten)]
+    plugin_args: PluginArgs,
   },
   #[clap(about = "Generate shell completions for tracexec")]
   GenerateCompletions {
@@ -106,12 +147,29 @@ pub enum CliCommand {
     format: ExportFormat,
     #[clap(short, long, help = "prettify the output if supported")]
     pretty: bool,
+    #[clap(flatten)]
+    notes_args: TraceNotesArgs,
+    #[clap(flatten)]
+    plugin_args: PluginArgs,
+    #[clap(
+      long,
+      value_name = "URL",
+      help = "Push the spans to this OTLP/HTTP endpoint, e.g. http://localhost:4318, instead of writing them to the output. Only available with the otlp format"
+    )]
+    otlp_endpoint: Option<String>,
     #[clap(
       short,
       long,
       help = "Output, stderr by default. A single hyphen '-' represents stdout."
     )]
     output: Option<PathBuf>,
+    #[clap(
+      long,
+      value_name = "POLICY",
+      default_value_t,
+      help = "When to flush the output: line after every event, block only when the buffer is full, or every-N after every N events"
+    )]
+    flush: FlushPolicy,
     #[clap(
       long,
       help = "Set the terminal foreground process group to tracee. This option is useful when tracexec is used interactively. [default]",
@@ -125,12 +183,129 @@ pub enum CliCommand {
     )]
     no_foreground: bool,
   },
+  #[clap(about = "Trace a command or load a saved trace and print a summary of the exec events")]
+  Stats {
+    #[arg(
+      last = true,
+      required_unless_present = "load",
+      help = "command to be executed"
+    )]
+    cmd: Vec<String>,
+    #[clap(
+      long,
+      help = "Load the output of `tracexec collect` instead of tracing a command",
+      conflicts_with = "cmd"
+    )]
+    load: Option<PathBuf>,
+    #[clap(long, default_value_t = 10, help = "Number of programs to show")]
+    top: usize,
+    #[clap(flatten)]
+    ptrace_args: PtraceArgs,
+    #[clap(
+      short,
+      long,
+      help = "Output, stderr by default. A single hyphen '-' represents stdout."
+    )]
+    output: Option<PathBuf>,
+  },
   #[cfg(feature = "ebpf")]
   #[clap(about = "Experimental ebpf mode")]
   Ebpf {
     #[clap(subcommand)]
     command: EbpfCommand,
   },
+  #[cfg(feature = "seccomp-notify")]
+  #[clap(
+    about = "Experimental mode that supervises execs with seccomp user notification instead of ptrace"
+  )]
+  Notify {
+    #[arg(last = true, required = true, help = "command to be executed")]
+    cmd: Vec<String>,
+    #[clap(
+      long,
+      value_name = "PATTERN",
+      value_parser = BreakPointPattern::from_editable,
+      help = "Make the execs matching this pattern fail with EPERM. This option can be used multiple times. The format is <pattern-type>:<pattern>, where pattern-type can be argv-regex, in-filename or exact-filename. This is not a security boundary because the tracee could change the arguments after they are checked"
+    )]
+    deny: Vec<BreakPointPattern>,
+    #[clap(
+      long,
+      value_name = "FILE",
+      help = "Allow, deny or log the execs according to the rules in this TOML file. Ask rules deny the execs because nobody could be asked in this mode. Send SIGHUP to tracexec to reload the rules"
+    )]
+    rules: Option<PathBuf>,
+    #[clap(flatten)]
+    tracing_args: LogModeArgs,
+    #[clap(flatten)]
+    modifier_args: ModifierArgs,
+    #[clap(
+      short,
+      long,
+      help = "Output, stderr by default. A single hyphen '-' represents stdout."
+    )]
+    output: Option<PathBuf>,
+  },
+  #[clap(about = "Control a running tracexec TUI session")]
+  Ctl {
+    #[clap(subcommand)]
+    command: CtlCommand,
+  },
+  #[clap(
+    about = "Verify that exec events of tricky short-lived processes are captured on this system"
+  )]
+  Selftest {
+    #[clap(flatten)]
+    ptrace_args: PtraceArgs,
+  },
+  #[clap(about = "Run a command with and without tracing and report the overhead of each backend")]
+  Bench {
+    #[arg(last = true, required = true, help = "command to be executed")]
+    cmd: Vec<String>,
+    #[clap(
+      long,
+      value_name = "BACKEND",
+      value_delimiter = ',',
+      help = "Only benchmark these backends. All the backends compiled into tracexec are benchmarked by default"
+    )]
+    backend: Vec<Backend>,
+    #[clap(
+      short = 'n',
+      long,
+      default_value = "5",
+      help = "Run the command this many times with each backend and without tracing"
+    )]
+    runs: NonZeroUsize,
+    #[clap(flatten)]
+    ptrace_args: PtraceArgs,
+  },
+  #[clap(hide = true)]
+  SelftestFixture {
+    fixture: Fixture,
+    #[clap(long, default_value_t = 0)]
+    depth: usize,
+  },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CtlCommand {
+  #[clap(about = "Launch a new root command in the session")]
+  Run {
+    #[arg(
+      long,
+      help = "Path to the control socket of the session. Defaults to $TRACEXEC_CTL_SOCKET"
+    )]
+    socket: Option<PathBuf>,
+    #[arg(last = true, required = true, help = "command to be executed")]
+    cmd: Vec<String>,
+  },
+  #[clap(about = "Reload the rules file of the session")]
+  ReloadRules {
+    #[arg(
+      long,
+      help = "Path to the control socket of the session. Defaults to $TRACEXEC_CTL_SOCKET"
+    )]
+    socket: Option<PathBuf>,
+  },
 }
 
 #[derive(Subcommand, Debug)]
@@ -153,6 +328,8 @@ pub enum EbpfCommand {
     modifier_args: ModifierArgs,
     #[clap(flatten)]
     log_args: LogModeArgs,
+    #[clap(flatten)]
+    ebpf_args: EbpfArgs,
   },
   #[clap(about = "Run tracexec in TUI mode, stdin/out/err are redirected to /dev/null by default")]
   Tui {
@@ -167,6 +344,8 @@ pub enum EbpfCommand {
     tracer_event_args: TracerEventArgs,
     #[clap(flatten)]
     tui_args: TuiModeArgs,
+    #[clap(flatten)]
+    ebpf_args: EbpfArgs,
   },
   #[clap(about = "Collect exec events and export them")]
   Collect {
@@ -181,12 +360,29 @@ pub enum EbpfCommand {
     format: ExportFormat,
     #[clap(short, long, help = "prettify the output if supported")]
     pretty: bool,
+    #[clap(flatten)]
+    notes_args: TraceNotesArgs,
+    #[clap(flatten)]
+    ebpf_args: EbpfArgs,
+    #[clap(
+      long,
+      value_name = "URL",
+      help = "Push the spans to this OTLP/HTTP endpoint, e.g. http://localhost:4318, instead of writing them to the output. Only available with the otlp format"
+    )]
+    otlp_endpoint: Option<String>,
     #[clap(
       short,
       long,
       help = "Output, stderr by default. A single hyphen '-' represents stdout."
     )]
     output: Option<PathBuf>,
+    #[clap(
+      long,
+      value_name = "POLICY",
+      default_value_t,
+      help = "When to flush the output: line after every event, block only when the buffer is full, or every-N after every N events"
+    )]
+    flush: FlushPolicy,
     #[clap(
       long,
       help = "Set the terminal foreground process group to tracee. This option is useful when tracexec is used interactively. [default]",
@@ -206,7 +402,8 @@ impl Cli {
   pub fn get_output(path: Option<PathBuf>, color: Color) -> std::io::Result<Box<PrinterOut>> {
     Ok(match path {
       None => Box::new(stderr()),
-      Some(ref x) if x.as_os_str() == "-" => Box::new(stdout()),
+      // Stdout is line buffered by default, which defeats --flush block when it is a pipe
+      Some(ref x) if x.as_os_str() == "-" => Box::new(BufWriter::new(stdout())),
       Some(path) => {
         let file = std::fs::OpenOptions::new()
           .create(true)
@@ -246,6 +443,19 @@ impl Cli {
           tracing_args.merge_config(c);
         }
       }
+      #[cfg(feature = "seccomp-notify")]
+      CliCommand::Notify {
+        tracing_args,
+        modifier_args,
+        ..
+      } => {
+        if let Some(c) = config.modifier {
+          modifier_args.merge_config(c);
+        }
+        if let Some(c) = config.log {
+          tracing_args.merge_config(c);
+        }
+      }
       CliCommand::Tui {
         modifier_args,
         ptrace_args,
@@ -287,6 +497,13 @@ impl Cli {
           }
         }
       }
+      CliCommand::Selftest { ptrace_args }
+      | CliCommand::Stats { ptrace_args, .. }
+      | CliCommand::Bench { ptrace_args, .. } => {
+        if let Some(c) = config.ptrace {
+          ptrace_args.merge_config(c);
+        }
+      }
       _ => (),
     }
   }
@@ -1,23 +1,37 @@
-use std::{borrow::Cow, num::ParseFloatError};
+use std::{
+  borrow::Cow,
+  num::{NonZeroUsize, ParseFloatError},
+  path::PathBuf,
+  sync::Arc,
+};
 
-use clap::{Args, ValueEnum};
+use clap::{ArgAction, Args, ValueEnum};
 use color_eyre::eyre::bail;
 use enumflags2::BitFlags;
 
 use crate::{
+  channel::{self, MessageReceiver, MessageSender},
   cli::config::{ColorLevel, EnvDisplay, FileDescriptorDisplay},
   event::TracerEventDetailsKind,
-  tracer::state::BreakPoint,
+  format_template::FormatTemplate,
+  proc::BaselineInfo,
+  tracer::state::{BreakPoint, BreakPointPattern, BreakPointStop, BreakPointType},
   tui::app::AppLayout,
 };
 
+#[cfg(feature = "ebpf")]
+use crate::bpf::interface::{ARGC_MAX, ARG_MAX};
+
 #[cfg(feature = "seccomp-bpf")]
 use super::options::SeccompBpf;
 use super::{
   config::{
     DebuggerConfig, ExitHandling, LogModeConfig, ModifierConfig, PtraceConfig, TuiModeConfig,
   },
-  options::ActivePane,
+  options::{
+    ActivePane, ColorBy, EscapeStyle, FlushPolicy, HashAlgorithm, LogFormat, MaxEventsAction,
+    Namespace, PauseMode, Redirect, TraceeStdio, TrappedSyscall,
+  },
 };
 
 #[derive(Args, Debug, Default, Clone)]
@@ -27,15 +41,150 @@ pub struct PtraceArgs {
   pub seccomp_bpf: SeccompBpf,
   #[clap(
     long,
-    help = "Delay between polling, in microseconds. The default is 500 when seccomp-bpf is enabled, otherwise 1."
+    help = "Poll for events of tracees with this delay in microseconds. By default, tracexec sleeps until tracees change state, which does not use CPU while they are idle."
   )]
   pub tracer_delay: Option<u64>,
+  #[clap(
+    long,
+    help = "Record signals delivered to tracees. This could be noisy.",
+    default_value_t = false
+  )]
+  pub trace_signals: bool,
+  #[clap(
+    long,
+    help = "Only trace the execs of the root process itself and do not follow its children. seccomp-bpf is disabled because the untraced children could not exec with it.",
+    default_value_t = false
+  )]
+  pub no_follow_forks: bool,
+  #[clap(
+    long,
+    value_delimiter = ',',
+    help = "Start the command in new namespaces, e.g. `--unshare net` to verify that a build does not access the network. Unprivileged users need to add the user namespace, e.g. `--unshare user,net`"
+  )]
+  pub unshare: Vec<Namespace>,
+  #[clap(
+    long,
+    value_delimiter = ',',
+    help = "Also trap these syscalls to keep track of the working directory, root directory and user ids of the tracees. With seccomp-bpf, only these syscalls and the execs stop the tracees"
+  )]
+  pub trap_syscalls: Vec<TrappedSyscall>,
+  #[clap(
+    long = "env",
+    value_name = "KEY=VALUE",
+    value_parser = key_value_parser,
+    help = "Set an environment variable for the command. Can be specified multiple times"
+  )]
+  pub set_env: Vec<(String, String)>,
+  #[clap(
+    long,
+    value_name = "KEY",
+    help = "Remove an environment variable for the command. Can be specified multiple times"
+  )]
+  pub unset_env: Vec<String>,
+  #[clap(
+    long,
+    value_name = "DIR",
+    help = "Start the command in this directory instead of the current directory of tracexec"
+  )]
+  pub tracee_cwd: Option<PathBuf>,
+  #[clap(
+    long,
+    value_name = "FILE",
+    help = "Allow, deny, ask for or log the execs according to the rules in this TOML file. Send SIGHUP to tracexec to reload the rules"
+  )]
+  pub rules: Option<PathBuf>,
+}
+
+#[cfg(feature = "ebpf")]
+#[derive(Args, Debug, Default, Clone)]
+pub struct EbpfArgs {
+  #[clap(
+    long,
+    conflicts_with = "cmd",
+    help = "Only trace the processes in this cgroup and its descendants, e.g. /system.slice/docker.service. The path is relative to the cgroup v2 mount point. This option is only available for system-wide tracing"
+  )]
+  pub cgroup: Option<PathBuf>,
+  #[clap(
+    long,
+    value_name = "N",
+    value_parser = clap::value_parser!(u32).range(1..=ARGC_MAX as i64),
+    help = "Capture at most this many argv entries per exec. The execs with more arguments are marked as truncated. [default: 233017]"
+  )]
+  pub max_argv_entries: Option<u32>,
+  #[clap(
+    long,
+    value_name = "BYTES",
+    value_parser = clap::value_parser!(u32).range(1..=ARG_MAX as i64),
+    help = "Capture at most this many bytes of argv per exec, including the NUL terminators. The execs with longer arguments are marked as truncated. [default: 2097152]"
+  )]
+  pub max_argv_bytes: Option<u32>,
+  #[clap(
+    long,
+    value_name = "BYTES",
+    value_parser = ringbuf_size_parser,
+    help = "Size of the ring buffer that carries the events from the kernel. It must be a power of 2 and a multiple of 4096. Increase it if events are lost under load. [default: 268435456]"
+  )]
+  pub ebpf_ringbuf_size: Option<u32>,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct TraceNotesArgs {
+  #[clap(long, help = "Attach a free-form note to the saved trace")]
+  pub note: Option<String>,
+  #[clap(
+    long,
+    value_name = "KEY=VALUE",
+    value_parser = key_value_parser,
+    help = "Attach metadata to the saved trace. Can be specified multiple times"
+  )]
+  pub meta: Vec<(String, String)>,
+}
+
+fn redirect_parser(s: &str) -> Result<Redirect, String> {
+  match s {
+    "" => Err("Expected a file or `inherit`".to_owned()),
+    "inherit" => Ok(Redirect::Inherit),
+    path => Ok(Redirect::File(path.into())),
+  }
+}
+
+/// The TUI is drawn on stderr so the tracee could not share it
+fn tui_stderr_parser(s: &str) -> Result<Redirect, String> {
+  match redirect_parser(s)? {
+    Redirect::Inherit => {
+      Err("The TUI is drawn on stderr. Redirect it to a file instead".to_owned())
+    }
+    redirect => Ok(redirect),
+  }
+}
+
+#[cfg(feature = "ebpf")]
+fn ringbuf_size_parser(s: &str) -> Result<u32, String> {
+  let size: u32 = s.parse().map_err(|e| format!("{e}"))?;
+  if !size.is_power_of_two() || size % 4096 != 0 {
+    return Err("The size must be a power of 2 and a multiple of 4096".to_owned());
+  }
+  Ok(size)
+}
+
+fn key_value_parser(s: &str) -> Result<(String, String), String> {
+  match s.split_once('=') {
+    Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+    _ => Err(format!("Expected KEY=VALUE, got {s:?}")),
+  }
 }
 
 #[derive(Args, Debug, Default, Clone)]
 pub struct ModifierArgs {
   #[clap(long, help = "Only show successful calls", default_value_t = false)]
   pub successful_only: bool,
+  #[clap(
+    long,
+    help = "Only show failed calls",
+    default_value_t = false,
+    conflicts_with = "successful_only"
+  )]
+  pub failed_only: bool,
   #[clap(
     long,
     help = "[Experimental] Try to reproduce file descriptors in commandline. This might result in an unexecutable cmdline if pipes, sockets, etc. are involved.",
@@ -57,6 +206,109 @@ pub struct ModifierArgs {
     conflicts_with = "resolve_proc_self_exe"
   )]
   pub no_resolve_proc_self_exe: bool,
+  #[clap(
+    long,
+    help = "How to escape non-printable characters in argv and env [default: rust]"
+  )]
+  pub escape_style: Option<EscapeStyle>,
+  #[clap(flatten)]
+  pub env_filter: EnvFilterArgs,
+  #[clap(flatten)]
+  pub redact: RedactArgs,
+  #[clap(
+    long,
+    value_name = "FROM=TO",
+    value_parser = key_value_parser,
+    help = "Rewrite the path prefix FROM to TO in the displayed filenames and argv, e.g. /nix/store=/store. Can be specified multiple times"
+  )]
+  pub map_path: Vec<(String, String)>,
+  #[clap(
+    long,
+    value_name = "LEN",
+    help = "Truncate the displayed arguments that are longer than this many characters"
+  )]
+  pub max_arg_len: Option<usize>,
+  #[clap(
+    long,
+    value_name = "N",
+    help = "Only display the first N arguments of argv. The details popup of TUI always shows all of them"
+  )]
+  pub max_args: Option<usize>,
+  #[clap(
+    long,
+    value_name = "ALGORITHM",
+    help = "Hash each distinct executed binary and include the hash in the exec events. Not available in eBPF mode"
+  )]
+  pub hash_exe: Option<HashAlgorithm>,
+  #[clap(
+    long,
+    help = "Only show the execs of setuid/setgid binaries or binaries with file capabilities. Not available in eBPF mode",
+    conflicts_with = "failed_only"
+  )]
+  pub privileged_only: bool,
+  #[clap(
+    long,
+    help = "Highlight the suspicious execs, e.g. binaries in temporary directories, deleted binaries, code fed to interpreters by -c/-e and LD_PRELOAD. Each finding is tagged with a rule ID"
+  )]
+  pub audit: bool,
+  #[clap(
+    long,
+    value_name = "N",
+    help = "Stop tracing after N exec events are collected, to bound the captures of busy or system-wide traces"
+  )]
+  pub max_events: Option<NonZeroUsize>,
+  #[clap(
+    long,
+    value_name = "ACTION",
+    default_value_t,
+    requires = "max_events",
+    help = "What to do when --max-events is reached: detach from the tracees and wait for the command to exit, or kill the tracees. detach is not compatible with seccomp-bpf"
+  )]
+  pub on_max_events: MaxEventsAction,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct RedactArgs {
+  #[clap(
+    long,
+    value_name = "PATTERN",
+    value_delimiter = ',',
+    help = "Redact the values of environment variables whose names match these patterns, in addition to *_TOKEN, *_SECRET and AWS_*. A * in the pattern matches any characters"
+  )]
+  pub redact_env: Vec<String>,
+  #[clap(
+    long,
+    value_name = "REGEX",
+    help = "Redact the matches of this regex in argv and env values. Can be specified multiple times"
+  )]
+  pub redact_regex: Vec<String>,
+  #[clap(long, help = "Do not redact secrets in argv and env")]
+  pub no_redact: bool,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct EnvFilterArgs {
+  #[clap(
+    long,
+    value_name = "VAR",
+    value_delimiter = ',',
+    help = "Only show these environment variables in env and env diff. Can be specified multiple times"
+  )]
+  pub show_env_vars: Vec<String>,
+  #[clap(
+    long,
+    value_name = "VAR",
+    value_delimiter = ',',
+    help = "Hide these environment variables from env and env diff. Can be specified multiple times"
+  )]
+  pub hide_env_vars: Vec<String>,
+}
+
+impl EnvFilterArgs {
+  pub fn is_shown(&self, key: &str) -> bool {
+    (self.show_env_vars.is_empty() || self.show_env_vars.iter().any(|var| var == key))
+      && !self.hide_env_vars.iter().any(|var| var == key)
+  }
 }
 
 impl PtraceArgs {
@@ -68,6 +320,17 @@ impl PtraceArgs {
         self.seccomp_bpf = setting;
       }
     }
+    self.trace_signals = self.trace_signals || config.trace_signals.unwrap_or_default();
+    self.no_follow_forks = self.no_follow_forks || config.follow_forks == Some(false);
+    if self.unshare.is_empty() {
+      self.unshare = config.unshare.unwrap_or_default();
+    }
+    if self.trap_syscalls.is_empty() {
+      self.trap_syscalls = config.trap_syscalls.unwrap_or_default();
+    }
+    if self.rules.is_none() {
+      self.rules = config.rules;
+    }
   }
 }
 
@@ -82,15 +345,46 @@ impl ModifierArgs {
     self
   }
 
+  /// Whether the tracees are detached when `--max-events` is reached
+  pub fn detaches_on_max_events(&self) -> bool {
+    self.max_events.is_some() && self.on_max_events == MaxEventsAction::Detach
+  }
+
   pub fn merge_config(&mut self, config: ModifierConfig) {
     // false by default flags
-    self.successful_only = self.successful_only || config.successful_only.unwrap_or_default();
+    if !self.failed_only {
+      self.successful_only = self.successful_only || config.successful_only.unwrap_or_default();
+    }
+    if !self.successful_only {
+      self.failed_only = self.failed_only || config.failed_only.unwrap_or_default();
+    }
     self.fd_in_cmdline |= config.fd_in_cmdline.unwrap_or_default();
     self.stdio_in_cmdline |= config.stdio_in_cmdline.unwrap_or_default();
     // flags that have negation counterparts
     if (!self.no_resolve_proc_self_exe) && (!self.resolve_proc_self_exe) {
       self.resolve_proc_self_exe = config.resolve_proc_self_exe.unwrap_or_default();
     }
+    if self.escape_style.is_none() {
+      self.escape_style = config.escape_style;
+    }
+    if self.env_filter.show_env_vars.is_empty() {
+      self.env_filter.show_env_vars = config.show_env_vars.unwrap_or_default();
+    }
+    if self.env_filter.hide_env_vars.is_empty() {
+      self.env_filter.hide_env_vars = config.hide_env_vars.unwrap_or_default();
+    }
+    self.redact.no_redact = self.redact.no_redact || config.redact == Some(false);
+    self
+      .redact
+      .redact_env
+      .extend(config.redact_env.unwrap_or_default());
+    self
+      .redact
+      .redact_regex
+      .extend(config.redact_regex.unwrap_or_default());
+    self.map_path.extend(config.map_path.unwrap_or_default());
+    self.max_arg_len = self.max_arg_len.or(config.max_arg_len);
+    self.max_args = self.max_args.or(config.max_args);
   }
 }
 
@@ -108,7 +402,7 @@ pub struct TracerEventArgs {
     long,
     help = "Set the default filter for events.",
     value_parser = tracer_event_filter_parser,
-    default_value = "warning,error,exec,tracee-exit"
+    default_value = "warning,error,exec,tracee-exit,daemonize"
   )]
   pub filter: BitFlags<TracerEventDetailsKind>,
   #[clap(
@@ -126,6 +420,16 @@ pub struct TracerEventArgs {
     default_value_t = BitFlags::empty()
   )]
   pub filter_exclude: BitFlags<TracerEventDetailsKind>,
+  #[clap(
+    long,
+    help = "Set the number of tracer messages queued in memory before the overflow spills to disk (16384 by default)"
+  )]
+  pub queue_size: Option<usize>,
+  #[clap(
+    long,
+    help = "Set the directory to spill the overflowing tracer messages to. Defaults to the temporary directory"
+  )]
+  pub spill_dir: Option<PathBuf>,
 }
 
 fn tracer_event_filter_parser(filter: &str) -> Result<BitFlags<TracerEventDetailsKind>, String> {
@@ -153,9 +457,20 @@ impl TracerEventArgs {
       filter: Default::default(),
       filter_include: Default::default(),
       filter_exclude: Default::default(),
+      queue_size: None,
+      spill_dir: None,
     }
   }
 
+  /// Create the channel of tracer messages
+  pub fn channel(&self, baseline: Arc<BaselineInfo>) -> (MessageSender, MessageReceiver) {
+    channel::channel(
+      self.queue_size.unwrap_or(channel::DEFAULT_CAPACITY),
+      self.spill_dir.clone().unwrap_or_else(std::env::temp_dir),
+      baseline,
+    )
+  }
+
   pub fn filter(&self) -> color_eyre::Result<BitFlags<TracerEventDetailsKind>> {
     let default_filter = if self.show_all_events {
       BitFlags::all()
@@ -213,6 +528,11 @@ pub struct LogModeArgs {
     conflicts_with = "foreground"
   )]
   pub no_foreground: bool,
+  #[clap(
+    long,
+    help = "Detach from the tracees instead of forwarding the signal to the command when tracexec is interrupted by SIGINT or SIGTERM, so that they keep running. seccomp-bpf is disabled because the detached tracees could not exec with it"
+  )]
+  pub detach_on_exit: bool,
   #[clap(
     long,
     help = "Diff file descriptors with the original std{in/out/err}",
@@ -264,6 +584,66 @@ pub struct LogModeArgs {
   pub show_comm: bool,
   #[clap(long, help = "Do not show comm", conflicts_with = "show_comm")]
   pub no_show_comm: bool,
+  #[clap(
+    long,
+    help = "Show the parent pid of the process",
+    conflicts_with = "no_show_ppid"
+  )]
+  pub show_ppid: bool,
+  #[clap(
+    long,
+    help = "Do not show the parent pid of the process",
+    conflicts_with = "show_ppid"
+  )]
+  pub no_show_ppid: bool,
+  #[clap(
+    long,
+    help = "Show the real and effective uid and gid of the process after the exec. Highlighted if they differ, e.g. after the exec of a setuid binary",
+    conflicts_with = "no_show_creds"
+  )]
+  pub show_creds: bool,
+  #[clap(
+    long,
+    help = "Do not show the uid and gid of the process",
+    conflicts_with = "show_creds"
+  )]
+  pub no_show_creds: bool,
+  #[clap(
+    long,
+    help = "Show the effective and permitted capabilities of the process after the exec. The capabilities that its parent does not have are highlighted",
+    conflicts_with = "no_show_caps"
+  )]
+  pub show_caps: bool,
+  #[clap(
+    long,
+    help = "Do not show the capabilities of the process",
+    conflicts_with = "show_caps"
+  )]
+  pub no_show_caps: bool,
+  #[clap(
+    long,
+    help = "Show the cgroup of the process, e.g. the systemd service or the container scope",
+    conflicts_with = "no_show_cgroup"
+  )]
+  pub show_cgroup: bool,
+  #[clap(
+    long,
+    help = "Do not show the cgroup of the process",
+    conflicts_with = "show_cgroup"
+  )]
+  pub no_show_cgroup: bool,
+  #[clap(
+    long,
+    help = "Show the SELinux context or the AppArmor profile of the process and highlight the transitions at exec",
+    conflicts_with = "no_show_security_label"
+  )]
+  pub show_security_label: bool,
+  #[clap(
+    long,
+    help = "Do not show the security label of the process",
+    conflicts_with = "show_security_label"
+  )]
+  pub no_show_security_label: bool,
   #[clap(long, help = "Show argv", conflicts_with = "no_show_argv")]
   pub show_argv: bool,
   #[clap(long, help = "Do not show argv", conflicts_with = "show_argv")]
@@ -272,10 +652,34 @@ pub struct LogModeArgs {
   pub show_filename: bool,
   #[clap(long, help = "Do not show filename", conflicts_with = "show_filename")]
   pub no_show_filename: bool,
+  #[clap(
+    long,
+    help = "Show the resolved path of the executable after a successful exec. Not available in eBPF mode",
+    conflicts_with = "no_show_exe"
+  )]
+  pub show_exe: bool,
+  #[clap(
+    long,
+    help = "Do not show the resolved path of the executable",
+    conflicts_with = "show_exe"
+  )]
+  pub no_show_exe: bool,
   #[clap(long, help = "Show cwd", conflicts_with = "no_show_cwd")]
   pub show_cwd: bool,
   #[clap(long, help = "Do not show cwd", conflicts_with = "show_cwd")]
   pub no_show_cwd: bool,
+  #[clap(
+    long,
+    help = "Show exit status of traced processes",
+    conflicts_with = "no_show_exit"
+  )]
+  pub show_exit: bool,
+  #[clap(
+    long,
+    help = "Do not show exit status of traced processes",
+    conflicts_with = "show_exit"
+  )]
+  pub no_show_exit: bool,
   #[clap(long, help = "Decode errno values", conflicts_with = "no_decode_errno")]
   pub decode_errno: bool,
   #[clap(
@@ -285,9 +689,82 @@ pub struct LogModeArgs {
   )]
   pub no_decode_errno: bool,
   // END ugly
+  #[clap(
+    long,
+    value_name = "FILE",
+    default_value = "inherit",
+    value_parser = redirect_parser,
+    help = "Redirect the stdin of the command from this file, e.g. /dev/null. `inherit` shares it with tracexec"
+  )]
+  pub stdin: Redirect,
+  #[clap(
+    long,
+    value_name = "FILE",
+    default_value = "inherit",
+    value_parser = redirect_parser,
+    help = "Redirect the stdout of the command to this file, e.g. /dev/null. `inherit` shares it with tracexec"
+  )]
+  pub stdout: Redirect,
+  #[clap(
+    long,
+    value_name = "FILE",
+    default_value = "inherit",
+    value_parser = redirect_parser,
+    help = "Redirect the stderr of the command to this file so that it does not interleave with the trace. `inherit` shares it with tracexec"
+  )]
+  pub stderr: Redirect,
+  #[clap(
+    short,
+    long,
+    action = ArgAction::Count,
+    conflicts_with = "quiet",
+    help = "Print more details of the execs. -v shows cwd, exit status and interpreter, -vv also shows the full environment and file descriptors. The options for individual details take precedence"
+  )]
+  pub verbose: u8,
+  #[clap(
+    short,
+    long,
+    conflicts_with = "verbose",
+    help = "Only print the failed execs without their environment and file descriptors. The options for individual details take precedence"
+  )]
+  pub quiet: bool,
+  #[clap(
+    long,
+    help = "Print identical execs (same filename, argv, environment and result) only once and summarize how many times they are repeated at the end"
+  )]
+  pub dedup: bool,
+  #[clap(
+    long,
+    value_name = "FORMAT",
+    default_value_t,
+    help = "The format of the printed events"
+  )]
+  pub format: LogFormat,
+  #[clap(
+    long,
+    value_name = "TEMPLATE",
+    conflicts_with = "format",
+    help = "Print each exec as a line rendered from this template, e.g. '{pid} {comm} {filename} {argv}'. The placeholders are {time}, {root}, {pid}, {ppid}, {comm}, {filename}, {argv}, {cwd}, {cgroup}, {exe}, {hash}, {result} and {errno}. Use {{ and }} for literal braces"
+  )]
+  pub format_template: Option<FormatTemplate>,
+  #[clap(
+    long,
+    value_name = "POLICY",
+    default_value_t,
+    help = "When to flush the output: line after every event, block only when the buffer is full, or every-N after every N events"
+  )]
+  pub flush: FlushPolicy,
 }
 
 impl LogModeArgs {
+  pub fn tracee_stdio(&self) -> TraceeStdio {
+    TraceeStdio {
+      stdin: self.stdin.clone(),
+      stdout: self.stdout.clone(),
+      stderr: self.stderr.clone(),
+    }
+  }
+
   pub fn foreground(&self) -> bool {
     match (self.foreground, self.no_foreground) {
       (false, true) => false,
@@ -316,9 +793,17 @@ impl LogModeArgs {
     fallback!(show_interpreter);
     fallback!(foreground);
     fallback!(show_comm);
+    fallback!(show_ppid);
+    fallback!(show_creds);
+    fallback!(show_caps);
+    fallback!(show_cgroup);
+    fallback!(show_security_label);
     fallback!(show_filename);
+    fallback!(show_exe);
     fallback!(show_cwd);
+    fallback!(show_exit);
     fallback!(decode_errno);
+    self.detach_on_exit |= config.detach_on_exit.unwrap_or_default();
     match config.fd_display {
       Some(FileDescriptorDisplay::Show) => {
         if (!self.no_show_fd) && (!self.diff_fd) {
@@ -386,19 +871,41 @@ pub struct TuiModeArgs {
     help = "Allocate a pseudo terminal and show it alongside the TUI"
   )]
   pub tty: bool,
+  #[clap(
+    long,
+    value_name = "FILE",
+    value_parser = redirect_parser,
+    conflicts_with = "tty",
+    help = "Without --tty, connect the stdout of the command to this file instead of discarding it. `inherit` passes it through to the stdout of tracexec"
+  )]
+  pub stdout: Option<Redirect>,
+  #[clap(
+    long,
+    value_name = "FILE",
+    value_parser = tui_stderr_parser,
+    conflicts_with = "tty",
+    help = "Without --tty, connect the stderr of the command to this file instead of discarding it"
+  )]
+  pub stderr: Option<Redirect>,
   #[clap(long, short, help = "Keep the event list scrolled to the bottom")]
   pub follow: bool,
   #[clap(
     long,
     help = "Instead of waiting for the root child to exit, terminate when the TUI exits",
-    conflicts_with = "kill_on_exit"
+    conflicts_with_all = ["kill_on_exit", "on_exit"]
   )]
   pub terminate_on_exit: bool,
   #[clap(
     long,
-    help = "Instead of waiting for the root child to exit, kill when the TUI exits"
+    help = "Instead of waiting for the root child to exit, kill when the TUI exits",
+    conflicts_with = "on_exit"
   )]
   pub kill_on_exit: bool,
+  #[clap(
+    long,
+    help = "What to do with the traced processes when the TUI exits. detach is not compatible with seccomp-bpf"
+  )]
+  pub on_exit: Option<ExitHandling>,
   #[clap(
     long,
     short = 'A',
@@ -413,6 +920,28 @@ pub struct TuiModeArgs {
     requires = "tty"
   )]
   pub layout: Option<AppLayout>,
+  #[clap(
+    long,
+    help = "Switch the active pane to the terminal when the tracee waits for input and back to the events when the terminal is quiet",
+    requires = "tty"
+  )]
+  pub auto_switch_pane: bool,
+  #[clap(
+    long,
+    help = "Set the number of lines kept in the scrollback buffer of the pseudo terminal (1000 by default)",
+    requires = "tty"
+  )]
+  pub scrollback: Option<usize>,
+  #[clap(
+    long,
+    help = "What to do with new events while the event list is paused (buffer by default)"
+  )]
+  pub pause_mode: Option<PauseMode>,
+  #[clap(
+    long,
+    help = "How to color the events in the event list (theme by default)"
+  )]
+  pub color_by: Option<ColorBy>,
   #[clap(
     long,
     short = 'F',
@@ -437,6 +966,49 @@ pub struct DebuggerArgs {
     help = "Add a new breakpoint to the tracer. This option can be used multiple times. The format is <syscall-stop>:<pattern-type>:<pattern>, where syscall-stop can be sysenter or sysexit, pattern-type can be argv-regex, in-filename or exact-filename. For example, sysexit:in-filename:/bash",
   )]
   pub breakpoints: Vec<BreakPoint>,
+  #[clap(
+    long,
+    value_name = "PATTERN",
+    value_parser = ask_parser,
+    help = "Ask for approval in a dialog before the execs matching this pattern proceed. A denied exec fails with EPERM. This option can be used multiple times. The format is <pattern-type>:<pattern>, where pattern-type can be argv-regex, in-filename or exact-filename. For example, in-filename:/curl"
+  )]
+  pub ask: Vec<BreakPoint>,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct ExecHookArgs {
+  #[clap(
+    long,
+    value_name = "CMD",
+    help = "Run this shell command for every exec event. The event is written to its stdin as JSON and its details are available in the TRACEXEC_* environment variables, e.g. TRACEXEC_PID, TRACEXEC_FILENAME and TRACEXEC_RESULT"
+  )]
+  pub exec_hook: Option<String>,
+  #[clap(
+    long,
+    value_name = "PATTERN",
+    value_parser = BreakPointPattern::from_editable,
+    requires = "exec_hook",
+    help = "Only run the exec hook for the execs matching this pattern. This option can be used multiple times. The format is <pattern-type>:<pattern>, where pattern-type can be argv-regex, in-filename or exact-filename"
+  )]
+  pub exec_hook_filter: Vec<BreakPointPattern>,
+  #[clap(
+    long,
+    value_name = "N",
+    requires = "exec_hook",
+    value_parser = clap::value_parser!(u32).range(1..),
+    help = "Run at most this many exec hooks at the same time. The hook is skipped with a warning for the execs past the limit (16 by default)"
+  )]
+  pub exec_hook_jobs: Option<u32>,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct PluginArgs {
+  #[clap(
+    long,
+    value_name = "CMD",
+    help = "Pass the events through this shell command before they are shown or exported. The command reads the events as JSON lines in the record format and answers each line with a JSON array of the events that replace it. This option can be used multiple times to chain plugins"
+  )]
+  pub plugin: Vec<String>,
 }
 
 impl TuiModeArgs {
@@ -444,13 +1016,37 @@ impl TuiModeArgs {
     self.active_pane = self.active_pane.or(config.active_pane);
     self.layout = self.layout.or(config.layout);
     self.frame_rate = self.frame_rate.or(config.frame_rate);
+    self.scrollback = self.scrollback.or(config.scrollback);
+    self.pause_mode = self.pause_mode.or(config.pause_mode);
+    self.color_by = self.color_by.or(config.color_by);
     self.follow |= config.follow.unwrap_or_default();
+    self.auto_switch_pane |= config.auto_switch_pane.unwrap_or_default();
     if (!self.terminate_on_exit) && (!self.kill_on_exit) {
-      match config.exit_handling {
-        Some(ExitHandling::Kill) => self.kill_on_exit = true,
-        Some(ExitHandling::Terminate) => self.terminate_on_exit = true,
-        _ => (),
-      }
+      self.on_exit = self.on_exit.or(config.exit_handling);
+    }
+  }
+
+  /// The standard streams of the root tracees. Without a pseudo terminal,
+  /// stdin is always `/dev/null` and the output is discarded unless it is redirected.
+  pub fn tracee_stdio(&self) -> TraceeStdio {
+    if self.tty {
+      return TraceeStdio::default();
+    }
+    let dev_null = || Redirect::File("/dev/null".into());
+    TraceeStdio {
+      stdin: dev_null(),
+      stdout: self.stdout.clone().unwrap_or_else(dev_null),
+      stderr: self.stderr.clone().unwrap_or_else(dev_null),
+    }
+  }
+
+  pub fn exit_handling(&self) -> ExitHandling {
+    if self.kill_on_exit {
+      ExitHandling::Kill
+    } else if self.terminate_on_exit {
+      ExitHandling::Terminate
+    } else {
+      self.on_exit.unwrap_or_default()
     }
   }
 }
@@ -478,6 +1074,15 @@ fn breakpoint_parser(s: &str) -> Result<BreakPoint, Cow<'static, str>> {
   BreakPoint::try_from(s)
 }
 
+fn ask_parser(s: &str) -> Result<BreakPoint, String> {
+  Ok(BreakPoint {
+    pattern: BreakPointPattern::from_editable(s)?,
+    ty: BreakPointType::Ask,
+    activated: true,
+    stop: BreakPointStop::SyscallEnter,
+  })
+}
+
 #[derive(thiserror::Error, Debug)]
 enum ParseFrameRateError {
   #[error("Failed to parse frame rate {0} as a floating point number")]
@@ -1,5 +1,6 @@
-use std::{io, path::PathBuf};
+use std::{collections::BTreeMap, io, path::PathBuf};
 
+use clap::ValueEnum;
 use directories::ProjectDirs;
 use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
@@ -7,7 +8,9 @@ use tracing::warn;
 
 use crate::tui::app::AppLayout;
 
-use super::options::{ActivePane, SeccompBpf};
+use super::options::{
+  ActivePane, ColorBy, EscapeStyle, Namespace, PauseMode, SeccompBpf, TrappedSyscall,
+};
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -56,14 +59,29 @@ impl Config {
 pub struct ModifierConfig {
   pub seccomp_bpf: Option<SeccompBpf>,
   pub successful_only: Option<bool>,
+  pub failed_only: Option<bool>,
   pub fd_in_cmdline: Option<bool>,
   pub stdio_in_cmdline: Option<bool>,
   pub resolve_proc_self_exe: Option<bool>,
+  pub escape_style: Option<EscapeStyle>,
+  pub show_env_vars: Option<Vec<String>>,
+  pub hide_env_vars: Option<Vec<String>>,
+  pub redact: Option<bool>,
+  pub redact_env: Option<Vec<String>>,
+  pub redact_regex: Option<Vec<String>>,
+  pub map_path: Option<BTreeMap<String, String>>,
+  pub max_arg_len: Option<usize>,
+  pub max_args: Option<usize>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct PtraceConfig {
   pub seccomp_bpf: Option<SeccompBpf>,
+  pub trace_signals: Option<bool>,
+  pub follow_forks: Option<bool>,
+  pub unshare: Option<Vec<Namespace>>,
+  pub trap_syscalls: Option<Vec<TrappedSyscall>>,
+  pub rules: Option<PathBuf>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
@@ -72,8 +90,12 @@ pub struct TuiModeConfig {
   pub exit_handling: Option<ExitHandling>,
   pub active_pane: Option<ActivePane>,
   pub layout: Option<AppLayout>,
+  pub auto_switch_pane: Option<bool>,
   #[serde(default, deserialize_with = "deserialize_frame_rate")]
   pub frame_rate: Option<f64>,
+  pub scrollback: Option<usize>,
+  pub pause_mode: Option<PauseMode>,
+  pub color_by: Option<ColorBy>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
@@ -103,11 +125,19 @@ pub struct LogModeConfig {
   pub fd_display: Option<FileDescriptorDisplay>,
   pub env_display: Option<EnvDisplay>,
   pub show_comm: Option<bool>,
+  pub show_ppid: Option<bool>,
+  pub show_creds: Option<bool>,
+  pub show_caps: Option<bool>,
+  pub show_cgroup: Option<bool>,
+  pub show_security_label: Option<bool>,
   pub show_argv: Option<bool>,
   pub show_filename: Option<bool>,
+  pub show_exe: Option<bool>,
   pub show_cwd: Option<bool>,
+  pub show_exit: Option<bool>,
   pub show_cmdline: Option<bool>,
   pub decode_errno: Option<bool>,
+  pub detach_on_exit: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
@@ -134,12 +164,17 @@ pub enum EnvDisplay {
   Diff,
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 pub enum ExitHandling {
+  /// Wait for the root child to exit
   #[default]
   Wait,
+  /// Kill the root child
   Kill,
+  /// Terminate the root child
   Terminate,
+  /// Detach from all tracees and leave them running
+  Detach,
 }
 
 pub fn project_directory() -> Option<ProjectDirs> {
@@ -1,4 +1,15 @@
+use std::{
+  fs::File,
+  io::{self, Write},
+  num::NonZeroUsize,
+  path::PathBuf,
+  process::Command,
+  str::FromStr,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
 use clap::ValueEnum;
+use color_eyre::eyre::Context;
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
@@ -10,7 +21,9 @@ pub enum Color {
   Never,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize)]
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
 #[strum(serialize_all = "kebab-case")]
 pub enum SeccompBpf {
   #[default]
@@ -29,12 +42,261 @@ pub enum ActivePane {
   Events,
 }
 
+/// What to do with new events while the event list is paused
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum PauseMode {
+  /// Show the events when resumed
+  #[default]
+  Buffer,
+  /// Never show the events
+  Discard,
+}
+
+/// How to color the events in the event list of the TUI
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ColorBy {
+  /// Use the colors of the theme
+  #[default]
+  Theme,
+  /// Show the failed execs in red
+  Status,
+  /// Give the events of each process a stable color
+  Process,
+}
+
+impl ColorBy {
+  pub fn next(self) -> Self {
+    match self {
+      Self::Theme => Self::Status,
+      Self::Status => Self::Process,
+      Self::Process => Self::Theme,
+    }
+  }
+}
+
+/// Namespaces that the tracee could be started in
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Deserialize, Serialize)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Namespace {
+  Cgroup,
+  Ipc,
+  Mount,
+  Net,
+  /// The command becomes the init process of the new pid namespace
+  Pid,
+  /// Required to create the other namespaces without privileges
+  User,
+  Uts,
+}
+
+/// Syscalls other than the execs that could be trapped to keep track of their effects on the tracees
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Deserialize, Serialize)]
+#[strum(serialize_all = "kebab-case")]
+pub enum TrappedSyscall {
+  /// chdir and fchdir, which change the working directory
+  Chdir,
+  /// chroot, which changes the root directory
+  Chroot,
+  /// setuid, setreuid and setresuid, which change the user ids
+  Setuid,
+}
+
+impl TrappedSyscall {
+  /// The names of the syscalls for the seccomp-bpf filter
+  pub fn names(self) -> &'static [&'static str] {
+    match self {
+      Self::Chdir => &["chdir", "fchdir"],
+      Self::Chroot => &["chroot"],
+      Self::Setuid => &["setuid", "setreuid", "setresuid"],
+    }
+  }
+}
+
+/// How to escape non-printable characters in strings
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum EscapeStyle {
+  /// `\n`, `\u{1b}`
+  #[default]
+  Rust,
+  /// `\n`, `\033`
+  C,
+  /// `\x0a`, `\x1b`
+  Hex,
+}
+
+impl EscapeStyle {
+  pub fn next(self) -> Self {
+    match self {
+      Self::Rust => Self::C,
+      Self::C => Self::Hex,
+      Self::Hex => Self::Rust,
+    }
+  }
+}
+
+/// The format of the lines printed in log mode
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum LogFormat {
+  /// The colored format for humans
+  #[default]
+  Human,
+  /// `key=value` pairs without colors for log aggregation
+  Logfmt,
+}
+
+/// The algorithm to hash the executed binaries with
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Deserialize, Serialize)]
+#[strum(serialize_all = "kebab-case")]
+pub enum HashAlgorithm {
+  Sha256,
+  Blake3,
+}
+
+/// What to do with the tracees when `--max-events` is reached
+#[derive(
+  Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Default, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MaxEventsAction {
+  /// Stop tracing and wait for the command to exit
+  #[default]
+  Detach,
+  /// Kill the tracees
+  Kill,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Display, Deserialize, Serialize)]
 #[strum(serialize_all = "kebab-case")]
 pub enum ExportFormat {
   // https://jsonlines.org/
   JsonStream,
   Json,
+  /// All events in JSON stream, which could be replayed by `tracexec tui --load`
+  Record,
+  /// Chrome's trace event format, which could be opened in Perfetto UI or chrome://tracing
+  ChromeTrace,
+  /// OpenTelemetry spans of the processes in the JSON encoding of OTLP, which could be pushed to a collector by --otlp-endpoint
+  Otlp,
+  /// Perfetto's protobuf trace format, which handles large traces much better than chrome-trace
+  Perfetto,
+  /// A shell script that runs the successful execs again in order
+  ShellScript,
   // https://clang.llvm.org/docs/JSONCompilationDatabase.html
   // CompileCommands,
 }
+
+/// When the buffered output is flushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+  /// After every event
+  #[default]
+  Line,
+  /// Only when the buffer is full
+  Block,
+  /// After every N events
+  Every(NonZeroUsize),
+}
+
+impl FromStr for FlushPolicy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "line" => Ok(Self::Line),
+      "block" => Ok(Self::Block),
+      _ => s
+        .strip_prefix("every-")
+        .and_then(|n| n.parse().ok())
+        .map(Self::Every)
+        .ok_or_else(|| format!("Expected line, block or every-N, got {s:?}")),
+    }
+  }
+}
+
+impl std::fmt::Display for FlushPolicy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Line => write!(f, "line"),
+      Self::Block => write!(f, "block"),
+      Self::Every(n) => write!(f, "every-{n}"),
+    }
+  }
+}
+
+/// Flushes the output after the events according to the [`FlushPolicy`]
+#[derive(Debug, Default)]
+pub struct Flusher {
+  policy: FlushPolicy,
+  events: AtomicUsize,
+}
+
+impl Flusher {
+  pub fn new(policy: FlushPolicy) -> Self {
+    Self {
+      policy,
+      events: AtomicUsize::new(0),
+    }
+  }
+
+  /// Called after an event is written to the output
+  pub fn event_written(&self, out: &mut (impl Write + ?Sized)) -> io::Result<()> {
+    match self.policy {
+      FlushPolicy::Line => out.flush(),
+      FlushPolicy::Block => Ok(()),
+      FlushPolicy::Every(n) => {
+        if (self.events.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(n.get()) {
+          out.flush()
+        } else {
+          Ok(())
+        }
+      }
+    }
+  }
+}
+
+/// Where a standard stream of the tracee is connected to
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Redirect {
+  /// Share the stream with tracexec
+  #[default]
+  Inherit,
+  File(PathBuf),
+}
+
+/// The standard streams of the root tracee
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceeStdio {
+  pub stdin: Redirect,
+  pub stdout: Redirect,
+  pub stderr: Redirect,
+}
+
+impl TraceeStdio {
+  /// Open the files and connect them to the command.
+  /// Like the shell, the output files are created or truncated.
+  pub fn apply(&self, cmd: &mut Command) -> color_eyre::Result<()> {
+    let open_failed = |path: &PathBuf| format!("Failed to open {}", path.display());
+    if let Redirect::File(path) = &self.stdin {
+      cmd.stdin(File::open(path).with_context(|| open_failed(path))?);
+    }
+    if let Redirect::File(path) = &self.stdout {
+      cmd.stdout(File::create(path).with_context(|| open_failed(path))?);
+    }
+    if let Redirect::File(path) = &self.stderr {
+      cmd.stderr(File::create(path).with_context(|| open_failed(path))?);
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,600 @@
+//! The subcommands of the `tracexec` binary
+
+use std::{
+  io::{self, Write},
+  process,
+  sync::Arc,
+  time::Instant,
+};
+
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, OptionExt};
+use nix::{
+  sys::signal::Signal,
+  unistd::{Uid, User},
+};
+use tokio::{
+  signal::unix::{signal, SignalKind},
+  sync::mpsc,
+};
+
+#[cfg(feature = "ebpf")]
+use crate::bpf;
+#[cfg(feature = "seccomp-notify")]
+use crate::notify;
+use crate::{
+  bench, channel,
+  cli::{
+    args::{LogModeArgs, ModifierArgs, TracerEventArgs},
+    config::{Config, ConfigLoadError},
+    options::{Color, ExportFormat},
+    Cli, CliCommand,
+  },
+  collect::{collect, CollectOptions},
+  ctl,
+  event::{TracerEvent, TracerEventDetails, TracerEventDetailsKind, TracerMessage},
+  export::{JsonMetaData, TraceNotes},
+  hook::ExecHook,
+  i18n, log,
+  log::initialize_panic_handler,
+  path_map,
+  proc::{cached_string, BaselineInfo},
+  pty::{native_pty_system, PtySize, PtySystem},
+  redact,
+  replay::Replay,
+  selftest, serialize_json_to_output,
+  stats::ExecStats,
+  tracer::{self, TracerMode},
+  tui::{
+    self,
+    app::{App, PTracer},
+    session::SessionWriter,
+  },
+};
+
+/// The entry point of the `tracexec` binary
+pub async fn main() -> color_eyre::Result<()> {
+  let mut cli = Cli::parse();
+  if let CliCommand::SelftestFixture { fixture, depth } = cli.cmd {
+    // Fixtures run as tracees of `tracexec selftest`. Keep them free of side effects.
+    return selftest::run_fixture(fixture, depth);
+  }
+  if cli.color == Color::Auto && std::env::var_os("NO_COLOR").is_some() {
+    // Respect NO_COLOR if --color=auto
+    cli.color = Color::Never;
+  }
+  if cli.color == Color::Always {
+    owo_colors::control::set_should_colorize(true);
+    color_eyre::install()?;
+  } else if cli.color == Color::Never {
+    owo_colors::control::set_should_colorize(false);
+  } else {
+    color_eyre::install()?;
+  }
+  initialize_panic_handler();
+  log::initialize_logging()?;
+  log::debug!("Commandline args: {:?}", cli);
+  if let Some(cwd) = &cli.cwd {
+    std::env::set_current_dir(cwd)?;
+  }
+  let user = if let Some(user) = cli.user.as_deref() {
+    if !Uid::effective().is_root() {
+      bail!("--user option is only available when running tracexec as root!");
+    }
+    Some(User::from_name(user)?.ok_or_eyre("Failed to get user info")?)
+  } else {
+    None
+  };
+  if !cli.no_profile {
+    match Config::load(cli.profile.clone()) {
+      Ok(config) => cli.merge_config(config),
+      Err(ConfigLoadError::NotFound) => (),
+      Err(e) => Err(e)?,
+    };
+  }
+  match i18n::Catalog::load() {
+    Ok(catalog) => i18n::init(catalog),
+    Err(ConfigLoadError::NotFound) => (),
+    Err(e) => Err(e)?,
+  };
+  match cli.cmd {
+    CliCommand::Log {
+      cmd,
+      also,
+      tracing_args,
+      modifier_args,
+      ptrace_args,
+      mut tracer_event_args,
+      exec_hook_args,
+      plugin_args,
+      output,
+      summary,
+    } => {
+      let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
+      let mut cmds = vec![cmd];
+      for also in also {
+        let args =
+          shell_words::split(&also).with_context(|| format!("Failed to parse command: {also}"))?;
+        if args.is_empty() {
+          bail!("The command passed to --also is empty");
+        }
+        cmds.push(args);
+      }
+      let output = Cli::get_output(output, cli.color)?;
+      if tracing_args.show_exit
+        && !tracer_event_args
+          .filter_exclude
+          .contains(TracerEventDetailsKind::ProcessExit)
+      {
+        tracer_event_args.filter_include |= TracerEventDetailsKind::ProcessExit;
+      }
+      let baseline = BaselineInfo::new()?;
+      let (tracer_tx, mut tracer_rx) = tracer_event_args.channel(Arc::new(baseline.clone()));
+      let overflow_stats = tracer_rx.overflow_stats();
+      tracer_rx.attach_plugins(&plugin_args.plugin, false)?;
+      let (req_tx, req_rx) = mpsc::unbounded_channel();
+      let mut tracer = tracer::Tracer::new(
+        TracerMode::Log {
+          foreground: tracing_args.foreground(),
+        },
+        tracing_args,
+        modifier_args,
+        ptrace_args,
+        tracer_event_args,
+        baseline,
+        tracer_tx,
+        user,
+        req_tx,
+      )?
+      .handle_interrupts();
+      if summary.is_some() {
+        // The processes that never exec are only known from their fork events
+        tracer = tracer.send_unprinted(TracerEventDetailsKind::NewChild.into());
+      }
+      let tracer = Arc::new(tracer);
+      let exec_hook = ExecHook::new(exec_hook_args, false);
+      let mut running = cmds.len();
+      // Exit with the first non-zero exit code of the root tracees
+      let mut final_exit_code = 0;
+      let mut stats = ExecStats::default();
+      let start = Instant::now();
+      // The tracer decides what to do with the tracees on SIGINT and SIGTERM.
+      // They are also caught here to exit with the conventional exit code after detaching.
+      let mut sigint = signal(SignalKind::interrupt())?;
+      let mut sigterm = signal(SignalKind::terminate())?;
+      let mut interrupted = None;
+      let tracer_thread = tracer.spawn_group(cmds, Some(output), req_rx);
+      let final_exit_code = loop {
+        let msg = tokio::select! {
+          msg = tracer_rx.recv() => msg,
+          _ = sigint.recv() => {
+            interrupted.get_or_insert(Signal::SIGINT);
+            continue;
+          }
+          _ = sigterm.recv() => {
+            interrupted.get_or_insert(Signal::SIGTERM);
+            continue;
+          }
+        };
+        match msg {
+          Some(TracerMessage::Event(TracerEvent {
+            details: TracerEventDetails::TraceeExit { exit_code, .. },
+            ..
+          })) => {
+            if final_exit_code == 0 {
+              final_exit_code = exit_code;
+            }
+            running -= 1;
+            if running == 0 {
+              break Some(final_exit_code);
+            }
+          }
+          Some(TracerMessage::Event(TracerEvent { details, id })) => {
+            if let (Some(hook), TracerEventDetails::Exec(exec)) = (&exec_hook, &details) {
+              hook.run(id, exec);
+            }
+            if summary.is_some() {
+              stats.record(&details);
+            }
+          }
+          // The tracer stops before the root tracees exit after detaching from them.
+          None if interrupted.is_some() => {
+            break Some(128 + interrupted.unwrap() as i32);
+          }
+          // channel closed abnormally.
+          None | Some(TracerMessage::FatalError(_)) => break None,
+          _ => (),
+        }
+      };
+      overflow_stats.report();
+      tracing::debug!("Waiting for tracer thread to exit");
+      tracer_thread.await??;
+      let Some(final_exit_code) = final_exit_code else {
+        process::exit(1);
+      };
+      if let Some(hook) = &exec_hook {
+        hook.flush().await;
+      }
+      if let Some(path) = &summary {
+        let summary = stats.summary(start.elapsed(), final_exit_code);
+        let mut writer: Box<dyn io::Write> = if path.as_os_str() == "-" {
+          Box::new(io::stdout())
+        } else {
+          Box::new(io::BufWriter::new(
+            std::fs::File::create(path)
+              .with_context(|| format!("Failed to create {}", path.display()))?,
+          ))
+        };
+        serialize_json_to_output(&mut writer, &summary, false)?;
+        writeln!(writer)?;
+        writer.flush()?;
+      }
+      process::exit(final_exit_code);
+    }
+    CliCommand::Tui {
+      cmd,
+      load,
+      save,
+      modifier_args,
+      ptrace_args,
+      tracer_event_args,
+      mut tui_args,
+      debugger_args,
+      exec_hook_args,
+      plugin_args,
+    } => {
+      let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
+      // Disable owo-colors when running TUI
+      owo_colors::control::set_should_colorize(false);
+      log::debug!(
+        "should colorize: {}",
+        owo_colors::control::should_colorize()
+      );
+      if let Some(path) = load {
+        let replay = Replay::load(&path)?;
+        let tracing_args = LogModeArgs {
+          show_cmdline: false,
+          show_argv: true,
+          show_interpreter: true,
+          more_colors: false,
+          less_colors: false,
+          diff_env: true,
+          ..Default::default()
+        };
+        // The recorded processes are not ours to signal
+        tui_args.kill_on_exit = false;
+        tui_args.terminate_on_exit = false;
+        tui_args.on_exit = None;
+        let baseline = Arc::new(replay.baseline);
+        // All the messages are sent up front so they should fit in memory.
+        let (tracer_tx, mut tracer_rx) = channel::channel(
+          replay.messages.len(),
+          std::env::temp_dir(),
+          baseline.clone(),
+        );
+        tracer_rx.attach_plugins(&plugin_args.plugin, true)?;
+        for msg in replay.messages {
+          tracer_tx.send(msg)?;
+        }
+        let session = save
+          .map(|save| SessionWriter::create(&save, &baseline, &replay.notes))
+          .transpose()?;
+        let frame_rate = tui_args.frame_rate.unwrap_or(60.);
+        let mut app = App::new(
+          None,
+          &tracing_args,
+          &modifier_args,
+          tui_args,
+          baseline,
+          None,
+        )?;
+        app.session = session;
+        app.trace_notes = replay.notes;
+        app
+          .event_list
+          .set_bookmarks(replay.bookmarks.iter().map(|&id| id as usize));
+        let mut tui = tui::Tui::new()?.frame_rate(frame_rate);
+        tui.enter(tracer_rx)?;
+        app.run(&mut tui).await?;
+        app.exit()?;
+        tui::restore_tui()?;
+        return Ok(());
+      }
+      // The seccomp filter stays after detaching and execs fail with ENOSYS without a tracer.
+      #[cfg(feature = "seccomp-bpf")]
+      let ptrace_args = {
+        use crate::cli::{config::ExitHandling, options::SeccompBpf};
+        let mut ptrace_args = ptrace_args;
+        if tui_args.exit_handling() == ExitHandling::Detach {
+          match ptrace_args.seccomp_bpf {
+            SeccompBpf::Auto => ptrace_args.seccomp_bpf = SeccompBpf::Off,
+            SeccompBpf::On => {
+              bail!("--on-exit detach could not be used with seccomp-bpf enabled")
+            }
+            SeccompBpf::Off => (),
+          }
+        }
+        ptrace_args
+      };
+      let ctl_listener = ctl::CtlListener::bind()
+        .inspect_err(|e| log::warn!("Failed to start control server: {e}"))
+        .ok();
+      let ctl_socket_env = ctl_listener.as_ref().map(|listener| {
+        (
+          ctl::CTL_SOCKET_ENV.to_owned(),
+          listener.path().to_string_lossy().into_owned(),
+        )
+      });
+      // Expose the control socket to tracees so that `tracexec ctl` works inside the session
+      let mut ptrace_args = ptrace_args;
+      ptrace_args.set_env.extend(ctl_socket_env.clone());
+      let (mut baseline, tracer_mode, pty_master) = if tui_args.tty {
+        let pty_system = native_pty_system();
+        let pair = pty_system.openpty(PtySize {
+          rows: 24,
+          cols: 80,
+          pixel_width: 0,
+          pixel_height: 0,
+        })?;
+        (
+          BaselineInfo::with_pts(&pair.slave)?,
+          TracerMode::Tui(Some(pair.slave)),
+          Some(pair.master),
+        )
+      } else {
+        (BaselineInfo::new()?, TracerMode::Tui(None), None)
+      };
+      let stdio = tui_args.tracee_stdio();
+      let tracing_args = LogModeArgs {
+        show_cmdline: false, // We handle cmdline in TUI
+        show_argv: true,
+        show_interpreter: true,
+        more_colors: false,
+        less_colors: false,
+        diff_env: true,
+        stdin: stdio.stdin,
+        stdout: stdio.stdout,
+        stderr: stdio.stderr,
+        ..Default::default()
+      };
+      // The variable is not a diff in env
+      if let Some((key, value)) = ctl_socket_env {
+        baseline
+          .env
+          .insert(cached_string(key).into(), cached_string(value).into());
+      }
+      let baseline = Arc::new(baseline);
+      let (tracer_tx, mut tracer_rx) = tracer_event_args.channel(baseline.clone());
+      tracer_rx.attach_plugins(&plugin_args.plugin, true)?;
+      let (req_tx, req_rx) = mpsc::unbounded_channel();
+      let tracer = Arc::new(tracer::Tracer::new(
+        tracer_mode,
+        tracing_args.clone(),
+        modifier_args.clone(),
+        ptrace_args,
+        tracer_event_args,
+        baseline.as_ref().clone(),
+        tracer_tx,
+        user,
+        req_tx,
+      )?);
+      let session = save
+        .map(|save| SessionWriter::create(&save, &baseline, &TraceNotes::default()))
+        .transpose()?;
+      let frame_rate = tui_args.frame_rate.unwrap_or(60.);
+      let mut app = App::new(
+        Some(PTracer {
+          tracer: tracer.clone(),
+          debugger_args,
+        }),
+        &tracing_args,
+        &modifier_args,
+        tui_args,
+        baseline,
+        pty_master,
+      )?;
+      app.overflow_stats = Some(tracer_rx.overflow_stats());
+      app.exec_hook = ExecHook::new(exec_hook_args, true);
+      app.session = session;
+      let ctl_server = ctl_listener.map(|listener| listener.serve(tracer.clone()));
+      let tracer_thread = tracer.spawn(cmd, None, req_rx);
+      let mut tui = tui::Tui::new()?.frame_rate(frame_rate);
+      tui.enter(tracer_rx)?;
+      app.run(&mut tui).await?;
+      // Now when TUI exits, the tracer thread is still running.
+      // Depending on --on-exit, we wait for the root process, terminate or kill it,
+      // or detach from all tracees so that the tracer thread exits.
+      app.exit()?;
+      tui::restore_tui()?;
+      if let Some((ctl_server, _socket)) = ctl_server {
+        ctl_server.abort();
+      }
+      tracer_thread.await??;
+    }
+    CliCommand::Collect {
+      cmd,
+      format,
+      output,
+      flush,
+      modifier_args,
+      ptrace_args,
+      pretty,
+      notes_args,
+      plugin_args,
+      otlp_endpoint,
+      foreground,
+      no_foreground,
+    } => {
+      let modifier_args = modifier_args.processed();
+      redact::init(redact::Redactor::new(&modifier_args.redact)?);
+      path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
+      let mut output = Cli::get_output(output, cli.color)?;
+      if otlp_endpoint.is_some() && format != ExportFormat::Otlp {
+        bail!("--otlp-endpoint is only available with the otlp format");
+      }
+      let tracing_args = LogModeArgs {
+        show_cmdline: false,
+        show_argv: true,
+        show_interpreter: true,
+        more_colors: false,
+        less_colors: false,
+        diff_env: false,
+        foreground,
+        no_foreground,
+        ..Default::default()
+      };
+      let baseline = BaselineInfo::new()?;
+      let (tracer_tx, mut tracer_rx) = TracerEventArgs::all().channel(Arc::new(baseline.clone()));
+      tracer_rx.attach_plugins(&plugin_args.plugin, false)?;
+      let (req_tx, req_rx) = mpsc::unbounded_channel();
+      let tracer = Arc::new(
+        tracer::Tracer::new(
+          TracerMode::Log {
+            foreground: tracing_args.foreground(),
+          },
+          tracing_args.clone(),
+          modifier_args.clone(),
+          ptrace_args,
+          TracerEventArgs::all(),
+          baseline.clone(),
+          tracer_tx,
+          user,
+          req_tx,
+        )?
+        .handle_interrupts(),
+      );
+      let metadata = JsonMetaData::new(baseline).with_notes(notes_args.into());
+      let tracer_thread = tracer.spawn(cmd, None, req_rx);
+      let options = CollectOptions {
+        pretty,
+        flush,
+        otlp_endpoint,
+      };
+      let exit_code = collect(
+        format,
+        options,
+        metadata,
+        &mut output,
+        &mut tracer_rx,
+        async move { tracer_thread.await? },
+      )
+      .await?;
+      process::exit(exit_code);
+    }
+    CliCommand::GenerateCompletions { shell } => {
+      Cli::generate_completions(shell);
+    }
+    #[cfg(feature = "ebpf")]
+    CliCommand::Ebpf { command } => {
+      // TODO: warn if --user is set when not follow-forks
+      bpf::run(command, user, cli.color).await?;
+    }
+    #[cfg(feature = "seccomp-notify")]
+    CliCommand::Notify {
+      cmd,
+      deny,
+      rules,
+      tracing_args,
+      modifier_args,
+      output,
+    } => {
+      notify::run(
+        cmd,
+        deny,
+        rules,
+        tracing_args,
+        modifier_args,
+        output,
+        user,
+        cli.color,
+      )?;
+    }
+    CliCommand::Ctl { command } => {
+      ctl::run(command).await?;
+    }
+    CliCommand::Stats {
+      cmd,
+      load,
+      top,
+      ptrace_args,
+      output,
+    } => {
+      let mut output = Cli::get_output(output, cli.color)?;
+      let mut stats = ExecStats::default();
+      if let Some(path) = load {
+        let replay = Replay::load(&path)?;
+        // Saved traces do not have timestamps
+        for msg in replay.messages {
+          if let TracerMessage::Event(TracerEvent { details, .. }) = msg {
+            stats.record(&details);
+          }
+        }
+        stats.print(&mut output, Some(&replay.notes), None, top)?;
+        return Ok(());
+      }
+      let baseline = BaselineInfo::new()?;
+      let (tracer_tx, mut tracer_rx) = TracerEventArgs::all().channel(Arc::new(baseline.clone()));
+      let overflow_stats = tracer_rx.overflow_stats();
+      let (req_tx, req_rx) = mpsc::unbounded_channel();
+      let tracer = Arc::new(
+        tracer::Tracer::new(
+          TracerMode::Log { foreground: false },
+          LogModeArgs::default(),
+          ModifierArgs::default().processed(),
+          ptrace_args,
+          TracerEventArgs::all(),
+          baseline,
+          tracer_tx,
+          user,
+          req_tx,
+        )?
+        .handle_interrupts(),
+      );
+      let start = Instant::now();
+      let tracer_thread = tracer.spawn(cmd, None, req_rx);
+      let exit_code = loop {
+        match tracer_rx.recv().await {
+          Some(TracerMessage::Event(TracerEvent {
+            details: TracerEventDetails::TraceeExit { exit_code, .. },
+            ..
+          })) => break Some(exit_code),
+          Some(TracerMessage::Event(TracerEvent { details, .. })) => {
+            stats.record(&details);
+          }
+          // channel closed abnormally.
+          None | Some(TracerMessage::FatalError(_)) => break None,
+          _ => (),
+        }
+      };
+      let span = start.elapsed();
+      overflow_stats.report();
+      tracing::debug!("Waiting for tracer thread to exit");
+      tracer_thread.await??;
+      let Some(exit_code) = exit_code else {
+        process::exit(1);
+      };
+      stats.print(&mut output, None, Some(span), top)?;
+      process::exit(exit_code);
+    }
+    CliCommand::Selftest { ptrace_args } => {
+      selftest::run(ptrace_args).await?;
+    }
+    CliCommand::Bench {
+      cmd,
+      backend,
+      runs,
+      ptrace_args,
+    } => {
+      if user.is_some() {
+        bail!("--user is not supported by bench");
+      }
+      bench::run(cmd, backend, runs, ptrace_args).await?;
+    }
+    CliCommand::SelftestFixture { .. } => unreachable!(),
+  }
+  Ok(())
+}
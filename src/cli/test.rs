@@ -16,3 +16,223 @@ fn log_mode_without_args_works() -> Result<(), Box<dyn std::error::Error>> {
     .stderr(predicate::str::contains("/proc/self/exe"));
   Ok(())
 }
+
+#[test]
+#[file_serial]
+fn log_mode_runs_exec_hook() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--exec-hook")
+    .arg("echo \"hook result: $TRACEXEC_RESULT\" >&2")
+    .arg("--")
+    .arg("/proc/self/exe")
+    .arg("--help");
+  cmd
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("hook result: 0"));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_dedup_summarizes_repeated_execs() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--dedup")
+    .arg("--")
+    .arg("/bin/sh")
+    .arg("-c")
+    .arg("/bin/true; /bin/true; /bin/true");
+  cmd
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("Repeated execs:").and(predicate::str::contains("3×")));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_captures_exec_from_non_leader_thread() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--format=logfmt")
+    .arg("--")
+    .arg(assert_cmd::cargo::cargo_bin("tracexec"))
+    .arg("selftest-fixture")
+    .arg("thread-exec");
+  cmd
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("argv=true"));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_follows_daemons() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--format=logfmt")
+    .arg("--")
+    .arg("/bin/sh")
+    .arg("-c")
+    // The intermediate subshell exits after the daemon has called setsid
+    .arg("(setsid /bin/sh -c 'sleep 0.3; exec /bin/true' & sleep 0.1)");
+  cmd.assert().success().stderr(
+    predicate::str::contains("event=daemonize").and(predicate::str::contains("file=/bin/true")),
+  );
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_summary_counts_forks_without_printing_them() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--summary=-")
+    .arg("--")
+    .arg("/bin/sh")
+    .arg("-c")
+    .arg("/bin/true; /bin/true");
+  cmd
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r#""processes":[2-9]"#)?)
+    .stderr(predicate::str::contains("new child").not());
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_kills_tracees_on_max_events() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--max-events=1")
+    .arg("--on-max-events=kill")
+    .arg("--")
+    .arg("/bin/sh")
+    .arg("-c")
+    .arg("sleep 10");
+  // 128 + SIGKILL
+  cmd.assert().code(137);
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn log_mode_flushes_block_buffered_output_on_exit() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("log")
+    .arg("--flush=block")
+    .arg("--output=-")
+    .arg("--")
+    .arg("/bin/true");
+  cmd
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("/bin/true"));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn collect_mode_exports_and_exits_with_tracee_status() -> Result<(), Box<dyn std::error::Error>> {
+  for format in ["json", "json-stream", "record", "shell-script"] {
+    let mut cmd = Command::cargo_bin("tracexec")?;
+    cmd
+      .arg("collect")
+      .arg(format!("--format={format}"))
+      .arg("--output=-")
+      .arg("--")
+      .arg("/bin/sh")
+      .arg("-c")
+      .arg("/bin/true; exit 3");
+    cmd
+      .assert()
+      .code(3)
+      .stdout(predicate::str::contains("/bin/true"));
+  }
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+#[cfg(feature = "seccomp-notify")]
+fn notify_mode_works() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("notify")
+    .arg("--")
+    .arg("/proc/self/exe")
+    .arg("--help");
+  cmd
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("/proc/self/exe"));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+#[cfg(feature = "seccomp-notify")]
+fn notify_mode_flushes_output_and_summary_on_exit() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("notify")
+    .arg("--dedup")
+    .arg("--flush=block")
+    .arg("--output=-")
+    .arg("--")
+    .arg("/bin/sh")
+    .arg("-c")
+    .arg("/bin/true; /bin/true; /bin/true");
+  cmd.assert().success().stdout(
+    predicate::str::contains("/bin/true").and(predicate::str::contains("Repeated execs:")),
+  );
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+#[cfg(feature = "seccomp-notify")]
+fn notify_mode_denies_matching_exec() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("notify")
+    .arg("--deny=argv-regex:--help")
+    .arg("--")
+    .arg("/proc/self/exe")
+    .arg("--help");
+  cmd
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Failed to spawn the command"));
+  Ok(())
+}
+
+#[test]
+#[file_serial]
+fn bench_reports_untraced_and_ptrace_timings() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tracexec")?;
+  cmd
+    .arg("bench")
+    .arg("--backend=ptrace")
+    .arg("--runs=1")
+    .arg("--")
+    .arg("/proc/self/exe")
+    .arg("--help");
+  cmd.assert().success().stdout(
+    predicate::str::contains("untraced")
+      .and(predicate::str::contains("ptrace"))
+      .and(predicate::str::contains("FAIL").not()),
+  );
+  Ok(())
+}
@@ -70,6 +70,8 @@ fn get_shell() -> String {
 pub struct CommandBuilder {
   args: Vec<OsString>,
   cwd: Option<OsString>,
+  /// Modifications to the inherited environment. `None` removes the variable.
+  envs: BTreeMap<OsString, Option<OsString>>,
   pub(crate) umask: Option<libc::mode_t>,
   controlling_tty: bool,
 }
@@ -81,6 +83,7 @@ impl CommandBuilder {
     Self {
       args: vec![program.as_ref().to_owned()],
       cwd: None,
+      envs: BTreeMap::new(),
       umask: None,
       controlling_tty: true,
     }
@@ -91,6 +94,7 @@ impl CommandBuilder {
     Self {
       args,
       cwd: None,
+      envs: BTreeMap::new(),
       umask: None,
       controlling_tty: true,
     }
@@ -116,6 +120,7 @@ impl CommandBuilder {
     Self {
       args: vec![],
       cwd: None,
+      envs: BTreeMap::new(),
       umask: None,
       controlling_tty: true,
     }
@@ -168,6 +173,25 @@ impl CommandBuilder {
   pub fn get_cwd(&self) -> Option<&OsString> {
     self.cwd.as_ref()
   }
+
+  /// Override the value of an environment variable
+  pub fn env<K, V>(&mut self, key: K, value: V)
+  where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+  {
+    self
+      .envs
+      .insert(key.as_ref().to_owned(), Some(value.as_ref().to_owned()));
+  }
+
+  /// Remove an environment variable from the inherited environment
+  pub fn env_remove<K>(&mut self, key: K)
+  where
+    K: AsRef<OsStr>,
+  {
+    self.envs.insert(key.as_ref().to_owned(), None);
+  }
 }
 
 impl CommandBuilder {
@@ -176,7 +200,10 @@ impl CommandBuilder {
   }
 
   fn resolve_path(&self) -> Option<OsString> {
-    env::var_os("PATH")
+    match self.envs.get(OsStr::new("PATH")) {
+      Some(path) => path.clone(),
+      None => env::var_os("PATH"),
+    }
   }
 
   fn search_path(&self, exe: &OsStr, cwd: &OsStr) -> color_eyre::Result<OsString> {
@@ -233,6 +260,12 @@ impl CommandBuilder {
     cmd.args(&self.args[1..]);
 
     cmd.current_dir(dir);
+    for (key, value) in &self.envs {
+      match value {
+        Some(value) => cmd.env(key, value),
+        None => cmd.env_remove(key),
+      };
+    }
 
     Ok(cmd)
   }
@@ -0,0 +1,307 @@
+//! The `collect` subcommands of the ptrace and eBPF backends.
+//!
+//! Both backends send their messages through a [`MessageReceiver`]
+//! and export them with the same [`Exporter`]s in [`collect`].
+
+use std::{future::Future, io::Write};
+
+use crate::{
+  channel::MessageReceiver,
+  cli::options::{ExportFormat, FlushPolicy, Flusher},
+  event::{TracerEvent, TracerEventDetails, TracerMessage},
+  export::{ChromeTraceBuilder, Json, JsonExecEvent, JsonMetaData, RecordEntry},
+  otlp::{self, OtlpTraceBuilder},
+  perfetto::PerfettoTraceWriter,
+  printer::PrinterOut,
+  script::ShellScriptBuilder,
+  serialize_json_to_output,
+};
+
+/// An export format of `collect`
+pub trait Exporter {
+  /// Called before the tracer starts
+  fn start(&mut self, _output: &mut dyn Write, _meta: &JsonMetaData) -> color_eyre::Result<()> {
+    Ok(())
+  }
+
+  /// Called for every message from the tracer, including the exit of the root tracee
+  fn record(&mut self, output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()>;
+
+  /// Called after the root tracee exits and the tracer thread finishes
+  async fn finish(self, output: &mut dyn Write, meta: JsonMetaData) -> color_eyre::Result<()>;
+}
+
+/// The options of the export formats
+pub struct CollectOptions {
+  pub pretty: bool,
+  pub flush: FlushPolicy,
+  pub otlp_endpoint: Option<String>,
+}
+
+/// Export the messages until the root tracee exits.
+///
+/// Returns the exit code of the root tracee, or 1 if the tracer fails.
+pub async fn collect(
+  format: ExportFormat,
+  options: CollectOptions,
+  meta: JsonMetaData,
+  output: &mut PrinterOut,
+  rx: &mut MessageReceiver,
+  tracer_thread: impl Future<Output = color_eyre::Result<()>>,
+) -> color_eyre::Result<i32> {
+  let CollectOptions {
+    pretty,
+    flush,
+    otlp_endpoint,
+  } = options;
+  match format {
+    ExportFormat::Json => {
+      let exporter = JsonExporter {
+        events: Vec::new(),
+        pretty,
+      };
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::JsonStream => {
+      let exporter = JsonStreamExporter {
+        flusher: Flusher::new(flush),
+        pretty,
+      };
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::Record => {
+      let exporter = RecordExporter {
+        flusher: Flusher::new(flush),
+      };
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::ChromeTrace => {
+      let exporter = ChromeTraceExporter {
+        trace: ChromeTraceBuilder::default(),
+        pretty,
+      };
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::ShellScript => {
+      let exporter = ShellScriptBuilder::default();
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::Perfetto => {
+      let exporter = PerfettoTraceWriter::default();
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+    ExportFormat::Otlp => {
+      let exporter = OtlpExporter {
+        trace: OtlpTraceBuilder::default(),
+        endpoint: otlp_endpoint,
+        pretty,
+      };
+      receive(exporter, meta, output, rx, tracer_thread).await
+    }
+  }
+}
+
+async fn receive(
+  mut exporter: impl Exporter,
+  meta: JsonMetaData,
+  output: &mut PrinterOut,
+  rx: &mut MessageReceiver,
+  tracer_thread: impl Future<Output = color_eyre::Result<()>>,
+) -> color_eyre::Result<i32> {
+  let overflow_stats = rx.overflow_stats();
+  exporter.start(output, &meta)?;
+  let exit_code = loop {
+    match rx.recv().await {
+      // channel closed abnormally.
+      None | Some(TracerMessage::FatalError(_)) => break None,
+      Some(msg) => {
+        let exit_code = match &msg {
+          TracerMessage::Event(TracerEvent {
+            details: TracerEventDetails::TraceeExit { exit_code, .. },
+            ..
+          }) => Some(*exit_code),
+          _ => None,
+        };
+        exporter.record(output, msg)?;
+        if exit_code.is_some() {
+          break exit_code;
+        }
+      }
+    }
+  };
+  overflow_stats.report();
+  tracing::debug!("Waiting for tracer thread to exit");
+  tracer_thread.await?;
+  let Some(exit_code) = exit_code else {
+    output.flush()?;
+    return Ok(1);
+  };
+  exporter.finish(output, meta).await?;
+  Ok(exit_code)
+}
+
+fn write_json_line(
+  output: &mut dyn Write,
+  value: &impl serde::Serialize,
+  pretty: bool,
+) -> color_eyre::Result<()> {
+  serialize_json_to_output(&mut *output, value, pretty)?;
+  output.write_all(b"\n")?;
+  Ok(())
+}
+
+struct JsonExporter {
+  events: Vec<JsonExecEvent>,
+  pretty: bool,
+}
+
+impl Exporter for JsonExporter {
+  fn record(&mut self, _output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      id,
+    }) = msg
+    {
+      self.events.push(JsonExecEvent::new(id, *exec));
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, meta: JsonMetaData) -> color_eyre::Result<()> {
+    let json = Json {
+      meta,
+      events: self.events,
+    };
+    write_json_line(output, &json, self.pretty)?;
+    output.flush()?;
+    Ok(())
+  }
+}
+
+struct JsonStreamExporter {
+  flusher: Flusher,
+  pretty: bool,
+}
+
+impl Exporter for JsonStreamExporter {
+  fn start(&mut self, output: &mut dyn Write, meta: &JsonMetaData) -> color_eyre::Result<()> {
+    serialize_json_to_output(&mut *output, meta, self.pretty)?;
+    Ok(())
+  }
+
+  fn record(&mut self, output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      id,
+    }) = msg
+    {
+      write_json_line(output, &JsonExecEvent::new(id, *exec), self.pretty)?;
+      self.flusher.event_written(output)?;
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, _meta: JsonMetaData) -> color_eyre::Result<()> {
+    output.flush()?;
+    Ok(())
+  }
+}
+
+struct RecordExporter {
+  flusher: Flusher,
+}
+
+impl Exporter for RecordExporter {
+  fn start(&mut self, output: &mut dyn Write, meta: &JsonMetaData) -> color_eyre::Result<()> {
+    write_json_line(output, meta, false)
+  }
+
+  fn record(&mut self, output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let Some(entry) = RecordEntry::from_message(msg) {
+      write_json_line(output, &entry, false)?;
+      self.flusher.event_written(output)?;
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, _meta: JsonMetaData) -> color_eyre::Result<()> {
+    output.flush()?;
+    Ok(())
+  }
+}
+
+struct ChromeTraceExporter {
+  trace: ChromeTraceBuilder,
+  pretty: bool,
+}
+
+impl Exporter for ChromeTraceExporter {
+  fn record(&mut self, _output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent { details, .. }) = msg {
+      self.trace.record(&details);
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, meta: JsonMetaData) -> color_eyre::Result<()> {
+    write_json_line(output, &self.trace.finish(meta), self.pretty)?;
+    output.flush()?;
+    Ok(())
+  }
+}
+
+impl Exporter for ShellScriptBuilder {
+  fn record(&mut self, _output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent { details, .. }) = msg {
+      Self::record(self, &details);
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, meta: JsonMetaData) -> color_eyre::Result<()> {
+    output.write_all(Self::finish(self, &meta).as_bytes())?;
+    output.flush()?;
+    Ok(())
+  }
+}
+
+impl Exporter for PerfettoTraceWriter {
+  fn record(&mut self, output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent { details, .. }) = msg {
+      Self::record(self, output, &details)?;
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, _meta: JsonMetaData) -> color_eyre::Result<()> {
+    Self::finish(self, output)?;
+    Ok(())
+  }
+}
+
+struct OtlpExporter {
+  trace: OtlpTraceBuilder,
+  /// Push the traces to the collector instead of writing them to the output
+  endpoint: Option<String>,
+  pretty: bool,
+}
+
+impl Exporter for OtlpExporter {
+  fn record(&mut self, _output: &mut dyn Write, msg: TracerMessage) -> color_eyre::Result<()> {
+    if let TracerMessage::Event(TracerEvent { details, .. }) = msg {
+      self.trace.record(&details);
+    }
+    Ok(())
+  }
+
+  async fn finish(self, output: &mut dyn Write, meta: JsonMetaData) -> color_eyre::Result<()> {
+    let traces = self.trace.finish(meta);
+    if let Some(endpoint) = self.endpoint {
+      otlp::push(&endpoint, &traces).await?;
+    } else {
+      write_json_line(output, &traces, self.pretty)?;
+      output.flush()?;
+    }
+    Ok(())
+  }
+}
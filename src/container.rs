@@ -0,0 +1,270 @@
+//! Detect the container that a process lives in.
+//!
+//! Container runtimes put every container in its own cgroup, whose name contains the container ID.
+//! Processes in unrecognized containers are detected by their namespaces.
+
+use std::{fmt::Display, os::unix::fs::MetadataExt};
+
+use arcstr::ArcStr;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+
+use crate::proc::cached_str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ContainerRuntime {
+  Docker,
+  Podman,
+  Containerd,
+  CriO,
+  Kubernetes,
+  Lxc,
+  /// The process is in other pid and mount namespaces than tracexec
+  Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Container {
+  pub runtime: ContainerRuntime,
+  /// The container ID or name. For unknown runtimes, it is the inode of the pid namespace.
+  pub id: ArcStr,
+}
+
+impl Display for Container {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    // Container IDs are usually shortened to 12 characters, like `docker ps` does
+    let id = if is_container_id(&self.id) {
+      &self.id[..12]
+    } else {
+      self.id.as_str()
+    };
+    write!(f, "{}:{}", self.runtime, id)
+  }
+}
+
+/// The runtimes that name the cgroups of the containers `<prefix><id>.scope` with systemd
+const SCOPE_PREFIXES: &[(&str, ContainerRuntime)] = &[
+  ("docker-", ContainerRuntime::Docker),
+  ("libpod-", ContainerRuntime::Podman),
+  ("cri-containerd-", ContainerRuntime::Containerd),
+  ("crio-", ContainerRuntime::CriO),
+];
+
+fn is_container_id(s: &str) -> bool {
+  s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Find the container in a cgroup path. Nested containers are attributed to the innermost one.
+fn parse_cgroup_path(path: &str) -> Option<Container> {
+  let segments = path
+    .split('/')
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>();
+  for (i, &segment) in segments.iter().enumerate().rev() {
+    let scope = segment.strip_suffix(".scope").unwrap_or(segment);
+    let found = SCOPE_PREFIXES
+      .iter()
+      .find_map(|(prefix, runtime)| {
+        scope
+          .strip_prefix(prefix)
+          .filter(|id| is_container_id(id))
+          .map(|id| (*runtime, id))
+      })
+      .or_else(|| {
+        segment
+          .strip_prefix("lxc.payload.")
+          .map(|name| (ContainerRuntime::Lxc, name))
+      })
+      .or_else(|| {
+        // cgroupfs driver: /docker/<id>, /lxc/<name>, /kubepods/<qos>/pod<uid>/<id>
+        let parent = *segments.get(i.checked_sub(1)?)?;
+        if parent == "lxc" {
+          Some((ContainerRuntime::Lxc, segment))
+        } else if !is_container_id(segment) {
+          None
+        } else if parent == "docker" {
+          Some((ContainerRuntime::Docker, segment))
+        } else if segments[..i].iter().any(|s| s.starts_with("kubepods")) {
+          Some((ContainerRuntime::Kubernetes, segment))
+        } else {
+          None
+        }
+      });
+    if let Some((runtime, id)) = found {
+      return Some(Container {
+        runtime,
+        id: cached_str(id),
+      });
+    }
+  }
+  None
+}
+
+/// Parse the content of `/proc/<pid>/cgroup`.
+/// Every line is `hierarchy-ID:controller-list:cgroup-path`.
+fn parse_cgroup(content: &str) -> Option<Container> {
+  content
+    .lines()
+    .filter_map(|line| line.splitn(3, ':').nth(2))
+    .find_map(parse_cgroup_path)
+}
+
+/// Find the cgroup path in the content of `/proc/<pid>/cgroup`.
+/// The unified hierarchy of cgroup v2 is preferred over the systemd hierarchy of cgroup v1.
+fn parse_cgroup_path_of_process(content: &str) -> Option<&str> {
+  let hierarchies = content
+    .lines()
+    .filter_map(|line| line.splitn(3, ':').collect_tuple::<(_, _, _)>());
+  let mut fallback = None;
+  for (id, controllers, path) in hierarchies {
+    if id == "0" && controllers.is_empty() {
+      return Some(path);
+    }
+    if controllers == "name=systemd" || fallback.is_none() {
+      fallback = Some(path);
+    }
+  }
+  fallback
+}
+
+/// The cgroup of a process and the container detected from it
+#[derive(Debug, Default)]
+pub struct ProcessCgroup {
+  /// The cgroup path of the process, e.g. `/system.slice/nginx.service`
+  pub path: Option<ArcStr>,
+  pub container: Option<Container>,
+}
+
+/// Read the cgroup path and detect the container of the process from one read of
+/// `/proc/<pid>/cgroup`. Both are `None` if the process is gone.
+pub fn read_cgroup(pid: Pid) -> ProcessCgroup {
+  let Ok(content) = std::fs::read_to_string(format!("/proc/{pid}/cgroup")) else {
+    return ProcessCgroup::default();
+  };
+  ProcessCgroup {
+    path: parse_cgroup_path_of_process(&content).map(cached_str),
+    container: detect_container(pid, &content),
+  }
+}
+
+fn namespace_inode(pid: &str, ns: &str) -> Option<u64> {
+  std::fs::metadata(format!("/proc/{pid}/ns/{ns}"))
+    .ok()
+    .map(|m| m.ino())
+}
+
+lazy_static! {
+  static ref OWN_NAMESPACES: Option<(u64, u64)> =
+    namespace_inode("self", "pid").zip(namespace_inode("self", "mnt"));
+}
+
+/// Detect the container of the process from its cgroups. Returns `None` if it is not in a container
+/// or it is gone.
+fn detect_container(pid: Pid, cgroup: &str) -> Option<Container> {
+  let pid = pid.to_string();
+  if let Some(container) = parse_cgroup(cgroup) {
+    return Some(container);
+  }
+  let (own_pid_ns, own_mnt_ns) = (*OWN_NAMESPACES)?;
+  let pid_ns = namespace_inode(&pid, "pid")?;
+  let mnt_ns = namespace_inode(&pid, "mnt")?;
+  (pid_ns != own_pid_ns && mnt_ns != own_mnt_ns).then(|| Container {
+    runtime: ContainerRuntime::Unknown,
+    id: cached_str(&pid_ns.to_string()),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+  fn container(runtime: ContainerRuntime, id: &str) -> Option<Container> {
+    Some(Container {
+      runtime,
+      id: id.into(),
+    })
+  }
+
+  #[test]
+  fn systemd_scopes() {
+    assert_eq!(
+      parse_cgroup(&format!("0::/system.slice/docker-{ID}.scope\n")),
+      container(ContainerRuntime::Docker, ID)
+    );
+    assert_eq!(
+      parse_cgroup(&format!(
+        "0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{ID}.scope/container\n"
+      )),
+      container(ContainerRuntime::Podman, ID)
+    );
+    assert_eq!(
+      parse_cgroup(&format!(
+        "0::/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod1234.slice/cri-containerd-{ID}.scope\n"
+      )),
+      container(ContainerRuntime::Containerd, ID)
+    );
+    // conmon is not in the container
+    assert_eq!(
+      parse_cgroup(&format!("0::/machine.slice/libpod-conmon-{ID}.scope\n")),
+      None
+    );
+  }
+
+  #[test]
+  fn cgroupfs_paths() {
+    assert_eq!(
+      parse_cgroup(&format!("12:pids:/docker/{ID}\n0::/docker/{ID}\n")),
+      container(ContainerRuntime::Docker, ID)
+    );
+    assert_eq!(
+      parse_cgroup(&format!("0::/kubepods/burstable/pod1234/{ID}\n")),
+      container(ContainerRuntime::Kubernetes, ID)
+    );
+    assert_eq!(
+      parse_cgroup("0::/lxc.payload.web/system.slice/nginx.service\n"),
+      container(ContainerRuntime::Lxc, "web")
+    );
+  }
+
+  #[test]
+  fn cgroup_paths_of_processes() {
+    assert_eq!(
+      parse_cgroup_path_of_process("0::/system.slice/nginx.service\n"),
+      Some("/system.slice/nginx.service")
+    );
+    assert_eq!(
+      parse_cgroup_path_of_process(
+        "12:pids:/user.slice\n1:name=systemd:/system.slice/cron.service\n0::/\n"
+      ),
+      Some("/")
+    );
+    assert_eq!(
+      parse_cgroup_path_of_process(
+        "12:pids:/user.slice\n1:name=systemd:/system.slice/cron.service\n"
+      ),
+      Some("/system.slice/cron.service")
+    );
+  }
+
+  #[test]
+  fn not_in_container() {
+    assert_eq!(
+      parse_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+      None
+    );
+    assert_eq!(parse_cgroup("0::/\n"), None);
+  }
+
+  #[test]
+  fn display() {
+    let docker = container(ContainerRuntime::Docker, ID).unwrap();
+    assert_eq!(docker.to_string(), "docker:0123456789ab");
+    let lxc = container(ContainerRuntime::Lxc, "web").unwrap();
+    assert_eq!(lxc.to_string(), "lxc:web");
+  }
+}
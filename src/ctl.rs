@@ -0,0 +1,264 @@
+//! Control a running tracexec TUI session from the command line.
+
+use std::{
+  ffi::CString,
+  os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+
+use color_eyre::{
+  eyre::{bail, eyre},
+  Section,
+};
+use lazy_static::lazy_static;
+use nix::{
+  libc,
+  unistd::{mkdtemp, Uid},
+};
+use serde::{Deserialize, Serialize};
+use signal_hook::{consts::SIGTERM, SigId};
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+  net::{UnixListener, UnixStream},
+  task::JoinHandle,
+};
+use tracing::{debug, warn};
+
+use crate::{cli::CtlCommand, tracer::Tracer};
+
+/// The environment variable that holds the path of the control socket of the current session.
+pub const CTL_SOCKET_ENV: &str = "TRACEXEC_CTL_SOCKET";
+
+/// The control socket of a session
+struct SocketPath {
+  socket: PathBuf,
+  /// The private directory created for the socket, if any
+  dir: Option<PathBuf>,
+}
+
+impl SocketPath {
+  /// The socket is placed in `$XDG_RUNTIME_DIR`, which is only accessible by the user.
+  /// Otherwise, a private directory is created for it in the temporary directory.
+  fn new() -> color_eyre::Result<Self> {
+    let name = format!("tracexec-{}.sock", std::process::id());
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR")
+      .map(PathBuf::from)
+      .filter(|dir| dir.is_absolute() && dir.is_dir())
+    {
+      return Ok(Self {
+        socket: dir.join(name),
+        dir: None,
+      });
+    }
+    // mkdtemp creates the directory with mode 0700
+    let dir = mkdtemp(&std::env::temp_dir().join("tracexec-XXXXXX"))?;
+    Ok(Self {
+      socket: dir.join(name),
+      dir: Some(dir),
+    })
+  }
+
+  fn remove(&self) {
+    let _ = std::fs::remove_file(&self.socket);
+    if let Some(dir) = &self.dir {
+      let _ = std::fs::remove_dir(dir);
+    }
+  }
+}
+
+lazy_static! {
+  /// The control socket of this process, if any
+  static ref SOCKET: Mutex<Option<SocketPath>> = Mutex::new(None);
+}
+
+/// Removes the control socket when dropped.
+///
+/// The destructors do not run on panic or when tracexec is killed by SIGTERM,
+/// so the socket is also removed by the panic handler and by a signal handler.
+/// SIGHUP is left alone because it reloads the rules.
+pub struct SocketGuard {
+  sigterm: Option<SigId>,
+}
+
+impl SocketGuard {
+  fn new(path: SocketPath) -> Self {
+    let c_socket = CString::new(path.socket.as_os_str().as_bytes()).ok();
+    let c_dir = path
+      .dir
+      .as_ref()
+      .and_then(|dir| CString::new(dir.as_os_str().as_bytes()).ok());
+    // SAFETY: unlink, rmdir and emulate_default_handler are async-signal-safe
+    let sigterm = unsafe {
+      signal_hook::low_level::register(SIGTERM, move || {
+        if let Some(socket) = &c_socket {
+          libc::unlink(socket.as_ptr());
+        }
+        if let Some(dir) = &c_dir {
+          libc::rmdir(dir.as_ptr());
+        }
+        let _ = signal_hook::low_level::emulate_default_handler(SIGTERM);
+      })
+    }
+    .inspect_err(|e| warn!("Failed to register signal handler to remove the control socket: {e}"))
+    .ok();
+    *SOCKET.lock().unwrap() = Some(path);
+    Self { sigterm }
+  }
+}
+
+impl Drop for SocketGuard {
+  fn drop(&mut self) {
+    if let Some(id) = self.sigterm.take() {
+      signal_hook::low_level::unregister(id);
+    }
+    remove_socket();
+  }
+}
+
+/// Remove the control socket of this process if there is one
+pub fn remove_socket() {
+  let path = SOCKET
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+    .take();
+  if let Some(path) = path {
+    path.remove();
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "kebab-case")]
+pub enum CtlRequest {
+  Run { cmd: Vec<String> },
+  ReloadRules,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", content = "message", rename_all = "kebab-case")]
+pub enum CtlResponse {
+  Ok,
+  Error(String),
+}
+
+/// The control socket of a TUI session, bound but not served yet
+pub struct CtlListener {
+  listener: UnixListener,
+  path: PathBuf,
+  guard: SocketGuard,
+}
+
+impl CtlListener {
+  pub fn bind() -> color_eyre::Result<Self> {
+    let path = SocketPath::new()?;
+    let socket = path.socket.clone();
+    // The guard removes the private directory if binding fails
+    let guard = SocketGuard::new(path);
+    // A socket left behind by a previous process with the same pid
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+    debug!("Control socket listening on {}", socket.display());
+    Ok(Self {
+      listener,
+      path: socket,
+      guard,
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Forward the requests to the tracer.
+  ///
+  /// Only the connections from the same user are accepted.
+  /// The socket is removed when the returned guard is dropped.
+  pub fn serve(self, tracer: Arc<Tracer>) -> (JoinHandle<()>, SocketGuard) {
+    let Self {
+      listener, guard, ..
+    } = self;
+    let uid = Uid::effective().as_raw();
+    let task = tokio::spawn(async move {
+      loop {
+        match listener.accept().await {
+          Ok((stream, _)) => {
+            let tracer = tracer.clone();
+            // A client that never finishes its request must not block the others
+            tokio::spawn(async move {
+              let result = match stream.peer_cred() {
+                Ok(cred) if cred.uid() == uid => handle_client(stream, &tracer).await,
+                Ok(cred) => {
+                  warn!("Rejected control connection from uid {}", cred.uid());
+                  respond(stream, CtlResponse::Error("permission denied".to_string())).await
+                }
+                Err(e) => Err(e.into()),
+              };
+              if let Err(e) = result {
+                warn!("Failed to handle control request: {e}");
+              }
+            });
+          }
+          Err(e) => {
+            warn!("Failed to accept control connection: {e}");
+            break;
+          }
+        }
+      }
+    });
+    (task, guard)
+  }
+}
+
+async fn handle_client(stream: UnixStream, tracer: &Tracer) -> color_eyre::Result<()> {
+  let mut reader = BufReader::new(stream);
+  let mut line = String::new();
+  reader.read_line(&mut line).await?;
+  let response = match serde_json::from_str::<CtlRequest>(&line) {
+    Ok(CtlRequest::Run { cmd }) if cmd.is_empty() => {
+      CtlResponse::Error("command cannot be empty".to_string())
+    }
+    Ok(CtlRequest::Run { cmd }) => match tracer.request_spawn_root(cmd) {
+      Ok(()) => CtlResponse::Ok,
+      Err(e) => CtlResponse::Error(e.to_string()),
+    },
+    Ok(CtlRequest::ReloadRules) => match tracer.reload_rules() {
+      Ok(_) => CtlResponse::Ok,
+      Err(e) => CtlResponse::Error(e.to_string()),
+    },
+    Err(e) => CtlResponse::Error(format!("invalid request: {e}")),
+  };
+  respond(reader.into_inner(), response).await
+}
+
+async fn respond(mut stream: UnixStream, response: CtlResponse) -> color_eyre::Result<()> {
+  let mut response = serde_json::to_string(&response)?;
+  response.push('\n');
+  stream.write_all(response.as_bytes()).await?;
+  Ok(())
+}
+
+pub async fn run(command: CtlCommand) -> color_eyre::Result<()> {
+  let (socket, request) = match command {
+    CtlCommand::Run { socket, cmd } => (socket, CtlRequest::Run { cmd }),
+    CtlCommand::ReloadRules { socket } => (socket, CtlRequest::ReloadRules),
+  };
+  let socket = socket
+    .or_else(|| std::env::var_os(CTL_SOCKET_ENV).map(PathBuf::from))
+    .ok_or_else(|| {
+      eyre!("No control socket specified").with_suggestion(|| {
+        "Run this command inside a tracexec TUI session or specify the socket path with --socket"
+      })
+    })?;
+  let stream = UnixStream::connect(&socket).await?;
+  let (reader, mut writer) = stream.into_split();
+  let mut request = serde_json::to_string(&request)?;
+  request.push('\n');
+  writer.write_all(request.as_bytes()).await?;
+  let mut line = String::new();
+  BufReader::new(reader).read_line(&mut line).await?;
+  match serde_json::from_str::<CtlResponse>(&line)? {
+    CtlResponse::Ok => Ok(()),
+    CtlResponse::Error(e) => bail!("tracexec session responded with error: {e}"),
+  }
+}
@@ -1,8 +1,8 @@
 use std::{
   borrow::Cow,
   collections::BTreeMap,
-  fmt::{Debug, Display},
-  hash::Hash,
+  fmt::{Debug, Display, Write as _},
+  hash::{DefaultHasher, Hash, Hasher},
   io::Write,
   sync::{atomic::AtomicU64, Arc},
 };
@@ -22,20 +22,25 @@ use ratatui::{
   style::{Style, Styled},
   text::{Line, Span},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum::Display;
-use tokio::sync::mpsc;
 
 use crate::{
-  action::CopyTarget,
-  cli::{self, args::ModifierArgs},
+  action::{CopyTarget, SupportedShell},
+  audit::AuditRule,
+  caps::Capabilities,
+  channel::MessageSender,
+  cli::{self, args::ModifierArgs, options::EscapeStyle},
+  container::Container,
+  path_map,
   printer::ListPrinter,
-  proc::{cached_string, BaselineInfo, EnvDiff, FileDescriptorInfoCollection, Interpreter},
-  tracer::{state::ProcessExit, BreakPointHit, InspectError},
-  tui::{
-    event_line::{EventLine, Mask},
-    theme::THEME,
+  proc::{
+    cached_str, cached_string, BaselineInfo, Credentials, EnvDiff, ExePrivileges,
+    FileDescriptorInfoCollection, Interpreter, ProcessHandle,
   },
+  redact,
+  tracer::{state::ProcessExit, BreakPointHit, InspectError},
+  tui::{event_line::EventLine, theme::THEME},
 };
 
 #[cfg(feature = "ebpf")]
@@ -92,6 +97,8 @@ impl From<&FriendlyError> for &'static str {
     match value {
       FriendlyError::InspectError(_) => "[err: failed to inspect]",
       #[cfg(feature = "ebpf")]
+      FriendlyError::Bpf(BpfError::ArgvTruncated) => "[err: argv truncated]",
+      #[cfg(feature = "ebpf")]
       FriendlyError::Bpf(_) => "[err: bpf error]",
     }
   }
@@ -129,6 +136,16 @@ impl Serialize for OutputMsg {
   }
 }
 
+/// Errors are not preserved and deserialized as plain strings.
+impl<'de> Deserialize<'de> for OutputMsg {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    String::deserialize(deserializer).map(|s| Self::Ok(cached_string(s)))
+  }
+}
+
 impl Display for OutputMsg {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -151,6 +168,14 @@ impl From<ArcStr> for OutputMsg {
   }
 }
 
+/// Split the arguments at `max_args`, returning the ones to display and the number of the omitted ones
+pub fn truncate_argv(argv: &[OutputMsg], max_args: Option<usize>) -> (&[OutputMsg], usize) {
+  match max_args {
+    Some(max_args) if argv.len() > max_args => (&argv[..max_args], argv.len() - max_args),
+    _ => (argv, 0),
+  }
+}
+
 impl OutputMsg {
   pub fn not_ok(&self) -> bool {
     !matches!(self, Self::Ok(_))
@@ -172,6 +197,22 @@ impl OutputMsg {
     }
   }
 
+  /// Truncate the message to `max_len` characters, followed by the number of the omitted characters
+  pub fn truncated(&self, max_len: Option<usize>) -> Self {
+    let (Self::Ok(s) | Self::PartialOk(s)) = self else {
+      return self.clone();
+    };
+    let Some((end, _)) = max_len.and_then(|max_len| s.char_indices().nth(max_len)) else {
+      return self.clone();
+    };
+    let omitted = s[end..].chars().count();
+    let truncated: ArcStr = format!("{}…(+{omitted} chars)", &s[..end]).into();
+    match self {
+      Self::Ok(_) => Self::Ok(truncated),
+      _ => Self::PartialOk(truncated),
+    }
+  }
+
   /// Join two paths with a '/', preserving the semantics of [`OutputMsg`]
   pub fn join(&self, path: impl AsRef<str>) -> Self {
     let path = path.as_ref();
@@ -229,6 +270,14 @@ impl OutputMsg {
     }
   }
 
+  /// Escape the content for the shell if it is not error
+  pub fn shell_escaped(&self, shell: SupportedShell) -> Cow<'static, str> {
+    match self {
+      Self::Ok(s) | Self::PartialOk(s) => Cow::Owned(shell.quote(s)),
+      Self::Err(e) => Cow::Borrowed(<&'static str>::from(e)),
+    }
+  }
+
   pub fn tui_styled(&self, style: Style) -> Span {
     match self {
       Self::Ok(s) => (*s).set_style(style),
@@ -251,18 +300,14 @@ impl OutputMsg {
 
   pub fn cli_escaped_styled(
     &self,
+    escape: EscapeStyle,
     style: owo_colors::Style,
   ) -> Either<impl Display + '_, impl Display> {
-    // We (ab)use Rust's Debug feature to escape our string.
-    struct DebugAsDisplay<T: Debug>(T);
-    impl<T: Debug> Display for DebugAsDisplay<T> {
-      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
-      }
-    }
     match self {
-      Self::Ok(s) => Either::Left(style.style(DebugAsDisplay(s))),
-      Self::PartialOk(s) => Either::Left(cli::theme::THEME.inline_error.style(DebugAsDisplay(s))),
+      Self::Ok(s) => Either::Left(style.style(Escaped { escape, s })),
+      Self::PartialOk(s) => {
+        Either::Left(cli::theme::THEME.inline_error.style(Escaped { escape, s }))
+      }
       Self::Err(e) => Either::Right(
         cli::theme::THEME
           .inline_error
@@ -270,6 +315,64 @@ impl OutputMsg {
       ),
     }
   }
+
+  /// Quote the content and escape the non-printable characters in it if it is not error
+  pub fn escaped(&self, escape: EscapeStyle) -> Cow<'static, str> {
+    match self {
+      Self::Ok(s) | Self::PartialOk(s) => Cow::Owned(Escaped { escape, s }.to_string()),
+      Self::Err(e) => Cow::Borrowed(<&'static str>::from(e)),
+    }
+  }
+
+  /// Like [`OutputMsg::escaped`], but the escape style only applies to content with
+  /// non-printable characters. Other content is displayed as usual.
+  pub fn escaped_if_unprintable(&self, escape: EscapeStyle) -> Cow<'static, str> {
+    match self {
+      Self::Ok(s) | Self::PartialOk(s) if s.chars().any(char::is_control) => self.escaped(escape),
+      _ => Cow::Owned(self.to_string()),
+    }
+  }
+}
+
+/// A quoted string whose non-printable characters are escaped
+pub struct Escaped<'a> {
+  pub escape: EscapeStyle,
+  pub s: &'a str,
+}
+
+impl Display for Escaped<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.escape == EscapeStyle::Rust {
+      // We (ab)use Rust's Debug feature to escape our string.
+      return Debug::fmt(self.s, f);
+    }
+    f.write_char('"')?;
+    for c in self.s.chars() {
+      match (self.escape, c) {
+        (_, '"') => f.write_str("\\\"")?,
+        (_, '\\') => f.write_str("\\\\")?,
+        (EscapeStyle::C, '\n') => f.write_str("\\n")?,
+        (EscapeStyle::C, '\t') => f.write_str("\\t")?,
+        (EscapeStyle::C, '\r') => f.write_str("\\r")?,
+        (EscapeStyle::C, '\x07') => f.write_str("\\a")?,
+        (EscapeStyle::C, '\x08') => f.write_str("\\b")?,
+        (EscapeStyle::C, '\x0b') => f.write_str("\\v")?,
+        (EscapeStyle::C, '\x0c') => f.write_str("\\f")?,
+        (escape, c) if c.is_control() => {
+          let mut buf = [0; 4];
+          for b in c.encode_utf8(&mut buf).bytes() {
+            if escape == EscapeStyle::C {
+              write!(f, "\\{b:03o}")?;
+            } else {
+              write!(f, "\\x{b:02x}")?;
+            }
+          }
+        }
+        (_, c) => f.write_char(c)?,
+      }
+    }
+    f.write_char('"')
+  }
 }
 
 #[derive(Debug, Clone, Display, PartialEq, Eq)]
@@ -341,11 +444,58 @@ pub enum TracerEventDetails {
     pid: Pid,
   },
   Exec(Box<ExecEvent>),
-  TraceeSpawn(Pid),
+  TraceeSpawn {
+    pid: Pid,
+    /// A handle to the root tracee, opened before it can be reaped
+    process: Option<Arc<ProcessHandle>>,
+  },
   TraceeExit {
     signal: Option<Signal>,
     exit_code: i32,
   },
+  ProcessExit {
+    pid: Pid,
+    comm: ArcStr,
+    exit: ProcessExit,
+  },
+  SignalDelivery {
+    pid: Pid,
+    comm: ArcStr,
+    signal: Signal,
+  },
+  /// The process left the session of its parent after its parent exited,
+  /// i.e. it daemonized by a double fork and `setsid`
+  Daemonize {
+    pid: Pid,
+    comm: ArcStr,
+    sid: Pid,
+  },
+}
+
+/// The kinds are serialized as their bit in the filter, i.e. `1 << n` for the n-th variant.
+///
+/// The serialized values are part of the export formats.
+/// Never reorder the variants of [`TracerEventDetails`]. New variants must be appended.
+impl Serialize for TracerEventDetailsKind {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    serializer.serialize_u32(*self as u32)
+  }
+}
+
+impl<'de> Deserialize<'de> for TracerEventDetailsKind {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let value = u32::deserialize(deserializer)?;
+    BitFlags::<Self>::from_bits(value)
+      .ok()
+      .and_then(BitFlags::exactly_one)
+      .ok_or_else(|| serde::de::Error::custom(format!("unknown event kind: {value}")))
+  }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -357,21 +507,80 @@ pub struct TracerEventMessage {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExecEvent {
   pub pid: Pid,
+  /// The parent pid of the process, if known
+  pub ppid: Option<Pid>,
   pub cwd: OutputMsg,
   pub comm: ArcStr,
   pub filename: OutputMsg,
   pub argv: Arc<Result<Vec<OutputMsg>, InspectError>>,
   pub envp: Arc<Result<BTreeMap<OutputMsg, OutputMsg>, InspectError>>,
   pub interpreter: Option<Vec<Interpreter>>,
+  /// The container that the process lives in
+  pub container: Option<Container>,
+  /// The cgroup path of the process
+  pub cgroup: Option<ArcStr>,
+  /// The resolved path of the executable if the exec is successful
+  pub exe: Option<OutputMsg>,
+  /// The hash of the executable in the form of `<algorithm>:<hex digest>`
+  pub exe_hash: Option<ArcStr>,
+  /// Whether the executable is setuid/setgid or has file capabilities
+  pub privileges: Option<ExePrivileges>,
+  /// The uid and gid of the process after the exec
+  pub credentials: Option<Credentials>,
+  /// The capabilities of the process after the exec
+  pub capabilities: Option<Capabilities>,
+  /// The SELinux context or the AppArmor profile of the process after the exec
+  pub security_label: Option<ArcStr>,
+  /// The security label before the exec if the exec changes it
+  pub security_label_before: Option<ArcStr>,
+  /// The suspicious patterns found in `--audit` mode
+  pub audit: Vec<AuditRule>,
   pub env_diff: Result<EnvDiff, InspectError>,
   pub fdinfo: Arc<FileDescriptorInfoCollection>,
   pub result: i64,
+  /// A handle to the process, opened by the tracer while the process is stopped after the exec
+  pub process: Option<Arc<ProcessHandle>>,
+}
+
+/// A hash of what makes two execs identical: the filename, argv, envp and result
+pub fn exec_dedup_key(
+  filename: &OutputMsg,
+  argv: &Result<Vec<OutputMsg>, InspectError>,
+  envp: &Result<BTreeMap<OutputMsg, OutputMsg>, InspectError>,
+  result: i64,
+) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  // Errno is not Hash, so hash the discriminant and the errno code instead
+  fn hash_result<T: Hash>(r: &Result<T, InspectError>, hasher: &mut DefaultHasher) {
+    match r {
+      Ok(v) => (0u8, v).hash(hasher),
+      Err(e) => (1u8, *e as i32).hash(hasher),
+    }
+  }
+  filename.hash(&mut hasher);
+  hash_result(argv, &mut hasher);
+  hash_result(envp, &mut hasher);
+  result.hash(&mut hasher);
+  hasher.finish()
+}
+
+impl ExecEvent {
+  pub fn dedup_key(&self) -> u64 {
+    exec_dedup_key(&self.filename, &self.argv, &self.envp, self.result)
+  }
+
+  pub fn is_successful(&self) -> bool {
+    self.result == 0
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RuntimeModifier {
   pub show_env: bool,
   pub show_cwd: bool,
+  pub show_argv: bool,
+  pub show_filename: bool,
+  pub show_comm: bool,
 }
 
 impl Default for RuntimeModifier {
@@ -379,6 +588,9 @@ impl Default for RuntimeModifier {
     Self {
       show_env: true,
       show_cwd: true,
+      show_argv: true,
+      show_filename: true,
+      show_comm: true,
     }
   }
 }
@@ -388,6 +600,60 @@ impl TracerEventDetails {
     TracerMessage::Event(self.into())
   }
 
+  pub fn kind(&self) -> TracerEventDetailsKind {
+    match self {
+      Self::Info(_) => TracerEventDetailsKind::Info,
+      Self::Warning(_) => TracerEventDetailsKind::Warning,
+      Self::Error(_) => TracerEventDetailsKind::Error,
+      Self::NewChild { .. } => TracerEventDetailsKind::NewChild,
+      Self::Exec(_) => TracerEventDetailsKind::Exec,
+      Self::TraceeSpawn { .. } => TracerEventDetailsKind::TraceeSpawn,
+      Self::TraceeExit { .. } => TracerEventDetailsKind::TraceeExit,
+      Self::ProcessExit { .. } => TracerEventDetailsKind::ProcessExit,
+      Self::SignalDelivery { .. } => TracerEventDetailsKind::SignalDelivery,
+      Self::Daemonize { .. } => TracerEventDetailsKind::Daemonize,
+    }
+  }
+
+  pub fn is_failed_exec(&self) -> bool {
+    matches!(self, Self::Exec(exec) if !exec.is_successful())
+  }
+
+  /// The process that the event is about
+  pub fn pid(&self) -> Option<Pid> {
+    match self {
+      Self::Info(msg) | Self::Warning(msg) | Self::Error(msg) => msg.pid,
+      // The parent process is the one that forks
+      Self::NewChild { ppid, .. } => Some(*ppid),
+      Self::Exec(exec) => Some(exec.pid),
+      Self::TraceeSpawn { pid, .. } => Some(*pid),
+      Self::TraceeExit { .. } => None,
+      Self::ProcessExit { pid, .. }
+      | Self::SignalDelivery { pid, .. }
+      | Self::Daemonize { pid, .. } => Some(*pid),
+    }
+  }
+
+  /// The name of the process after the event happens, if known
+  pub fn comm(&self) -> Option<ArcStr> {
+    match self {
+      Self::NewChild { pcomm, .. } => Some(pcomm.clone()),
+      Self::Exec(exec) if exec.result == 0 => match &exec.filename {
+        OutputMsg::Ok(filename) => Some(
+          filename
+            .rsplit_once('/')
+            .map_or_else(|| filename.clone(), |(_, name)| cached_str(name)),
+        ),
+        _ => None,
+      },
+      Self::Exec(exec) => Some(exec.comm.clone()),
+      Self::ProcessExit { comm, .. }
+      | Self::SignalDelivery { comm, .. }
+      | Self::Daemonize { comm, .. } => Some(comm.clone()),
+      _ => None,
+    }
+  }
+
   pub fn to_tui_line(
     &self,
     baseline: &BaselineInfo,
@@ -397,20 +663,13 @@ impl TracerEventDetails {
     event_status: Option<EventStatus>,
   ) -> Line<'static> {
     self
-      .to_event_line(
-        baseline,
-        cmdline_only,
-        modifier,
-        rt_modifier,
-        event_status,
-        false,
-      )
+      .to_event_line(baseline, cmdline_only, modifier, rt_modifier, event_status)
       .line
   }
 
   /// Convert the event to a EventLine
   ///
-  /// This method is resource intensive and the caller should cache the result
+  /// This method is resource intensive and the caller should only convert the events in view
   pub fn to_event_line(
     &self,
     baseline: &BaselineInfo,
@@ -418,19 +677,8 @@ impl TracerEventDetails {
     modifier: &ModifierArgs,
     rt_modifier: RuntimeModifier,
     event_status: Option<EventStatus>,
-    enable_mask: bool,
   ) -> EventLine {
-    let mut env_range = None;
-    let mut cwd_range = None;
-
-    let rt_modifier_effective = if enable_mask {
-      // Enable all modifiers so that the mask can be toggled later
-      RuntimeModifier::default()
-    } else {
-      rt_modifier
-    };
-
-    let mut line = match self {
+    let line: Line = match self {
       Self::Info(TracerEventMessage { ref msg, pid }) => chain!(
         pid
           .map(|p| [p.to_string().set_style(THEME.pid_in_msg)])
@@ -477,6 +725,9 @@ impl TracerEventDetails {
           env_diff,
           result,
           fdinfo,
+          container,
+          privileges,
+          audit,
           ..
         } = exec.as_ref();
         let mut spans: Vec<Span> = if !cmdline_only {
@@ -489,7 +740,17 @@ impl TracerEventDetails {
               THEME.pid_failure
             })),
             event_status.map(|s| <&'static str>::from(s).into()),
-            Some(format!("<{}>", comm).set_style(THEME.comm)),
+            rt_modifier
+              .show_comm
+              .then(|| format!("<{}>", comm).set_style(THEME.comm)),
+            container
+              .as_ref()
+              .map(|c| format!("[{c}]").set_style(THEME.container)),
+            privileges
+              .filter(|p| p.is_privileged())
+              .map(|p| format!("[{p}]").set_style(THEME.privileged)),
+            (!audit.is_empty())
+              .then(|| format!("[{}]", audit.iter().join(",")).set_style(THEME.audit_finding)),
             Some(": ".into()),
             Some("env".set_style(THEME.tracer_event)),
           ]
@@ -504,58 +765,73 @@ impl TracerEventDetails {
         // Handle argv[0]
         let _ = argv.as_deref().inspect(|v| {
           v.first().inspect(|&arg0| {
-            if filename != arg0 {
+            if filename != arg0 && rt_modifier.show_filename {
               spans.push(space.clone());
               spans.push("-a ".set_style(THEME.arg0));
-              spans.push(arg0.tui_bash_escaped_with_style(THEME.arg0));
+              spans.push(redact::arg(&path_map::map(arg0)).tui_bash_escaped_with_style(THEME.arg0));
             }
           });
         });
         // Handle cwd
-        if cwd != &baseline.cwd && rt_modifier_effective.show_cwd {
-          let range_start = spans.len();
+        if cwd != &baseline.cwd && rt_modifier.show_cwd {
           spans.push(space.clone());
           spans.push("-C ".set_style(THEME.cwd));
           spans.push(cwd.tui_bash_escaped_with_style(THEME.cwd));
-          cwd_range = Some(range_start..(spans.len()))
         }
-        if rt_modifier_effective.show_env {
-          env_range = Some((spans.len(), 0));
+        if rt_modifier.show_env {
           if let Ok(env_diff) = env_diff {
+            let shown = |k: &OutputMsg| modifier.env_filter.is_shown(k.as_ref());
             // Handle env diff
-            for k in env_diff.removed.iter() {
+            for k in env_diff.removed.iter().filter(|k| shown(k)) {
               spans.push(space.clone());
               spans.push("-u ".set_style(THEME.deleted_env_var));
               spans.push(k.tui_bash_escaped_with_style(THEME.deleted_env_var));
             }
-            for (k, v) in env_diff.added.iter() {
+            for (k, v) in env_diff.added.iter().filter(|(k, _)| shown(k)) {
               // Added env vars
               spans.push(space.clone());
               spans.push(k.tui_bash_escaped_with_style(THEME.added_env_var));
               spans.push("=".set_style(THEME.added_env_var));
-              spans.push(v.tui_bash_escaped_with_style(THEME.added_env_var));
+              spans.push(redact::env(k, v).tui_bash_escaped_with_style(THEME.added_env_var));
             }
-            for (k, v) in env_diff.modified.iter() {
+            for (k, v) in env_diff.modified.iter().filter(|(k, _)| shown(k)) {
               // Modified env vars
               spans.push(space.clone());
               spans.push(k.tui_bash_escaped_with_style(THEME.modified_env_var));
               spans.push("=".set_style(THEME.modified_env_var));
-              spans.push(v.tui_bash_escaped_with_style(THEME.modified_env_var));
+              spans.push(redact::env(k, v).tui_bash_escaped_with_style(THEME.modified_env_var));
             }
           }
-          if let Some(r) = env_range.as_mut() {
-            r.1 = spans.len();
-          }
         }
         spans.push(space.clone());
-        // Filename
-        spans.push(filename.tui_bash_escaped_with_style(THEME.filename));
+        // Filename, or argv[0] in its place if the filename is hidden
+        match argv.as_deref().ok().and_then(|v| v.first()) {
+          Some(arg0) if !rt_modifier.show_filename => {
+            spans.push(redact::arg(&path_map::map(arg0)).tui_bash_escaped_with_style(THEME.arg0));
+          }
+          _ => spans.push(path_map::map(filename).tui_bash_escaped_with_style(THEME.filename)),
+        }
         // Argv[1..]
         match argv.as_ref() {
           Ok(argv) => {
+            // Only argv[0] is kept if argv is hidden
+            let max_args = if rt_modifier.show_argv {
+              modifier.max_args
+            } else {
+              Some(1)
+            };
+            let (argv, omitted) = truncate_argv(argv, max_args);
             for arg in argv.iter().skip(1) {
               spans.push(space.clone());
-              spans.push(arg.tui_bash_escaped_with_style(THEME.argv));
+              spans.push(
+                redact::arg(&path_map::map(arg))
+                  .truncated(modifier.max_arg_len)
+                  .tui_bash_escaped_with_style(THEME.argv),
+              );
+            }
+            if omitted > 0 {
+              spans.push(space.clone());
+              spans.push(format!("…(+{omitted} args)").set_style(THEME.argv));
             }
           }
           Err(_) => {
@@ -655,31 +931,53 @@ impl TracerEventDetails {
         signal, exit_code
       )
       .into(),
-      Self::TraceeSpawn(pid) => format!("tracee spawned: {}", pid).into(),
-    };
-    let mut cwd_mask = None;
-    let mut env_mask = None;
-    if enable_mask {
-      if let Some(range) = cwd_range {
-        let mut mask = Mask::new(range);
-        if !rt_modifier.show_cwd {
-          mask.toggle(&mut line);
-        }
-        cwd_mask.replace(mask);
-      }
-      if let Some((start, end)) = env_range {
-        let mut mask = Mask::new(start..end);
-        if !rt_modifier.show_env {
-          mask.toggle(&mut line);
-        }
-        env_mask.replace(mask);
+      Self::TraceeSpawn { pid, .. } => format!("tracee spawned: {}", pid).into(),
+      Self::ProcessExit { pid, comm, exit } => {
+        let (pid_style, description) = match exit {
+          ProcessExit::Code(0) => (
+            THEME.pid_success,
+            "exited with code 0".set_style(THEME.status_process_exited_normally),
+          ),
+          ProcessExit::Code(code) => (
+            THEME.pid_failure,
+            format!("exited with code {code}").set_style(THEME.status_process_exited_abnormally),
+          ),
+          ProcessExit::Signal(sig) => (
+            THEME.pid_failure,
+            format!("terminated by signal {sig}").set_style(THEME.status_process_signaled),
+          ),
+        };
+        [
+          Some(pid.to_string().set_style(pid_style)),
+          event_status.map(|s| <&'static str>::from(s).into()),
+          Some(format!("<{}>", comm).set_style(THEME.comm)),
+          Some(": ".into()),
+          Some(description),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
       }
-    }
-    EventLine {
-      line,
-      cwd_mask,
-      env_mask,
-    }
+      Self::SignalDelivery { pid, comm, signal } => [
+        pid.to_string().set_style(THEME.pid_in_msg),
+        format!("<{}>", comm).set_style(THEME.comm),
+        ": ".into(),
+        "received ".set_style(THEME.tracer_event),
+        signal.to_string().set_style(THEME.signal),
+      ]
+      .into_iter()
+      .collect(),
+      Self::Daemonize { pid, comm, sid } => [
+        pid.to_string().set_style(THEME.pid_in_msg),
+        format!("<{}>", comm).set_style(THEME.comm),
+        ": ".into(),
+        "daemonized".set_style(THEME.tracer_event),
+        format!(" into session {sid}").into(),
+      ]
+      .into_iter()
+      .collect(),
+    };
+    line.into()
   }
 }
 
@@ -693,7 +991,7 @@ impl TracerEventDetails {
   ) -> Cow<'a, str> {
     if CopyTarget::Line == target {
       return self
-        .to_event_line(baseline, false, modifier_args, rt_modifier, None, false)
+        .to_event_line(baseline, false, modifier_args, rt_modifier, None)
         .to_string()
         .into();
     }
@@ -703,28 +1001,17 @@ impl TracerEventDetails {
     };
     let mut modifier_args = ModifierArgs::default();
     match target {
-      CopyTarget::Commandline(_) => self
-        .to_event_line(
-          baseline,
-          true,
-          &modifier_args,
-          Default::default(),
-          None,
-          false,
-        )
+      CopyTarget::Commandline(SupportedShell::Bash) => self
+        .to_event_line(baseline, true, &modifier_args, Default::default(), None)
         .to_string()
         .into(),
+      CopyTarget::Commandline(shell) => {
+        Self::cmdline_for_shell(event, Some(baseline), shell).into()
+      }
       CopyTarget::CommandlineWithStdio(_) => {
         modifier_args.stdio_in_cmdline = true;
         self
-          .to_event_line(
-            baseline,
-            true,
-            &modifier_args,
-            Default::default(),
-            None,
-            false,
-          )
+          .to_event_line(baseline, true, &modifier_args, Default::default(), None)
           .to_string()
           .into()
       }
@@ -732,21 +1019,14 @@ impl TracerEventDetails {
         modifier_args.fd_in_cmdline = true;
         modifier_args.stdio_in_cmdline = true;
         self
-          .to_event_line(
-            baseline,
-            true,
-            &modifier_args,
-            Default::default(),
-            None,
-            false,
-          )
+          .to_event_line(baseline, true, &modifier_args, Default::default(), None)
           .to_string()
           .into()
       }
       CopyTarget::Env => match event.envp.as_ref() {
         Ok(envp) => envp
           .iter()
-          .map(|(k, v)| format!("{}={}", k, v))
+          .map(|(k, v)| format!("{}={}", k, redact::env(k, v)))
           .join("\n")
           .into(),
         Err(e) => format!("[failed to read envp: {e}]").into(),
@@ -758,40 +1038,106 @@ impl TracerEventDetails {
         let mut result = String::new();
         result.push_str("# Added:\n");
         for (k, v) in env_diff.added.iter() {
-          result.push_str(&format!("{}={}\n", k, v));
+          result.push_str(&format!("{}={}\n", k, redact::env(k, v)));
         }
         result.push_str("# Modified: (original first)\n");
         for (k, v) in env_diff.modified.iter() {
           result.push_str(&format!(
             "{}={}\n{}={}\n",
             k,
-            baseline.env.get(k).unwrap(),
+            redact::env(k, baseline.env.get(k).unwrap()),
             k,
-            v
+            redact::env(k, v)
           ));
         }
         result.push_str("# Removed:\n");
         for k in env_diff.removed.iter() {
-          result.push_str(&format!("{}={}\n", k, baseline.env.get(k).unwrap()));
+          result.push_str(&format!(
+            "{}={}\n",
+            k,
+            redact::env(k, baseline.env.get(k).unwrap())
+          ));
         }
         result.into()
       }
-      CopyTarget::Argv => Self::argv_to_string(&event.argv).into(),
+      CopyTarget::Argv => Self::argv_to_string(&event.argv, EscapeStyle::default()).into(),
+      CopyTarget::ArgvJson => match event.argv.as_ref() {
+        Ok(argv) => serde_json::to_string(
+          &argv
+            .iter()
+            .map(|arg| redact::arg(&path_map::map(arg)))
+            .collect_vec(),
+        )
+        .unwrap()
+        .into(),
+        Err(e) => format!("[failed to read argv: {e}]").into(),
+      },
+      CopyTarget::Pid => event.pid.to_string().into(),
       CopyTarget::Filename => Cow::Borrowed(event.filename.as_ref()),
       CopyTarget::SyscallResult => event.result.to_string().into(),
       CopyTarget::Line => unreachable!(),
     }
   }
 
-  pub fn argv_to_string(argv: &Result<Vec<OutputMsg>, InspectError>) -> String {
+  /// The command line without file descriptors, quoted for the shell.
+  ///
+  /// The command changes to the working directory of the exec if it differs from the
+  /// working directory of the baseline. Pass no baseline to leave that to the caller.
+  pub fn cmdline_for_shell(
+    exec: &ExecEvent,
+    baseline: Option<&BaselineInfo>,
+    shell: SupportedShell,
+  ) -> String {
+    let quote = |msg: &OutputMsg| msg.shell_escaped(shell).into_owned();
+    let mut words = vec!["env".to_owned()];
+    if let Some(arg0) = exec.argv.as_deref().ok().and_then(|argv| argv.first()) {
+      if &exec.filename != arg0 {
+        words.push("-a".to_owned());
+        words.push(quote(&redact::arg(&path_map::map(arg0))));
+      }
+    }
+    if baseline.is_some_and(|baseline| exec.cwd != baseline.cwd) {
+      words.push("-C".to_owned());
+      words.push(quote(&exec.cwd));
+    }
+    if let Ok(env_diff) = exec.env_diff.as_ref() {
+      for k in env_diff.removed.iter() {
+        words.push("-u".to_owned());
+        words.push(quote(k));
+      }
+      for (k, v) in chain!(env_diff.added.iter(), env_diff.modified.iter()) {
+        words.push(format!("{}={}", quote(k), quote(&redact::env(k, v))));
+      }
+    }
+    words.push(quote(&path_map::map(&exec.filename)));
+    match exec.argv.as_ref() {
+      Ok(argv) => words.extend(
+        argv
+          .iter()
+          .skip(1)
+          .map(|arg| quote(&redact::arg(&path_map::map(arg)))),
+      ),
+      Err(_) => words.push("[failed to read argv]".to_owned()),
+    }
+    words.join(" ")
+  }
+
+  pub fn argv_to_string(
+    argv: &Result<Vec<OutputMsg>, InspectError>,
+    escape_style: EscapeStyle,
+  ) -> String {
     let Ok(argv) = argv else {
       return "[failed to read argv]".into();
     };
+    let argv = argv
+      .iter()
+      .map(|arg| redact::arg(&path_map::map(arg)).escaped(escape_style))
+      .collect_vec();
     let mut result =
       Vec::with_capacity(argv.iter().map(|s| s.as_ref().len() + 3).sum::<usize>() + 2);
     let list_printer = ListPrinter::new(crate::printer::ColorLevel::Less);
-    list_printer.print_string_list(&mut result, argv).unwrap();
-    // SAFETY: argv is printed in debug format, which is always UTF-8
+    list_printer.print_string_list(&mut result, &argv).unwrap();
+    // SAFETY: argv is printed in escaped format, which is always UTF-8
     unsafe { String::from_utf8_unchecked(result) }
   }
 
@@ -824,7 +1170,7 @@ impl TracerEventDetails {
 impl FilterableTracerEventDetails {
   pub fn send_if_match(
     self,
-    tx: &mpsc::UnboundedSender<TracerMessage>,
+    tx: &MessageSender,
     filter: BitFlags<TracerEventDetailsKind>,
   ) -> color_eyre::Result<()> {
     if let Some(evt) = self.filter_and_take(filter) {
@@ -880,6 +1226,22 @@ pub enum EventStatus {
   ProcessSignaled(Signal),
 }
 
+impl From<ProcessExit> for EventStatus {
+  fn from(exit: ProcessExit) -> Self {
+    match exit {
+      ProcessExit::Code(0) => Self::ProcessExitedNormally,
+      ProcessExit::Code(c) => Self::ProcessExitedAbnormally(c),
+      ProcessExit::Signal(Signal::SIGTERM) => Self::ProcessTerminated,
+      ProcessExit::Signal(Signal::SIGKILL) => Self::ProcessKilled,
+      ProcessExit::Signal(Signal::SIGINT) => Self::ProcessInterrupted,
+      ProcessExit::Signal(Signal::SIGSEGV) => Self::ProcessSegfault,
+      ProcessExit::Signal(Signal::SIGABRT) => Self::ProcessAborted,
+      ProcessExit::Signal(Signal::SIGILL) => Self::ProcessIllegalInstruction,
+      ProcessExit::Signal(s) => Self::ProcessSignaled(s),
+    }
+  }
+}
+
 impl From<EventStatus> for &'static str {
   fn from(value: EventStatus) -> Self {
     match value {
@@ -0,0 +1,81 @@
+//! Hash the executed binaries so that it could be proven exactly which binary ran.
+
+use std::{collections::HashMap, fs::File, io, os::unix::fs::MetadataExt, path::Path, sync::Mutex};
+
+use arcstr::ArcStr;
+use nix::unistd::Pid;
+use sha2::Digest;
+
+use crate::cli::options::HashAlgorithm;
+
+pub struct ExeHasher {
+  algorithm: HashAlgorithm,
+  /// (dev, inode, mtime, mtime nsec) -> hash.
+  /// The modification time tells apart a binary that is rewritten in place.
+  cache: Mutex<HashMap<(u64, u64, i64, i64), ArcStr>>,
+}
+
+impl ExeHasher {
+  pub fn new(algorithm: HashAlgorithm) -> Self {
+    Self {
+      algorithm,
+      cache: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Hash the executable of the process.
+  ///
+  /// `/proc/<pid>/exe` is opened instead of the path of the executable so that
+  /// the binary that is deleted or replaced after the exec is still the one hashed.
+  pub fn hash_exe(&self, pid: Pid) -> io::Result<ArcStr> {
+    self.hash_file(Path::new(&format!("/proc/{pid}/exe")))
+  }
+
+  /// Returns the hash in the form of `<algorithm>:<hex digest>`
+  pub fn hash_file(&self, path: &Path) -> io::Result<ArcStr> {
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    let key = (meta.dev(), meta.ino(), meta.mtime(), meta.mtime_nsec());
+    if let Some(hash) = self.cache.lock().unwrap().get(&key) {
+      return Ok(hash.clone());
+    }
+    let digest = match self.algorithm {
+      HashAlgorithm::Sha256 => {
+        let mut hasher = sha2::Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        format!("{:x}", hasher.finalize())
+      }
+      HashAlgorithm::Blake3 => {
+        let mut hasher = blake3::Hasher::new();
+        io::copy(&mut file, &mut hasher)?;
+        hasher.finalize().to_hex().to_string()
+      }
+    };
+    let hash = ArcStr::from(format!("{}:{digest}", self.algorithm));
+    self.cache.lock().unwrap().insert(key, hash.clone());
+    Ok(hash)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use super::*;
+
+  #[test]
+  fn hashes_are_cached_per_file() {
+    let path = std::env::temp_dir().join(format!("tracexec-exe-hash-{}", std::process::id()));
+    File::create(&path).unwrap().write_all(b"abc").unwrap();
+    let hasher = ExeHasher::new(HashAlgorithm::Sha256);
+    let hash = hasher.hash_file(&path).unwrap();
+    assert_eq!(
+      hash,
+      "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(hasher.cache.lock().unwrap().len(), 1);
+    assert_eq!(hasher.hash_file(&path).unwrap(), hash);
+    assert_eq!(hasher.cache.lock().unwrap().len(), 1);
+    std::fs::remove_file(path).unwrap();
+  }
+}
@@ -1,16 +1,35 @@
 //! Data structures for export command
-use std::{error::Error, sync::Arc};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap},
+  error::Error,
+  fmt::Display,
+  str::FromStr,
+  sync::Arc,
+  time::Instant,
+};
 
 use arcstr::ArcStr;
-use nix::libc::pid_t;
-use serde::Serialize;
+use nix::{errno::Errno, libc::pid_t, sys::signal::Signal, unistd::Pid};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-  event::{ExecEvent, OutputMsg},
-  proc::{BaselineInfo, EnvDiff, FileDescriptorInfoCollection},
+  audit::AuditRule,
+  caps::Capabilities,
+  cli::args::TraceNotesArgs,
+  container::Container,
+  event::{
+    ExecEvent, OutputMsg, ProcessStateUpdate, ProcessStateUpdateEvent, TracerEvent,
+    TracerEventDetails, TracerEventDetailsKind, TracerEventMessage, TracerMessage,
+  },
+  proc::{
+    cached_argv, cached_envp, BaselineInfo, Credentials, EnvDiff, ExePrivileges,
+    FileDescriptorInfoCollection, Interpreter,
+  },
+  tracer::state::ProcessExit,
 };
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "result", content = "value", rename_all = "kebab-case")]
 pub enum JsonResult<T: Clone> {
   Success(T),
@@ -24,12 +43,30 @@ impl<T: Serialize + Clone> JsonResult<T> {
       Err(e) => Self::Error(e.to_string()),
     }
   }
+
+  /// The errno is recovered from the message produced by [`JsonResult::from_result`].
+  /// Other errors become [`Errno::UnknownErrno`].
+  pub fn into_result(self) -> Result<T, Errno> {
+    match self {
+      Self::Success(v) => Ok(v),
+      Self::Error(message) => Err(
+        (1..256)
+          .map(Errno::from_raw)
+          .find(|errno| *errno != Errno::UnknownErrno && errno.to_string() == message)
+          .unwrap_or(Errno::UnknownErrno),
+      ),
+    }
+  }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonExecEvent {
   pub id: u64,
+  /// Always [`TracerEventDetailsKind::Exec`], failed execs have a non-zero `result`
+  pub kind: TracerEventDetailsKind,
   pub pid: pid_t,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ppid: Option<pid_t>,
   pub cwd: OutputMsg,
   pub comm_before_exec: ArcStr,
   pub result: i64,
@@ -37,6 +74,29 @@ pub struct JsonExecEvent {
   pub argv: JsonResult<Vec<OutputMsg>>,
   pub env: JsonResult<EnvDiff>,
   pub fdinfo: FileDescriptorInfoCollection,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub interpreter: Option<Vec<Interpreter>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub container: Option<Container>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cgroup: Option<ArcStr>,
+  /// The resolved path of the executable
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exe: Option<OutputMsg>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exe_hash: Option<ArcStr>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub privileges: Option<ExePrivileges>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub credentials: Option<Credentials>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub capabilities: Option<Capabilities>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub security_label: Option<ArcStr>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub security_label_before: Option<ArcStr>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub audit: Vec<AuditRule>,
 }
 
 impl JsonExecEvent {
@@ -49,7 +109,9 @@ impl JsonExecEvent {
     );
     Self {
       id,
+      kind: TracerEventDetailsKind::Exec,
       pid: event.pid.as_raw(),
+      ppid: event.ppid.map(Pid::as_raw),
       cwd: event.cwd,
       comm_before_exec: event.comm,
       result: event.result,
@@ -57,26 +119,129 @@ impl JsonExecEvent {
       argv: JsonResult::from_result(Arc::unwrap_or_clone(event.argv)),
       env: JsonResult::from_result(event.env_diff),
       fdinfo: Arc::unwrap_or_clone(event.fdinfo),
+      interpreter: event.interpreter,
+      container: event.container,
+      cgroup: event.cgroup,
+      exe: event.exe,
+      exe_hash: event.exe_hash,
+      privileges: event.privileges,
+      credentials: event.credentials,
+      capabilities: event.capabilities,
+      security_label: event.security_label,
+      security_label_before: event.security_label_before,
+      audit: event.audit,
+    }
+  }
+
+  /// Reconstruct the exec event. The environment is restored from the baseline and the diff.
+  pub fn into_exec_event(self, baseline: &BaselineInfo) -> ExecEvent {
+    let env_diff = self.env.into_result();
+    let envp = env_diff.as_ref().map(|diff| {
+      let mut envp = baseline.env.clone();
+      for key in diff.removed.iter() {
+        envp.remove(key);
+      }
+      for (key, value) in diff.added.iter().chain(diff.modified.iter()) {
+        envp.insert(key.clone(), value.clone());
+      }
+      envp
+    });
+    ExecEvent {
+      pid: Pid::from_raw(self.pid),
+      ppid: self.ppid.map(Pid::from_raw),
+      cwd: self.cwd,
+      comm: self.comm_before_exec,
+      filename: self.filename,
+      argv: cached_argv(self.argv.into_result()),
+      envp: cached_envp(envp.map_err(|e| *e)),
+      interpreter: self.interpreter,
+      container: self.container,
+      cgroup: self.cgroup,
+      exe: self.exe,
+      exe_hash: self.exe_hash,
+      privileges: self.privileges,
+      credentials: self.credentials,
+      capabilities: self.capabilities,
+      security_label: self.security_label,
+      security_label_before: self.security_label_before,
+      audit: self.audit,
+      env_diff,
+      fdinfo: Arc::new(self.fdinfo),
+      result: self.result,
+      process: None,
     }
   }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Notes attached to a trace by the operator, so that archived traces remain interpretable
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceNotes {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub meta: BTreeMap<String, String>,
+}
+
+impl TraceNotes {
+  pub fn is_empty(&self) -> bool {
+    self.note.is_none() && self.meta.is_empty()
+  }
+}
+
+impl From<TraceNotesArgs> for TraceNotes {
+  fn from(value: TraceNotesArgs) -> Self {
+    Self {
+      note: value.note,
+      meta: value.meta.into_iter().collect(),
+    }
+  }
+}
+
+impl Display for TraceNotes {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if let Some(note) = &self.note {
+      write!(f, "{note}")?;
+      if !self.meta.is_empty() {
+        write!(f, " ")?;
+      }
+    }
+    if !self.meta.is_empty() {
+      write!(f, "(")?;
+      for (i, (key, value)) in self.meta.iter().enumerate() {
+        if i > 0 {
+          write!(f, ", ")?;
+        }
+        write!(f, "{key}={value}")?;
+      }
+      write!(f, ")")?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonMetaData {
   /// version of tracexec that generates this json
-  pub version: &'static str,
-  pub generator: &'static str,
+  pub version: Cow<'static, str>,
+  pub generator: Cow<'static, str>,
   pub baseline: BaselineInfo,
+  #[serde(flatten)]
+  pub notes: TraceNotes,
 }
 
 impl JsonMetaData {
   pub fn new(baseline: BaselineInfo) -> Self {
     Self {
-      version: env!("CARGO_PKG_VERSION"),
-      generator: env!("CARGO_CRATE_NAME"),
+      version: Cow::Borrowed(env!("CARGO_PKG_VERSION")),
+      generator: Cow::Borrowed(env!("CARGO_CRATE_NAME")),
       baseline,
+      notes: TraceNotes::default(),
     }
   }
+
+  pub fn with_notes(self, notes: TraceNotes) -> Self {
+    Self { notes, ..self }
+  }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -85,3 +250,515 @@ pub struct Json {
   pub meta: JsonMetaData,
   pub events: Vec<JsonExecEvent>,
 }
+
+/// A signal, serialized by its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonSignal(pub Signal);
+
+impl Serialize for JsonSignal {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    self.0.as_str().serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for JsonSignal {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let name = Cow::<'de, str>::deserialize(deserializer)?;
+    Signal::from_str(&name)
+      .map(Self)
+      .map_err(|_| serde::de::Error::custom(format!("unknown signal: {name}")))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonExit {
+  Code(i32),
+  Signal(JsonSignal),
+}
+
+impl From<ProcessExit> for JsonExit {
+  fn from(value: ProcessExit) -> Self {
+    match value {
+      ProcessExit::Code(code) => Self::Code(code),
+      ProcessExit::Signal(sig) => Self::Signal(JsonSignal(sig)),
+    }
+  }
+}
+
+impl From<JsonExit> for ProcessExit {
+  fn from(value: JsonExit) -> Self {
+    match value {
+      JsonExit::Code(code) => Self::Code(code),
+      JsonExit::Signal(sig) => Self::Signal(sig.0),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonMessage {
+  pub id: u64,
+  /// Either [`TracerEventDetailsKind::Info`], [`TracerEventDetailsKind::Warning`]
+  /// or [`TracerEventDetailsKind::Error`]
+  pub kind: TracerEventDetailsKind,
+  pub pid: Option<pid_t>,
+  pub msg: String,
+}
+
+impl JsonMessage {
+  fn new(id: u64, kind: TracerEventDetailsKind, msg: TracerEventMessage) -> Self {
+    Self {
+      id,
+      kind,
+      pid: msg.pid.map(Pid::as_raw),
+      msg: msg.msg,
+    }
+  }
+
+  fn into_message(self) -> TracerEventMessage {
+    TracerEventMessage {
+      pid: self.pid.map(Pid::from_raw),
+      msg: self.msg,
+    }
+  }
+}
+
+/// An entry of the record format.
+///
+/// A record is a JSON stream. The first line is the [`JsonMetaData`]
+/// and each of the following lines is a [`RecordEntry`].
+/// Unlike the other export formats, it keeps every event so that it could be replayed in the TUI.
+/// Every event carries its [`TracerEventDetailsKind`] in the `kind` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordEntry {
+  Info(JsonMessage),
+  Warning(JsonMessage),
+  Error(JsonMessage),
+  NewChild {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    ppid: pid_t,
+    pcomm: ArcStr,
+    pid: pid_t,
+  },
+  Exec(Box<JsonExecEvent>),
+  TraceeSpawn {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    pid: pid_t,
+  },
+  TraceeExit {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    signal: Option<JsonSignal>,
+    exit_code: i32,
+  },
+  ProcessExit {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    pid: pid_t,
+    comm: ArcStr,
+    exit: JsonExit,
+  },
+  SignalDelivery {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    pid: pid_t,
+    comm: ArcStr,
+    signal: JsonSignal,
+  },
+  Daemonize {
+    id: u64,
+    kind: TracerEventDetailsKind,
+    pid: pid_t,
+    comm: ArcStr,
+    sid: pid_t,
+  },
+  /// The process that produced the events has exited
+  StateUpdate {
+    pid: pid_t,
+    ids: Vec<u64>,
+    exit: JsonExit,
+  },
+  /// The event is bookmarked in the TUI
+  Bookmark {
+    id: u64,
+  },
+}
+
+impl RecordEntry {
+  /// Returns [`None`] for messages that are not recorded
+  pub fn from_message(msg: TracerMessage) -> Option<Self> {
+    Some(match msg {
+      TracerMessage::Event(TracerEvent { details, id }) => {
+        let kind = details.kind();
+        match details {
+          TracerEventDetails::Info(m) => Self::Info(JsonMessage::new(id, kind, m)),
+          TracerEventDetails::Warning(m) => Self::Warning(JsonMessage::new(id, kind, m)),
+          TracerEventDetails::Error(m) => Self::Error(JsonMessage::new(id, kind, m)),
+          TracerEventDetails::NewChild { ppid, pcomm, pid } => Self::NewChild {
+            id,
+            kind,
+            ppid: ppid.as_raw(),
+            pcomm,
+            pid: pid.as_raw(),
+          },
+          TracerEventDetails::Exec(exec) => Self::Exec(Box::new(JsonExecEvent::new(id, *exec))),
+          TracerEventDetails::TraceeSpawn { pid, .. } => Self::TraceeSpawn {
+            id,
+            kind,
+            pid: pid.as_raw(),
+          },
+          TracerEventDetails::TraceeExit { signal, exit_code } => Self::TraceeExit {
+            id,
+            kind,
+            signal: signal.map(JsonSignal),
+            exit_code,
+          },
+          TracerEventDetails::ProcessExit { pid, comm, exit } => Self::ProcessExit {
+            id,
+            kind,
+            pid: pid.as_raw(),
+            comm,
+            exit: exit.into(),
+          },
+          TracerEventDetails::SignalDelivery { pid, comm, signal } => Self::SignalDelivery {
+            id,
+            kind,
+            pid: pid.as_raw(),
+            comm,
+            signal: JsonSignal(signal),
+          },
+          TracerEventDetails::Daemonize { pid, comm, sid } => Self::Daemonize {
+            id,
+            kind,
+            pid: pid.as_raw(),
+            comm,
+            sid: sid.as_raw(),
+          },
+        }
+      }
+      TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+        update: ProcessStateUpdate::Exit(exit),
+        pid,
+        ids,
+      }) => Self::StateUpdate {
+        pid: pid.as_raw(),
+        ids,
+        exit: exit.into(),
+      },
+      // Breakpoint related updates are meaningless in a record
+      TracerMessage::StateUpdate(_) | TracerMessage::FatalError(_) => return None,
+    })
+  }
+
+  /// Returns [`None`] for bookmarks, which are not messages
+  pub fn into_message(self, baseline: &BaselineInfo) -> Option<TracerMessage> {
+    let event = |id, details| TracerMessage::Event(TracerEvent { details, id });
+    Some(match self {
+      Self::Info(m) => event(m.id, TracerEventDetails::Info(m.into_message())),
+      Self::Warning(m) => event(m.id, TracerEventDetails::Warning(m.into_message())),
+      Self::Error(m) => event(m.id, TracerEventDetails::Error(m.into_message())),
+      Self::NewChild {
+        id,
+        kind: _,
+        ppid,
+        pcomm,
+        pid,
+      } => event(
+        id,
+        TracerEventDetails::NewChild {
+          ppid: Pid::from_raw(ppid),
+          pcomm,
+          pid: Pid::from_raw(pid),
+        },
+      ),
+      Self::Exec(exec) => event(
+        exec.id,
+        TracerEventDetails::Exec(Box::new(exec.into_exec_event(baseline))),
+      ),
+      Self::TraceeSpawn { id, pid, .. } => event(
+        id,
+        TracerEventDetails::TraceeSpawn {
+          pid: Pid::from_raw(pid),
+          process: None,
+        },
+      ),
+      Self::TraceeExit {
+        id,
+        kind: _,
+        signal,
+        exit_code,
+      } => event(
+        id,
+        TracerEventDetails::TraceeExit {
+          signal: signal.map(|s| s.0),
+          exit_code,
+        },
+      ),
+      Self::ProcessExit {
+        id,
+        kind: _,
+        pid,
+        comm,
+        exit,
+      } => event(
+        id,
+        TracerEventDetails::ProcessExit {
+          pid: Pid::from_raw(pid),
+          comm,
+          exit: exit.into(),
+        },
+      ),
+      Self::SignalDelivery {
+        id,
+        kind: _,
+        pid,
+        comm,
+        signal,
+      } => event(
+        id,
+        TracerEventDetails::SignalDelivery {
+          pid: Pid::from_raw(pid),
+          comm,
+          signal: signal.0,
+        },
+      ),
+      Self::Daemonize {
+        id, pid, comm, sid, ..
+      } => event(
+        id,
+        TracerEventDetails::Daemonize {
+          pid: Pid::from_raw(pid),
+          comm,
+          sid: Pid::from_raw(sid),
+        },
+      ),
+      Self::StateUpdate { pid, ids, exit } => TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+        update: ProcessStateUpdate::Exit(exit.into()),
+        pid: Pid::from_raw(pid),
+        ids,
+      }),
+      Self::Bookmark { .. } => return None,
+    })
+  }
+}
+
+/// An event in Chrome's trace event format
+///
+/// https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+#[derive(Debug, Clone, Serialize)]
+pub struct ChromeTraceEvent {
+  pub name: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cat: Option<&'static str>,
+  /// Phase
+  pub ph: &'static str,
+  /// Timestamp in microseconds
+  pub ts: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dur: Option<u64>,
+  pub pid: pid_t,
+  pub tid: pid_t,
+  /// Scope of instant events
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub s: Option<&'static str>,
+  #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+  pub args: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChromeTrace {
+  pub trace_events: Vec<ChromeTraceEvent>,
+  pub display_time_unit: &'static str,
+  pub other_data: JsonMetaData,
+}
+
+/// A process that has not exited, with the start time and name of its current slice
+struct RunningProcess {
+  since: u64,
+  comm: ArcStr,
+}
+
+/// Turns the events into a timeline in Chrome's trace event format.
+///
+/// The events do not carry timestamps, so the time they are received is used.
+/// Each process is a track of duration events, one for every successful exec.
+/// The execs themselves are instant events.
+pub struct ChromeTraceBuilder {
+  start: Instant,
+  events: Vec<ChromeTraceEvent>,
+  running: HashMap<Pid, RunningProcess>,
+  /// pid -> the last known name
+  names: HashMap<Pid, ArcStr>,
+}
+
+impl Default for ChromeTraceBuilder {
+  fn default() -> Self {
+    Self {
+      start: Instant::now(),
+      events: Vec::new(),
+      running: HashMap::new(),
+      names: HashMap::new(),
+    }
+  }
+}
+
+impl ChromeTraceBuilder {
+  fn now(&self) -> u64 {
+    self.start.elapsed().as_micros() as u64
+  }
+
+  fn begin(&mut self, pid: Pid, comm: ArcStr, ts: u64) {
+    self.names.insert(pid, comm.clone());
+    self.running.insert(pid, RunningProcess { since: ts, comm });
+  }
+
+  fn end(&mut self, pid: Pid, ts: u64, args: serde_json::Value) {
+    let Some(process) = self.running.remove(&pid) else {
+      return;
+    };
+    self.events.push(ChromeTraceEvent {
+      name: process.comm.to_string(),
+      cat: Some("process"),
+      ph: "X",
+      ts: process.since,
+      dur: Some(ts - process.since),
+      pid: pid.as_raw(),
+      tid: pid.as_raw(),
+      s: None,
+      args,
+    });
+  }
+
+  pub fn record(&mut self, details: &TracerEventDetails) {
+    let ts = self.now();
+    match details {
+      TracerEventDetails::TraceeSpawn { pid, .. } => {
+        self.begin(*pid, arcstr::literal!("tracee"), ts);
+      }
+      TracerEventDetails::NewChild { pid, pcomm, .. } => {
+        self.begin(*pid, pcomm.clone(), ts);
+      }
+      TracerEventDetails::Exec(exec) => {
+        if !self.running.contains_key(&exec.pid) {
+          self.begin(exec.pid, exec.comm.clone(), ts);
+        }
+        self.events.push(ChromeTraceEvent {
+          name: exec.filename.as_ref().to_owned(),
+          cat: Some("exec"),
+          ph: "i",
+          ts,
+          dur: None,
+          pid: exec.pid.as_raw(),
+          tid: exec.pid.as_raw(),
+          s: Some("t"),
+          args: serde_json::json!({
+            "argv": JsonResult::from_result((*exec.argv).clone()),
+            "cwd": exec.cwd,
+            "result": exec.result,
+          }),
+        });
+        if exec.result == 0 {
+          // Start a new slice for the new program
+          self.end(exec.pid, ts, serde_json::Value::Null);
+          let comm = details.comm().unwrap_or_else(|| exec.comm.clone());
+          self.begin(exec.pid, comm, ts);
+        }
+      }
+      TracerEventDetails::ProcessExit { pid, exit, .. } => {
+        self.end(
+          *pid,
+          ts,
+          serde_json::json!({ "exit": JsonExit::from(*exit) }),
+        );
+      }
+      _ => (),
+    }
+  }
+
+  /// Processes that have not exited are ended at the time of calling this function.
+  pub fn finish(mut self, meta: JsonMetaData) -> ChromeTrace {
+    let ts = self.now();
+    let pids = self.running.keys().copied().collect::<Vec<_>>();
+    for pid in pids {
+      self.end(pid, ts, serde_json::Value::Null);
+    }
+    for (pid, comm) in self.names.iter() {
+      self.events.push(ChromeTraceEvent {
+        name: "process_name".to_string(),
+        cat: None,
+        ph: "M",
+        ts: 0,
+        dur: None,
+        pid: pid.as_raw(),
+        tid: pid.as_raw(),
+        s: None,
+        args: serde_json::json!({ "name": format!("{comm} ({pid})") }),
+      });
+    }
+    ChromeTrace {
+      trace_events: self.events,
+      display_time_unit: "ms",
+      other_data: meta,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nix::{errno::Errno, sys::signal::Signal, unistd::Pid};
+
+  use super::{JsonResult, RecordEntry};
+  use crate::{event::TracerEventDetails, tracer::state::ProcessExit};
+
+  #[test]
+  fn json_result_keeps_errno() {
+    for errno in [Errno::EFAULT, Errno::ESRCH, Errno::E2BIG] {
+      let result = JsonResult::<Vec<String>>::from_result(Err(errno));
+      let json = serde_json::to_string(&result).unwrap();
+      let result: JsonResult<Vec<String>> = serde_json::from_str(&json).unwrap();
+      assert_eq!(result.into_result(), Err(errno));
+    }
+    assert_eq!(
+      JsonResult::<Vec<String>>::Error("something else".to_owned()).into_result(),
+      Err(Errno::UnknownErrno)
+    );
+  }
+
+  #[test]
+  fn record_entries_carry_kind() {
+    let pid = Pid::from_raw(42);
+    for (details, kind) in [
+      (TracerEventDetails::TraceeSpawn { pid, process: None }, 32),
+      (
+        TracerEventDetails::ProcessExit {
+          pid,
+          comm: "sh".into(),
+          exit: ProcessExit::Code(0),
+        },
+        128,
+      ),
+      (
+        TracerEventDetails::TraceeExit {
+          signal: Some(Signal::SIGKILL),
+          exit_code: 137,
+        },
+        64,
+      ),
+    ] {
+      let entry = RecordEntry::from_message(details.into_tracer_msg()).unwrap();
+      let json = serde_json::to_value(&entry).unwrap();
+      let (_, value) = json.as_object().unwrap().iter().next().unwrap();
+      assert_eq!(value["kind"], kind);
+    }
+  }
+}
@@ -0,0 +1,133 @@
+//! Templates like `{pid} {filename} {argv}` that shape the line printed for each exec.
+//!
+//! The template is parsed once when the arguments are parsed and rendered for every exec.
+//! `{{` and `}}` stand for literal braces.
+
+use std::str::FromStr;
+
+use strum::{Display, EnumString, VariantNames};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, VariantNames)]
+#[strum(serialize_all = "snake_case")]
+pub enum Placeholder {
+  /// The time in RFC 3339
+  Time,
+  /// The index of the root command when `--also` is used
+  Root,
+  Pid,
+  /// The parent pid, empty if unknown
+  Ppid,
+  /// The comm before the exec
+  Comm,
+  Filename,
+  /// The arguments quoted for sh
+  Argv,
+  Cwd,
+  /// The cgroup path, empty if unknown
+  Cgroup,
+  /// The resolved executable
+  Exe,
+  /// The hash of the executable when `--hash-exe` is used
+  Hash,
+  /// The return value of the exec, 0 or a negative errno
+  Result,
+  /// The name of the errno, empty if the exec is successful
+  Errno,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+  Literal(String),
+  Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatTemplate {
+  parts: Vec<Part>,
+}
+
+impl FromStr for FormatTemplate {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        '{' if chars.as_str().starts_with('{') => {
+          chars.next();
+          literal.push('{');
+        }
+        '}' if chars.as_str().starts_with('}') => {
+          chars.next();
+          literal.push('}');
+        }
+        '{' => {
+          let rest = chars.as_str();
+          let Some(end) = rest.find('}') else {
+            return Err(format!("Unclosed placeholder in {s:?}"));
+          };
+          let name = &rest[..end];
+          let placeholder = Placeholder::from_str(name).map_err(|_| {
+            format!(
+              "Unknown placeholder {{{name}}}, expected one of: {}",
+              Placeholder::VARIANTS.join(", ")
+            )
+          })?;
+          if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+          }
+          parts.push(Part::Placeholder(placeholder));
+          chars = rest[end + 1..].chars();
+        }
+        '}' => return Err(format!("Unmatched }} in {s:?}, use }}}} for a literal }}")),
+        c => literal.push(c),
+      }
+    }
+    if !literal.is_empty() {
+      parts.push(Part::Literal(literal));
+    }
+    Ok(Self { parts })
+  }
+}
+
+impl FormatTemplate {
+  /// Render the template. `value` appends the value of the placeholder to the line.
+  pub fn render(&self, mut value: impl FnMut(Placeholder, &mut String)) -> String {
+    let mut line = String::new();
+    for part in self.parts.iter() {
+      match part {
+        Part::Literal(literal) => line.push_str(literal),
+        Part::Placeholder(placeholder) => value(*placeholder, &mut line),
+      }
+    }
+    line
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn template_is_rendered() {
+    let template = FormatTemplate::from_str("{{{pid}}} {filename}: {result}").unwrap();
+    let line = template.render(|placeholder, line| {
+      line.push_str(match placeholder {
+        Placeholder::Pid => "42",
+        Placeholder::Filename => "/bin/true",
+        Placeholder::Result => "0",
+        _ => unreachable!(),
+      })
+    });
+    assert_eq!(line, "{42} /bin/true: 0");
+  }
+
+  #[test]
+  fn invalid_templates_are_rejected() {
+    assert!(FormatTemplate::from_str("{pid").is_err());
+    assert!(FormatTemplate::from_str("{nope}").is_err());
+    assert!(FormatTemplate::from_str("pid}").is_err());
+  }
+}
@@ -0,0 +1,125 @@
+//! Running a user command on exec events.
+//!
+//! The command is run by `sh -c` for every exec event that matches the filter. It receives the
+//! event as a JSON object on stdin, in the same format as `tracexec collect --format json-stream`,
+//! and the most useful fields in the `TRACEXEC_*` environment variables. tracexec does not wait
+//! for the command, so a slow hook does not slow down the tracees. At most `--exec-hook-jobs`
+//! commands run at the same time and the hook is skipped for the execs past the limit.
+
+use std::{
+  process::Stdio,
+  sync::{Arc, Mutex},
+};
+
+use itertools::Itertools;
+use tokio::{io::AsyncWriteExt, process::Command, sync::Semaphore, task::JoinSet};
+use tracing::warn;
+
+use crate::{
+  cli::args::ExecHookArgs, event::ExecEvent, export::JsonExecEvent,
+  tracer::state::BreakPointPattern,
+};
+
+pub const DEFAULT_JOBS: u32 = 16;
+
+pub struct ExecHook {
+  cmd: String,
+  filter: Vec<BreakPointPattern>,
+  /// Discard the output of the command, e.g. because it would mess up the TUI
+  quiet: bool,
+  /// Limits the number of the running commands
+  jobs: Arc<Semaphore>,
+  /// The tasks that write the events to the stdin of the commands
+  writers: Mutex<JoinSet<()>>,
+}
+
+impl ExecHook {
+  pub fn new(args: ExecHookArgs, quiet: bool) -> Option<Self> {
+    Some(Self {
+      cmd: args.exec_hook?,
+      filter: args.exec_hook_filter,
+      quiet,
+      jobs: Arc::new(Semaphore::new(
+        args.exec_hook_jobs.unwrap_or(DEFAULT_JOBS) as usize
+      )),
+      writers: Mutex::new(JoinSet::new()),
+    })
+  }
+
+  /// The hook runs for all the execs if there is no filter
+  pub fn matches(&self, exec: &ExecEvent) -> bool {
+    self.filter.is_empty()
+      || self
+        .filter
+        .iter()
+        .any(|pattern| pattern.matches(exec.argv.as_deref().ok(), &exec.filename))
+  }
+
+  /// Start the command for an exec event if it matches the filter. Errors are only logged.
+  pub fn run(&self, id: u64, exec: &ExecEvent) {
+    if !self.matches(exec) {
+      return;
+    }
+    if let Err(e) = self.spawn(id, exec) {
+      warn!("Failed to run exec hook for event {id}: {e}");
+    }
+  }
+
+  fn spawn(&self, id: u64, exec: &ExecEvent) -> color_eyre::Result<()> {
+    let Ok(permit) = self.jobs.clone().try_acquire_owned() else {
+      warn!("Skipped exec hook for event {id} because too many hooks are running");
+      return Ok(());
+    };
+    let json = serde_json::to_string(&JsonExecEvent::new(id, exec.clone()))?;
+    let output = || {
+      if self.quiet {
+        Stdio::null()
+      } else {
+        Stdio::inherit()
+      }
+    };
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg(&self.cmd)
+      .env("TRACEXEC_EVENT_ID", id.to_string())
+      .env("TRACEXEC_PID", exec.pid.to_string())
+      .env("TRACEXEC_COMM", exec.comm.as_str())
+      .env("TRACEXEC_FILENAME", exec.filename.to_string())
+      .env(
+        "TRACEXEC_ARGV",
+        exec
+          .argv
+          .as_deref()
+          .map(|argv| argv.iter().join(" "))
+          .unwrap_or_default(),
+      )
+      .env("TRACEXEC_CWD", exec.cwd.to_string())
+      .env("TRACEXEC_RESULT", exec.result.to_string())
+      .stdin(Stdio::piped())
+      .stdout(output())
+      .stderr(output())
+      .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut writers = self.writers.lock().unwrap();
+    // Forget about the finished writers
+    while writers.try_join_next().is_some() {}
+    writers.spawn(async move {
+      // The command might not read its stdin at all
+      let _ = stdin.write_all(json.as_bytes()).await;
+      let _ = stdin.write_all(b"\n").await;
+    });
+    // Keep the permit until the command exits
+    tokio::spawn(async move {
+      let _ = child.wait().await;
+      drop(permit);
+    });
+    Ok(())
+  }
+
+  /// Wait until all the events are written to the commands, so that they are not cut off
+  /// when tracexec exits.
+  pub async fn flush(&self) {
+    let mut writers = std::mem::take(&mut *self.writers.lock().unwrap());
+    while writers.join_next().await.is_some() {}
+  }
+}
@@ -0,0 +1,132 @@
+//! Localization of user-facing messages.
+//!
+//! Every translatable message has an id and an English default that is used when the
+//! message catalog does not translate it. A catalog is a flat TOML table mapping the ids
+//! to translated messages, e.g.
+//!
+//! ```toml
+//! "warning.no-argv0" = "argv[0] fehlt! Die ausgegebene Kommandozeile ist möglicherweise falsch!"
+//! "warning.failed-reading-argv" = "argv konnte nicht gelesen werden: {error}"
+//! "errno.ENOENT" = "Datei oder Verzeichnis nicht gefunden"
+//! ```
+//!
+//! Placeholders like `{error}` are substituted with the arguments of the message.
+//! The catalog is loaded from `$TRACEXEC_MESSAGES` or `messages.toml` in the config directory.
+
+use std::{collections::HashMap, fmt::Display, io, path::PathBuf, sync::OnceLock};
+
+use nix::errno::Errno;
+use tracing::warn;
+
+use crate::cli::config::{project_directory, ConfigLoadError};
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+#[derive(Debug, Default, Clone)]
+pub struct Catalog {
+  messages: HashMap<String, String>,
+}
+
+impl Catalog {
+  pub fn load() -> Result<Self, ConfigLoadError> {
+    let text = match std::env::var_os("TRACEXEC_MESSAGES") {
+      Some(path) => std::fs::read_to_string(PathBuf::from(path))?, // explicitly specified, so it must exist
+      None => {
+        let Some(project_dirs) = project_directory() else {
+          warn!("No valid home directory found! Not loading messages.toml.");
+          return Err(ConfigLoadError::NotFound);
+        };
+        // ~/.config/tracexec/messages.toml
+        let path = project_dirs.config_dir().join("messages.toml");
+        std::fs::read_to_string(path).map_err(|e| match e.kind() {
+          io::ErrorKind::NotFound => ConfigLoadError::NotFound,
+          _ => ConfigLoadError::from(e),
+        })?
+      }
+    };
+    Ok(Self {
+      messages: toml::from_str(&text)?,
+    })
+  }
+
+  fn get(&self, id: &str) -> Option<&str> {
+    self.messages.get(id).map(String::as_str)
+  }
+}
+
+/// Install the message catalog. Messages are not translated if this is never called.
+pub fn init(catalog: Catalog) {
+  if CATALOG.set(catalog).is_err() {
+    warn!("The message catalog is already initialized");
+  }
+}
+
+fn lookup(id: &str) -> Option<&'static str> {
+  CATALOG.get().and_then(|c| c.get(id))
+}
+
+/// Translate a message without placeholders
+pub fn tr(id: &str, default: &'static str) -> &'static str {
+  lookup(id).unwrap_or(default)
+}
+
+/// Translate a message and substitute its `{name}` placeholders with `args`.
+pub fn tr_args(id: &str, default: &'static str, args: &[(&str, &dyn Display)]) -> String {
+  substitute(lookup(id).unwrap_or(default), args)
+}
+
+fn substitute(template: &str, args: &[(&str, &dyn Display)]) -> String {
+  let mut result = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    result.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+    match after
+      .find('}')
+      .and_then(|end| Some((end, args.iter().find(|(name, _)| *name == &after[..end])?)))
+    {
+      Some((end, (_, value))) => {
+        result.push_str(&value.to_string());
+        rest = &after[end + 1..];
+      }
+      None => {
+        // Not a known placeholder, keep it as is
+        result.push('{');
+        rest = after;
+      }
+    }
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Describe an errno in the form of `ENOENT: No such file or directory`.
+/// The description is translated by the `errno.<NAME>` message.
+pub fn describe_errno(errno: Errno) -> String {
+  let name = format!("{errno:?}");
+  match lookup(&format!("errno.{name}")) {
+    Some(desc) => format!("{name}: {desc}"),
+    None => errno.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::substitute;
+
+  #[test]
+  fn substitute_placeholders() {
+    assert_eq!(
+      substitute(
+        "Failed to read {what}: {error}",
+        &[("error", &"EFAULT"), ("what", &"argv")]
+      ),
+      "Failed to read argv: EFAULT"
+    );
+  }
+
+  #[test]
+  fn substitute_keeps_unknown_placeholders() {
+    assert_eq!(substitute("{unknown} {x} {", &[("x", &1)]), "{unknown} 1 {");
+  }
+}
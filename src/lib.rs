@@ -0,0 +1,125 @@
+//! tracexec traces the execs of a program and its descendants.
+//!
+//! Besides the `tracexec` binary, this crate could be embedded in other programs to trace
+//! commands and consume the events programmatically. Configure a tracer with a
+//! [`TracerBuilder`] and spawn a command to get a [`Stream`](futures::Stream) of its events:
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use tracexec::{TracerBuilder, TracerEventDetails};
+//!
+//! #[tokio::main]
+//! async fn main() -> color_eyre::Result<()> {
+//!   let mut events = TracerBuilder::new().spawn(vec!["make".to_owned()])?;
+//!   while let Some(event) = events.next().await {
+//!     if let TracerEventDetails::Exec(exec) = event.details {
+//!       println!("{} execs {}", exec.pid, exec.filename);
+//!     }
+//!   }
+//!   events.wait().await
+//! }
+//! ```
+//!
+//! [`TracerEventStream::control`] pauses, resumes and kills the tracees while the events are
+//! streamed. [`TracerBuilder::build`] returns the [`Tracer`] itself, the receiver of all the
+//! [`TracerMessage`]s and the receiver of the requests, for programs that need to control the
+//! tracer, e.g. to set breakpoints.
+//!
+//! The tracer uses ptrace, so the command is traced in a blocking task of the tokio runtime.
+//!
+//! Only the items at the crate root are public. Options are set one by one on the
+//! [`TracerBuilder`] so that new options do not break existing programs.
+
+#![warn(
+  clippy::all,
+  // clippy::pedantic,
+  clippy::nursery,
+)]
+#![allow(
+  clippy::option_if_let_else,
+  clippy::missing_const_for_fn,
+  clippy::significant_drop_tightening,
+  clippy::cognitive_complexity, // FIXME
+  clippy::large_stack_frames, // In generated bpf skel, not really used to store on stack.
+  clippy::future_not_send, // FIXME: The futures of the tracer are not meant to be sent.
+  clippy::branches_sharing_code,
+  clippy::non_send_fields_in_send_ty, // In bpf skel, maybe open an issue in libbpf-rs?
+  clippy::literal_string_with_formatting_args, // The placeholders of the message catalog
+)]
+
+mod action;
+mod arch;
+mod audit;
+mod bench;
+#[cfg(feature = "ebpf")]
+mod bpf;
+mod cache;
+mod caps;
+mod channel;
+mod cli;
+mod cmdbuilder;
+mod collect;
+mod container;
+mod ctl;
+mod event;
+mod exe_hash;
+mod export;
+mod format_template;
+mod hook;
+mod i18n;
+mod log;
+mod logfmt;
+#[cfg(feature = "seccomp-notify")]
+mod notify;
+mod otlp;
+mod path_map;
+mod perfetto;
+mod plugin;
+mod printer;
+mod probe;
+mod proc;
+mod pty;
+mod redact;
+mod regex;
+mod replay;
+mod rules;
+mod script;
+#[cfg(feature = "seccomp-bpf")]
+mod seccomp;
+mod selftest;
+mod stats;
+mod tracer;
+mod tui;
+
+use std::io;
+
+use serde::Serialize;
+
+pub use channel::MessageReceiver;
+pub use event::{
+  ExecEvent, OutputMsg, ProcessStateUpdate, ProcessStateUpdateEvent, TracerEvent,
+  TracerEventDetails, TracerEventDetailsKind, TracerMessage,
+};
+pub use export::{JsonExecEvent, RecordEntry};
+pub use proc::{BaselineInfo, ProcessHandle};
+pub use tracer::{
+  BuiltTracer, PendingRequest, Tracer, TracerBuilder, TracerControl, TracerEventStream,
+};
+
+/// The entry point of the `tracexec` binary. It is not meant to be called by other programs.
+#[doc(hidden)]
+pub async fn cli_main() -> color_eyre::Result<()> {
+  cli::run::main().await
+}
+
+pub(crate) fn serialize_json_to_output<W, T>(writer: W, value: &T, pretty: bool) -> serde_json::Result<()>
+where
+  W: io::Write,
+  T: ?Sized + Serialize,
+{
+  if pretty {
+    serde_json::ser::to_writer_pretty(writer, value)
+  } else {
+    serde_json::ser::to_writer(writer, value)
+  }
+}
@@ -113,6 +113,8 @@ pub fn initialize_panic_handler() {
     if let Err(e) = restore_tui() {
       error!("Unable to restore Terminal: {e:?}");
     }
+    // The process exits here without running the destructors
+    crate::ctl::remove_socket();
     better_panic::Settings::auto()
       .most_recent_first(false)
       .lineno_suffix(true)
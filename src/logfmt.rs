@@ -0,0 +1,119 @@
+//! Lines of `key=value` pairs in the logfmt format, which is preferred by many log aggregation stacks.
+
+use std::{
+  borrow::Cow,
+  fmt::Display,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A line in the logfmt format. The time is always the first field.
+pub struct LogfmtLine {
+  line: String,
+}
+
+impl Default for LogfmtLine {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LogfmtLine {
+  pub fn new() -> Self {
+    Self {
+      line: format!("time={}", rfc3339(SystemTime::now())),
+    }
+  }
+
+  pub fn field(&mut self, key: &str, value: impl Display) -> &mut Self {
+    self.line.push(' ');
+    self.line.push_str(key);
+    self.line.push('=');
+    self.line.push_str(&quote(&value.to_string()));
+    self
+  }
+
+  pub fn finish(&self) -> &str {
+    &self.line
+  }
+}
+
+/// Quote the value if it is empty or contains spaces, `=`, quotes or control characters
+pub fn quote(value: &str) -> Cow<'_, str> {
+  if !value.is_empty()
+    && !value
+      .chars()
+      .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control())
+  {
+    return Cow::Borrowed(value);
+  }
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  Cow::Owned(quoted)
+}
+
+/// Format the time in RFC 3339 in UTC with microseconds, e.g. `2024-05-01T12:34:56.789012Z`
+pub fn rfc3339(time: SystemTime) -> String {
+  let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+  let secs = since_epoch.as_secs();
+  let (year, month, day) = civil_from_days((secs / 86400) as i64);
+  let secs_of_day = secs % 86400;
+  format!(
+    "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+    secs_of_day / 3600,
+    secs_of_day % 3600 / 60,
+    secs_of_day % 60,
+    since_epoch.subsec_micros()
+  )
+}
+
+/// Convert the days since the unix epoch to the date in the proleptic Gregorian calendar.
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+
+  #[test]
+  fn values_are_quoted_when_needed() {
+    assert_eq!(quote("/usr/bin/ls"), "/usr/bin/ls");
+    assert_eq!(quote(""), "\"\"");
+    assert_eq!(quote("ls -l"), "\"ls -l\"");
+    assert_eq!(quote("a=\"b\""), "\"a=\\\"b\\\"\"");
+    assert_eq!(quote("a\nb"), "\"a\\nb\"");
+  }
+
+  #[test]
+  fn time_is_formatted_in_rfc3339() {
+    assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000000Z");
+    assert_eq!(
+      rfc3339(UNIX_EPOCH + Duration::from_micros(1_709_210_096_123_456)),
+      "2024-02-29T12:34:56.123456Z"
+    );
+  }
+}
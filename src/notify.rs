@@ -0,0 +1,533 @@
+//! An exec supervisor based on seccomp user notification.
+//!
+//! The tracee loads a seccomp filter that sends execve and execveat to tracexec instead of
+//! stopping for ptrace. The tracee is blocked in the syscall until tracexec has inspected it
+//! and decided whether the syscall should continue or fail, which is much cheaper than ptrace
+//! because no other syscall or signal is intercepted.
+//!
+//! The arguments are read from `/proc/<pid>/mem` while the other threads of the tracee keep
+//! running, so they could be changed after the inspection. This mode is not a security boundary
+//! and `--deny` or `--rules` could be bypassed by a malicious tracee.
+//!
+//! The kernel does not tell us the result of an exec that is allowed to continue, so all the
+//! allowed execs are reported as successful.
+//!
+//! Nobody could be asked to approve an exec in this mode, so the `Ask` rules deny the execs.
+
+use std::{
+  collections::BTreeMap,
+  fs::File,
+  io::{self, IoSlice, IoSliceMut, Read},
+  os::{
+    fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+    unix::{
+      fs::FileExt,
+      net::UnixStream,
+      process::{CommandExt, ExitStatusExt},
+    },
+  },
+  path::PathBuf,
+  process,
+  sync::Arc,
+  thread,
+};
+
+use arcstr::ArcStr;
+use color_eyre::eyre::{bail, Context};
+use libseccomp::{
+  notify_id_valid, ScmpArch, ScmpNotifReq, ScmpNotifResp, ScmpNotifRespFlags, ScmpSyscall,
+};
+use nix::{
+  errno::Errno,
+  libc::{self, AT_EMPTY_PATH},
+  poll::{poll, PollFd, PollFlags, PollTimeout},
+  sys::socket::{
+    recvmsg, sendmsg, socketpair, AddressFamily, ControlMessage, ControlMessageOwned, MsgFlags,
+    SockFlag, SockType,
+  },
+  unistd::{close, Pid, User},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+  cli::{
+    args::{LogModeArgs, ModifierArgs},
+    options::Color,
+    Cli,
+  },
+  cmdbuilder::CommandBuilder,
+  container::read_cgroup,
+  event::{FriendlyError, OutputMsg},
+  path_map,
+  printer::{Printer, PrinterArgs, PrinterOut},
+  proc::{
+    cached_str, cached_string, parse_env_entry, read_comm, read_cwd, read_exe, read_fd, read_fds,
+    read_interpreter_recursive, read_ppid, read_security_label, read_uid, BaselineInfo,
+  },
+  redact,
+  rules::{ExecSubject, RuleAction, RuleSet},
+  seccomp,
+  tracer::{
+    state::{BreakPointPattern, ExecData},
+    InspectError,
+  },
+};
+
+pub struct NotifyTracer {
+  cmd: Vec<String>,
+  deny: Vec<BreakPointPattern>,
+  rules: RuleSet,
+  /// The rules file to reload the rules from
+  rules_path: Option<PathBuf>,
+  printer: Arc<Printer>,
+  baseline: Arc<BaselineInfo>,
+  resolve_proc_self_exe: bool,
+}
+
+impl NotifyTracer {
+  /// Spawn the command under the supervision of this tracer and wait for it to exit.
+  ///
+  /// Returns the exit code of the command.
+  pub fn run(
+    mut self,
+    output: Box<PrinterOut>,
+    tracing_args: &LogModeArgs,
+  ) -> color_eyre::Result<i32> {
+    let mut cmd = CommandBuilder::new(&self.cmd[0]);
+    cmd.args(self.cmd.iter().skip(1));
+    cmd.cwd(std::env::current_dir()?);
+    let mut cmd = cmd.as_command()?;
+    tracing_args.tracee_stdio().apply(&mut cmd)?;
+    let (supervisor_socket, tracee_socket) = socketpair(
+      AddressFamily::Unix,
+      SockType::SeqPacket,
+      None,
+      SockFlag::SOCK_CLOEXEC,
+    )?;
+    let sender = tracee_socket.as_raw_fd();
+    unsafe {
+      cmd.pre_exec(move || {
+        // The filter must be loaded by the tracee itself, which then hands the listener over.
+        let listener =
+          seccomp::load_notify_filter().map_err(|e| io::Error::other(e.to_string()))?;
+        sendmsg::<()>(
+          sender,
+          &[IoSlice::new(&[0])],
+          &[ControlMessage::ScmRights(&[listener])],
+          MsgFlags::empty(),
+          None,
+        )?;
+        close(listener)?;
+        Ok(())
+      });
+    }
+    // SIGHUP reloads the rules. Only catch it when there is a rules file.
+    let sighup = match self.rules_path {
+      Some(_) => {
+        let (reader, writer) = UnixStream::pair()?;
+        signal_hook::low_level::pipe::register(signal_hook::consts::SIGHUP, writer)?;
+        reader.set_nonblocking(true)?;
+        Some(reader)
+      }
+      None => None,
+    };
+    // Closing the writer tells the supervisor to stop
+    let (stop, stop_writer) = UnixStream::pair()?;
+    // The tracee is blocked on its first exec until the supervisor responds,
+    // so the supervisor must be running before spawning the tracee.
+    let supervisor = thread::Builder::new()
+      .name("supervisor".to_string())
+      .spawn(move || {
+        self.printer.init_thread_local(Some(output));
+        let result = self.supervise(supervisor_socket, stop, sighup);
+        if self.printer.args.dedup {
+          self.printer.print_dedup_summary()?;
+        }
+        self.printer.flush()?;
+        result
+      })?;
+    let child = cmd.spawn();
+    // Let the supervisor see EOF if the tracee fails before sending the listener
+    drop(tracee_socket);
+    let mut child = child.context("Failed to spawn the command")?;
+    let status = child.wait();
+    // Orphaned descendants of the tracee could keep the listener alive, don't wait for them.
+    // The supervisor still prints the summary and flushes the output before it exits.
+    drop(stop_writer);
+    supervisor.join().unwrap()?;
+    let status = status?;
+    Ok(
+      status
+        .code()
+        .unwrap_or_else(|| 128 + status.signal().unwrap_or(0)),
+    )
+  }
+
+  fn supervise(
+    &mut self,
+    socket: OwnedFd,
+    stop: UnixStream,
+    mut sighup: Option<UnixStream>,
+  ) -> color_eyre::Result<()> {
+    let Some(listener) = receive_listener(&socket)? else {
+      debug!("The tracee exited before sending the seccomp listener");
+      return Ok(());
+    };
+    drop(socket);
+    loop {
+      let mut fds = vec![
+        PollFd::new(listener.as_fd(), PollFlags::POLLIN),
+        PollFd::new(stop.as_fd(), PollFlags::POLLIN),
+      ];
+      if let Some(sighup) = &sighup {
+        fds.push(PollFd::new(sighup.as_fd(), PollFlags::POLLIN));
+      }
+      match poll(&mut fds, PollTimeout::NONE) {
+        Err(Errno::EINTR) => continue,
+        r => r?,
+      };
+      let revents = fds[0].revents().unwrap_or(PollFlags::empty());
+      let stopped = fds[1].revents().is_some_and(|revents| !revents.is_empty());
+      let reload = fds
+        .get(2)
+        .and_then(|fd| fd.revents())
+        .is_some_and(|revents| revents.contains(PollFlags::POLLIN));
+      drop(fds);
+      if stopped {
+        debug!("The tracee exited, stop supervising its descendants");
+        return Ok(());
+      }
+      if reload {
+        // Consume all the pending wakeups
+        let mut buf = [0u8; 16];
+        while sighup.as_mut().unwrap().read(&mut buf).is_ok_and(|n| n > 0) {}
+        self.reload_rules();
+      }
+      if revents.contains(PollFlags::POLLIN) {
+        self.handle_notification(listener.as_raw_fd())?;
+      } else if revents.intersects(PollFlags::POLLHUP | PollFlags::POLLERR) {
+        // All the processes using the filter are gone
+        return Ok(());
+      }
+    }
+  }
+
+  /// Reload the rules from the rules file. The old rules stay in effect if it fails.
+  fn reload_rules(&mut self) {
+    let Some(path) = &self.rules_path else {
+      return;
+    };
+    match RuleSet::load(path) {
+      Ok(rules) => {
+        info!("Reloaded {} rules from {}", rules.len(), path.display());
+        self.rules = rules;
+      }
+      Err(e) => error!("Failed to reload rules from {}: {e}", path.display()),
+    }
+  }
+
+  fn handle_notification(&self, listener: i32) -> color_eyre::Result<()> {
+    let req = match ScmpNotifReq::receive(listener) {
+      Ok(req) => req,
+      Err(e) => {
+        // The tracee could be killed before we receive the notification
+        debug!("Failed to receive seccomp notification: {e}");
+        return Ok(());
+      }
+    };
+    let pid = Pid::from_raw(req.pid as i32);
+    let exec_data = match self.inspect(pid, &req) {
+      Ok(exec_data) => exec_data,
+      Err(e) => {
+        // Don't block the tracee forever because of a failed inspection
+        warn!("Failed to inspect the exec of tracee {pid}: {e}");
+        if let Err(e) =
+          ScmpNotifResp::new_continue(req.id, ScmpNotifRespFlags::empty()).respond(listener)
+        {
+          debug!("Failed to respond to tracee {pid}: {e}");
+        }
+        return Ok(());
+      }
+    };
+    // The pid could have been reused if the tracee is killed while we are inspecting it.
+    if notify_id_valid(listener, req.id).is_err() {
+      debug!("Tracee {pid} is gone before its exec is inspected");
+      return Ok(());
+    }
+    let comm = read_comm(pid).unwrap_or_else(|_| cached_str("[err]"));
+    let mut denied = self
+      .deny
+      .iter()
+      .any(|pattern| pattern.matches(exec_data.argv.as_deref().ok(), &exec_data.filename));
+    if !denied && !self.rules.is_empty() {
+      let subject = ExecSubject {
+        filename: &exec_data.filename,
+        argv: exec_data.argv.as_deref().ok(),
+        comm: &comm,
+        uid: read_uid(pid).ok(),
+      };
+      if let Some((index, rule)) = self.rules.evaluate(&subject) {
+        denied = matches!(rule.action, RuleAction::Deny | RuleAction::Ask);
+        if rule.action != RuleAction::Allow {
+          self.printer.print_rule_match(
+            pid,
+            comm.clone(),
+            None,
+            &exec_data.filename,
+            index,
+            rule.action,
+          )?;
+        }
+      }
+    }
+    let result = if denied { -libc::EPERM as i64 } else { 0 };
+    if !((result != 0 && self.printer.args.successful_only)
+      || (result == 0 && self.printer.args.failed_only))
+    {
+      self.printer.print_exec_trace(
+        pid,
+        comm,
+        None,
+        result,
+        &exec_data,
+        &self.baseline.env,
+        &self.baseline.cwd,
+      )?;
+    }
+    let resp = if denied {
+      ScmpNotifResp::new_error(req.id, -libc::EPERM, ScmpNotifRespFlags::empty())
+    } else {
+      ScmpNotifResp::new_continue(req.id, ScmpNotifRespFlags::empty())
+    };
+    if let Err(e) = resp.respond(listener) {
+      debug!("Failed to respond to tracee {pid}: {e}");
+    }
+    Ok(())
+  }
+
+  fn inspect(&self, pid: Pid, req: &ScmpNotifReq) -> color_eyre::Result<ExecData> {
+    let arch = req.data.arch;
+    let args = req.data.args;
+    let memory = TraceeMemory::open(pid, matches!(arch, ScmpArch::X86 | ScmpArch::X32))?;
+    let is_execveat = req.data.syscall == ScmpSyscall::from_name_by_arch("execveat", arch)?;
+    let (filename, argv, envp) = if is_execveat {
+      // int execveat(int dirfd, const char *pathname,
+      //              char *const _Nullable argv[],
+      //              char *const _Nullable envp[],
+      //              int flags);
+      let dirfd = args[0] as i32;
+      let flags = args[4] as i32;
+      let filename = match memory.read_arcstr(args[1]) {
+        Ok(pathname) if pathname.starts_with('/') => Ok(pathname),
+        Ok(pathname) if pathname.is_empty() && (flags & AT_EMPTY_PATH) != 0 => {
+          Ok(read_fd(pid, dirfd)?)
+        }
+        Ok(pathname) => {
+          let dir = read_fd(pid, dirfd)?;
+          Ok(cached_string(format!("{dir}/{pathname}")))
+        }
+        Err(e) => Err(e),
+      };
+      (filename, args[2], args[3])
+    } else {
+      (memory.read_arcstr(args[0]), args[1], args[2])
+    };
+    let filename = match filename {
+      Ok(f) if self.resolve_proc_self_exe && f == "/proc/self/exe" => Ok(read_exe(pid)?),
+      f => f,
+    };
+    let argv = memory.read_array(argv, |memory, address| {
+      memory.read_arcstr(address).map(OutputMsg::Ok)
+    });
+    let envp = memory
+      .read_array(envp, |memory, address| {
+        memory.read_with(address, |bytes| {
+          let utf8 = String::from_utf8_lossy(bytes);
+          let (k, v) = parse_env_entry(&utf8);
+          (OutputMsg::Ok(cached_str(k)), OutputMsg::Ok(cached_str(v)))
+        })
+      })
+      .map(|entries| entries.into_iter().collect::<BTreeMap<_, _>>());
+    let interpreters = if self.printer.args.trace_interpreter && filename.is_ok() {
+      read_interpreter_recursive(filename.as_deref().unwrap())
+    } else {
+      vec![]
+    };
+    let filename = match filename {
+      Ok(s) => OutputMsg::Ok(s),
+      Err(e) => OutputMsg::Err(FriendlyError::InspectError(e)),
+    };
+    let mut exec_data = ExecData::new(
+      filename,
+      argv,
+      envp,
+      OutputMsg::Ok(read_cwd(pid)?),
+      Some(interpreters),
+      read_fds(pid)?,
+      read_cgroup(pid),
+    );
+    exec_data.ppid = read_ppid(pid).ok();
+    exec_data.security_label = read_security_label(pid);
+    Ok(exec_data)
+  }
+}
+
+/// Receive the seccomp listener fd sent by the tracee. Returns `None` if the tracee
+/// closed the socket without sending it.
+fn receive_listener(socket: &OwnedFd) -> color_eyre::Result<Option<OwnedFd>> {
+  let mut buf = [0u8; 1];
+  let mut iov = [IoSliceMut::new(&mut buf)];
+  let mut cmsg = nix::cmsg_space!(libc::c_int);
+  let msg = recvmsg::<()>(
+    socket.as_raw_fd(),
+    &mut iov,
+    Some(&mut cmsg),
+    MsgFlags::MSG_CMSG_CLOEXEC,
+  )?;
+  for cmsg in msg.cmsgs()? {
+    if let ControlMessageOwned::ScmRights(fds) = cmsg {
+      if let Some(&fd) = fds.first() {
+        return Ok(Some(unsafe { OwnedFd::from_raw_fd(fd) }));
+      }
+    }
+  }
+  Ok(None)
+}
+
+/// The memory of a tracee that is blocked in a syscall but not stopped by ptrace.
+struct TraceeMemory {
+  mem: File,
+  is_32bit: bool,
+}
+
+impl TraceeMemory {
+  fn open(pid: Pid, is_32bit: bool) -> io::Result<Self> {
+    Ok(Self {
+      mem: File::open(format!("/proc/{pid}/mem"))?,
+      is_32bit,
+    })
+  }
+
+  fn read_exact_at(&self, buf: &mut [u8], address: u64) -> Result<(), InspectError> {
+    self.mem.read_exact_at(buf, address).map_err(|e| {
+      let e = Errno::from_raw(e.raw_os_error().unwrap_or(libc::EIO));
+      warn!("Cannot read tracee memory {address:#x}: {e}");
+      e
+    })
+  }
+
+  fn read_with<T>(&self, address: u64, ctor: impl FnOnce(&[u8]) -> T) -> Result<T, InspectError> {
+    // Reading across the end of the mapping returns the bytes before it.
+    const CHUNK_SIZE: u64 = 256;
+    let mut res = Vec::new();
+    let mut address = address;
+    loop {
+      let mut chunk = [0u8; CHUNK_SIZE as usize];
+      let len = match self.mem.read_at(&mut chunk, address) {
+        Ok(0) => return Err(Errno::EIO),
+        Ok(len) => len,
+        Err(e) => {
+          let e = Errno::from_raw(e.raw_os_error().unwrap_or(libc::EIO));
+          warn!("Cannot read tracee memory {address:#x}: {e}");
+          return Err(e);
+        }
+      };
+      if let Some(nul) = chunk[..len].iter().position(|&byte| byte == 0) {
+        res.extend_from_slice(&chunk[..nul]);
+        return Ok(ctor(&res));
+      }
+      res.extend_from_slice(&chunk[..len]);
+      address += len as u64;
+    }
+  }
+
+  fn read_arcstr(&self, address: u64) -> Result<ArcStr, InspectError> {
+    self.read_with(address, |x| cached_str(&String::from_utf8_lossy(x)))
+  }
+
+  fn read_array<T>(
+    &self,
+    mut address: u64,
+    reader: impl Fn(&Self, u64) -> Result<T, InspectError>,
+  ) -> Result<Vec<T>, InspectError> {
+    let mut res = Vec::new();
+    loop {
+      let ptr = if self.is_32bit {
+        let mut buf = [0u8; 4];
+        self.read_exact_at(&mut buf, address)?;
+        address += 4;
+        u32::from_ne_bytes(buf) as u64
+      } else {
+        let mut buf = [0u8; 8];
+        self.read_exact_at(&mut buf, address)?;
+        address += 8;
+        u64::from_ne_bytes(buf)
+      };
+      if ptr == 0 {
+        return Ok(res);
+      }
+      res.push(reader(self, ptr)?);
+    }
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+  cmd: Vec<String>,
+  deny: Vec<BreakPointPattern>,
+  rules: Option<PathBuf>,
+  tracing_args: LogModeArgs,
+  modifier_args: ModifierArgs,
+  output: Option<PathBuf>,
+  user: Option<User>,
+  color: Color,
+) -> color_eyre::Result<()> {
+  if user.is_some() {
+    bail!("--user is not supported by the seccomp user notification backend");
+  }
+  let modifier_args = modifier_args.processed();
+  redact::init(redact::Redactor::new(&modifier_args.redact)?);
+  path_map::init(path_map::PathMap::new(modifier_args.map_path.clone()));
+  let baseline = Arc::new(BaselineInfo::new()?);
+  let output = Cli::get_output(output, color)?;
+  let rule_set = match &rules {
+    Some(path) => {
+      RuleSet::load(path).map_err(|e| color_eyre::eyre::eyre!("{e} ({})", path.display()))?
+    }
+    None => RuleSet::default(),
+  };
+  let tracer = NotifyTracer {
+    cmd,
+    deny,
+    rules: rule_set,
+    rules_path: rules,
+    printer: Arc::new(Printer::new(
+      PrinterArgs::from_cli(&tracing_args, &modifier_args),
+      baseline.clone(),
+    )),
+    baseline,
+    resolve_proc_self_exe: modifier_args.resolve_proc_self_exe,
+  };
+  let exit_code = tracer.run(output, &tracing_args)?;
+  process::exit(exit_code)
+}
+
+/// Supervise the command without printing anything and return its exit code.
+/// Used by `tracexec bench`.
+pub fn run_silently(cmd: Vec<String>, tracing_args: &LogModeArgs) -> color_eyre::Result<i32> {
+  let modifier_args = ModifierArgs::default().processed();
+  let baseline = Arc::new(BaselineInfo::new()?);
+  let tracer = NotifyTracer {
+    cmd,
+    deny: Vec::new(),
+    rules: RuleSet::default(),
+    rules_path: None,
+    printer: Arc::new(Printer::new(
+      PrinterArgs::from_cli(tracing_args, &modifier_args),
+      baseline.clone(),
+    )),
+    baseline,
+    resolve_proc_self_exe: modifier_args.resolve_proc_self_exe,
+  };
+  tracer.run(Box::new(io::sink()), tracing_args)
+}
@@ -0,0 +1,397 @@
+//! Export process lifetimes as OpenTelemetry spans.
+//!
+//! Each process becomes a span whose parent is the span of its parent process.
+//! The execs of a process are events of its span. The spans are encoded in the JSON
+//! encoding of OTLP and could be pushed to a collector over OTLP/HTTP.
+//!
+//! https://opentelemetry.io/docs/specs/otlp/
+
+use std::{
+  collections::HashMap,
+  hash::{BuildHasher, RandomState},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{bail, eyre};
+use nix::unistd::Pid;
+use serde::Serialize;
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpStream,
+};
+
+use crate::{event::TracerEventDetails, export::JsonMetaData, tracer::state::ProcessExit};
+
+/// The default port of OTLP/HTTP
+const DEFAULT_PORT: u16 = 4318;
+const TRACES_PATH: &str = "/v1/traces";
+
+const SPAN_KIND_INTERNAL: u8 = 1;
+const STATUS_CODE_OK: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::enum_variant_names)] // The names of the fields in OTLP
+pub enum AnyValue {
+  StringValue(String),
+  /// int64 is encoded as a decimal string in the JSON encoding of protobuf
+  IntValue(String),
+  ArrayValue(ArrayValue),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArrayValue {
+  pub values: Vec<AnyValue>,
+}
+
+impl From<String> for AnyValue {
+  fn from(value: String) -> Self {
+    Self::StringValue(value)
+  }
+}
+
+impl From<i64> for AnyValue {
+  fn from(value: i64) -> Self {
+    Self::IntValue(value.to_string())
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyValue {
+  pub key: &'static str,
+  pub value: AnyValue,
+}
+
+fn kv(key: &'static str, value: impl Into<AnyValue>) -> KeyValue {
+  KeyValue {
+    key,
+    value: value.into(),
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanEvent {
+  pub time_unix_nano: String,
+  pub name: &'static str,
+  pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+  pub code: u8,
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+  pub trace_id: String,
+  pub span_id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub parent_span_id: Option<String>,
+  pub name: String,
+  pub kind: u8,
+  pub start_time_unix_nano: String,
+  pub end_time_unix_nano: String,
+  pub attributes: Vec<KeyValue>,
+  pub events: Vec<SpanEvent>,
+  pub status: Status,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Resource {
+  pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Scope {
+  pub name: String,
+  pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopeSpans {
+  pub scope: Scope,
+  pub spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSpans {
+  pub resource: Resource,
+  pub scope_spans: Vec<ScopeSpans>,
+}
+
+/// `ExportTraceServiceRequest` of OTLP
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OtlpTraces {
+  pub resource_spans: Vec<ResourceSpans>,
+}
+
+/// A random non-zero id. The random keys of [`RandomState`] save us a dependency.
+fn random_id() -> u64 {
+  RandomState::new().hash_one(SystemTime::now()).max(1)
+}
+
+fn now_unix_nano() -> u128 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_nanos()
+}
+
+/// A process that has not exited
+struct RunningSpan {
+  span_id: String,
+  parent_span_id: Option<String>,
+  start: u128,
+  name: String,
+  attributes: Vec<KeyValue>,
+  events: Vec<SpanEvent>,
+}
+
+/// Turns the events into a trace in which every process is a span.
+///
+/// The events do not carry timestamps, so the time they are received is used.
+pub struct OtlpTraceBuilder {
+  trace_id: String,
+  running: HashMap<Pid, RunningSpan>,
+  spans: Vec<Span>,
+}
+
+impl Default for OtlpTraceBuilder {
+  fn default() -> Self {
+    Self {
+      trace_id: format!("{:016x}{:016x}", random_id(), random_id()),
+      running: HashMap::new(),
+      spans: Vec::new(),
+    }
+  }
+}
+
+impl OtlpTraceBuilder {
+  fn begin(&mut self, pid: Pid, ppid: Option<Pid>, name: String, start: u128) {
+    let parent_span_id = ppid
+      .and_then(|ppid| self.running.get(&ppid))
+      .map(|parent| parent.span_id.clone());
+    let mut attributes = vec![kv("process.pid", pid.as_raw() as i64)];
+    if let Some(ppid) = ppid {
+      attributes.push(kv("process.parent_pid", ppid.as_raw() as i64));
+    }
+    self.running.insert(
+      pid,
+      RunningSpan {
+        span_id: format!("{:016x}", random_id()),
+        parent_span_id,
+        start,
+        name,
+        attributes,
+        events: Vec::new(),
+      },
+    );
+  }
+
+  fn end(&mut self, pid: Pid, end: u128, exit: Option<ProcessExit>) {
+    let Some(mut span) = self.running.remove(&pid) else {
+      return;
+    };
+    let status = match exit {
+      Some(ProcessExit::Code(0)) => Status {
+        code: STATUS_CODE_OK,
+        message: String::new(),
+      },
+      Some(ProcessExit::Code(code)) => {
+        span.attributes.push(kv("process.exit.code", code as i64));
+        Status {
+          code: STATUS_CODE_ERROR,
+          message: format!("exited with code {code}"),
+        }
+      }
+      Some(ProcessExit::Signal(signal)) => Status {
+        code: STATUS_CODE_ERROR,
+        message: format!("killed by {signal}"),
+      },
+      // Still running when the trace ends
+      None => Status {
+        code: 0,
+        message: String::new(),
+      },
+    };
+    self.spans.push(Span {
+      trace_id: self.trace_id.clone(),
+      span_id: span.span_id,
+      parent_span_id: span.parent_span_id,
+      name: span.name,
+      kind: SPAN_KIND_INTERNAL,
+      start_time_unix_nano: span.start.to_string(),
+      end_time_unix_nano: end.to_string(),
+      attributes: span.attributes,
+      events: span.events,
+      status,
+    });
+  }
+
+  pub fn record(&mut self, details: &TracerEventDetails) {
+    let now = now_unix_nano();
+    match details {
+      TracerEventDetails::TraceeSpawn { pid, .. } => {
+        self.begin(*pid, None, "tracee".to_string(), now);
+      }
+      TracerEventDetails::NewChild { ppid, pcomm, pid } => {
+        self.begin(*pid, Some(*ppid), pcomm.to_string(), now);
+      }
+      TracerEventDetails::Exec(exec) => {
+        if !self.running.contains_key(&exec.pid) {
+          self.begin(exec.pid, None, exec.comm.to_string(), now);
+        }
+        let span = self.running.get_mut(&exec.pid).unwrap();
+        let mut attributes = vec![
+          kv("process.executable.path", exec.filename.as_ref().to_owned()),
+          kv("process.working_directory", exec.cwd.as_ref().to_owned()),
+          kv("tracexec.exec.result", exec.result),
+        ];
+        if let Ok(argv) = exec.argv.as_deref() {
+          attributes.push(kv(
+            "process.command_args",
+            AnyValue::ArrayValue(ArrayValue {
+              values: argv
+                .iter()
+                .map(|arg| AnyValue::StringValue(arg.as_ref().to_owned()))
+                .collect(),
+            }),
+          ));
+        }
+        span.events.push(SpanEvent {
+          time_unix_nano: now.to_string(),
+          name: "exec",
+          attributes,
+        });
+        if exec.result == 0 {
+          // Name the span after the program the process ends up running
+          span.name = exec.filename.as_ref().to_owned();
+        }
+      }
+      TracerEventDetails::ProcessExit { pid, exit, .. } => {
+        self.end(*pid, now, Some(*exit));
+      }
+      _ => (),
+    }
+  }
+
+  /// Processes that have not exited are ended at the time of calling this function.
+  pub fn finish(mut self, meta: JsonMetaData) -> OtlpTraces {
+    let now = now_unix_nano();
+    let pids = self.running.keys().copied().collect::<Vec<_>>();
+    for pid in pids {
+      self.end(pid, now, None);
+    }
+    OtlpTraces {
+      resource_spans: vec![ResourceSpans {
+        resource: Resource {
+          attributes: vec![kv("service.name", meta.generator.to_string())],
+        },
+        scope_spans: vec![ScopeSpans {
+          scope: Scope {
+            name: meta.generator.into_owned(),
+            version: meta.version.into_owned(),
+          },
+          spans: self.spans,
+        }],
+      }],
+    }
+  }
+}
+
+/// Push the traces to an OTLP/HTTP endpoint like `http://localhost:4318`.
+///
+/// `/v1/traces` is used as the path if the endpoint does not have one.
+/// Only plain HTTP is supported.
+pub async fn push(endpoint: &str, traces: &OtlpTraces) -> color_eyre::Result<()> {
+  let rest = endpoint
+    .strip_prefix("http://")
+    .ok_or_else(|| eyre!("Unsupported OTLP endpoint {endpoint}: only http:// is supported"))?;
+  let (authority, path) = match rest.find('/') {
+    Some(idx) => rest.split_at(idx),
+    None => (rest, ""),
+  };
+  let path = if path.trim_end_matches('/').is_empty() {
+    TRACES_PATH
+  } else {
+    path
+  };
+  let has_port = authority
+    .rsplit_once(':')
+    .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+  let address = if has_port {
+    authority.to_string()
+  } else {
+    format!("{authority}:{DEFAULT_PORT}")
+  };
+  let body = serde_json::to_vec(traces)?;
+  let mut stream = TcpStream::connect(address.as_str()).await?;
+  let head = format!(
+    "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+    body.len()
+  );
+  stream.write_all(head.as_bytes()).await?;
+  stream.write_all(&body).await?;
+  stream.flush().await?;
+  let mut response = Vec::new();
+  stream.read_to_end(&mut response).await?;
+  let response = String::from_utf8_lossy(&response);
+  let status_line = response.lines().next().unwrap_or_default();
+  let status = status_line
+    .split_whitespace()
+    .nth(1)
+    .and_then(|code| code.parse::<u16>().ok());
+  match status {
+    Some(200..=299) => Ok(()),
+    _ => {
+      let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+      bail!("The OTLP endpoint responded with {status_line:?}: {body}")
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arcstr::literal;
+  use nix::{sys::signal::Signal, unistd::Pid};
+
+  use super::*;
+  use crate::proc::BaselineInfo;
+
+  #[test]
+  fn child_process_span_has_parent() {
+    let mut builder = OtlpTraceBuilder::default();
+    builder.record(&TracerEventDetails::TraceeSpawn {
+      pid: Pid::from_raw(42),
+      process: None,
+    });
+    builder.record(&TracerEventDetails::NewChild {
+      ppid: Pid::from_raw(42),
+      pcomm: literal!("sh"),
+      pid: Pid::from_raw(43),
+    });
+    builder.record(&TracerEventDetails::ProcessExit {
+      pid: Pid::from_raw(43),
+      comm: literal!("sh"),
+      exit: ProcessExit::Signal(Signal::SIGTERM),
+    });
+    let traces = builder.finish(JsonMetaData::new(BaselineInfo::new().unwrap()));
+    let spans = &traces.resource_spans[0].scope_spans[0].spans;
+    assert_eq!(spans.len(), 2);
+    let (child, root) = (&spans[0], &spans[1]);
+    assert_eq!(child.trace_id, root.trace_id);
+    assert_eq!(root.parent_span_id, None);
+    assert_eq!(child.parent_span_id.as_ref(), Some(&root.span_id));
+    assert_eq!(child.status.code, STATUS_CODE_ERROR);
+  }
+}
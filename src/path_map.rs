@@ -0,0 +1,106 @@
+//! Rewriting of path prefixes in the displayed filenames and argv.
+//!
+//! Long and meaningless prefixes like Nix store paths or container mount points can be mapped to
+//! something shorter with `--map-path FROM=TO`. Only the display is affected, exports keep the raw values.
+
+use std::sync::OnceLock;
+
+use crate::event::OutputMsg;
+
+static PATH_MAP: OnceLock<PathMap> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+pub struct PathMap {
+  mappings: Vec<(String, String)>,
+}
+
+impl PathMap {
+  pub fn new(mappings: Vec<(String, String)>) -> Self {
+    Self {
+      mappings: mappings
+        .into_iter()
+        .filter(|(from, _)| !from.is_empty())
+        .collect(),
+    }
+  }
+
+  /// Replace the occurrences of the prefixes that end at a path component boundary
+  fn map_str(&self, s: &str) -> Option<String> {
+    let mut mapped: Option<String> = None;
+    for (from, to) in self.mappings.iter() {
+      let current = mapped.as_deref().unwrap_or(s);
+      if !current.contains(from.as_str()) {
+        continue;
+      }
+      let mut result = String::with_capacity(current.len());
+      let mut rest = current;
+      let mut changed = false;
+      while let Some(idx) = rest.find(from.as_str()) {
+        result.push_str(&rest[..idx]);
+        rest = &rest[idx + from.len()..];
+        // Do not map /build/abc to /src in /build/abcdef
+        if rest.is_empty() || rest.starts_with('/') || from.ends_with('/') {
+          result.push_str(to);
+          changed = true;
+        } else {
+          result.push_str(from);
+        }
+      }
+      if changed {
+        result.push_str(rest);
+        mapped = Some(result);
+      }
+    }
+    mapped
+  }
+
+  pub fn map(&self, msg: &OutputMsg) -> OutputMsg {
+    match msg {
+      OutputMsg::Ok(s) => self
+        .map_str(s)
+        .map_or_else(|| msg.clone(), |s| OutputMsg::Ok(s.into())),
+      OutputMsg::PartialOk(s) => self
+        .map_str(s)
+        .map_or_else(|| msg.clone(), |s| OutputMsg::PartialOk(s.into())),
+      OutputMsg::Err(_) => msg.clone(),
+    }
+  }
+}
+
+/// Install the path mappings. Paths are not rewritten if this is never called.
+pub fn init(path_map: PathMap) {
+  if PATH_MAP.set(path_map).is_err() {
+    tracing::warn!("The path map is already initialized");
+  }
+}
+
+/// Rewrite the path prefixes in a filename or an argument for display
+pub fn map(msg: &OutputMsg) -> OutputMsg {
+  match PATH_MAP.get() {
+    Some(path_map) => path_map.map(msg),
+    None => msg.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::PathMap;
+
+  #[test]
+  fn map_path_prefixes() {
+    let path_map = PathMap::new(vec![
+      ("/build/abc123".to_owned(), "/src".to_owned()),
+      ("/nix/store/".to_owned(), "/store/".to_owned()),
+    ]);
+    assert_eq!(
+      path_map.map_str("-I/build/abc123/include").as_deref(),
+      Some("-I/src/include")
+    );
+    assert_eq!(path_map.map_str("/build/abc123").as_deref(), Some("/src"));
+    assert_eq!(path_map.map_str("/build/abc1234/include"), None);
+    assert_eq!(
+      path_map.map_str("/nix/store/xyz-gcc/bin/gcc").as_deref(),
+      Some("/store/xyz-gcc/bin/gcc")
+    );
+  }
+}
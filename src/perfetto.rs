@@ -0,0 +1,324 @@
+//! Export the events as a trace in Perfetto's protobuf format.
+//!
+//! Each process is a track with a slice for every program it runs.
+//! The slices are annotated with argv, cwd and the env diff of the exec.
+//! Unlike JSON, the packets are written as the events arrive and the trace is never held in memory.
+//!
+//! https://perfetto.dev/docs/reference/trace-packet-proto
+
+use std::{collections::HashMap, io::Write, time::Instant};
+
+use arcstr::ArcStr;
+use nix::unistd::Pid;
+
+use crate::{event::TracerEventDetails, tracer::state::ProcessExit};
+
+/// A minimal protobuf encoder, which is enough for writing the few messages we need.
+#[derive(Default)]
+struct ProtoWriter {
+  buf: Vec<u8>,
+}
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_LEN: u64 = 2;
+
+impl ProtoWriter {
+  fn varint(&mut self, mut value: u64) {
+    while value >= 0x80 {
+      self.buf.push((value as u8) | 0x80);
+      value >>= 7;
+    }
+    self.buf.push(value as u8);
+  }
+
+  fn key(&mut self, field: u64, wire_type: u64) {
+    self.varint((field << 3) | wire_type);
+  }
+
+  fn uint(&mut self, field: u64, value: u64) {
+    self.key(field, WIRE_VARINT);
+    self.varint(value);
+  }
+
+  fn int(&mut self, field: u64, value: i64) {
+    self.uint(field, value as u64);
+  }
+
+  fn bytes(&mut self, field: u64, value: &[u8]) {
+    self.key(field, WIRE_LEN);
+    self.varint(value.len() as u64);
+    self.buf.extend_from_slice(value);
+  }
+
+  fn string(&mut self, field: u64, value: &str) {
+    self.bytes(field, value.as_bytes());
+  }
+
+  fn message(&mut self, field: u64, f: impl FnOnce(&mut Self)) {
+    let mut inner = Self::default();
+    f(&mut inner);
+    self.bytes(field, &inner.buf);
+  }
+}
+
+// Field numbers of the messages in perfetto's trace.proto
+const TRACE_PACKET: u64 = 1;
+const PACKET_TIMESTAMP: u64 = 8;
+const PACKET_SEQUENCE_ID: u64 = 10;
+const PACKET_TRACK_EVENT: u64 = 11;
+const PACKET_SEQUENCE_FLAGS: u64 = 13;
+const PACKET_TRACK_DESCRIPTOR: u64 = 60;
+const TRACK_UUID: u64 = 1;
+const TRACK_NAME: u64 = 2;
+const TRACK_PROCESS: u64 = 3;
+const PROCESS_PID: u64 = 1;
+const PROCESS_NAME: u64 = 6;
+const EVENT_DEBUG_ANNOTATIONS: u64 = 4;
+const EVENT_TYPE: u64 = 9;
+const EVENT_TRACK_UUID: u64 = 11;
+const EVENT_CATEGORIES: u64 = 22;
+const EVENT_NAME: u64 = 23;
+const ANNOTATION_INT_VALUE: u64 = 4;
+const ANNOTATION_STRING_VALUE: u64 = 6;
+const ANNOTATION_NAME: u64 = 10;
+const ANNOTATION_ARRAY_VALUES: u64 = 11;
+
+const TYPE_SLICE_BEGIN: u64 = 1;
+const TYPE_SLICE_END: u64 = 2;
+const TYPE_INSTANT: u64 = 3;
+const SEQ_INCREMENTAL_STATE_CLEARED: u64 = 1;
+/// We write all packets on a single sequence
+const SEQUENCE_ID: u64 = 1;
+
+enum Annotation {
+  Int(i64),
+  String(String),
+  Array(Vec<String>),
+}
+
+/// A process with an open slice
+struct RunningProcess {
+  track: u64,
+}
+
+/// Writes the events as packets of a Perfetto trace.
+///
+/// The events do not carry timestamps, so the time they are received is used.
+pub struct PerfettoTraceWriter {
+  start: Instant,
+  next_track: u64,
+  running: HashMap<Pid, RunningProcess>,
+  first_packet: bool,
+}
+
+impl Default for PerfettoTraceWriter {
+  fn default() -> Self {
+    Self {
+      start: Instant::now(),
+      next_track: 1,
+      running: HashMap::new(),
+      first_packet: true,
+    }
+  }
+}
+
+impl PerfettoTraceWriter {
+  fn packet(
+    &mut self,
+    out: &mut dyn Write,
+    f: impl FnOnce(&mut ProtoWriter),
+  ) -> std::io::Result<()> {
+    let timestamp = self.start.elapsed().as_nanos() as u64;
+    let first_packet = std::mem::replace(&mut self.first_packet, false);
+    let mut trace = ProtoWriter::default();
+    trace.message(TRACE_PACKET, |packet| {
+      packet.uint(PACKET_TIMESTAMP, timestamp);
+      packet.uint(PACKET_SEQUENCE_ID, SEQUENCE_ID);
+      if first_packet {
+        packet.uint(PACKET_SEQUENCE_FLAGS, SEQ_INCREMENTAL_STATE_CLEARED);
+      }
+      f(packet);
+    });
+    out.write_all(&trace.buf)
+  }
+
+  fn track_event(
+    &mut self,
+    out: &mut dyn Write,
+    track: u64,
+    kind: u64,
+    name: Option<&str>,
+    annotations: &[(&str, Annotation)],
+  ) -> std::io::Result<()> {
+    self.packet(out, |packet| {
+      packet.message(PACKET_TRACK_EVENT, |event| {
+        event.uint(EVENT_TYPE, kind);
+        event.uint(EVENT_TRACK_UUID, track);
+        if let Some(name) = name {
+          event.string(EVENT_CATEGORIES, "process");
+          event.string(EVENT_NAME, name);
+        }
+        for (name, value) in annotations {
+          event.message(EVENT_DEBUG_ANNOTATIONS, |annotation| {
+            annotation.string(ANNOTATION_NAME, name);
+            match value {
+              Annotation::Int(value) => annotation.int(ANNOTATION_INT_VALUE, *value),
+              Annotation::String(value) => annotation.string(ANNOTATION_STRING_VALUE, value),
+              Annotation::Array(values) => {
+                for value in values {
+                  annotation.message(ANNOTATION_ARRAY_VALUES, |item| {
+                    item.string(ANNOTATION_STRING_VALUE, value)
+                  });
+                }
+              }
+            }
+          });
+        }
+      });
+    })
+  }
+
+  /// Describe a new track for the process and open a slice on it
+  fn begin(
+    &mut self,
+    out: &mut dyn Write,
+    pid: Pid,
+    name: &str,
+    annotations: &[(&str, Annotation)],
+  ) -> std::io::Result<()> {
+    let track = match self.running.get(&pid) {
+      Some(process) => process.track,
+      None => {
+        let track = self.next_track;
+        self.next_track += 1;
+        self.packet(out, |packet| {
+          packet.message(PACKET_TRACK_DESCRIPTOR, |descriptor| {
+            descriptor.uint(TRACK_UUID, track);
+            descriptor.string(TRACK_NAME, &format!("{name} ({pid})"));
+            descriptor.message(TRACK_PROCESS, |process| {
+              process.int(PROCESS_PID, pid.as_raw() as i64);
+              process.string(PROCESS_NAME, name);
+            });
+          });
+        })?;
+        self.running.insert(pid, RunningProcess { track });
+        track
+      }
+    };
+    self.track_event(out, track, TYPE_SLICE_BEGIN, Some(name), annotations)
+  }
+
+  fn end(
+    &mut self,
+    out: &mut dyn Write,
+    pid: Pid,
+    annotations: &[(&str, Annotation)],
+  ) -> std::io::Result<()> {
+    let Some(process) = self.running.get(&pid) else {
+      return Ok(());
+    };
+    let track = process.track;
+    self.track_event(out, track, TYPE_SLICE_END, None, annotations)
+  }
+
+  pub fn record(
+    &mut self,
+    out: &mut dyn Write,
+    details: &TracerEventDetails,
+  ) -> std::io::Result<()> {
+    match details {
+      TracerEventDetails::TraceeSpawn { pid, .. } => {
+        self.begin(out, *pid, "tracee", &[])?;
+      }
+      TracerEventDetails::NewChild { ppid, pcomm, pid } => {
+        self.begin(
+          out,
+          *pid,
+          pcomm,
+          &[("ppid", Annotation::Int(ppid.as_raw() as i64))],
+        )?;
+      }
+      TracerEventDetails::Exec(exec) => {
+        let mut annotations = vec![
+          (
+            "filename",
+            Annotation::String(exec.filename.as_ref().to_owned()),
+          ),
+          ("cwd", Annotation::String(exec.cwd.as_ref().to_owned())),
+          ("result", Annotation::Int(exec.result)),
+        ];
+        if let Ok(argv) = exec.argv.as_deref() {
+          annotations.push((
+            "argv",
+            Annotation::Array(argv.iter().map(|arg| arg.as_ref().to_owned()).collect()),
+          ));
+        }
+        if let Ok(diff) = &exec.env_diff {
+          let diff = diff
+            .added
+            .iter()
+            .map(|(k, v)| format!("+{}={}", k.as_ref(), v.as_ref()))
+            .chain(diff.removed.iter().map(|k| format!("-{}", k.as_ref())))
+            .chain(
+              diff
+                .modified
+                .iter()
+                .map(|(k, v)| format!("~{}={}", k.as_ref(), v.as_ref())),
+            )
+            .collect();
+          annotations.push(("env_diff", Annotation::Array(diff)));
+        }
+        if exec.result == 0 {
+          // End the slice of the old program and start a new one for the new program
+          self.end(out, exec.pid, &[])?;
+          let comm: ArcStr = details.comm().unwrap_or_else(|| exec.comm.clone());
+          self.begin(out, exec.pid, &comm, &annotations)?;
+        } else {
+          if !self.running.contains_key(&exec.pid) {
+            self.begin(out, exec.pid, &exec.comm, &[])?;
+          }
+          let track = self.running[&exec.pid].track;
+          self.track_event(out, track, TYPE_INSTANT, Some("failed exec"), &annotations)?;
+        }
+      }
+      TracerEventDetails::ProcessExit { pid, exit, .. } => {
+        let exit = match exit {
+          ProcessExit::Code(code) => ("exit_code", Annotation::Int(*code as i64)),
+          ProcessExit::Signal(signal) => ("signal", Annotation::String(signal.to_string())),
+        };
+        self.end(out, *pid, &[exit])?;
+        self.running.remove(pid);
+      }
+      _ => (),
+    }
+    Ok(())
+  }
+
+  /// Slices of processes that have not exited are ended at the time of calling this function.
+  pub fn finish(mut self, out: &mut dyn Write) -> std::io::Result<()> {
+    let pids = self.running.keys().copied().collect::<Vec<_>>();
+    for pid in pids {
+      self.end(out, pid, &[])?;
+    }
+    out.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ProtoWriter;
+
+  #[test]
+  fn encode_varint() {
+    let mut w = ProtoWriter::default();
+    w.uint(1, 300);
+    assert_eq!(w.buf, [0x08, 0xac, 0x02]);
+  }
+
+  #[test]
+  fn encode_nested_message() {
+    let mut w = ProtoWriter::default();
+    w.message(2, |inner| inner.string(1, "hi"));
+    assert_eq!(w.buf, [0x12, 0x04, 0x0a, 0x02, b'h', b'i']);
+  }
+}
@@ -0,0 +1,257 @@
+//! Plugins that observe and transform the stream of tracer messages.
+//!
+//! A plugin is a shell command run by `sh -c` for the whole session. For every event, tracexec
+//! writes a line of JSON in the record format, i.e. the format of `tracexec collect -F record`,
+//! to the stdin of the plugin and reads back a line with a JSON array of entries in the same
+//! format. The entries replace the event: an empty array drops it, and more than one entry adds
+//! events. So a plugin could filter the events, enrich them or send them elsewhere.
+//!
+//! Plugins are chained in the order they are given. A plugin that fails is dropped from the chain
+//! and the events pass through it unchanged from then on.
+//!
+//! The consumer of the channel assumes that the event ids are contiguous, so the events that come
+//! out of the plugins are renumbered and the process state updates are translated accordingly.
+
+use std::{collections::HashMap, process::Stdio, sync::Arc};
+
+use color_eyre::eyre::{bail, Context};
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+  process::{Child, ChildStdin, ChildStdout, Command},
+};
+use tracing::warn;
+
+use crate::{
+  event::{TracerEvent, TracerEventDetails, TracerEventMessage, TracerMessage},
+  export::RecordEntry,
+  proc::BaselineInfo,
+};
+
+struct Plugin {
+  cmd: String,
+  /// Keep the child so that it is killed when we are dropped
+  _child: Child,
+  stdin: ChildStdin,
+  stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Plugin {
+  fn spawn(cmd: &str, quiet: bool) -> color_eyre::Result<Self> {
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg(cmd)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(if quiet {
+        Stdio::null()
+      } else {
+        Stdio::inherit()
+      })
+      .kill_on_drop(true)
+      .spawn()
+      .with_context(|| format!("Failed to start plugin {cmd:?}"))?;
+    Ok(Self {
+      cmd: cmd.to_owned(),
+      stdin: child.stdin.take().unwrap(),
+      stdout: BufReader::new(child.stdout.take().unwrap()).lines(),
+      _child: child,
+    })
+  }
+
+  async fn process(&mut self, entry: &RecordEntry) -> color_eyre::Result<Vec<RecordEntry>> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    self.stdin.write_all(&line).await?;
+    self.stdin.flush().await?;
+    let Some(reply) = self.stdout.next_line().await? else {
+      bail!("the plugin closed its stdout");
+    };
+    Ok(serde_json::from_str(&reply)?)
+  }
+}
+
+/// The plugins that the tracer messages pass through before they reach the consumer
+pub struct PluginChain {
+  plugins: Vec<Plugin>,
+  baseline: Arc<BaselineInfo>,
+  /// The id of the next event that comes out of the chain
+  next_id: u64,
+  /// Maps the ids of the events that go into the chain to the ids of the events that replace
+  /// them, so that the process state updates refer to the right events.
+  ids: HashMap<u64, u64>,
+}
+
+impl PluginChain {
+  pub fn spawn(
+    cmds: &[String],
+    quiet: bool,
+    baseline: Arc<BaselineInfo>,
+  ) -> color_eyre::Result<Self> {
+    Ok(Self {
+      plugins: cmds
+        .iter()
+        .map(|cmd| Plugin::spawn(cmd, quiet))
+        .collect::<Result<_, _>>()?,
+      baseline,
+      next_id: 0,
+      ids: HashMap::new(),
+    })
+  }
+
+  /// Pass a message through the plugins and return the messages that replace it
+  pub async fn process(&mut self, msg: TracerMessage) -> Vec<TracerMessage> {
+    let input_id = match &msg {
+      TracerMessage::Event(event) => Some(event.id),
+      _ => None,
+    };
+    let Some(entry) = RecordEntry::from_message(msg.clone()) else {
+      // Breakpoint related updates and fatal errors bypass the plugins
+      return vec![self.renumber(input_id, msg)];
+    };
+    let mut entries = vec![entry];
+    let mut failures = vec![];
+    let mut i = 0;
+    while i < self.plugins.len() {
+      let plugin = &mut self.plugins[i];
+      let mut output = Vec::with_capacity(entries.len());
+      let mut failed = None;
+      for entry in entries.iter() {
+        match plugin.process(entry).await {
+          Ok(replaced) => output.extend(replaced),
+          Err(e) => {
+            failed = Some(e);
+            break;
+          }
+        }
+      }
+      match failed {
+        Some(e) => {
+          let plugin = self.plugins.remove(i);
+          warn!("Plugin {:?} failed: {e}", plugin.cmd);
+          failures.push(format!(
+            "Plugin {:?} failed and is disabled: {e}",
+            plugin.cmd
+          ));
+        }
+        None => {
+          entries = output;
+          i += 1;
+        }
+      }
+    }
+    let mut result = Vec::with_capacity(entries.len() + failures.len());
+    for msg in failures {
+      result.push(self.renumber(
+        None,
+        TracerMessage::Event(TracerEvent {
+          details: TracerEventDetails::Error(TracerEventMessage { pid: None, msg }),
+          id: 0,
+        }),
+      ));
+    }
+    // Bookmarks from the plugins are ignored
+    for entry in entries {
+      if let Some(msg) = entry.into_message(&self.baseline) {
+        result.push(self.renumber(input_id, msg));
+      }
+    }
+    result
+  }
+
+  fn renumber(&mut self, input_id: Option<u64>, msg: TracerMessage) -> TracerMessage {
+    match msg {
+      TracerMessage::Event(mut event) => {
+        event.id = self.next_id;
+        self.next_id += 1;
+        // The first event that replaces an event takes over its process state updates
+        if let Some(input_id) = input_id {
+          self.ids.entry(input_id).or_insert(event.id);
+        }
+        TracerMessage::Event(event)
+      }
+      TracerMessage::StateUpdate(mut update) => {
+        update.ids = update
+          .ids
+          .iter()
+          .filter_map(|id| self.ids.get(id).copied())
+          .collect();
+        TracerMessage::StateUpdate(update)
+      }
+      TracerMessage::FatalError(_) => msg,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nix::unistd::Pid;
+
+  use super::*;
+  use crate::event::{ProcessStateUpdate, ProcessStateUpdateEvent};
+
+  fn event(id: u64, details: TracerEventDetails) -> TracerMessage {
+    TracerMessage::Event(TracerEvent { details, id })
+  }
+
+  fn info(msg: &str) -> TracerEventDetails {
+    TracerEventDetails::Info(TracerEventMessage {
+      pid: None,
+      msg: msg.to_owned(),
+    })
+  }
+
+  #[tokio::test]
+  async fn plugins_transform_and_renumber_events() {
+    let baseline = Arc::new(BaselineInfo::new().unwrap());
+    // Drop the tracee spawn events and duplicate the others
+    let cmds = [
+      r#"while read -r line; do case "$line" in '{"tracee-spawn"'*) echo '[]';; *) echo "[$line,$line]";; esac; done"#.to_owned(),
+    ];
+    let mut chain = PluginChain::spawn(&cmds, true, baseline).unwrap();
+    let pid = Pid::from_raw(1);
+    let mut received = vec![];
+    for msg in [
+      event(5, TracerEventDetails::TraceeSpawn { pid, process: None }),
+      event(6, info("hello")),
+      TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+        update: ProcessStateUpdate::Resumed,
+        pid,
+        ids: vec![5, 6],
+      }),
+    ] {
+      received.extend(chain.process(msg).await);
+    }
+    assert_eq!(
+      received,
+      vec![
+        event(0, info("hello")),
+        event(1, info("hello")),
+        TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+          update: ProcessStateUpdate::Resumed,
+          pid,
+          ids: vec![0],
+        }),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn failed_plugin_is_disabled() {
+    let baseline = Arc::new(BaselineInfo::new().unwrap());
+    let mut chain = PluginChain::spawn(&["exit 0".to_owned()], true, baseline).unwrap();
+    let received = chain.process(event(3, info("hello"))).await;
+    assert_eq!(received.len(), 2);
+    assert!(matches!(
+      &received[0],
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::Error(_),
+        id: 0
+      })
+    ));
+    assert_eq!(received[1], event(1, info("hello")));
+    assert_eq!(
+      chain.process(event(4, info("world"))).await,
+      vec![event(2, info("world"))]
+    );
+  }
+}
@@ -3,22 +3,32 @@ use std::{
   collections::BTreeMap,
   fmt::{Debug, Display},
   io::{self, Write},
-  sync::Arc,
+  sync::{Arc, Mutex},
+  time::SystemTime,
 };
 
 use crate::{
+  action::SupportedShell,
   cli::{
-    args::{LogModeArgs, ModifierArgs},
+    args::{EnvFilterArgs, LogModeArgs, ModifierArgs},
+    options::{EscapeStyle, FlushPolicy, Flusher, LogFormat},
     theme::THEME,
   },
-  event::{FriendlyError, OutputMsg},
-  proc::{diff_env, BaselineInfo, FileDescriptorInfoCollection, Interpreter},
-  tracer::state::{ExecData, ProcessState},
+  event::{exec_dedup_key, truncate_argv, FriendlyError, OutputMsg, TracerEventDetails},
+  format_template::{FormatTemplate, Placeholder},
+  i18n,
+  logfmt::{self, LogfmtLine},
+  path_map,
+  proc::{diff_env, ArgSizeReport, BaselineInfo, FileDescriptorInfoCollection, Interpreter},
+  redact,
+  rules::RuleAction,
+  tracer::state::{ExecData, ProcessExit, ProcessState},
 };
 
 use arcstr::ArcStr;
-use itertools::chain;
-use nix::{fcntl::OFlag, libc::ENOENT, unistd::Pid};
+use indexmap::IndexMap;
+use itertools::{chain, Itertools};
+use nix::{errno::Errno, fcntl::OFlag, libc::ENOENT, sys::signal::Signal, unistd::Pid};
 use owo_colors::{OwoColorize, Style};
 
 macro_rules! escape_str_for_bash {
@@ -53,24 +63,51 @@ pub enum ColorLevel {
 #[derive(Debug, Clone)]
 pub struct PrinterArgs {
   pub trace_comm: bool,
+  pub trace_ppid: bool,
+  pub trace_creds: bool,
+  pub trace_caps: bool,
+  pub trace_cgroup: bool,
+  pub trace_security_label: bool,
   pub trace_argv: bool,
   pub trace_env: EnvPrintFormat,
   pub trace_fd: FdPrintFormat,
   pub trace_cwd: bool,
+  pub trace_exit: bool,
   pub print_cmdline: bool,
   pub successful_only: bool,
+  pub failed_only: bool,
   pub trace_interpreter: bool,
   pub trace_filename: bool,
+  pub trace_exe: bool,
   pub decode_errno: bool,
   pub color: ColorLevel,
   pub stdio_in_cmdline: bool,
   pub fd_in_cmdline: bool,
+  pub escape_style: EscapeStyle,
+  pub env_filter: EnvFilterArgs,
+  pub max_arg_len: Option<usize>,
+  pub max_args: Option<usize>,
+  /// Print identical execs only once and summarize their counts at the end
+  pub dedup: bool,
+  /// Whether the execs are audited
+  pub audit: bool,
+  pub format: LogFormat,
+  pub format_template: Option<FormatTemplate>,
+  pub flush: FlushPolicy,
 }
 
 impl PrinterArgs {
   pub fn from_cli(tracing_args: &LogModeArgs, modifier_args: &ModifierArgs) -> Self {
+    // -q and -v only change the defaults of the details that are not specified
+    let quiet = tracing_args.quiet;
+    let verbose = tracing_args.verbose;
     Self {
       trace_comm: !tracing_args.no_show_comm,
+      trace_ppid: tracing_args.show_ppid,
+      trace_creds: tracing_args.show_creds,
+      trace_caps: tracing_args.show_caps,
+      trace_cgroup: tracing_args.show_cgroup,
+      trace_security_label: tracing_args.show_security_label,
       trace_argv: !tracing_args.no_show_argv && !tracing_args.show_cmdline,
       trace_env: match (
         tracing_args.show_cmdline,
@@ -81,6 +118,9 @@ impl PrinterArgs {
       ) {
         (true, ..) | (.., true) => EnvPrintFormat::None,
         (false, .., true, _) | (false, _, true, ..) => EnvPrintFormat::Raw,
+        (false, true, ..) => EnvPrintFormat::Diff,
+        _ if quiet => EnvPrintFormat::None,
+        _ if verbose >= 2 => EnvPrintFormat::Raw,
         _ => EnvPrintFormat::Diff, // diff_env is enabled by default
       },
       trace_fd: match (
@@ -92,6 +132,8 @@ impl PrinterArgs {
         (false, _, true, false) => FdPrintFormat::Raw,
         (_, true, _, _) => FdPrintFormat::None,
         (true, _, _, _) => FdPrintFormat::Diff,
+        _ if quiet => FdPrintFormat::None,
+        _ if verbose >= 2 => FdPrintFormat::Raw,
         _ => {
           // The default is diff fd,
           // but if fd_in_cmdline or stdio_in_cmdline is enabled, we disable diff fd by default
@@ -102,16 +144,20 @@ impl PrinterArgs {
           }
         }
       },
-      trace_cwd: tracing_args.show_cwd,
+      trace_cwd: tracing_args.show_cwd || (verbose >= 1 && !tracing_args.no_show_cwd),
+      trace_exit: tracing_args.show_exit || (verbose >= 1 && !tracing_args.no_show_exit),
       print_cmdline: tracing_args.show_cmdline,
       successful_only: modifier_args.successful_only,
-      trace_interpreter: tracing_args.show_interpreter,
+      failed_only: modifier_args.failed_only || (quiet && !modifier_args.successful_only),
+      trace_interpreter: tracing_args.show_interpreter
+        || (verbose >= 1 && !tracing_args.no_show_interpreter),
       trace_filename: match (tracing_args.show_filename, tracing_args.no_show_filename) {
         (_, true) => false,
         (true, _) => true,
         // default
         _ => true,
       },
+      trace_exe: tracing_args.show_exe,
       decode_errno: !tracing_args.no_decode_errno,
       color: match (tracing_args.more_colors, tracing_args.less_colors) {
         (false, false) => ColorLevel::Normal,
@@ -121,6 +167,15 @@ impl PrinterArgs {
       },
       stdio_in_cmdline: modifier_args.stdio_in_cmdline,
       fd_in_cmdline: modifier_args.fd_in_cmdline,
+      escape_style: modifier_args.escape_style.unwrap_or_default(),
+      env_filter: modifier_args.env_filter.clone(),
+      max_arg_len: modifier_args.max_arg_len,
+      max_args: modifier_args.max_args,
+      dedup: tracing_args.dedup,
+      audit: modifier_args.audit,
+      format: tracing_args.format,
+      format_template: tracing_args.format_template.clone(),
+      flush: tracing_args.flush,
     }
   }
 }
@@ -132,6 +187,7 @@ enum DeferredWarningKind {
   FailedReadingArgv(FriendlyError),
   FailedReadingFilename(FriendlyError),
   FailedReadingEnvp(FriendlyError),
+  ArgumentListTooLong(ArgSizeReport),
 }
 
 struct DeferredWarnings {
@@ -145,22 +201,65 @@ impl Drop for DeferredWarnings {
       if let Some(out) = out {
         write!(out, "{}", self.pid.bright_red()).unwrap();
         write!(out, "[{}]: ", "warning".bright_yellow()).unwrap();
-        match self.warning {
+        match &self.warning {
           DeferredWarningKind::NoArgv0 => {
             write!(
               out,
-              "No argv[0] provided! The printed commandline might be incorrect!"
+              "{}",
+              i18n::tr(
+                "warning.no-argv0",
+                "No argv[0] provided! The printed commandline might be incorrect!"
+              )
             )
             .unwrap();
           }
           DeferredWarningKind::FailedReadingArgv(e) => {
-            write!(out, "Failed to read argv: {}", e).unwrap();
+            write!(
+              out,
+              "{}",
+              i18n::tr_args(
+                "warning.failed-reading-argv",
+                "Failed to read argv: {error}",
+                &[("error", &e)]
+              )
+            )
+            .unwrap();
           }
           DeferredWarningKind::FailedReadingFilename(e) => {
-            write!(out, "Failed to read filename: {}", e).unwrap();
+            write!(
+              out,
+              "{}",
+              i18n::tr_args(
+                "warning.failed-reading-filename",
+                "Failed to read filename: {error}",
+                &[("error", &e)]
+              )
+            )
+            .unwrap();
           }
           DeferredWarningKind::FailedReadingEnvp(e) => {
-            write!(out, "Failed to read envp: {}", e).unwrap();
+            write!(
+              out,
+              "{}",
+              i18n::tr_args(
+                "warning.failed-reading-envp",
+                "Failed to read envp: {error}",
+                &[("error", &e)]
+              )
+            )
+            .unwrap();
+          }
+          DeferredWarningKind::ArgumentListTooLong(report) => {
+            write!(
+              out,
+              "{}",
+              i18n::tr_args(
+                "warning.e2big",
+                "Argument list too long! {report}",
+                &[("report", report)]
+              )
+            )
+            .unwrap();
           }
         };
         writeln!(out).unwrap();
@@ -218,6 +317,7 @@ impl ListPrinter {
     &self,
     out: &mut dyn Write,
     env: &BTreeMap<OutputMsg, OutputMsg>,
+    filter: &EnvFilterArgs,
   ) -> io::Result<()> {
     self.begin(out)?;
     let mut first_item_written = false;
@@ -229,29 +329,272 @@ impl ListPrinter {
       }
       Ok(())
     };
-    for (k, v) in env.iter() {
+    for (k, v) in env.iter().filter(|(k, _)| filter.is_shown(k.as_ref())) {
       write_separator(out)?;
       // TODO: Maybe stylize error
-      write!(out, "{}={}", k, v)?;
+      write!(out, "{}={}", k, redact::env(k, v))?;
     }
     self.end(out)
   }
 }
 
+/// Attribute the output to a root command when tracing a group of commands
+/// The arguments quoted for sh and joined by spaces
+fn quoted_argv(argv: &[OutputMsg]) -> String {
+  argv
+    .iter()
+    .map(|arg| SupportedShell::Sh.quote(redact::arg(&path_map::map(arg)).as_ref()))
+    .join(" ")
+}
+
+fn logfmt_root(line: &mut LogfmtLine, root_index: Option<usize>) -> &mut LogfmtLine {
+  if let Some(index) = root_index {
+    line.field("root", index + 1);
+  }
+  line
+}
+
+fn write_root_tag(out: &mut dyn Write, root_index: Option<usize>) -> io::Result<()> {
+  if let Some(index) = root_index {
+    write!(out, "{} ", format!("[{}]", index + 1).bright_blue())?;
+  }
+  Ok(())
+}
+
+/// An exec that is printed only once
+struct PrintedExec {
+  pid: Pid,
+  filename: OutputMsg,
+  argv: String,
+  repeats: usize,
+}
+
 pub struct Printer {
   pub args: PrinterArgs,
   baseline: Arc<BaselineInfo>,
+  /// The printed execs by [`exec_dedup_key`] if identical execs are printed only once
+  printed_execs: Mutex<IndexMap<u64, PrintedExec>>,
+  flusher: Flusher,
 }
 
 impl Printer {
   pub fn new(args: PrinterArgs, baseline: Arc<BaselineInfo>) -> Self {
-    Self { args, baseline }
+    Self {
+      flusher: Flusher::new(args.flush),
+      args,
+      baseline,
+      printed_execs: Mutex::new(IndexMap::new()),
+    }
+  }
+
+  /// Flush the output regardless of `--flush`. Must be called before the tracing ends.
+  pub fn flush(&self) -> io::Result<()> {
+    Self::OUT.with_borrow_mut(|out| match out {
+      Some(out) => out.flush(),
+      None => Ok(()),
+    })
+  }
+
+  /// Print the counts of the identical execs that are not printed again
+  pub fn print_dedup_summary(&self) -> color_eyre::Result<()> {
+    let printed_execs = self.printed_execs.lock().unwrap();
+    let repeated = printed_execs
+      .values()
+      .filter(|exec| exec.repeats > 0)
+      .sorted_by_key(|exec| std::cmp::Reverse(exec.repeats))
+      .collect_vec();
+    if repeated.is_empty() {
+      return Ok(());
+    }
+    if self.args.format == LogFormat::Logfmt {
+      for exec in repeated {
+        let mut line = LogfmtLine::new();
+        line
+          .field("event", "repeated-exec")
+          .field("count", exec.repeats + 1)
+          .field("pid", exec.pid)
+          .field("file", path_map::map(&exec.filename).as_ref())
+          .field("argv", &exec.argv);
+        self.print_logfmt(&line)?;
+      }
+      return Ok(());
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      writeln!(out, "{}", "Repeated execs:".bold())?;
+      for exec in repeated {
+        writeln!(
+          out,
+          "{:>8} {}: {} {}",
+          format!("{}×", exec.repeats + 1).bright_yellow(),
+          exec.pid.bright_green(),
+          path_map::map(&exec.filename).cli_escaped_styled(self.args.escape_style, THEME.filename),
+          exec.argv
+        )?;
+      }
+      out.flush()?;
+      Ok(())
+    })
   }
 
   thread_local! {
     pub static OUT: RefCell<Option<Box<PrinterOut>>> = RefCell::new(None);
   }
 
+  fn print_logfmt(&self, line: &LogfmtLine) -> color_eyre::Result<()> {
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      writeln!(out, "{}", line.finish())?;
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
+  fn print_exec_logfmt(
+    &self,
+    pid: Pid,
+    comm: &str,
+    root_index: Option<usize>,
+    result: i64,
+    exec_data: &ExecData,
+  ) -> color_eyre::Result<()> {
+    let mut line = LogfmtLine::new();
+    line.field("event", "exec");
+    logfmt_root(&mut line, root_index).field("pid", pid);
+    if self.args.trace_ppid {
+      if let Some(ppid) = exec_data.ppid {
+        line.field("ppid", ppid);
+      }
+    }
+    line
+      .field("comm", comm)
+      .field("file", path_map::map(&exec_data.filename).as_ref());
+    match exec_data.argv.as_ref() {
+      Ok(argv) => {
+        line.field("argv", quoted_argv(argv));
+      }
+      Err(e) => {
+        line.field("argv_error", e);
+      }
+    }
+    if self.args.trace_cwd {
+      line.field("cwd", exec_data.cwd.as_ref());
+    }
+    if self.args.trace_cgroup {
+      if let Some(cgroup) = exec_data.cgroup.as_ref() {
+        line.field("cgroup", cgroup);
+      }
+    }
+    if self.args.trace_exe {
+      if let Some(exe) = exec_data.exe.as_ref() {
+        line.field("exe", path_map::map(exe).as_ref());
+      }
+    }
+    if let Some(hash) = exec_data.exe_hash.as_ref() {
+      line.field("hash", hash);
+    }
+    if let Some(privileges) = exec_data.privileges.filter(|p| p.is_privileged()) {
+      line.field("privileges", privileges);
+    }
+    if self.args.trace_creds {
+      if let Some(creds) = exec_data.credentials {
+        line
+          .field("uid", creds.uid)
+          .field("gid", creds.gid)
+          .field("euid", creds.euid)
+          .field("egid", creds.egid);
+      }
+    }
+    if self.args.trace_security_label {
+      if let Some(label) = exec_data.security_label.as_ref() {
+        line.field("security_label", label);
+      }
+      if let Some(label) = exec_data.security_label_before.as_ref() {
+        line.field("security_label_before", label);
+      }
+    }
+    if self.args.trace_caps {
+      if let Some(caps) = exec_data.capabilities {
+        for (key, set) in [
+          ("cap_eff", caps.effective),
+          ("cap_prm", caps.permitted),
+          ("cap_gained", caps.gained),
+        ] {
+          if !set.is_empty() {
+            line.field(key, set);
+          }
+        }
+      }
+    }
+    if !exec_data.audit.is_empty() {
+      line.field("audit", exec_data.audit.iter().join(","));
+    }
+    line.field("result", result);
+    self.print_logfmt(&line)
+  }
+
+  fn print_exec_with_template(
+    &self,
+    template: &FormatTemplate,
+    pid: Pid,
+    comm: &str,
+    root_index: Option<usize>,
+    result: i64,
+    exec_data: &ExecData,
+  ) -> color_eyre::Result<()> {
+    let line = template.render(|placeholder, line| {
+      use std::fmt::Write;
+      let _ = match placeholder {
+        Placeholder::Time => write!(line, "{}", logfmt::rfc3339(SystemTime::now())),
+        Placeholder::Root => match root_index {
+          Some(index) => write!(line, "{}", index + 1),
+          None => Ok(()),
+        },
+        Placeholder::Pid => write!(line, "{pid}"),
+        Placeholder::Ppid => match exec_data.ppid {
+          Some(ppid) => write!(line, "{ppid}"),
+          None => Ok(()),
+        },
+        Placeholder::Comm => write!(line, "{comm}"),
+        Placeholder::Filename => write!(line, "{}", path_map::map(&exec_data.filename)),
+        Placeholder::Argv => match exec_data.argv.as_ref() {
+          Ok(argv) => write!(line, "{}", quoted_argv(argv)),
+          Err(e) => write!(line, "[failed to read argv: {e}]"),
+        },
+        Placeholder::Cwd => write!(line, "{}", exec_data.cwd),
+        Placeholder::Cgroup => match exec_data.cgroup.as_ref() {
+          Some(cgroup) => write!(line, "{cgroup}"),
+          None => Ok(()),
+        },
+        Placeholder::Exe => match exec_data.exe.as_ref() {
+          Some(exe) => write!(line, "{}", path_map::map(exe)),
+          None => Ok(()),
+        },
+        Placeholder::Hash => match exec_data.exe_hash.as_ref() {
+          Some(hash) => write!(line, "{hash}"),
+          None => Ok(()),
+        },
+        Placeholder::Result => write!(line, "{result}"),
+        Placeholder::Errno => match result {
+          0 => Ok(()),
+          _ => write!(line, "{:?}", Errno::from_raw(-result as i32)),
+        },
+      };
+    });
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      writeln!(out, "{line}")?;
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
   pub fn init_thread_local(&self, output: Option<Box<PrinterOut>>) {
     Self::OUT.with(|out| {
       *out.borrow_mut() = output;
@@ -259,16 +602,182 @@ impl Printer {
   }
 
   pub fn print_new_child(&self, state: &ProcessState, child: Pid) -> color_eyre::Result<()> {
+    if self.args.format == LogFormat::Logfmt {
+      let mut line = LogfmtLine::new();
+      line.field("event", "new-child");
+      logfmt_root(&mut line, state.root_index)
+        .field("pid", state.pid)
+        .field("comm", &state.comm)
+        .field("child", child);
+      return self.print_logfmt(&line);
+    }
     Self::OUT.with_borrow_mut(|out| {
       let Some(out) = out else {
         return Ok(());
       };
+      write_root_tag(out, state.root_index)?;
       write!(out, "{}", state.pid.bright_green())?;
       if self.args.trace_comm {
         write!(out, "<{}>", state.comm.cyan())?;
       }
       writeln!(out, ": {}: {}", "new child".purple(), child.bright_green())?;
-      out.flush()?;
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
+  pub fn print_exit(
+    &self,
+    pid: Pid,
+    comm: ArcStr,
+    root_index: Option<usize>,
+    exit: ProcessExit,
+  ) -> color_eyre::Result<()> {
+    if !self.args.trace_exit {
+      return Ok(());
+    }
+    if self.args.format == LogFormat::Logfmt {
+      let mut line = LogfmtLine::new();
+      line.field("event", "exit");
+      logfmt_root(&mut line, root_index)
+        .field("pid", pid)
+        .field("comm", &comm);
+      match exit {
+        ProcessExit::Code(code) => line.field("code", code),
+        ProcessExit::Signal(sig) => line.field("signal", sig),
+      };
+      return self.print_logfmt(&line);
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      write_root_tag(out, root_index)?;
+      match exit {
+        ProcessExit::Code(0) => write!(out, "{}", pid.bright_green())?,
+        _ => write!(out, "{}", pid.bright_red())?,
+      }
+      if self.args.trace_comm {
+        write!(out, "<{}>", comm.cyan())?;
+      }
+      write!(out, ": ")?;
+      match exit {
+        ProcessExit::Code(0) => writeln!(out, "{}", "exited with code 0".green())?,
+        ProcessExit::Code(code) => writeln!(
+          out,
+          "{}",
+          format!("exited with code {code}").bright_yellow()
+        )?,
+        ProcessExit::Signal(sig) => writeln!(
+          out,
+          "{}",
+          format!("terminated by signal {sig}").bright_red()
+        )?,
+      }
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
+  pub fn print_signal(
+    &self,
+    pid: Pid,
+    comm: ArcStr,
+    root_index: Option<usize>,
+    signal: Signal,
+  ) -> color_eyre::Result<()> {
+    if self.args.format == LogFormat::Logfmt {
+      let mut line = LogfmtLine::new();
+      line.field("event", "signal");
+      logfmt_root(&mut line, root_index)
+        .field("pid", pid)
+        .field("comm", &comm)
+        .field("signal", signal);
+      return self.print_logfmt(&line);
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      write_root_tag(out, root_index)?;
+      write!(out, "{}", pid.bright_green())?;
+      if self.args.trace_comm {
+        write!(out, "<{}>", comm.cyan())?;
+      }
+      writeln!(out, ": {} {}", "received".purple(), signal.bright_magenta())?;
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
+  pub fn print_daemonize(
+    &self,
+    pid: Pid,
+    comm: ArcStr,
+    root_index: Option<usize>,
+    sid: Pid,
+  ) -> color_eyre::Result<()> {
+    if self.args.format == LogFormat::Logfmt {
+      let mut line = LogfmtLine::new();
+      line.field("event", "daemonize");
+      logfmt_root(&mut line, root_index)
+        .field("pid", pid)
+        .field("comm", &comm)
+        .field("sid", sid);
+      return self.print_logfmt(&line);
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      write_root_tag(out, root_index)?;
+      write!(out, "{}", pid.bright_green())?;
+      if self.args.trace_comm {
+        write!(out, "<{}>", comm.cyan())?;
+      }
+      writeln!(out, ": {} into session {sid}", "daemonized".purple())?;
+      self.flusher.event_written(out)?;
+      Ok(())
+    })
+  }
+
+  pub fn print_rule_match(
+    &self,
+    pid: Pid,
+    comm: ArcStr,
+    root_index: Option<usize>,
+    filename: &OutputMsg,
+    index: usize,
+    action: RuleAction,
+  ) -> color_eyre::Result<()> {
+    if self.args.format == LogFormat::Logfmt {
+      let mut line = LogfmtLine::new();
+      line.field("event", "rule-match");
+      logfmt_root(&mut line, root_index)
+        .field("pid", pid)
+        .field("comm", &comm)
+        .field("file", filename.as_ref())
+        .field("rule", index + 1)
+        .field("action", action);
+      return self.print_logfmt(&line);
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      write_root_tag(out, root_index)?;
+      write!(out, "{}", pid.bright_green())?;
+      if self.args.trace_comm {
+        write!(out, "<{}>", comm.cyan())?;
+      }
+      writeln!(
+        out,
+        ": exec of {} matched {}: {}",
+        filename.bright_blue(),
+        format!("rule #{}", index + 1).purple(),
+        action.bright_magenta()
+      )?;
+      self.flusher.event_written(out)?;
       Ok(())
     })
   }
@@ -401,6 +910,7 @@ impl Printer {
     &self,
     pid: Pid,
     comm: ArcStr,
+    root_index: Option<usize>,
     result: i64,
     exec_data: &ExecData,
     env: &BTreeMap<OutputMsg, OutputMsg>,
@@ -410,6 +920,36 @@ impl Printer {
     // 1. execve syscall exit, which leads to 2
     // 2. state.exec_data is Some
 
+    if self.args.dedup {
+      let key = exec_dedup_key(
+        &exec_data.filename,
+        &exec_data.argv,
+        &exec_data.envp,
+        result,
+      );
+      let mut printed_execs = self.printed_execs.lock().unwrap();
+      if let Some(printed) = printed_execs.get_mut(&key) {
+        printed.repeats += 1;
+        return Ok(());
+      }
+      printed_execs.insert(
+        key,
+        PrintedExec {
+          pid,
+          filename: exec_data.filename.clone(),
+          argv: TracerEventDetails::argv_to_string(&exec_data.argv, self.args.escape_style),
+          repeats: 0,
+        },
+      );
+    }
+
+    if let Some(template) = self.args.format_template.as_ref() {
+      return self.print_exec_with_template(template, pid, &comm, root_index, result, exec_data);
+    }
+    if self.args.format == LogFormat::Logfmt {
+      return self.print_exec_logfmt(pid, &comm, root_index, result, exec_data);
+    }
+
     // Defer the warnings so that they are printed after the main message
     #[allow(clippy::collection_is_never_read)]
     let mut _deferred_warnings = vec![];
@@ -419,6 +959,7 @@ impl Printer {
         return Ok(());
       };
       let list_printer = ListPrinter::new(self.args.color);
+      write_root_tag(out, root_index)?;
       if result == 0 {
         write!(out, "{}", pid.bright_green())?;
       } else if result == -ENOENT as i64 {
@@ -426,16 +967,65 @@ impl Printer {
       } else {
         write!(out, "{}", pid.bright_red())?;
       }
+      if self.args.trace_ppid {
+        if let Some(ppid) = exec_data.ppid {
+          write!(out, "({} {})", "ppid".purple(), ppid)?;
+        }
+      }
       if self.args.trace_comm {
         write!(out, "<{}>", comm.cyan())?;
       }
+      if let Some(container) = &exec_data.container {
+        write!(out, "[{}]", container.bright_cyan())?;
+      }
+      if self.args.trace_cgroup {
+        if let Some(cgroup) = &exec_data.cgroup {
+          write!(out, "[{}]", cgroup.cyan())?;
+        }
+      }
+      if let Some(privileges) = exec_data.privileges.filter(|p| p.is_privileged()) {
+        write!(out, "{}", format!("[{privileges}]").bright_red().bold())?;
+      }
+      if self.args.trace_creds {
+        if let Some(creds) = exec_data.credentials {
+          if creds.is_escalated() {
+            write!(out, "{}", format!("[{creds}]").bright_red().bold())?;
+          } else {
+            write!(out, "[{creds}]")?;
+          }
+        }
+      }
+      if self.args.trace_security_label {
+        match (&exec_data.security_label_before, &exec_data.security_label) {
+          (Some(before), Some(label)) => write!(
+            out,
+            "{}",
+            format!("[{before} => {label}]").bright_yellow().bold()
+          )?,
+          (None, Some(label)) => write!(out, "[{label}]")?,
+          _ => (),
+        }
+      }
+      if self.args.trace_caps {
+        if let Some(caps) = exec_data.capabilities.filter(|caps| !caps.is_empty()) {
+          write!(out, "[eff={} prm={}]", caps.effective, caps.permitted)?;
+          if !caps.gained.is_empty() {
+            write!(out, "{}", format!("[+{}]", caps.gained).bright_red().bold())?;
+          }
+        }
+      }
+      if !exec_data.audit.is_empty() {
+        let findings = exec_data.audit.iter().join(",");
+        write!(out, "{}", format!("[{findings}]").bright_red().bold())?;
+      }
       write!(out, ":")?;
 
       if self.args.trace_filename {
         write!(
           out,
           " {}",
-          exec_data.filename.cli_escaped_styled(THEME.filename)
+          path_map::map(&exec_data.filename)
+            .cli_escaped_styled(self.args.escape_style, THEME.filename)
         )?;
       }
       if let OutputMsg::Err(e) = exec_data.filename {
@@ -455,7 +1045,19 @@ impl Printer {
         Ok(argv) => {
           if self.args.trace_argv {
             write!(out, " ")?;
-            list_printer.print_string_list(out, argv)?;
+            let (argv, omitted) = truncate_argv(argv, self.args.max_args);
+            let mut argv = argv
+              .iter()
+              .map(|arg| redact::arg(&path_map::map(arg)).truncated(self.args.max_arg_len))
+              .collect_vec();
+            if omitted > 0 {
+              argv.push(OutputMsg::Ok(format!("…(+{omitted} args)").into()));
+            }
+            let argv = argv
+              .iter()
+              .map(|arg| arg.cli_escaped_styled(self.args.escape_style, Style::new()))
+              .collect_vec();
+            list_printer.print_string_list(out, &argv)?;
           }
         }
       }
@@ -467,16 +1069,33 @@ impl Printer {
           out,
           " {} {}",
           "at".purple(),
-          exec_data
-            .cwd
-            .cli_escaped_styled(if self.args.color >= ColorLevel::Normal {
+          exec_data.cwd.cli_escaped_styled(
+            self.args.escape_style,
+            if self.args.color >= ColorLevel::Normal {
               THEME.cwd
             } else {
               THEME.plain
-            })
+            }
+          )
         )?;
       }
 
+      // Executable
+
+      if self.args.trace_exe {
+        if let Some(exe) = exec_data.exe.as_ref() {
+          write!(
+            out,
+            " {} {}",
+            "exe".purple(),
+            path_map::map(exe).cli_escaped_styled(self.args.escape_style, THEME.filename)
+          )?;
+        }
+      }
+      if let Some(hash) = exec_data.exe_hash.as_ref() {
+        write!(out, " {} {}", "hash".purple(), hash)?;
+      }
+
       // Interpreter
 
       if self.args.trace_interpreter && result == 0 {
@@ -529,16 +1148,18 @@ impl Printer {
                 Ok(())
               };
 
-              let diff = diff_env(&env, envp);
+              let mut diff = diff_env(&env, envp);
+              diff.retain(|k| self.args.env_filter.is_shown(k.as_ref()));
               for (k, v) in diff.added.into_iter() {
                 write_separator(out)?;
                 write!(
                   out,
                   "{}{}{}{}",
                   "+".bright_green().bold(),
-                  k.cli_escaped_styled(THEME.added_env_var),
+                  k.cli_escaped_styled(self.args.escape_style, THEME.added_env_var),
                   "=".bright_green().bold(),
-                  v.cli_escaped_styled(THEME.added_env_var)
+                  redact::env(&k, &v)
+                    .cli_escaped_styled(self.args.escape_style, THEME.added_env_var)
                 )?;
               }
               for (k, v) in diff.modified.into_iter() {
@@ -547,9 +1168,10 @@ impl Printer {
                   out,
                   "{}{}{}{}",
                   "M".bright_yellow().bold(),
-                  k.cli_escaped_styled(THEME.modified_env_key),
+                  k.cli_escaped_styled(self.args.escape_style, THEME.modified_env_key),
                   "=".bright_yellow().bold(),
-                  v.cli_escaped_styled(THEME.modified_env_val)
+                  redact::env(&k, &v)
+                    .cli_escaped_styled(self.args.escape_style, THEME.modified_env_val)
                 )?;
               }
               // Now we have the tracee removed entries in env
@@ -559,12 +1181,10 @@ impl Printer {
                   out,
                   "{}{}{}{}",
                   "-".bright_red().bold(),
-                  k.cli_escaped_styled(THEME.removed_env_var),
+                  k.cli_escaped_styled(self.args.escape_style, THEME.removed_env_var),
                   "=".bright_red().strikethrough(),
-                  env
-                    .get(&k)
-                    .unwrap()
-                    .cli_escaped_styled(THEME.removed_env_var)
+                  redact::env(&k, env.get(&k).unwrap())
+                    .cli_escaped_styled(self.args.escape_style, THEME.removed_env_var)
                 )?;
               }
               list_printer.end(out)?;
@@ -576,7 +1196,7 @@ impl Printer {
             }
             EnvPrintFormat::Raw => {
               write!(out, " {} ", "with".purple())?;
-              list_printer.print_env(out, envp)?;
+              list_printer.print_env(out, envp, &self.args.env_filter)?;
             }
             EnvPrintFormat::None => (),
           }
@@ -691,7 +1311,9 @@ impl Printer {
                   out,
                   " {} {}",
                   "-a".bright_white().italic(),
-                  escape_str_for_bash!(arg0.as_ref()).bright_white().italic()
+                  escape_str_for_bash!(redact::arg(&path_map::map(arg0)).as_ref())
+                    .bright_white()
+                    .italic()
                 )?;
               }
             } else {
@@ -734,7 +1356,7 @@ impl Printer {
                     " {}{}{}",
                     k.cli_bash_escaped_with_style(THEME.added_env_var),
                     "=".green().bold(),
-                    v.cli_bash_escaped_with_style(THEME.added_env_var)
+                    redact::env(&k, &v).cli_bash_escaped_with_style(THEME.added_env_var)
                   )?;
                 }
                 for (k, v) in diff.modified.into_iter() {
@@ -743,19 +1365,38 @@ impl Printer {
                     " {}{}{}",
                     k.bash_escaped(),
                     "=".bright_yellow().bold(),
-                    v.cli_bash_escaped_with_style(THEME.modified_env_val)
+                    redact::env(&k, &v).cli_bash_escaped_with_style(THEME.modified_env_val)
                   )?;
                 }
               } else {
                 for (k, v) in chain!(diff.added.into_iter(), diff.modified.into_iter()) {
-                  write!(out, " {}={}", k.bash_escaped(), v.bash_escaped())?;
+                  write!(
+                    out,
+                    " {}={}",
+                    k.bash_escaped(),
+                    redact::env(&k, &v).bash_escaped()
+                  )?;
                 }
               }
             }
-            write!(out, " {}", exec_data.filename.bash_escaped())?;
+            write!(
+              out,
+              " {}",
+              path_map::map(&exec_data.filename).bash_escaped()
+            )?;
+            let (argv, omitted) = truncate_argv(argv, self.args.max_args);
             for arg in argv.iter().skip(1) {
               // TODO: don't escape err msg
-              write!(out, " {}", arg.bash_escaped())?;
+              write!(
+                out,
+                " {}",
+                redact::arg(&path_map::map(arg))
+                  .truncated(self.args.max_arg_len)
+                  .bash_escaped()
+              )?;
+            }
+            if omitted > 0 {
+              write!(out, " …(+{omitted} args)")?;
             }
           }
           Err(e) => {
@@ -778,16 +1419,24 @@ impl Printer {
             out,
             "{} ({})",
             result.bright_red().bold(),
-            nix::errno::Errno::from_raw(-result as i32).red()
+            i18n::describe_errno(nix::errno::Errno::from_raw(-result as i32)).red()
           )?;
         } else {
           writeln!(out, "{}", result.bright_red().bold())?;
         }
+        if result == -(nix::errno::Errno::E2BIG as i64) {
+          if let (Ok(argv), Ok(envp)) = (exec_data.argv.as_ref(), exec_data.envp.as_ref()) {
+            _deferred_warnings.push(DeferredWarnings {
+              warning: DeferredWarningKind::ArgumentListTooLong(ArgSizeReport::new(argv, envp)),
+              pid,
+            });
+          }
+        }
       }
-      // It is critical to call [flush] before BufWriter<W> is dropped.
+      // The output is flushed according to --flush here.
+      // It is critical to call [Printer::flush] before BufWriter<W> is dropped.
       // Though dropping will attempt to flush the contents of the buffer, any errors that happen in the process of dropping will be ignored.
-      // Calling [flush] ensures that the buffer is empty and thus dropping will not even attempt file operations.
-      out.flush()?;
+      self.flusher.event_written(out)?;
       Ok(())
     })
   }
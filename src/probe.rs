@@ -0,0 +1,213 @@
+//! Runtime probing of the kernel features that tracexec relies on.
+
+use std::{
+  collections::HashMap,
+  fmt::Display,
+  mem::{size_of, MaybeUninit},
+  sync::{Mutex, OnceLock},
+};
+
+use lazy_static::lazy_static;
+use nix::{
+  errno::Errno,
+  libc::{self, ptrace_syscall_info, PTRACE_GET_SYSCALL_INFO},
+  sys::{
+    ptrace,
+    signal::{kill, raise, Signal},
+    wait::{waitpid, WaitStatus},
+  },
+  unistd::{fork, getpid, ForkResult},
+};
+use tracing::warn;
+
+lazy_static! {
+  /// Each feature is probed once on first use
+  pub static ref KERNEL_FEATURES: KernelFeatures = KernelFeatures::default();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+  Ptrace,
+  PtraceGetSyscallInfo,
+  SeccompTrace,
+  Pidfd,
+}
+
+impl Feature {
+  /// What stops working when the feature is missing
+  pub fn impact(&self) -> &'static str {
+    match self {
+      Self::Ptrace => "The ptrace backend will not work. Consider using the eBPF backend.",
+      Self::PtraceGetSyscallInfo => {
+        "The ptrace backend will not work. Linux 5.3 or later is required."
+      }
+      Self::SeccompTrace => "seccomp-bpf optimization is disabled. Tracing will be slower.",
+      Self::Pidfd => "Signals are sent by pid, which could race with pid reuse.",
+    }
+  }
+}
+
+impl Display for Feature {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Self::Ptrace => "ptrace",
+      Self::PtraceGetSyscallInfo => "PTRACE_GET_SYSCALL_INFO",
+      Self::SeccompTrace => "seccomp filter with SECCOMP_RET_TRACE",
+      Self::Pidfd => "pidfd",
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct MissingFeature {
+  pub feature: Feature,
+  pub reason: String,
+}
+
+impl Display for MissingFeature {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} is not available ({}). {}",
+      self.feature,
+      self.reason,
+      self.feature.impact()
+    )
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct KernelFeatures {
+  probed: Mutex<HashMap<Feature, Option<MissingFeature>>>,
+  ptrace_reported: OnceLock<()>,
+}
+
+impl KernelFeatures {
+  /// Probe the feature if it is not probed yet.
+  pub fn missing(&self, feature: Feature) -> Option<MissingFeature> {
+    let mut probed = self.probed.lock().unwrap();
+    if let Some(missing) = probed.get(&feature) {
+      return missing.clone();
+    }
+    let result = match feature {
+      Feature::Ptrace => probe_ptrace(),
+      // PTRACE_GET_SYSCALL_INFO could not be probed without ptrace
+      Feature::PtraceGetSyscallInfo => match probe_ptrace() {
+        Ok(()) => probe_ptrace_get_syscall_info(),
+        Err(reason) => Err(reason),
+      },
+      Feature::SeccompTrace => probe_seccomp_trace(),
+      Feature::Pidfd => probe_pidfd(),
+    };
+    let missing = result
+      .err()
+      .map(|reason| MissingFeature { feature, reason });
+    if let Some(missing) = &missing {
+      warn!("{missing}");
+    }
+    probed.insert(feature, missing.clone());
+    missing
+  }
+
+  pub fn has(&self, feature: Feature) -> bool {
+    self.missing(feature).is_none()
+  }
+
+  /// Report on stderr the missing features that the ptrace backend could not work without.
+  /// They are only reported once.
+  pub fn report_ptrace(&self) {
+    self.ptrace_reported.get_or_init(|| {
+      if let Some(missing) = self
+        .missing(Feature::Ptrace)
+        .or_else(|| self.missing(Feature::PtraceGetSyscallInfo))
+      {
+        eprintln!("{missing}");
+      }
+    });
+  }
+}
+
+fn kernel_release() -> String {
+  nix::sys::utsname::uname()
+    .map(|u| u.release().to_string_lossy().into_owned())
+    .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn probe_ptrace() -> Result<(), String> {
+  // https://www.kernel.org/doc/Documentation/security/Yama.txt
+  match std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope") {
+    Ok(scope) if scope.trim() == "3" => Err("disabled by kernel.yama.ptrace_scope = 3".to_string()),
+    _ => Ok(()),
+  }
+}
+
+fn probe_ptrace_get_syscall_info() -> Result<(), String> {
+  // The request is only valid for a tracee in ptrace-stop, so we need a child to probe it.
+  // SAFETY: the child only calls async-signal-safe functions
+  match unsafe { fork() }.map_err(|e| format!("failed to fork: {e}"))? {
+    ForkResult::Child => {
+      if ptrace::traceme().is_ok() {
+        raise(Signal::SIGSTOP).ok();
+      }
+      unsafe { libc::_exit(0) }
+    }
+    ForkResult::Parent { child } => {
+      let result = match waitpid(child, None) {
+        Ok(WaitStatus::Stopped(_, Signal::SIGSTOP)) => {
+          let mut info = MaybeUninit::<ptrace_syscall_info>::uninit();
+          let ret = unsafe {
+            libc::ptrace(
+              PTRACE_GET_SYSCALL_INFO,
+              child.as_raw(),
+              size_of::<ptrace_syscall_info>(),
+              info.as_mut_ptr(),
+            )
+          };
+          match Errno::result(ret) {
+            Ok(_) => Ok(()),
+            Err(Errno::EIO) => Err(format!("unsupported by kernel {}", kernel_release())),
+            Err(e) => Err(e.to_string()),
+          }
+        }
+        Ok(status) => Err(format!("unexpected status of probe process: {status:?}")),
+        Err(e) => Err(e.to_string()),
+      };
+      kill(child, Signal::SIGKILL).ok();
+      waitpid(child, None).ok();
+      result
+    }
+  }
+}
+
+fn probe_seccomp_trace() -> Result<(), String> {
+  const SECCOMP_GET_ACTION_AVAIL: libc::c_uint = 2;
+  const SECCOMP_RET_TRACE: u32 = 0x7ff00000;
+  let action = SECCOMP_RET_TRACE;
+  let ret = unsafe {
+    libc::syscall(
+      libc::SYS_seccomp,
+      SECCOMP_GET_ACTION_AVAIL,
+      0,
+      &action as *const u32,
+    )
+  };
+  match Errno::result(ret) {
+    Ok(_) => Ok(()),
+    Err(Errno::ENOSYS | Errno::EINVAL | Errno::EOPNOTSUPP) => {
+      Err(format!("unsupported by kernel {}", kernel_release()))
+    }
+    Err(e) => Err(e.to_string()),
+  }
+}
+
+fn probe_pidfd() -> Result<(), String> {
+  let ret = unsafe { libc::syscall(libc::SYS_pidfd_open, getpid().as_raw(), 0) };
+  match Errno::result(ret) {
+    Ok(fd) => {
+      unsafe { libc::close(fd as libc::c_int) };
+      Ok(())
+    }
+    Err(Errno::ENOSYS) => Err(format!("unsupported by kernel {}", kernel_release())),
+    Err(e) => Err(e.to_string()),
+  }
+}
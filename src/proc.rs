@@ -7,7 +7,11 @@ use std::{
   ffi::CString,
   fmt::{Display, Formatter},
   io::{self, BufRead, BufReader, Read},
-  os::raw::c_int,
+  os::{
+    fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    raw::c_int,
+    unix::fs::MetadataExt,
+  },
   path::{Path, PathBuf},
   sync::{Arc, RwLock},
 };
@@ -18,14 +22,23 @@ use lazy_static::lazy_static;
 use owo_colors::OwoColorize;
 
 use nix::{
+  errno::Errno,
   fcntl::OFlag,
-  libc::AT_FDCWD,
+  libc::{self, AT_FDCWD},
+  sys::signal::{kill, Signal},
   unistd::{getpid, Pid},
 };
-use serde::{ser::SerializeSeq, Serialize, Serializer};
+use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
 use tracing::warn;
 
-use crate::{cache::StringCache, event::OutputMsg, pty::UnixSlavePty};
+use crate::{
+  cache::{ArcCache, StringCache},
+  event::OutputMsg,
+  i18n,
+  probe::{Feature, KERNEL_FEATURES},
+  pty::UnixSlavePty,
+  tracer::InspectError,
+};
 
 #[allow(unused)]
 pub fn read_argv(pid: Pid) -> color_eyre::Result<Vec<CString>> {
@@ -48,21 +61,170 @@ pub fn read_comm(pid: Pid) -> color_eyre::Result<ArcStr> {
   Ok(cache.get_or_insert(&utf8))
 }
 
+/// Read the real user id of a process
+pub fn read_uid(pid: Pid) -> color_eyre::Result<u32> {
+  let filename = format!("/proc/{pid}/status");
+  let status = std::fs::read_to_string(filename)?;
+  let uid = status
+    .lines()
+    .find_map(|line| line.strip_prefix("Uid:"))
+    .and_then(|uids| uids.split_whitespace().next())
+    .ok_or_else(|| color_eyre::eyre::eyre!("No Uid field in /proc/{pid}/status"))?;
+  Ok(uid.parse()?)
+}
+
+/// The real and effective user and group ids of a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+  pub uid: u32,
+  pub gid: u32,
+  pub euid: u32,
+  pub egid: u32,
+}
+
+impl Credentials {
+  /// The effective ids differ from the real ids, e.g. after the exec of a setuid binary
+  pub fn is_escalated(&self) -> bool {
+    self.euid != self.uid || self.egid != self.gid
+  }
+}
+
+impl Display for Credentials {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "uid={} gid={} euid={} egid={}",
+      self.uid, self.gid, self.euid, self.egid
+    )
+  }
+}
+
+/// Read the real and effective user and group ids of a process
+pub fn read_credentials(pid: Pid) -> color_eyre::Result<Credentials> {
+  let filename = format!("/proc/{pid}/status");
+  let status = std::fs::read_to_string(filename)?;
+  // The fields are the real, effective, saved set and filesystem ids
+  let ids = |key: &str| -> color_eyre::Result<(u32, u32)> {
+    let mut ids = status
+      .lines()
+      .find_map(|line| line.strip_prefix(key))
+      .ok_or_else(|| color_eyre::eyre::eyre!("No {key} field in /proc/{pid}/status"))?
+      .split_whitespace();
+    let mut next = || -> color_eyre::Result<u32> {
+      Ok(
+        ids
+          .next()
+          .ok_or_else(|| color_eyre::eyre::eyre!("Truncated {key} field in /proc/{pid}/status"))?
+          .parse()?,
+      )
+    };
+    Ok((next()?, next()?))
+  };
+  let (uid, euid) = ids("Uid:")?;
+  let (gid, egid) = ids("Gid:")?;
+  Ok(Credentials {
+    uid,
+    gid,
+    euid,
+    egid,
+  })
+}
+
+/// Read the parent pid of a process
+pub fn read_ppid(pid: Pid) -> color_eyre::Result<Pid> {
+  let filename = format!("/proc/{pid}/status");
+  let status = std::fs::read_to_string(filename)?;
+  let ppid = status
+    .lines()
+    .find_map(|line| line.strip_prefix("PPid:"))
+    .ok_or_else(|| color_eyre::eyre::eyre!("No PPid field in /proc/{pid}/status"))?;
+  Ok(Pid::from_raw(ppid.trim().parse()?))
+}
+
+/// Read the SELinux context or the AppArmor profile of a process.
+/// Returns `None` if no LSM that labels the processes is active.
+pub fn read_security_label(pid: Pid) -> Option<ArcStr> {
+  let buf = std::fs::read(format!("/proc/{pid}/attr/current")).ok()?;
+  let label = String::from_utf8_lossy(&buf);
+  let label = label.trim_end_matches(['\0', '\n']);
+  (!label.is_empty()).then(|| cached_str(label))
+}
+
 pub fn read_cwd(pid: Pid) -> std::io::Result<ArcStr> {
   let filename = format!("/proc/{pid}/cwd");
   let buf = std::fs::read_link(filename)?;
   Ok(cached_str(&buf.to_string_lossy()))
 }
 
+pub fn read_root(pid: Pid) -> std::io::Result<ArcStr> {
+  let filename = format!("/proc/{pid}/root");
+  let buf = std::fs::read_link(filename)?;
+  Ok(cached_str(&buf.to_string_lossy()))
+}
+
 pub fn read_exe(pid: Pid) -> std::io::Result<ArcStr> {
   let filename = format!("/proc/{pid}/exe");
   let buf = std::fs::read_link(filename)?;
   Ok(cached_str(&buf.to_string_lossy()))
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+/// The privileges that an executable could grant to the process that execs it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExePrivileges {
+  pub setuid: bool,
+  pub setgid: bool,
+  /// The executable has file capabilities
+  pub file_caps: bool,
+}
+
+impl ExePrivileges {
+  pub fn is_privileged(&self) -> bool {
+    self.setuid || self.setgid || self.file_caps
+  }
+}
+
+impl Display for ExePrivileges {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let names = [
+      (self.setuid, "setuid"),
+      (self.setgid, "setgid"),
+      (self.file_caps, "caps"),
+    ];
+    for (i, (_, name)) in names.iter().filter(|(set, _)| *set).enumerate() {
+      if i > 0 {
+        write!(f, ",")?;
+      }
+      write!(f, "{name}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Read the setuid/setgid bits and the file capabilities of the executable of the process
+pub fn read_exe_privileges(pid: Pid) -> std::io::Result<ExePrivileges> {
+  let filename = format!("/proc/{pid}/exe");
+  let mode = std::fs::metadata(&filename)?.mode();
+  let path = CString::new(filename)?;
+  // getxattr follows the symlink to the executable
+  let file_caps = unsafe {
+    libc::getxattr(
+      path.as_ptr(),
+      c"security.capability".as_ptr(),
+      std::ptr::null_mut(),
+      0,
+    )
+  } > 0;
+  Ok(ExePrivileges {
+    setuid: mode & libc::S_ISUID != 0,
+    // setgid without group execute permission means mandatory locking instead
+    setgid: mode & libc::S_ISGID != 0 && mode & libc::S_IXGRP != 0,
+    file_caps,
+  })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct FileDescriptorInfoCollection {
-  #[serde(flatten)]
   pub fdinfo: BTreeMap<c_int, FileDescriptorInfo>,
 }
 
@@ -106,12 +268,15 @@ impl FileDescriptorInfoCollection {
   }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FileDescriptorInfo {
   pub fd: c_int,
   pub path: OutputMsg,
   pub pos: usize,
-  #[serde(serialize_with = "serialize_oflags")]
+  #[serde(
+    serialize_with = "serialize_oflags",
+    deserialize_with = "deserialize_oflags"
+  )]
   pub flags: OFlag,
   pub mnt_id: c_int,
   pub ino: u64,
@@ -143,6 +308,17 @@ where
   seq.end()
 }
 
+fn deserialize_oflags<'de, D>(deserializer: D) -> Result<OFlag, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let mut oflag = OFlag::empty();
+  for f in Vec::<String>::deserialize(deserializer)? {
+    oflag |= bitflags::parser::from_str(&f).map_err(serde::de::Error::custom)?;
+  }
+  Ok(oflag)
+}
+
 impl Default for FileDescriptorInfo {
   fn default() -> Self {
     Self {
@@ -170,10 +346,12 @@ pub fn read_fd(pid: Pid, fd: i32) -> std::io::Result<ArcStr> {
 pub fn read_fdinfo(pid: Pid, fd: i32) -> color_eyre::Result<FileDescriptorInfo> {
   let filename = format!("/proc/{pid}/fdinfo/{fd}");
   let file = std::fs::File::open(filename)?;
-  let reader = BufReader::new(file);
+  let mut reader = BufReader::new(file);
   let mut info = FileDescriptorInfo::default();
-  for line in reader.lines() {
-    let line = line?;
+  // Reuse the line buffer to avoid an allocation per line
+  let mut buf = String::new();
+  while reader.read_line(&mut buf)? > 0 {
+    let line = buf.trim_end_matches('\n');
     let mut parts = line.split_ascii_whitespace();
     let key = parts.next().unwrap_or("");
     let value = parts.next().unwrap_or("");
@@ -182,12 +360,9 @@ pub fn read_fdinfo(pid: Pid, fd: i32) -> color_eyre::Result<FileDescriptorInfo>
       "flags:" => info.flags = OFlag::from_bits_truncate(c_int::from_str_radix(value, 8)?),
       "mnt_id:" => info.mnt_id = value.parse()?,
       "ino:" => info.ino = value.parse()?,
-      _ => {
-        let mut cache = CACHE.write().unwrap();
-        let line = cache.get_or_insert_owned(line);
-        info.extra.push(line)
-      }
+      _ => info.extra.push(cached_str(line)),
     }
+    buf.clear();
   }
   info.mnt = get_mountinfo_by_mnt_id(pid, info.mnt_id)?;
   info.path = read_fd(pid, fd).map(OutputMsg::Ok)?;
@@ -208,20 +383,21 @@ pub fn read_fds(pid: Pid) -> color_eyre::Result<FileDescriptorInfoCollection> {
 fn get_mountinfo_by_mnt_id(pid: Pid, mnt_id: c_int) -> color_eyre::Result<ArcStr> {
   let filename = format!("/proc/{pid}/mountinfo");
   let file = std::fs::File::open(filename)?;
-  let reader = BufReader::new(file);
-  for line in reader.lines() {
-    let line = line?;
+  let mut reader = BufReader::new(file);
+  let mut buf = String::new();
+  while reader.read_line(&mut buf)? > 0 {
+    let line = buf.trim_end_matches('\n');
     let parts = line.split_once(' ');
     if parts.map(|(mount_id, _)| mount_id.parse()) == Some(Ok(mnt_id)) {
-      let mut cache = CACHE.write().unwrap();
-      return Ok(cache.get_or_insert_owned(line));
+      return Ok(cached_str(line));
     }
+    buf.clear();
   }
   let mut cache = CACHE.write().unwrap();
   Ok(cache.get_or_insert("Not found. This is probably a pipe or something else."))
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "what", content = "value", rename_all = "kebab-case")]
 pub enum Interpreter {
   None,
@@ -343,20 +519,6 @@ pub fn parse_env_entry(item: &str) -> (&str, &str) {
   (head, &tail[1..])
 }
 
-pub fn parse_envp(envp: Vec<String>) -> BTreeMap<OutputMsg, OutputMsg> {
-  envp
-    .into_iter()
-    .map(|entry| {
-      let (key, value) = parse_env_entry(&entry);
-      let mut cache = CACHE.write().unwrap();
-      (
-        OutputMsg::Ok(cache.get_or_insert(key)),
-        OutputMsg::Ok(cache.get_or_insert(value)),
-      )
-    })
-    .collect()
-}
-
 pub fn parse_failiable_envp(envp: Vec<OutputMsg>) -> BTreeMap<OutputMsg, OutputMsg> {
   envp
     .into_iter()
@@ -385,7 +547,155 @@ pub fn cached_string(s: String) -> ArcStr {
   cache.get_or_insert_owned(s)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub type Argv = Result<Vec<OutputMsg>, InspectError>;
+pub type Envp = Result<BTreeMap<OutputMsg, OutputMsg>, InspectError>;
+
+/// Share the argv with the previous events that have the same argv
+pub fn cached_argv(mut argv: Argv) -> Arc<Argv> {
+  let mut cache = ARGV_CACHE.write().unwrap();
+  if let Ok(argv) = argv.as_mut() {
+    argv.shrink_to_fit();
+  }
+  cache.get_or_insert(argv)
+}
+
+/// Share the environment with the previous events that have the same environment.
+/// Most of the execs inherit the environment unchanged, so only a few copies are kept.
+pub fn cached_envp(envp: Envp) -> Arc<Envp> {
+  let mut cache = ENVP_CACHE.write().unwrap();
+  cache.get_or_insert(envp)
+}
+
+/// The size of argv and envp, to explain why an exec failed with E2BIG
+#[derive(Debug, Clone)]
+pub struct ArgSizeReport {
+  pub argv_bytes: usize,
+  pub envp_bytes: usize,
+  /// ARG_MAX of tracexec. The stack limit of the tracee could be different.
+  pub arg_max: Option<usize>,
+  /// The strings rejected by the kernel because they exceed [`MAX_ARG_STRLEN`]
+  pub too_long: Vec<String>,
+  /// The biggest environment variables and their sizes, in descending order
+  pub biggest_env: Vec<(OutputMsg, usize)>,
+}
+
+/// Limit of a single argument or environment string, which is 32 pages on Linux.
+pub const MAX_ARG_STRLEN: usize = 32 * 4096;
+
+impl ArgSizeReport {
+  const BIGGEST_ENV_COUNT: usize = 3;
+
+  pub fn new(argv: &[OutputMsg], envp: &BTreeMap<OutputMsg, OutputMsg>) -> Self {
+    // Every string is referenced by a pointer on the stack
+    let pointer = std::mem::size_of::<usize>();
+    let mut too_long = vec![];
+    let mut argv_bytes = 0;
+    for (idx, arg) in argv.iter().enumerate() {
+      let len = arg.as_ref().len() + 1;
+      if len > MAX_ARG_STRLEN {
+        too_long.push(format!("argv[{idx}]"));
+      }
+      argv_bytes += len + pointer;
+    }
+    let mut envp_bytes = 0;
+    let mut env_sizes = vec![];
+    for (key, value) in envp.iter() {
+      // KEY=VALUE
+      let len = key.as_ref().len() + 1 + value.as_ref().len() + 1;
+      if len > MAX_ARG_STRLEN {
+        too_long.push(key.as_ref().to_owned());
+      }
+      envp_bytes += len + pointer;
+      env_sizes.push((key.clone(), len));
+    }
+    env_sizes.sort_by(|(_, a), (_, b)| b.cmp(a));
+    env_sizes.truncate(Self::BIGGEST_ENV_COUNT);
+    Self {
+      argv_bytes,
+      envp_bytes,
+      arg_max: nix::unistd::sysconf(nix::unistd::SysconfVar::ARG_MAX)
+        .ok()
+        .flatten()
+        .map(|v| v as usize),
+      too_long,
+      biggest_env: env_sizes,
+    }
+  }
+}
+
+/// Format a size in bytes with a binary unit
+pub fn human_size(bytes: usize) -> String {
+  const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+  if bytes < 1024 {
+    return format!("{bytes} B");
+  }
+  let mut size = bytes as f64 / 1024.;
+  for unit in UNITS.iter().take(UNITS.len() - 1) {
+    if size < 1024. {
+      return format!("{size:.1} {unit}");
+    }
+    size /= 1024.;
+  }
+  format!("{size:.1} {}", UNITS[UNITS.len() - 1])
+}
+
+impl Display for ArgSizeReport {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      i18n::tr_args(
+        "e2big.size",
+        "argv and envp take {total} (argv {argv}, envp {envp}), ARG_MAX is {arg_max}.",
+        &[
+          ("total", &human_size(self.argv_bytes + self.envp_bytes)),
+          ("argv", &human_size(self.argv_bytes)),
+          ("envp", &human_size(self.envp_bytes)),
+          (
+            "arg_max",
+            &self
+              .arg_max
+              .map_or_else(|| "unknown".to_string(), human_size)
+          ),
+        ]
+      )
+    )?;
+    if !self.biggest_env.is_empty() {
+      let biggest = self
+        .biggest_env
+        .iter()
+        .map(|(key, size)| format!("{} ({})", key.as_ref(), human_size(*size)))
+        .collect::<Vec<_>>()
+        .join(", ");
+      write!(
+        f,
+        " {}",
+        i18n::tr_args(
+          "e2big.biggest-env",
+          "The biggest env vars are {vars}.",
+          &[("vars", &biggest)]
+        )
+      )?;
+    }
+    if !self.too_long.is_empty() {
+      write!(
+        f,
+        " {}",
+        i18n::tr_args(
+          "e2big.too-long",
+          "These exceed the limit of {limit} per string: {strings}.",
+          &[
+            ("limit", &human_size(MAX_ARG_STRLEN)),
+            ("strings", &self.too_long.join(", ")),
+          ]
+        )
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EnvDiff {
   pub added: BTreeMap<OutputMsg, OutputMsg>,
   pub removed: BTreeSet<OutputMsg>,
@@ -393,6 +703,13 @@ pub struct EnvDiff {
 }
 
 impl EnvDiff {
+  /// Only keep the variables whose keys satisfy the predicate
+  pub fn retain(&mut self, mut f: impl FnMut(&OutputMsg) -> bool) {
+    self.added.retain(|k, _| f(k));
+    self.removed.retain(|k| f(k));
+    self.modified.retain(|k, _| f(k));
+  }
+
   pub fn is_modified_or_removed(&self, key: &OutputMsg) -> bool {
     self.modified.contains_key(key) || self.removed.contains(key)
   }
@@ -425,7 +742,7 @@ pub fn diff_env(
   }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BaselineInfo {
   pub cwd: OutputMsg,
   pub env: BTreeMap<OutputMsg, OutputMsg>,
@@ -466,4 +783,81 @@ impl BaselineInfo {
 
 lazy_static! {
   static ref CACHE: Arc<RwLock<StringCache>> = Arc::new(RwLock::new(StringCache::new()));
+  static ref ARGV_CACHE: RwLock<ArcCache<Vec<OutputMsg>, InspectError>> =
+    RwLock::new(ArcCache::new());
+  static ref ENVP_CACHE: RwLock<ArcCache<BTreeMap<OutputMsg, OutputMsg>, InspectError>> =
+    RwLock::new(ArcCache::new());
+}
+
+/// A handle to a process.
+///
+/// If pidfd is supported, the handle keeps referring to the same process
+/// even if its pid is reused after it exits.
+#[derive(Debug)]
+pub struct ProcessHandle {
+  pid: Pid,
+  inner: ProcessHandleInner,
+}
+
+#[derive(Debug)]
+enum ProcessHandleInner {
+  Pid,
+  Pidfd(OwnedFd),
+  /// The process has already been reaped when the handle is created
+  Gone,
+}
+
+impl ProcessHandle {
+  pub fn new(pid: Pid) -> Self {
+    let inner = if KERNEL_FEATURES.has(Feature::Pidfd) {
+      match pidfd_open(pid) {
+        Ok(fd) => ProcessHandleInner::Pidfd(fd),
+        Err(Errno::ESRCH) => ProcessHandleInner::Gone,
+        Err(e) => {
+          warn!("pidfd_open({pid}) failed: {e}");
+          ProcessHandleInner::Pid
+        }
+      }
+    } else {
+      ProcessHandleInner::Pid
+    };
+    Self { pid, inner }
+  }
+
+  pub fn pid(&self) -> Pid {
+    self.pid
+  }
+
+  /// Returns [`Errno::ESRCH`] if the process has exited.
+  pub fn signal(&self, sig: Signal) -> Result<(), Errno> {
+    match &self.inner {
+      ProcessHandleInner::Pid => kill(self.pid, sig),
+      ProcessHandleInner::Pidfd(fd) => Errno::result(unsafe {
+        libc::syscall(
+          libc::SYS_pidfd_send_signal,
+          fd.as_raw_fd(),
+          sig as c_int,
+          std::ptr::null::<libc::siginfo_t>(),
+          0,
+        )
+      })
+      .map(drop),
+      ProcessHandleInner::Gone => Err(Errno::ESRCH),
+    }
+  }
+}
+
+/// Handles are compared by pid so that the events carrying them can be compared
+impl PartialEq for ProcessHandle {
+  fn eq(&self, other: &Self) -> bool {
+    self.pid == other.pid
+  }
+}
+
+impl Eq for ProcessHandle {}
+
+fn pidfd_open(pid: Pid) -> Result<OwnedFd, Errno> {
+  let fd = Errno::result(unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_raw(), 0) })?;
+  // SAFETY: pidfd_open returns a new file descriptor on success
+  Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
 }
@@ -32,10 +32,9 @@ use nix::unistd::Pid;
 use std::cell::RefCell;
 use std::ffi::OsStr;
 
-use std::fs::File;
 use std::io::{Read, Write};
 use std::os::unix::ffi::OsStrExt;
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::io::{AsRawFd, BorrowedFd, FromRawFd, RawFd};
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::Command;
@@ -697,7 +696,8 @@ impl MasterPty for UnixMasterPty {
   }
 
   fn get_termios(&self) -> Option<nix::sys::termios::Termios> {
-    nix::sys::termios::tcgetattr(unsafe { File::from_raw_fd(self.fd.0.as_raw_fd()) }).ok()
+    // Borrow the fd instead of wrapping it in a File, which would close it on drop
+    nix::sys::termios::tcgetattr(unsafe { BorrowedFd::borrow_raw(self.fd.0.as_raw_fd()) }).ok()
   }
 }
 
@@ -0,0 +1,195 @@
+//! Redaction of secrets in the displayed argv and environment.
+//!
+//! The values of environment variables whose names match a pattern are replaced as a whole.
+//! The same applies to `KEY=VALUE` arguments, e.g. those passed to `env`.
+//! Matches of user provided regexes are replaced in all arguments and environment values.
+
+use std::sync::OnceLock;
+
+use arcstr::ArcStr;
+use color_eyre::eyre::eyre;
+use regex_cursor::engines::pikevm::{self, PikeVM};
+
+use crate::{cli::args::RedactArgs, event::OutputMsg};
+
+pub const REDACTED: &str = "<redacted>";
+
+const DEFAULT_ENV_PATTERNS: &[&str] = &["*_TOKEN", "*_SECRET", "AWS_*"];
+
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct Redactor {
+  env_patterns: Vec<String>,
+  regexes: Vec<PikeVM>,
+}
+
+impl Default for Redactor {
+  fn default() -> Self {
+    Self {
+      env_patterns: DEFAULT_ENV_PATTERNS.iter().map(|&p| p.to_owned()).collect(),
+      regexes: vec![],
+    }
+  }
+}
+
+impl Redactor {
+  pub fn new(args: &RedactArgs) -> color_eyre::Result<Self> {
+    if args.no_redact {
+      return Ok(Self {
+        env_patterns: vec![],
+        regexes: vec![],
+      });
+    }
+    let mut redactor = Self::default();
+    redactor
+      .env_patterns
+      .extend(args.redact_env.iter().cloned());
+    for regex in args.redact_regex.iter() {
+      redactor
+        .regexes
+        .push(PikeVM::new(regex).map_err(|e| eyre!("Invalid redaction regex {regex:?}: {e}"))?);
+    }
+    Ok(redactor)
+  }
+
+  fn is_secret_env(&self, key: &str) -> bool {
+    self
+      .env_patterns
+      .iter()
+      .any(|pattern| glob_match(pattern, key))
+  }
+
+  fn redact_matches(&self, msg: &OutputMsg) -> OutputMsg {
+    let mut redacted: Option<String> = None;
+    for re in self.regexes.iter() {
+      let s = redacted.as_deref().unwrap_or_else(|| msg.as_ref());
+      let mut result = String::new();
+      let mut last = 0;
+      let mut cache = pikevm::Cache::new(re);
+      for m in pikevm::find_iter(re, &mut cache, regex_cursor::Input::new(s)) {
+        if m.is_empty() {
+          continue;
+        }
+        result.push_str(&s[last..m.start()]);
+        result.push_str(REDACTED);
+        last = m.end();
+      }
+      if last > 0 {
+        result.push_str(&s[last..]);
+        redacted = Some(result);
+      }
+    }
+    match redacted {
+      Some(redacted) => replace(msg, redacted.into()),
+      None => msg.clone(),
+    }
+  }
+
+  /// Redact the value of an environment variable
+  pub fn env(&self, key: &OutputMsg, value: &OutputMsg) -> OutputMsg {
+    if self.is_secret_env(key.as_ref()) {
+      replace(value, REDACTED.into())
+    } else {
+      self.redact_matches(value)
+    }
+  }
+
+  /// Redact an argument
+  pub fn arg(&self, arg: &OutputMsg) -> OutputMsg {
+    if let Some((key, _)) = arg.as_ref().split_once('=') {
+      if self.is_secret_env(key) {
+        return replace(arg, arcstr::format!("{key}={REDACTED}"));
+      }
+    }
+    self.redact_matches(arg)
+  }
+}
+
+/// Errors are kept because they do not contain the secret
+fn replace(msg: &OutputMsg, redacted: ArcStr) -> OutputMsg {
+  match msg {
+    OutputMsg::Ok(_) => OutputMsg::Ok(redacted),
+    OutputMsg::PartialOk(_) => OutputMsg::PartialOk(redacted),
+    OutputMsg::Err(_) => msg.clone(),
+  }
+}
+
+/// Match a name against a pattern in which `*` matches any characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+  let mut parts = pattern.split('*');
+  // split always yields at least one part
+  let first = parts.next().unwrap();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let mut parts = parts.collect::<Vec<_>>();
+  let Some(last) = parts.pop() else {
+    // No wildcard
+    return rest.is_empty();
+  };
+  for part in parts {
+    match rest.find(part) {
+      Some(idx) => rest = &rest[idx + part.len()..],
+      None => return false,
+    }
+  }
+  rest.ends_with(last)
+}
+
+/// Install the redactor. The default patterns are used if this is never called.
+pub fn init(redactor: Redactor) {
+  if REDACTOR.set(redactor).is_err() {
+    tracing::warn!("The redactor is already initialized");
+  }
+}
+
+fn redactor() -> &'static Redactor {
+  REDACTOR.get_or_init(Redactor::default)
+}
+
+/// Redact the value of an environment variable for display
+pub fn env(key: &OutputMsg, value: &OutputMsg) -> OutputMsg {
+  redactor().env(key, value)
+}
+
+/// Redact an argument for display
+pub fn arg(arg: &OutputMsg) -> OutputMsg {
+  redactor().arg(arg)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn glob_patterns() {
+    assert!(glob_match("*_TOKEN", "GITHUB_TOKEN"));
+    assert!(!glob_match("*_TOKEN", "GITHUB_TOKENS"));
+    assert!(glob_match("AWS_*", "AWS_SECRET_ACCESS_KEY"));
+    assert!(glob_match("A*B*C", "AxxBxxC"));
+    assert!(!glob_match("A*B*C", "AxxCxxB"));
+    assert!(glob_match("PATH", "PATH"));
+    assert!(!glob_match("PATH", "PATHS"));
+  }
+
+  #[test]
+  fn redact_env_and_args() {
+    let redactor = Redactor::new(&RedactArgs {
+      redact_regex: vec!["ghp_[a-zA-Z0-9]+".to_owned()],
+      ..Default::default()
+    })
+    .unwrap();
+    let msg = |s: &str| OutputMsg::Ok(s.into());
+    assert_eq!(redactor.env(&msg("NPM_TOKEN"), &msg("abc")), msg(REDACTED));
+    assert_eq!(redactor.env(&msg("HOME"), &msg("/root")), msg("/root"));
+    assert_eq!(
+      redactor.arg(&msg("MY_SECRET=hunter2")),
+      msg("MY_SECRET=<redacted>")
+    );
+    assert_eq!(
+      redactor.arg(&msg("https://ghp_abc123@github.com")),
+      msg("https://<redacted>@github.com")
+    );
+  }
+}
@@ -0,0 +1,217 @@
+//! Load recorded events for replaying them in the TUI.
+
+use std::{collections::HashMap, path::Path};
+
+use color_eyre::eyre::{Context, OptionExt};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+  event::{ProcessStateUpdateEvent, TracerEvent, TracerEventDetails, TracerMessage},
+  export::{JsonExecEvent, JsonMetaData, RecordEntry, TraceNotes},
+  proc::BaselineInfo,
+};
+
+#[derive(Debug)]
+pub struct Replay {
+  pub baseline: BaselineInfo,
+  pub notes: TraceNotes,
+  pub messages: Vec<TracerMessage>,
+  /// The ids of the bookmarked events
+  pub bookmarks: Vec<u64>,
+}
+
+impl Replay {
+  /// Load the output of `tracexec collect`.
+  ///
+  /// All the formats are supported. But only the `record` format preserves
+  /// events other than exec events.
+  pub fn load(path: &Path) -> color_eyre::Result<Self> {
+    let content = std::fs::read_to_string(path)
+      .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut values = serde_json::Deserializer::from_str(&content).into_iter::<Value>();
+    let header = values
+      .next()
+      .ok_or_eyre("The file is empty")?
+      .context("Failed to parse the metadata")?;
+    let meta = JsonMetaData::deserialize(&header).context("Failed to parse the metadata")?;
+    let mut messages = Vec::new();
+    let mut bookmarks = Vec::new();
+    if let Some(events) = header.get("events") {
+      // json: a single object that contains all the exec events
+      for event in Vec::<JsonExecEvent>::deserialize(events).context("Failed to parse events")? {
+        messages.push(exec_message(event, &meta.baseline));
+      }
+    } else {
+      // json-stream or record: one value per event
+      for value in values {
+        let value = value.context("Failed to parse event")?;
+        // Exec events in json-stream are not wrapped in an externally tagged entry
+        if value.get("id").is_some() {
+          messages.push(exec_message(
+            JsonExecEvent::deserialize(&value).context("Failed to parse exec event")?,
+            &meta.baseline,
+          ));
+        } else {
+          match RecordEntry::deserialize(&value).context("Failed to parse event")? {
+            RecordEntry::Bookmark { id } => bookmarks.push(id),
+            entry => messages.extend(entry.into_message(&meta.baseline)),
+          }
+        }
+      }
+    }
+    Ok(Self {
+      notes: meta.notes,
+      ..Self::new(meta.baseline, messages, bookmarks)
+    })
+  }
+
+  /// The TUI requires the ids of events to be consecutive and start from zero,
+  /// which does not hold for filtered recordings. So the events are renumbered here.
+  fn new(baseline: BaselineInfo, recorded: Vec<TracerMessage>, bookmarks: Vec<u64>) -> Self {
+    let mut id_map = HashMap::new();
+    let mut messages = Vec::with_capacity(recorded.len());
+    for msg in recorded {
+      match msg {
+        TracerMessage::Event(TracerEvent { details, id }) => {
+          let new_id = id_map.len() as u64;
+          id_map.insert(id, new_id);
+          messages.push(TracerMessage::Event(TracerEvent {
+            details,
+            id: new_id,
+          }));
+        }
+        TracerMessage::StateUpdate(ProcessStateUpdateEvent { update, pid, ids }) => {
+          let ids = ids
+            .into_iter()
+            .filter_map(|id| id_map.get(&id).copied())
+            .collect();
+          messages.push(TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+            update,
+            pid,
+            ids,
+          }));
+        }
+        TracerMessage::FatalError(_) => (),
+      }
+    }
+    Self {
+      baseline,
+      notes: TraceNotes::default(),
+      messages,
+      bookmarks: bookmarks
+        .into_iter()
+        .filter_map(|id| id_map.get(&id).copied())
+        .collect(),
+    }
+  }
+}
+
+fn exec_message(event: JsonExecEvent, baseline: &BaselineInfo) -> TracerMessage {
+  TracerMessage::Event(TracerEvent {
+    id: event.id,
+    details: TracerEventDetails::Exec(Box::new(event.into_exec_event(baseline))),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use arcstr::literal;
+  use nix::{sys::signal::Signal, unistd::Pid};
+
+  use super::*;
+  use crate::{
+    event::{OutputMsg, ProcessStateUpdate},
+    tracer::state::ProcessExit,
+  };
+
+  fn roundtrip(msg: TracerMessage, baseline: &BaselineInfo) -> TracerMessage {
+    let entry = RecordEntry::from_message(msg).unwrap();
+    let json = serde_json::to_string(&entry).unwrap();
+    serde_json::from_str::<RecordEntry>(&json)
+      .unwrap()
+      .into_message(baseline)
+      .unwrap()
+  }
+
+  #[test]
+  fn replay_renumbers_events() {
+    let baseline = BaselineInfo::new().unwrap();
+    let recorded = vec![
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::TraceeSpawn {
+          pid: Pid::from_raw(42),
+          process: None,
+        },
+        id: 3,
+      }),
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::ProcessExit {
+          pid: Pid::from_raw(42),
+          comm: literal!("sh"),
+          exit: ProcessExit::Signal(Signal::SIGTERM),
+        },
+        id: 7,
+      }),
+      TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+        update: ProcessStateUpdate::Exit(ProcessExit::Code(1)),
+        pid: Pid::from_raw(42),
+        ids: vec![3, 5],
+      }),
+    ]
+    .into_iter()
+    .map(|msg| roundtrip(msg, &baseline))
+    .collect();
+    let replay = Replay::new(baseline, recorded, vec![7]);
+    let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::ProcessExit { exit, .. },
+      id,
+    }) = &replay.messages[1]
+    else {
+      panic!("unexpected message: {:?}", replay.messages[1]);
+    };
+    assert_eq!(*id, 1);
+    assert_eq!(*exit, ProcessExit::Signal(Signal::SIGTERM));
+    let TracerMessage::StateUpdate(update) = &replay.messages[2] else {
+      panic!("unexpected message: {:?}", replay.messages[2]);
+    };
+    assert_eq!(update.ids, vec![0]);
+    assert_eq!(replay.bookmarks, vec![1]);
+    assert_eq!(
+      update.update,
+      ProcessStateUpdate::Exit(ProcessExit::Code(1))
+    );
+  }
+
+  #[test]
+  fn load_json_stream() {
+    let meta = JsonMetaData::new(BaselineInfo::new().unwrap()).with_notes(TraceNotes {
+      note: Some("bisect step 5".to_string()),
+      meta: [("commit".to_string(), "abc123".to_string())].into(),
+    });
+    let event = r#"{"id":5,"kind":16,"pid":42,"cwd":"/","comm_before_exec":"sh","result":0,"filename":"/bin/true","argv":{"result":"success","value":["true"]},"env":{"result":"success","value":{"added":{"FOO":"bar"},"removed":[],"modified":{}}},"fdinfo":{}}"#;
+    let path = std::env::temp_dir().join(format!("tracexec-replay-{}.jsonl", std::process::id()));
+    std::fs::write(
+      &path,
+      format!("{}\n{event}\n", serde_json::to_string(&meta).unwrap()),
+    )
+    .unwrap();
+    let replay = Replay::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let replay = replay.unwrap();
+    assert_eq!(replay.notes.to_string(), "bisect step 5 (commit=abc123)");
+    let [TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      id: 0,
+    })] = replay.messages.as_slice()
+    else {
+      panic!("unexpected messages: {:?}", replay.messages);
+    };
+    assert_eq!(exec.pid, Pid::from_raw(42));
+    let envp = exec.envp.as_ref().as_ref().unwrap();
+    assert_eq!(
+      envp.get(&OutputMsg::Ok(literal!("FOO"))),
+      Some(&OutputMsg::Ok(literal!("bar")))
+    );
+  }
+}
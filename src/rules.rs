@@ -0,0 +1,316 @@
+//! Rules that decide what happens to the execs of the tracees.
+//!
+//! A rules file is a TOML file with a list of `[[rule]]` tables. The rules are evaluated in order
+//! and the first rule that matches an exec decides what happens to it. Execs that match no rule
+//! are allowed. A rule matches an exec if all of its conditions match, so a rule without any
+//! condition matches every exec.
+//!
+//! ```toml
+//! [[rule]]
+//! action = "Deny"
+//! filename = "/usr/bin/curl"
+//!
+//! [[rule]]
+//! action = "Ask"
+//! argv = "regex:^rm -rf"
+//! comm = "make"
+//! uid = 1000
+//! ```
+//!
+//! Like `--deny`, the rules are not a security boundary because the tracee could change the
+//! arguments after they are checked.
+
+use std::{
+  fmt::{Display, Formatter},
+  io,
+  path::Path,
+};
+
+use regex_cursor::engines::pikevm::{self, PikeVM};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+  event::OutputMsg,
+  regex::{ArgvCursor, SPACE},
+};
+
+#[derive(Debug, Error)]
+pub enum RuleLoadError {
+  #[error("Failed to read rules file: {0}")]
+  IoError(#[from] io::Error),
+  #[error("Failed to parse rules file: {0}")]
+  TomlError(#[from] toml::de::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum::Display)]
+pub enum RuleAction {
+  /// Let the exec proceed.
+  Allow,
+  /// Make the exec fail with EPERM.
+  Deny,
+  /// Ask the user to approve or deny the exec. Where nobody could be asked, e.g. in log mode,
+  /// the exec is denied.
+  Ask,
+  /// Let the exec proceed and report that the rule matched.
+  Log,
+}
+
+/// A pattern that matches a string. Patterns starting with `regex:` are regular expressions
+/// and the others are glob patterns.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+  Glob(glob::Pattern),
+  Regex { regex: PikeVM, source: String },
+}
+
+impl TryFrom<String> for Matcher {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    match value.strip_prefix("regex:") {
+      Some(source) => Ok(Self::Regex {
+        regex: PikeVM::new(source).map_err(|e| e.to_string())?,
+        source: source.to_string(),
+      }),
+      None => Ok(Self::Glob(
+        glob::Pattern::new(&value).map_err(|e| e.to_string())?,
+      )),
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for Matcher {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let value = String::deserialize(deserializer)?;
+    Self::try_from(value).map_err(serde::de::Error::custom)
+  }
+}
+
+impl Display for Matcher {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Glob(pattern) => write!(f, "{}", pattern.as_str()),
+      Self::Regex { source, .. } => write!(f, "regex:{source}"),
+    }
+  }
+}
+
+impl Matcher {
+  pub fn matches_str(&self, s: &str) -> bool {
+    match self {
+      Self::Glob(pattern) => pattern.matches(s),
+      Self::Regex { regex, .. } => pikevm::is_match(
+        regex,
+        &mut pikevm::Cache::new(regex),
+        &mut regex_cursor::Input::new(s),
+      ),
+    }
+  }
+
+  /// Match the cmdline, i.e. the argv concatenated with spaces without any escaping.
+  pub fn matches_argv(&self, argv: &[OutputMsg]) -> bool {
+    match self {
+      Self::Glob(pattern) => {
+        let cmdline = argv
+          .iter()
+          .map(|arg| arg.as_ref())
+          .collect::<Vec<&str>>()
+          .join(" ");
+        pattern.matches(&cmdline)
+      }
+      Self::Regex { regex, .. } => {
+        let space = &SPACE;
+        let argv = ArgvCursor::new(argv, space);
+        pikevm::is_match(
+          regex,
+          &mut pikevm::Cache::new(regex),
+          &mut regex_cursor::Input::new(argv),
+        )
+      }
+    }
+  }
+}
+
+/// The information about an exec that the rules are matched against
+pub struct ExecSubject<'a> {
+  pub filename: &'a OutputMsg,
+  pub argv: Option<&'a [OutputMsg]>,
+  /// The comm of the process before the exec
+  pub comm: &'a str,
+  /// The real user id of the process. Rules with a uid never match if it is unknown.
+  pub uid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+  pub action: RuleAction,
+  pub filename: Option<Matcher>,
+  pub argv: Option<Matcher>,
+  pub comm: Option<Matcher>,
+  pub uid: Option<u32>,
+}
+
+impl Rule {
+  pub fn matches(&self, subject: &ExecSubject) -> bool {
+    if let Some(matcher) = &self.filename {
+      let OutputMsg::Ok(filename) = subject.filename else {
+        return false;
+      };
+      if !matcher.matches_str(filename) {
+        return false;
+      }
+    }
+    if let Some(matcher) = &self.argv {
+      let Some(argv) = subject.argv else {
+        return false;
+      };
+      if !matcher.matches_argv(argv) {
+        return false;
+      }
+    }
+    if let Some(matcher) = &self.comm {
+      if !matcher.matches_str(subject.comm) {
+        return false;
+      }
+    }
+    if let Some(uid) = self.uid {
+      if subject.uid != Some(uid) {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+impl Display for Rule {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.action)?;
+    if let Some(filename) = &self.filename {
+      write!(f, " filename={filename}")?;
+    }
+    if let Some(argv) = &self.argv {
+      write!(f, " argv={argv}")?;
+    }
+    if let Some(comm) = &self.comm {
+      write!(f, " comm={comm}")?;
+    }
+    if let Some(uid) = self.uid {
+      write!(f, " uid={uid}")?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+  #[serde(default, rename = "rule")]
+  pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+  pub fn load(path: &Path) -> Result<Self, RuleLoadError> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&text)?)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.rules.len()
+  }
+
+  pub fn get(&self, index: usize) -> Option<&Rule> {
+    self.rules.get(index)
+  }
+
+  /// Find the first rule that matches the exec and return its index
+  pub fn evaluate(&self, subject: &ExecSubject) -> Option<(usize, &Rule)> {
+    self
+      .rules
+      .iter()
+      .enumerate()
+      .find(|(_, rule)| rule.matches(subject))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arcstr::literal;
+
+  use super::{ExecSubject, RuleAction, RuleSet};
+  use crate::event::OutputMsg;
+
+  const RULES: &str = r#"
+[[rule]]
+action = "Allow"
+filename = "/usr/bin/curl"
+uid = 0
+
+[[rule]]
+action = "Deny"
+filename = "/usr/bin/*"
+argv = "regex:^curl .*example"
+
+[[rule]]
+action = "Ask"
+comm = "ma?e"
+
+[[rule]]
+action = "Log"
+"#;
+
+  fn evaluate(rules: &RuleSet, filename: &str, argv: &[&str], comm: &str, uid: u32) -> usize {
+    let filename = OutputMsg::Ok(filename.into());
+    let argv: Vec<_> = argv
+      .iter()
+      .map(|arg| OutputMsg::Ok((*arg).into()))
+      .collect();
+    let subject = ExecSubject {
+      filename: &filename,
+      argv: Some(&argv),
+      comm,
+      uid: Some(uid),
+    };
+    rules.evaluate(&subject).unwrap().0
+  }
+
+  #[test]
+  fn first_matching_rule_wins() {
+    let rules: RuleSet = toml::from_str(RULES).unwrap();
+    assert_eq!(rules.len(), 4);
+    let curl = ["curl", "https://example.com"];
+    assert_eq!(evaluate(&rules, "/usr/bin/curl", &curl, "bash", 0), 0);
+    assert_eq!(evaluate(&rules, "/usr/bin/curl", &curl, "bash", 1000), 1);
+    assert_eq!(evaluate(&rules, "/bin/curl", &curl, "make", 1000), 2);
+    assert_eq!(evaluate(&rules, "/bin/curl", &curl, "bash", 1000), 3);
+    assert_eq!(rules.get(2).unwrap().action, RuleAction::Ask);
+  }
+
+  #[test]
+  fn unknown_uid_does_not_match() {
+    let rules: RuleSet = toml::from_str(RULES).unwrap();
+    let filename = OutputMsg::Ok(literal!("/usr/bin/curl"));
+    let subject = ExecSubject {
+      filename: &filename,
+      argv: None,
+      comm: "bash",
+      uid: None,
+    };
+    assert_eq!(rules.evaluate(&subject).unwrap().0, 3);
+  }
+
+  #[test]
+  fn invalid_rules_are_rejected() {
+    assert!(toml::from_str::<RuleSet>("[[rule]]\naction = \"Maybe\"").is_err());
+    assert!(toml::from_str::<RuleSet>("[[rule]]\naction = \"Deny\"\nargv = \"regex:(\"").is_err());
+    assert!(toml::from_str::<RuleSet>("[[rule]]\naction = \"Deny\"\nexe = \"/bin/sh\"").is_err());
+  }
+}
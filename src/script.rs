@@ -0,0 +1,179 @@
+//! Turn the exec events into a shell script that runs the same commands again.
+//!
+//! The successful execs become the commands of the script in the order they happened.
+//! A `cd` precedes a command whose working directory differs from that of the previous one,
+//! and the environment variables that the exec changed are set by `env`.
+//!
+//! An exec whose process or a descendant of it execs again later, e.g. `make` or `sh -c`,
+//! already runs the commands that follow it. It is kept as a comment so that the commands are
+//! not run twice.
+
+use std::{collections::HashMap, fmt::Write};
+
+use nix::unistd::Pid;
+
+use crate::{
+  action::SupportedShell,
+  event::{OutputMsg, TracerEventDetails},
+  export::JsonMetaData,
+};
+
+struct Command {
+  pid: Pid,
+  cwd: OutputMsg,
+  cmdline: String,
+  /// Whether the command runs some of the commands after it
+  driver: bool,
+}
+
+#[derive(Default)]
+pub struct ShellScriptBuilder {
+  commands: Vec<Command>,
+  /// pid -> ppid
+  parents: HashMap<Pid, Pid>,
+  /// pid -> the commands of the process that are not known to be drivers yet
+  pending: HashMap<Pid, Vec<usize>>,
+}
+
+impl ShellScriptBuilder {
+  pub fn record(&mut self, details: &TracerEventDetails) {
+    match details {
+      TracerEventDetails::NewChild { ppid, pid, .. } => {
+        self.parents.insert(*pid, *ppid);
+      }
+      TracerEventDetails::Exec(exec) if exec.result == 0 => {
+        // The earlier commands of this process and of its ancestors run this one
+        let mut pid = Some(exec.pid);
+        // Guard against loops in case of pid reuse
+        let mut depth = 0;
+        while let Some(p) = pid.filter(|_| depth <= self.parents.len()) {
+          for i in self.pending.remove(&p).unwrap_or_default() {
+            self.commands[i].driver = true;
+          }
+          pid = self.parents.get(&p).copied();
+          depth += 1;
+        }
+        self
+          .pending
+          .entry(exec.pid)
+          .or_default()
+          .push(self.commands.len());
+        self.commands.push(Command {
+          pid: exec.pid,
+          cwd: exec.cwd.clone(),
+          cmdline: TracerEventDetails::cmdline_for_shell(exec, None, SupportedShell::Sh),
+          driver: false,
+        });
+      }
+      _ => (),
+    }
+  }
+
+  pub fn finish(self, meta: &JsonMetaData) -> String {
+    let mut script = String::new();
+    let _ = writeln!(script, "#!/bin/sh");
+    let _ = writeln!(script, "# Generated by {} {}", meta.generator, meta.version);
+    if !meta.notes.is_empty() {
+      let _ = writeln!(script, "# {}", meta.notes);
+    }
+    let _ = writeln!(
+      script,
+      "# The environment variables are relative to the environment that tracexec ran in."
+    );
+    let mut cwd = None;
+    for command in self.commands {
+      if command.driver {
+        let _ = writeln!(script, "\n# [{}] {}", command.pid, command.cmdline);
+        continue;
+      }
+      if cwd.as_ref() != Some(&command.cwd) {
+        let _ = writeln!(
+          script,
+          "\ncd {}",
+          command.cwd.shell_escaped(SupportedShell::Sh)
+        );
+        cwd = Some(command.cwd);
+      }
+      let _ = writeln!(script, "{}", command.cmdline);
+    }
+    script
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+  };
+
+  use arcstr::literal;
+
+  use super::*;
+  use crate::{
+    event::ExecEvent,
+    proc::{BaselineInfo, EnvDiff},
+  };
+
+  fn exec(pid: i32, cwd: &str, argv: &[&str]) -> TracerEventDetails {
+    TracerEventDetails::Exec(Box::new(ExecEvent {
+      pid: Pid::from_raw(pid),
+      ppid: None,
+      cwd: OutputMsg::Ok(cwd.into()),
+      comm: literal!("sh"),
+      filename: OutputMsg::Ok(format!("/usr/bin/{}", argv[0]).into()),
+      argv: Arc::new(Ok(
+        argv.iter().map(|&arg| OutputMsg::Ok(arg.into())).collect(),
+      )),
+      envp: Arc::new(Ok(BTreeMap::new())),
+      interpreter: None,
+      container: None,
+      cgroup: None,
+      exe: None,
+      exe_hash: None,
+      privileges: None,
+      credentials: None,
+      capabilities: None,
+      security_label: None,
+      security_label_before: None,
+      audit: Vec::new(),
+      env_diff: Ok(EnvDiff {
+        added: BTreeMap::new(),
+        removed: BTreeSet::new(),
+        modified: BTreeMap::new(),
+      }),
+      fdinfo: Default::default(),
+      result: 0,
+      process: None,
+    }))
+  }
+
+  #[test]
+  fn drivers_are_commented_out() {
+    let mut builder = ShellScriptBuilder::default();
+    builder.record(&exec(1, "/src", &["make"]));
+    builder.record(&TracerEventDetails::NewChild {
+      ppid: Pid::from_raw(1),
+      pcomm: literal!("make"),
+      pid: Pid::from_raw(2),
+    });
+    builder.record(&exec(2, "/src/lib", &["cc", "-c", "a b.c"]));
+    builder.record(&exec(3, "/src/lib", &["ls"]));
+    let script = builder.finish(&JsonMetaData::new(BaselineInfo::new().unwrap()));
+    let commands = script
+      .lines()
+      // Skip the header
+      .skip_while(|line| line.starts_with('#'))
+      .filter(|line| !line.is_empty())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      commands,
+      [
+        "# [1] env -a make /usr/bin/make",
+        "cd /src/lib",
+        "env -a cc /usr/bin/cc -c a' b.c'",
+        "env -a ls /usr/bin/ls",
+      ]
+    );
+  }
+}
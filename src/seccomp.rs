@@ -1,20 +1,53 @@
-use libseccomp::{ScmpAction, ScmpArch, ScmpFilterContext};
+#[cfg(feature = "seccomp-notify")]
+use std::os::fd::RawFd;
 
+use libseccomp::{ScmpAction, ScmpArch, ScmpFilterContext, ScmpSyscall};
 
-pub fn load_seccomp_filters() -> color_eyre::Result<()> {
+use crate::cli::options::TrappedSyscall;
+
+/// The ABIs other than the native one that a tracee could use to exec.
+///
+/// The tracer also needs to recognize and inspect the exec syscalls of these ABIs.
+/// Currently we can't inspect 32bit ARM processes on aarch64.
+const COMPAT_ARCHES: &[ScmpArch] = if cfg!(target_arch = "x86_64") {
+  &[ScmpArch::X86, ScmpArch::X32]
+} else {
+  &[]
+};
+
+/// Load a filter that only traps execve, execveat and the `trapped` syscalls.
+///
+/// All other syscalls are allowed without stopping the tracee so they run at full speed.
+pub fn load_seccomp_filters(trapped: &[TrappedSyscall]) -> color_eyre::Result<()> {
+  let names = trapped.iter().flat_map(|syscall| syscall.names());
+  load_exec_filter(ScmpAction::Trace(0), names.copied())?;
+  Ok(())
+}
+
+/// Load a filter that sends execve and execveat to a supervisor in userspace and return the
+/// listener fd, from which the supervisor receives the syscalls and decides their results.
+#[cfg(feature = "seccomp-notify")]
+pub fn load_notify_filter() -> color_eyre::Result<RawFd> {
+  let filter = load_exec_filter(ScmpAction::Notify, [])?;
+  Ok(filter.get_notify_fd()?)
+}
+
+fn load_exec_filter(
+  action: ScmpAction,
+  extra: impl IntoIterator<Item = &'static str>,
+) -> color_eyre::Result<ScmpFilterContext> {
   libseccomp::reset_global_state()?;
   let mut filter = ScmpFilterContext::new_filter(ScmpAction::Allow)?;
-  filter.add_rule(ScmpAction::Trace(0), nix::libc::SYS_execve as i32)?;
-  filter.add_rule(ScmpAction::Trace(0), nix::libc::SYS_execveat as i32)?;
-  if cfg!(target_arch = "x86_64") {
-    let mut filter32 = ScmpFilterContext::new_filter(ScmpAction::Allow)?;
-    filter32.remove_arch(ScmpArch::native())?;
-    filter32.add_arch(ScmpArch::X86)?;
-    // libseccomp translates the syscall number for us.
-    filter32.add_rule(ScmpAction::Trace(0), nix::libc::SYS_execve as i32)?;
-    filter32.add_rule(ScmpAction::Trace(0), nix::libc::SYS_execveat as i32)?;
-    filter.merge(filter32)?;
+  // The default is to kill the process if it makes a syscall of an ABI not in the filter.
+  // Tracees using an unknown ABI are not traced but should keep working.
+  filter.set_act_badarch(ScmpAction::Allow)?;
+  for &arch in COMPAT_ARCHES {
+    filter.add_arch(arch)?;
+  }
+  // libseccomp translates the syscall number for each ABI for us.
+  for name in ["execve", "execveat"].into_iter().chain(extra) {
+    filter.add_rule(action, ScmpSyscall::from_name(name)?)?;
   }
   filter.load()?;
-  Ok(())
+  Ok(filter)
 }
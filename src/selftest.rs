@@ -0,0 +1,271 @@
+//! Verify that the tracer captures the exec events of tricky short-lived processes.
+//!
+//! Each fixture is a hidden subcommand of tracexec itself, so that the self test does not
+//! depend on a compiler or tools other than `true` being available on the system.
+
+use std::{
+  ffi::CString,
+  os::unix::ffi::OsStringExt,
+  path::PathBuf,
+  sync::{Arc, OnceLock},
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, OptionExt};
+use nix::{
+  libc,
+  sys::{
+    signal::{raise, signal, SigHandler, Signal},
+    wait::waitpid,
+  },
+  unistd::{execv, fork, ForkResult, Pid},
+};
+use owo_colors::OwoColorize;
+use strum::Display;
+use tokio::sync::mpsc;
+
+use crate::{
+  cli::args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+  event::{OutputMsg, TracerEvent, TracerEventDetails, TracerMessage},
+  proc::BaselineInfo,
+  tracer::{Tracer, TracerMode},
+};
+
+/// Depth of the process chain in the deep recursion fixture
+const RECURSION_DEPTH: usize = 64;
+/// Number of concurrent children in the exec storm fixture
+const STORM_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Fixture {
+  /// Fork and exec without doing anything in between
+  ImmediateExec,
+  /// vfork and exec
+  VforkExec,
+  /// Exec from a signal handler
+  SignalHandlerExec,
+  /// Many children that exec concurrently
+  ExecStorm,
+  /// A long chain of processes that exec
+  DeepRecursion,
+  /// Exec from a thread other than the main thread
+  ThreadExec,
+}
+
+impl Fixture {
+  /// The number of successful exec events of `true` the fixture is expected to produce
+  fn expected_true_execs(&self) -> usize {
+    match self {
+      Self::ExecStorm => STORM_SIZE,
+      _ => 1,
+    }
+  }
+
+  /// The number of successful exec events of the fixture itself, including the root command
+  fn expected_fixture_execs(&self) -> usize {
+    match self {
+      Self::DeepRecursion => RECURSION_DEPTH + 1,
+      _ => 1,
+    }
+  }
+}
+
+static TRUE_EXECUTABLE: OnceLock<CString> = OnceLock::new();
+
+fn true_executable() -> color_eyre::Result<PathBuf> {
+  let paths = std::env::var_os("PATH").ok_or_eyre("PATH is not set")?;
+  std::env::split_paths(&paths)
+    .map(|dir| dir.join("true"))
+    .find(|path| path.is_file())
+    .ok_or_eyre("executable `true` not found in PATH")
+}
+
+fn exec_true() -> ! {
+  let path = TRUE_EXECUTABLE.get().unwrap();
+  let _ = execv(path, &[c"true"]);
+  unsafe { libc::_exit(127) }
+}
+
+extern "C" fn exec_true_on_signal(_: libc::c_int) {
+  exec_true()
+}
+
+fn wait_all(children: &[Pid]) -> color_eyre::Result<()> {
+  for &child in children {
+    waitpid(child, None)?;
+  }
+  Ok(())
+}
+
+/// Run a fixture. This is called in the tracee.
+pub fn run_fixture(fixture: Fixture, depth: usize) -> color_eyre::Result<()> {
+  TRUE_EXECUTABLE
+    .set(CString::new(
+      true_executable()?.into_os_string().into_vec(),
+    )?)
+    .unwrap();
+  match fixture {
+    Fixture::ImmediateExec => match unsafe { fork()? } {
+      ForkResult::Child => exec_true(),
+      ForkResult::Parent { child } => wait_all(&[child])?,
+    },
+    Fixture::VforkExec => {
+      let path = TRUE_EXECUTABLE.get().unwrap();
+      let argv = [c"true".as_ptr(), std::ptr::null()];
+      // Only async-signal-safe calls on memory prepared before vfork are made in the child
+      #[allow(deprecated)]
+      match unsafe { libc::vfork() } {
+        -1 => return Err(std::io::Error::last_os_error().into()),
+        0 => unsafe {
+          libc::execv(path.as_ptr(), argv.as_ptr());
+          libc::_exit(127)
+        },
+        child => wait_all(&[Pid::from_raw(child)])?,
+      }
+    }
+    Fixture::SignalHandlerExec => {
+      unsafe { signal(Signal::SIGUSR1, SigHandler::Handler(exec_true_on_signal))? };
+      raise(Signal::SIGUSR1)?;
+      bail!("The signal handler did not exec");
+    }
+    Fixture::ExecStorm => {
+      let mut children = Vec::with_capacity(STORM_SIZE);
+      for _ in 0..STORM_SIZE {
+        match unsafe { fork()? } {
+          ForkResult::Child => exec_true(),
+          ForkResult::Parent { child } => children.push(child),
+        }
+      }
+      wait_all(&children)?;
+    }
+    Fixture::DeepRecursion => {
+      if depth == 0 {
+        exec_true();
+      }
+      let status = std::process::Command::new(std::env::current_exe()?)
+        .args(fixture_args(fixture, depth - 1))
+        .status()?;
+      if !status.success() {
+        bail!("The child fixture failed: {status}");
+      }
+    }
+    Fixture::ThreadExec => {
+      // The exec replaces the whole thread group, so the thread never finishes
+      let _ = std::thread::spawn(|| {
+        exec_true();
+      })
+      .join();
+      bail!("The thread did not exec");
+    }
+  }
+  Ok(())
+}
+
+fn fixture_args(fixture: Fixture, depth: usize) -> [String; 4] {
+  [
+    "selftest-fixture".to_string(),
+    fixture.to_string(),
+    "--depth".to_string(),
+    depth.to_string(),
+  ]
+}
+
+/// Trace the fixture and count the successful exec events of `true` and the fixture.
+async fn trace_fixture(
+  fixture: Fixture,
+  exe: &str,
+  ptrace_args: &PtraceArgs,
+) -> color_eyre::Result<(usize, usize)> {
+  let baseline = BaselineInfo::new()?;
+  let tracer_event_args = TracerEventArgs::all();
+  let (msg_tx, mut msg_rx) = tracer_event_args.channel(Arc::new(baseline.clone()));
+  let (req_tx, req_rx) = mpsc::unbounded_channel();
+  let tracer = Arc::new(Tracer::new(
+    TracerMode::Log { foreground: false },
+    LogModeArgs::default(),
+    ModifierArgs::default(),
+    ptrace_args.clone(),
+    tracer_event_args,
+    baseline,
+    msg_tx,
+    None,
+    req_tx,
+  )?);
+  let mut cmd = vec![exe.to_string()];
+  cmd.extend(fixture_args(
+    fixture,
+    if fixture == Fixture::DeepRecursion {
+      RECURSION_DEPTH
+    } else {
+      0
+    },
+  ));
+  tracer.spawn(cmd, None, req_rx).await??;
+  let mut true_execs = 0;
+  let mut fixture_execs = 0;
+  let fixture_name = fixture.to_string();
+  while let Some(msg) = msg_rx.recv().await {
+    let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      ..
+    }) = msg
+    else {
+      continue;
+    };
+    if exec.result != 0 {
+      continue;
+    }
+    let Ok(argv) = exec.argv.as_deref() else {
+      continue;
+    };
+    match argv {
+      [OutputMsg::Ok(arg0)] if arg0.as_str() == "true" => true_execs += 1,
+      [_, OutputMsg::Ok(subcommand), OutputMsg::Ok(name), ..]
+        if subcommand.as_str() == "selftest-fixture" && name.as_str() == fixture_name =>
+      {
+        fixture_execs += 1
+      }
+      _ => (),
+    }
+  }
+  Ok((true_execs, fixture_execs))
+}
+
+pub async fn run(ptrace_args: PtraceArgs) -> color_eyre::Result<()> {
+  // Fail early if the fixtures cannot run
+  true_executable()?;
+  let exe = std::env::current_exe()?.to_string_lossy().into_owned();
+  let mut failures = 0;
+  for fixture in Fixture::value_variants() {
+    let expected = (
+      fixture.expected_true_execs(),
+      fixture.expected_fixture_execs(),
+    );
+    match trace_fixture(*fixture, &exe, &ptrace_args).await {
+      Ok(captured) if captured == expected => {
+        println!("{} {fixture}", "PASS".green().bold());
+      }
+      Ok((true_execs, fixture_execs)) => {
+        failures += 1;
+        println!(
+          "{} {fixture}: captured {true_execs}/{} execs of true and {fixture_execs}/{} execs of the fixture",
+          "FAIL".red().bold(),
+          expected.0,
+          expected.1
+        );
+      }
+      Err(e) => {
+        failures += 1;
+        println!("{} {fixture}: {e}", "FAIL".red().bold());
+      }
+    }
+  }
+  if failures > 0 {
+    bail!(
+      "{failures} of {} fixtures failed",
+      Fixture::value_variants().len()
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,204 @@
+//! Summarize the exec events of a trace.
+
+use std::{
+  collections::{BTreeMap, HashMap, HashSet},
+  io::Write,
+  time::Duration,
+};
+
+use itertools::Itertools;
+use nix::{errno::Errno, unistd::Pid};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::{event::TracerEventDetails, export::TraceNotes, i18n};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct ProgramStats {
+  execs: u64,
+  failures: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ExecStats {
+  processes: HashSet<Pid>,
+  /// filename -> stats
+  programs: HashMap<String, ProgramStats>,
+  execs: u64,
+  failures: u64,
+  /// exec result -> count
+  failures_per_errno: HashMap<i64, u64>,
+}
+
+/// The machine-readable summary of a traced session
+#[derive(Debug, Serialize)]
+pub struct ExecSummary {
+  pub processes: usize,
+  pub execs: u64,
+  pub failures: u64,
+  /// errno name -> count, e.g. `ENOENT`
+  pub failures_per_errno: BTreeMap<String, u64>,
+  /// Wall-clock time of the session in seconds
+  pub wall_time: f64,
+  /// The exit code of the root process
+  pub exit_code: i32,
+}
+
+fn failure_rate(failures: u64, execs: u64) -> f64 {
+  if execs == 0 {
+    0.
+  } else {
+    failures as f64 * 100. / execs as f64
+  }
+}
+
+impl ExecStats {
+  pub fn record(&mut self, details: &TracerEventDetails) {
+    if let Some(pid) = details.pid() {
+      self.processes.insert(pid);
+    }
+    match details {
+      TracerEventDetails::NewChild { pid, .. } => {
+        self.processes.insert(*pid);
+      }
+      TracerEventDetails::Exec(exec) => {
+        let failed = exec.result != 0;
+        let program = self
+          .programs
+          .entry(exec.filename.as_ref().to_owned())
+          .or_default();
+        program.execs += 1;
+        self.execs += 1;
+        if failed {
+          program.failures += 1;
+          self.failures += 1;
+          *self.failures_per_errno.entry(exec.result).or_default() += 1;
+        }
+      }
+      _ => (),
+    }
+  }
+
+  pub fn summary(&self, span: Duration, exit_code: i32) -> ExecSummary {
+    let mut failures_per_errno = BTreeMap::new();
+    for (&result, &count) in &self.failures_per_errno {
+      let name = format!("{:?}", Errno::from_raw(-result as i32));
+      *failures_per_errno.entry(name).or_default() += count;
+    }
+    ExecSummary {
+      processes: self.processes.len(),
+      execs: self.execs,
+      failures: self.failures,
+      failures_per_errno,
+      wall_time: span.as_secs_f64(),
+      exit_code,
+    }
+  }
+
+  /// Print the summary. `notes` are the notes of the loaded trace and
+  /// `span` is the wall-clock time of the trace if known.
+  pub fn print(
+    &self,
+    out: &mut dyn Write,
+    notes: Option<&TraceNotes>,
+    span: Option<Duration>,
+    top: usize,
+  ) -> std::io::Result<()> {
+    if let Some(notes) = notes.filter(|notes| !notes.is_empty()) {
+      writeln!(
+        out,
+        "{}: {}",
+        i18n::tr("stats.notes", "Notes").bold(),
+        notes
+      )?;
+    }
+    let span_label = i18n::tr("stats.span", "Wall-clock span");
+    match span {
+      Some(span) => writeln!(out, "{}: {:.3}s", span_label.bold(), span.as_secs_f64())?,
+      None => writeln!(
+        out,
+        "{}: {}",
+        span_label.bold(),
+        i18n::tr("stats.span-unknown", "unknown")
+      )?,
+    }
+    writeln!(
+      out,
+      "{}: {}",
+      i18n::tr("stats.processes", "Processes").bold(),
+      self.processes.len()
+    )?;
+    writeln!(
+      out,
+      "{}: {}",
+      i18n::tr("stats.execs", "Execs").bold(),
+      i18n::tr_args(
+        "stats.execs-summary",
+        "{execs} ({failures} failed, {rate}%)",
+        &[
+          ("execs", &self.execs),
+          ("failures", &self.failures),
+          (
+            "rate",
+            &format!("{:.1}", failure_rate(self.failures, self.execs))
+          ),
+        ]
+      )
+    )?;
+    writeln!(
+      out,
+      "{}: {}",
+      i18n::tr("stats.programs", "Programs").bold(),
+      self.programs.len()
+    )?;
+    if self.programs.is_empty() {
+      return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(
+      out,
+      "{}",
+      format!(
+        "{:>8} {:>8} {:>8}  {}",
+        i18n::tr("stats.column.execs", "Execs"),
+        i18n::tr("stats.column.failed", "Failed"),
+        i18n::tr("stats.column.fail-rate", "Fail%"),
+        i18n::tr("stats.column.program", "Program")
+      )
+      .bold()
+    )?;
+    for (program, stats) in self
+      .programs
+      .iter()
+      .sorted_by(|(p1, s1), (p2, s2)| s2.execs.cmp(&s1.execs).then_with(|| p1.cmp(p2)))
+      .take(top)
+    {
+      // Pad before coloring. Otherwise the escape sequences count towards the width.
+      let rate = format!("{:>8.1}", failure_rate(stats.failures, stats.execs));
+      writeln!(
+        out,
+        "{:>8} {:>8} {}  {}",
+        stats.execs,
+        stats.failures,
+        if stats.failures > 0 {
+          rate.red().to_string()
+        } else {
+          rate
+        },
+        program
+      )?;
+    }
+    if self.programs.len() > top {
+      writeln!(
+        out,
+        "{}",
+        i18n::tr_args(
+          "stats.more",
+          "... and {count} more",
+          &[("count", &(self.programs.len() - top))]
+        )
+      )?;
+    }
+    out.flush()
+  }
+}
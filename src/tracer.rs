@@ -1,11 +1,15 @@
 use std::{
-  collections::BTreeMap,
+  collections::{BTreeMap, HashSet},
   ffi::CString,
   io::{self, stdin},
   ops::ControlFlow,
   os::fd::AsRawFd,
+  path::PathBuf,
   process::exit,
-  sync::{atomic::AtomicU32, Arc, RwLock},
+  sync::{
+    atomic::{AtomicU32, AtomicUsize},
+    Arc, Mutex, RwLock,
+  },
   time::Duration,
 };
 
@@ -13,59 +17,74 @@ use arcstr::ArcStr;
 use cfg_if::cfg_if;
 use either::Either;
 use enumflags2::BitFlags;
-use inspect::{read_arcstr, read_output_msg_array};
+use inspect::{read_arcstr, read_envp, read_output_msg_array};
 use nix::{
   errno::Errno,
   libc::{
-    self, dup2, pid_t, pthread_self, pthread_setname_np, raise, AT_EMPTY_PATH, SIGSTOP, S_ISGID,
-    S_ISUID,
+    self, pid_t, pthread_self, pthread_setname_np, raise, AT_EMPTY_PATH, SIGSTOP, S_ISGID, S_ISUID,
   },
+  sched::CloneFlags,
   sys::{
     signal::{kill, Signal},
     stat::fstat,
     wait::{waitpid, WaitPidFlag, WaitStatus},
   },
   unistd::{
-    getpid, initgroups, setpgid, setresgid, setresuid, setsid, tcsetpgrp, Gid, Pid, Uid, User,
+    getgid, getpid, getsid, getuid, initgroups, setpgid, setresgid, setresuid, setsid, tcsetpgrp,
+    Gid, Pid, Uid, User,
   },
 };
 use state::{PendingDetach, Syscall};
 use tokio::{
   select,
+  signal::unix::{signal, SignalKind},
   sync::mpsc::{error::SendError, UnboundedReceiver, UnboundedSender},
 };
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
   arch::RegsExt,
-  cli::args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+  audit::audit_exec,
+  caps::read_exec_capabilities,
+  channel::MessageSender,
+  cli::{
+    args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+    options::{MaxEventsAction, TraceeStdio, TrappedSyscall},
+  },
   cmdbuilder::CommandBuilder,
+  container::read_cgroup,
   event::{
-    filterable_event, ExecEvent, OutputMsg, ProcessStateUpdate, ProcessStateUpdateEvent,
-    TracerEvent, TracerEventDetails, TracerEventDetailsKind, TracerEventMessage, TracerMessage,
+    filterable_event, ExecEvent, FilterableTracerEventDetails, OutputMsg, ProcessStateUpdate,
+    ProcessStateUpdateEvent, TracerEvent, TracerEventDetails, TracerEventDetailsKind,
+    TracerEventMessage, TracerMessage,
   },
+  exe_hash::ExeHasher,
   printer::{Printer, PrinterArgs, PrinterOut},
+  probe::KERNEL_FEATURES,
   proc::{
-    cached_string, diff_env, parse_envp, read_comm, read_cwd, read_exe, read_fd, read_fds,
-    read_interpreter_recursive, BaselineInfo,
+    cached_string, diff_env, read_comm, read_credentials, read_cwd, read_exe, read_exe_privileges,
+    read_fd, read_fds, read_interpreter_recursive, read_root, read_security_label, read_uid, Argv,
+    BaselineInfo, ProcessHandle,
   },
   pty::{self, Child, UnixSlavePty},
+  rules::{ExecSubject, Rule, RuleAction, RuleSet},
   tracer::{inspect::read_env, state::ProcessExit},
 };
 
-use self::state::{ExecData, ProcessState, ProcessStateStore, ProcessStatus};
-use self::{
-  inspect::{read_string, read_string_array},
-  state::BreakPoint,
+use self::state::{
+  BreakPoint, BreakPointType, ExecData, ProcessState, ProcessStateStore, ProcessStatus,
 };
 use self::{ptrace::*, state::BreakPointStop};
 
+mod builder;
 mod inspect;
+mod namespace;
 mod ptrace;
 pub mod state;
 #[cfg(test)]
 mod test;
 
+pub use builder::{BuiltTracer, TracerBuilder, TracerControl, TracerEventStream};
 pub use inspect::InspectError;
 
 cfg_if! {
@@ -77,20 +96,70 @@ cfg_if! {
 }
 
 pub struct Tracer {
-  with_tty: bool,
   mode: TracerMode,
   pub store: RwLock<ProcessStateStore>,
   printer: Printer,
   modifier_args: ModifierArgs,
   filter: BitFlags<TracerEventDetailsKind>,
+  /// The events that are sent but not printed, e.g. the forks for the summary of log mode
+  unprinted: BitFlags<TracerEventDetailsKind>,
   baseline: Arc<BaselineInfo>,
   #[cfg(feature = "seccomp-bpf")]
   seccomp_bpf: SeccompBpf,
-  msg_tx: UnboundedSender<TracerMessage>,
+  msg_tx: MessageSender,
   user: Option<User>,
   breakpoints: RwLock<BTreeMap<u32, BreakPoint>>,
   req_tx: UnboundedSender<PendingRequest>,
-  delay: Duration,
+  /// Poll with this delay instead of waiting for SIGCHLD
+  delay: Option<Duration>,
+  follow_forks: bool,
+  /// The namespaces to start the root tracees in
+  unshare: CloneFlags,
+  /// Environment variables to set for the root tracees
+  set_env: Vec<(String, String)>,
+  /// Environment variables to remove for the root tracees
+  unset_env: Vec<String>,
+  /// The working directory of the root tracees
+  cwd: Option<PathBuf>,
+  /// Where the standard streams of the root tracees go without a pseudo terminal
+  stdio: TraceeStdio,
+  /// The syscalls other than the execs whose effects on the tracees are followed
+  trapped_syscalls: Vec<TrappedSyscall>,
+  rules: RwLock<LoadedRules>,
+  /// The rules file to reload the rules from
+  rules_path: Option<PathBuf>,
+  /// Hashes the executed binaries if `--hash-exe` is used
+  exe_hasher: Option<ExeHasher>,
+  /// The number of exec events collected, for `--max-events`
+  exec_events: AtomicUsize,
+  /// Detach from all tracees instead of killing them when interrupted
+  detach_on_exit: bool,
+  /// Catch SIGINT and SIGTERM and forward them to the tracees
+  handles_interrupts: bool,
+  /// The exit of the last root tracee, which is held back while the daemonized tracees are followed
+  last_tracee_exit: Mutex<Option<FilterableTracerEventDetails>>,
+}
+
+/// The rules in effect.
+///
+/// Every rule takes an id from the breakpoint ids so that the exec approvals asked by the rules
+/// could be managed like breakpoint hits.
+struct LoadedRules {
+  rules: RuleSet,
+  first_id: u32,
+}
+
+impl LoadedRules {
+  fn new(rules: RuleSet) -> Self {
+    let first_id = BREAKPOINT_ID.fetch_add(rules.len() as u32, std::sync::atomic::Ordering::SeqCst);
+    Self { rules, first_id }
+  }
+
+  /// Get a rule and its index by its id
+  fn get(&self, id: u32) -> Option<(usize, &Rule)> {
+    let index = id.checked_sub(self.first_id)? as usize;
+    self.rules.get(index).map(|rule| (index, rule))
+  }
 }
 
 pub enum TracerMode {
@@ -98,6 +167,62 @@ pub enum TracerMode {
   Log { foreground: bool },
 }
 
+/// How the tracer learns about state changes of the tracees.
+///
+/// The kernel sends SIGCHLD to the tracer on every ptrace stop and exit of the tracees.
+/// Waiting for it costs no CPU while the tracees are idle and wakes up the tracer right
+/// after a state change. SIGCHLDs are coalesced while the tracer is busy, so the tracer
+/// handles all the pending events after every wakeup and does not wait at all while it
+/// knows that some events are left behind.
+///
+/// Polling wakes up the tracer even if nothing happens, which wastes CPU while the
+/// tracees are idle, and adds up to one delay of latency to every event. It is only
+/// used when `--tracer-delay` is specified, e.g. to throttle the tracer.
+enum WaitStrategy {
+  Blocking(tokio::signal::unix::Signal),
+  Polling(tokio::time::Interval),
+}
+
+impl WaitStrategy {
+  /// Wait until there might be new events. Returns immediately if some events are still pending.
+  async fn ready(&mut self, pending: bool) {
+    match self {
+      Self::Blocking(_) if pending => (),
+      Self::Blocking(sigchld) => {
+        sigchld.recv().await;
+      }
+      Self::Polling(interval) => {
+        interval.tick().await;
+      }
+    }
+  }
+}
+
+/// Wait for SIGHUP if it is caught. Otherwise, wait forever.
+async fn recv_sighup(sighup: &mut Option<tokio::signal::unix::Signal>) {
+  match sighup {
+    Some(sighup) => {
+      sighup.recv().await;
+    }
+    None => std::future::pending().await,
+  }
+}
+
+/// Wait for SIGINT or SIGTERM if they are caught. Otherwise, wait forever.
+async fn recv_interrupt(
+  interrupt: &mut Option<(tokio::signal::unix::Signal, tokio::signal::unix::Signal)>,
+) -> Signal {
+  match interrupt {
+    Some((sigint, sigterm)) => {
+      select! {
+        _ = sigint.recv() => Signal::SIGINT,
+        _ = sigterm.recv() => Signal::SIGTERM,
+      }
+    }
+    None => std::future::pending().await,
+  }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BreakPointHit {
   pub bid: u32,
@@ -107,6 +232,10 @@ pub struct BreakPointHit {
 
 pub enum PendingRequest {
   ResumeProcess(BreakPointHit),
+  /// Resume all the processes stopped at a breakpoint
+  ResumeBreakPoint(u32),
+  /// Make the exec of a process stopped on syscall entry fail with EPERM
+  DenyExec(BreakPointHit),
   DetachProcess {
     hit: BreakPointHit,
     signal: Option<Signal>,
@@ -114,6 +243,14 @@ pub enum PendingRequest {
   },
   #[cfg(feature = "seccomp-bpf")]
   SuspendSeccompBpf(Pid),
+  SpawnRoot(Vec<String>),
+  Shutdown,
+  /// Detach from all tracees and exit
+  DetachAll,
+  /// Detach from all tracees but keep waiting for the root tracees to exit
+  DetachAllAndWait,
+  /// Kill all tracees
+  KillAll,
 }
 
 impl PartialEq for TracerMode {
@@ -128,39 +265,75 @@ impl PartialEq for TracerMode {
 }
 
 impl Tracer {
-  // TODO: create a TracerBuilder maybe
+  /// Programs that embed tracexec create the tracer with a [`TracerBuilder`].
   #[allow(clippy::too_many_arguments)]
-  pub fn new(
+  pub(crate) fn new(
     mode: TracerMode,
     tracing_args: LogModeArgs,
     modifier_args: ModifierArgs,
     ptrace_args: PtraceArgs,
     tracer_event_args: TracerEventArgs,
     baseline: BaselineInfo,
-    event_tx: UnboundedSender<TracerMessage>,
+    event_tx: MessageSender,
     user: Option<User>,
     req_tx: UnboundedSender<PendingRequest>,
   ) -> color_eyre::Result<Self> {
     let baseline = Arc::new(baseline);
+    KERNEL_FEATURES.report_ptrace();
     #[cfg(feature = "seccomp-bpf")]
     let seccomp_bpf = if ptrace_args.seccomp_bpf == SeccompBpf::Auto {
-      // TODO: check if the kernel supports seccomp-bpf
-      // Let's just enable it for now and see if anyone complains
-      if user.is_some() {
+      use crate::probe::Feature;
+      if !KERNEL_FEATURES.has(Feature::SeccompTrace) {
+        SeccompBpf::Off
+      } else if user.is_some() {
         // Seccomp-bpf enforces no-new-privs, so when using --user to trace set(u|g)id
         // binaries, we disable seccomp-bpf by default.
         SeccompBpf::Off
+      } else if ptrace_args.no_follow_forks
+        || modifier_args.detaches_on_max_events()
+        || tracing_args.detach_on_exit
+      {
+        SeccompBpf::Off
       } else {
         SeccompBpf::On
       }
     } else {
       ptrace_args.seccomp_bpf
     };
+    // The seccomp filter is inherited by the children. Without a tracer, their execs fail with ENOSYS.
+    #[cfg(feature = "seccomp-bpf")]
+    if seccomp_bpf == SeccompBpf::On && ptrace_args.no_follow_forks {
+      color_eyre::eyre::bail!("seccomp-bpf could not be enabled when not following forks");
+    }
+    #[cfg(feature = "seccomp-bpf")]
+    if seccomp_bpf == SeccompBpf::On && modifier_args.detaches_on_max_events() {
+      color_eyre::eyre::bail!("--on-max-events detach could not be used with seccomp-bpf enabled");
+    }
+    #[cfg(feature = "seccomp-bpf")]
+    if seccomp_bpf == SeccompBpf::On && tracing_args.detach_on_exit {
+      color_eyre::eyre::bail!("--detach-on-exit could not be used with seccomp-bpf enabled");
+    }
+    let unshare = namespace::clone_flags(&ptrace_args.unshare);
+    // The command only enters the pid namespace in a forked child
+    if unshare.contains(CloneFlags::CLONE_NEWPID) && ptrace_args.no_follow_forks {
+      color_eyre::eyre::bail!("The pid namespace could not be unshared when not following forks");
+    }
+    if let Some(cwd) = &ptrace_args.tracee_cwd {
+      if !cwd.is_dir() {
+        color_eyre::eyre::bail!("{} is not a directory", cwd.display());
+      }
+    }
+    // The user is not mapped in the new user namespace
+    if unshare.contains(CloneFlags::CLONE_NEWUSER) && user.is_some() {
+      color_eyre::eyre::bail!("The user namespace could not be unshared when using --user");
+    }
+    let rules = match &ptrace_args.rules {
+      Some(path) => {
+        RuleSet::load(path).map_err(|e| color_eyre::eyre::eyre!("{e} ({})", path.display()))?
+      }
+      None => RuleSet::default(),
+    };
     Ok(Self {
-      with_tty: match &mode {
-        TracerMode::Tui(tty) => tty.is_some(),
-        TracerMode::Log { .. } => true,
-      },
       store: RwLock::new(ProcessStateStore::new()),
       #[cfg(feature = "seccomp-bpf")]
       seccomp_bpf,
@@ -175,25 +348,31 @@ impl Tracer {
           //        Ideally we should use another channel to send the exit code to the main thread.
           filter |= TracerEventDetailsKind::TraceeExit;
         }
+        if ptrace_args.trace_signals {
+          filter |= TracerEventDetailsKind::SignalDelivery;
+        }
         filter
       },
+      unprinted: BitFlags::empty(),
       printer: Printer::new(
         PrinterArgs::from_cli(&tracing_args, &modifier_args),
         baseline.clone(),
       ),
-      delay: {
-        #[allow(clippy::useless_let_if_seq)]
-        let mut default = Duration::from_micros(1);
-        #[cfg(feature = "seccomp-bpf")]
-        #[allow(clippy::useless_let_if_seq)]
-        if seccomp_bpf == SeccompBpf::On {
-          default = Duration::from_micros(500);
-        }
-        ptrace_args
-          .tracer_delay
-          .map(Duration::from_micros)
-          .unwrap_or(default)
-      },
+      stdio: tracing_args.tracee_stdio(),
+      detach_on_exit: tracing_args.detach_on_exit,
+      handles_interrupts: false,
+      delay: ptrace_args.tracer_delay.map(Duration::from_micros),
+      follow_forks: !ptrace_args.no_follow_forks,
+      unshare,
+      set_env: ptrace_args.set_env,
+      unset_env: ptrace_args.unset_env,
+      cwd: ptrace_args.tracee_cwd,
+      trapped_syscalls: ptrace_args.trap_syscalls,
+      rules: RwLock::new(LoadedRules::new(rules)),
+      rules_path: ptrace_args.rules,
+      exe_hasher: modifier_args.hash_exe.map(ExeHasher::new),
+      exec_events: AtomicUsize::new(0),
+      last_tracee_exit: Mutex::new(None),
       modifier_args,
       baseline,
       mode,
@@ -202,11 +381,42 @@ impl Tracer {
     })
   }
 
+  /// Also send these events without printing them.
+  /// The events that pass the filter are still printed.
+  pub(crate) fn send_unprinted(mut self, kinds: BitFlags<TracerEventDetailsKind>) -> Self {
+    self.unprinted = kinds & !self.filter;
+    self.filter |= kinds;
+    self
+  }
+
+  /// Catch SIGINT and SIGTERM while tracing and forward them to the root tracees.
+  ///
+  /// The signal handlers stay installed for the rest of the process,
+  /// so only the `tracexec` binary does this.
+  pub(crate) fn handle_interrupts(mut self) -> Self {
+    self.handles_interrupts = true;
+    self
+  }
+
   pub fn spawn(
     self: Arc<Self>,
     args: Vec<String>,
     output: Option<Box<PrinterOut>>,
     req_rx: UnboundedReceiver<PendingRequest>,
+  ) -> tokio::task::JoinHandle<color_eyre::Result<()>> {
+    let cmds = if args.is_empty() { vec![] } else { vec![args] };
+    self.spawn_group(cmds, output, req_rx)
+  }
+
+  /// Trace a group of root commands concurrently.
+  ///
+  /// When there are more than one commands, the output of the printer is attributed to
+  /// the root command that the process descends from.
+  pub fn spawn_group(
+    self: Arc<Self>,
+    cmds: Vec<Vec<String>>,
+    output: Option<Box<PrinterOut>>,
+    req_rx: UnboundedReceiver<PendingRequest>,
   ) -> tokio::task::JoinHandle<color_eyre::Result<()>> {
     tokio::task::spawn_blocking({
       move || {
@@ -217,7 +427,12 @@ impl Tracer {
         let tx = self.msg_tx.clone();
         let result = tokio::runtime::Handle::current().block_on(async move {
           self.printer.init_thread_local(output);
-          self.run(args, req_rx).await
+          let result = self.clone().run(cmds, req_rx).await;
+          if self.printer.args.dedup {
+            self.printer.print_dedup_summary()?;
+          }
+          self.printer.flush()?;
+          result
         });
         if let Err(e) = &result {
           tx.send(TracerMessage::FatalError(e.to_string())).unwrap();
@@ -227,56 +442,69 @@ impl Tracer {
     })
   }
 
-  async fn run(
-    self: Arc<Self>,
-    args: Vec<String>,
-    mut req_rx: UnboundedReceiver<PendingRequest>,
-  ) -> color_eyre::Result<()> {
+  /// Spawn a new root tracee and wait for it to be ready for tracing.
+  ///
+  /// There could be multiple root tracees in a single session.
+  fn spawn_root(&self, args: Vec<String>, root_index: Option<usize>) -> color_eyre::Result<Pid> {
     trace!("start_root_process: {:?}", args);
 
     let mut cmd = CommandBuilder::new(&args[0]);
     cmd.args(args.iter().skip(1));
-    cmd.cwd(std::env::current_dir()?);
+    let cwd = std::env::current_dir()?;
+    cmd.cwd(match &self.cwd {
+      Some(dir) => cwd.join(dir),
+      None => cwd,
+    });
+    for key in &self.unset_env {
+      cmd.env_remove(key);
+    }
+    for (key, value) in &self.set_env {
+      cmd.env(key, value);
+    }
 
     #[cfg(feature = "seccomp-bpf")]
     let seccomp_bpf = self.seccomp_bpf;
+    #[cfg(feature = "seccomp-bpf")]
+    let trapped_syscalls = self.trapped_syscalls.clone();
     let slave_pty = match &self.mode {
       TracerMode::Tui(tty) => tty.as_ref(),
       TracerMode::Log { .. } => None,
     };
-    let with_tty = self.with_tty;
     let use_pseudo_term = slave_pty.is_some();
     let user = self.user.clone();
+    let unshare = self.unshare;
+    let (uid, gid) = (getuid(), getgid());
+    let stdio = self.stdio.clone();
 
     let root_child = pty::spawn_command(
       slave_pty,
       cmd,
-      |_| Ok(()),
+      move |cmd| stdio.apply(cmd),
       move |program_path| {
         #[cfg(feature = "seccomp-bpf")]
         if seccomp_bpf == SeccompBpf::On {
-          seccomp::load_seccomp_filters()?;
-        }
-
-        if !with_tty {
-          unsafe {
-            let dev_null = std::fs::File::open("/dev/null")?;
-            dup2(dev_null.as_raw_fd(), 0);
-            dup2(dev_null.as_raw_fd(), 1);
-            dup2(dev_null.as_raw_fd(), 2);
-          }
+          seccomp::load_seccomp_filters(&trapped_syscalls)?;
         }
 
         if use_pseudo_term {
           setsid()?;
           if unsafe { libc::ioctl(0, libc::TIOCSCTTY as _, 0) } == -1 {
-            Err(io::Error::last_os_error())?;
+            let err = io::Error::last_os_error();
+            // EPERM: The terminal is already the controlling terminal of another root tracee
+            // in this session. It is still usable without being the controlling terminal.
+            if err.raw_os_error() != Some(libc::EPERM) {
+              Err(err)?;
+            }
           }
         } else {
           let me = getpid();
           setpgid(me, me)?;
         }
 
+        if !unshare.is_empty() {
+          namespace::unshare_namespaces(unshare, uid, gid)?;
+        }
+
         traceme()?;
         trace!("traceme setup!");
 
@@ -308,11 +536,19 @@ impl Tracer {
         }
         trace!("raise success!");
 
+        if unshare.contains(CloneFlags::CLONE_NEWPID) {
+          namespace::enter_pid_namespace(unshare.contains(CloneFlags::CLONE_NEWNS))?;
+        }
+
         Ok(())
       },
     )?
     .process_id();
-    filterable_event!(TraceeSpawn(root_child)).send_if_match(&self.msg_tx, self.filter)?;
+    filterable_event!(TraceeSpawn {
+      pid: root_child,
+      process: self.process_handle(root_child),
+    })
+    .send_if_match(&self.msg_tx, self.filter)?;
     // wait for child to be stopped by SIGSTOP
     loop {
       let status = waitpid(root_child, Some(WaitPidFlag::WSTOPPED))?;
@@ -329,6 +565,7 @@ impl Tracer {
     trace!("child stopped");
     let mut root_child_state = ProcessState::new(root_child, 0)?;
     root_child_state.ppid = Some(getpid());
+    root_child_state.root_index = root_index;
     {
       self.store.write().unwrap().insert(root_child_state);
     }
@@ -348,10 +585,12 @@ impl Tracer {
         | Options::PTRACE_O_TRACEEXIT
         | Options::PTRACE_O_EXITKILL
         | Options::PTRACE_O_TRACESYSGOOD
-        | Options::PTRACE_O_TRACEFORK
-        | Options::PTRACE_O_TRACECLONE
-        | Options::PTRACE_O_TRACEVFORK
     };
+    if self.follow_forks {
+      use nix::sys::ptrace::Options;
+      ptrace_opts |=
+        Options::PTRACE_O_TRACEFORK | Options::PTRACE_O_TRACECLONE | Options::PTRACE_O_TRACEVFORK;
+    }
     #[cfg(feature = "seccomp-bpf")]
     if self.seccomp_bpf == SeccompBpf::On {
       ptrace_opts |= ptrace::Options::PTRACE_O_TRACESECCOMP;
@@ -360,18 +599,97 @@ impl Tracer {
     // restart child
     trace!("resuming child");
     self.seccomp_aware_cont(root_child)?;
-    let mut collect_interval = tokio::time::interval(self.delay);
+    Ok(root_child)
+  }
 
-    loop {
+  async fn run(
+    self: Arc<Self>,
+    cmds: Vec<Vec<String>>,
+    mut req_rx: UnboundedReceiver<PendingRequest>,
+  ) -> color_eyre::Result<()> {
+    // Start listening before spawning any tracee so that no SIGCHLD is missed
+    let mut wait_strategy = match self.delay {
+      Some(delay) => WaitStrategy::Polling(tokio::time::interval(delay)),
+      None => WaitStrategy::Blocking(signal(SignalKind::child())?),
+    };
+    // SIGHUP reloads the rules. Only catch it when there is a rules file.
+    let mut sighup = match self.rules_path {
+      Some(_) => Some(signal(SignalKind::hangup())?),
+      None => None,
+    };
+    // Without catching SIGINT and SIGTERM, tracexec is killed by them in the middle of writing
+    // and all tracees are killed because of PTRACE_O_EXITKILL.
+    // The TUI handles the keys and the exit by itself.
+    let mut interrupt = if self.handles_interrupts {
+      Some((
+        signal(SignalKind::interrupt())?,
+        signal(SignalKind::terminate())?,
+      ))
+    } else {
+      None
+    };
+    let mut roots = HashSet::new();
+    let is_group = cmds.len() > 1;
+    for (i, args) in cmds.into_iter().enumerate() {
+      roots.insert(self.spawn_root(args, is_group.then_some(i))?);
+    }
+    let mut shutdown = false;
+    // Whether tracexec is interrupted by SIGINT or SIGTERM
+    let mut interrupted = false;
+    // Whether we are detaching from all tracees before exiting
+    let mut detaching = false;
+    // Whether there might be events that are not handled yet
+    let mut pending = true;
+
+    let result = loop {
       select! {
-        _ = collect_interval.tick() => {
-          let action = self.handle_waitpid_events(root_child)?;
+        _ = wait_strategy.ready(pending) => {
+          let action = self.handle_waitpid_events(&mut roots, detaching)?;
+          if detaching && shutdown && !self.store.read().unwrap().has_live_processes() {
+            break Ok(());
+          }
           match action {
             ControlFlow::Break(_) => {
-              break Ok(());
+              // Events of other tracees might be left behind
+              pending = true;
+              // All root tracees have exited.
+              // In an interactive session, more root tracees might be spawned later.
+              if shutdown || !self.is_interactive() {
+                break Ok(());
+              }
+            }
+            ControlFlow::Continue(more) => pending = more,
+          }
+        }
+        _ = recv_sighup(&mut sighup) => {
+          // Errors are already reported
+          let _ = self.reload_rules();
+        }
+        sig = recv_interrupt(&mut interrupt) => {
+          if interrupted {
+            warn!("Interrupted by {sig} again, killing all tracees");
+            self.kill_all()?;
+          } else if self.detach_on_exit {
+            info!("Interrupted by {sig}, detaching from all tracees");
+            self.request_detach_all()?;
+          } else {
+            // The root tracees are expected to exit in an orderly manner, which ends the tracing.
+            // After they have exited, the daemonized tracees are the ones left.
+            let targets = if roots.is_empty() {
+              self.store.read().unwrap().live_daemons().collect()
+            } else {
+              roots.iter().copied().collect::<Vec<_>>()
+            };
+            info!("Interrupted by {sig}, forwarding it to {targets:?}");
+            for target in targets {
+              match kill(target, sig) {
+                Ok(_) | Err(Errno::ESRCH) => (),
+                Err(e) => warn!("Failed to send {sig} to {target}: {e}"),
+              }
             }
-            ControlFlow::Continue(_) => {}
           }
+          interrupted = true;
+          pending = true;
         }
         Some(req) = req_rx.recv() => {
           match req {
@@ -380,6 +698,21 @@ impl Tracer {
               let state = store.get_current_mut(hit.pid).unwrap();
               self.proprgate_operation_error(hit, true, self.resume_process(state, hit.stop))?;
             }
+            PendingRequest::ResumeBreakPoint(bid) => {
+              let mut store = self.store.write().unwrap();
+              for state in store.live_processes_mut() {
+                if let ProcessStatus::BreakPointHit(hit) = state.status {
+                  if hit.bid == bid {
+                    self.proprgate_operation_error(hit, true, self.resume_process(state, hit.stop))?;
+                  }
+                }
+              }
+            }
+            PendingRequest::DenyExec(hit) => {
+              let mut store = self.store.write().unwrap();
+              let state = store.get_current_mut(hit.pid).unwrap();
+              self.proprgate_operation_error(hit, true, self.deny_exec(state, hit))?;
+            }
             PendingRequest::DetachProcess { hit, signal, hid } => {
               let mut store = self.store.write().unwrap();
               let state = store.get_current_mut(hit.pid).unwrap();
@@ -401,16 +734,70 @@ impl Tracer {
                 error!("Failed to suspend seccomp-bpf for {pid}: {e}");
               });
             }
+            PendingRequest::SpawnRoot(args) => {
+              match self.spawn_root(args, None) {
+                Ok(pid) => {
+                  roots.insert(pid);
+                }
+                Err(e) => {
+                  error!("Failed to spawn root tracee: {e}");
+                  filterable_event!(Error(TracerEventMessage {
+                    pid: None,
+                    msg: format!("Failed to spawn command: {e}"),
+                  }))
+                  .send_if_match(&self.msg_tx, self.filter)?;
+                }
+              }
+            }
+            PendingRequest::Shutdown => {
+              shutdown = true;
+              if roots.is_empty() {
+                break Ok(());
+              }
+            }
+            PendingRequest::DetachAll => {
+              shutdown = true;
+              detaching = true;
+              self.stop_all_for_detach()?;
+              if !self.store.read().unwrap().has_live_processes() {
+                break Ok(());
+              }
+              pending = true;
+            }
+            PendingRequest::DetachAllAndWait => {
+              detaching = true;
+              self.stop_all_for_detach()?;
+              pending = true;
+            }
+            PendingRequest::KillAll => {
+              self.kill_all()?;
+              pending = true;
+            }
           }
         }
       }
-    }
+    };
+    // The daemonized tracees might be detached or killed after the root tracees exit
+    self.send_last_tracee_exit()?;
+    result
   }
 
-  fn handle_waitpid_events(&self, root_child: Pid) -> color_eyre::Result<ControlFlow<()>> {
+  /// Returns `ControlFlow::Break` when the last root tracee exits.
+  /// Otherwise, returns whether it gives up before handling all the events.
+  ///
+  /// When `detaching` is set, processes are detached when they stop with SIGSTOP.
+  fn handle_waitpid_events(
+    &self,
+    roots: &mut HashSet<Pid>,
+    detaching: bool,
+  ) -> color_eyre::Result<ControlFlow<(), bool>> {
     let mut counter = 0;
     loop {
-      let status = waitpid(None, Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG))?;
+      let status = match waitpid(None, Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG)) {
+        // No root tracee is running.
+        Err(Errno::ECHILD) => break,
+        r => r?,
+      };
       if status != WaitStatus::StillAlive {
         counter += 1;
       } else {
@@ -421,6 +808,10 @@ impl Tracer {
         WaitStatus::Stopped(pid, sig) => {
           trace!("stopped: {pid}, sig {:?}", sig);
           match sig {
+            Signal::SIGSTOP if detaching => {
+              trace!("sigstop event while detaching, pid: {pid}");
+              self.detach_on_exit(pid)?;
+            }
             Signal::SIGSTOP => {
               trace!("sigstop event, child: {pid}");
               {
@@ -470,10 +861,12 @@ impl Tracer {
               // This is also important if we trace without the following forks option.
 
               // The SIGCHLD can't come from other untraced subprocesses because WUNTRACED is not set.
+              self.report_signal_delivery(pid, Signal::SIGCHLD)?;
               self.seccomp_aware_cont_with_signal(pid, Signal::SIGCHLD)?;
             }
             _ => {
               trace!("other signal: {pid}, sig {:?}", sig);
+              self.report_signal_delivery(pid, sig)?;
               // Just deliver the signal to tracee
               self.seccomp_aware_cont_with_signal(pid, sig)?;
             }
@@ -485,17 +878,23 @@ impl Tracer {
           let mut store = self.store.write().unwrap();
           if let Some(state) = store.get_current_mut(pid) {
             state.status = ProcessStatus::Exited(ProcessExit::Code(code));
-            let should_exit = if pid == root_child {
-              filterable_event!(TraceeExit {
+            let associated_events = state.associated_events.clone();
+            if !associated_events.is_empty() {
+              self.report_process_exit(state, ProcessExit::Code(code))?;
+            }
+            let daemonized = state.daemonized;
+            let is_root = roots.remove(&pid);
+            if is_root {
+              let event = filterable_event!(TraceeExit {
                 signal: None,
                 exit_code: code,
-              })
-              .send_if_match(&self.msg_tx, self.filter)?;
-              true
-            } else {
-              false
-            };
-            let associated_events = state.associated_events.clone();
+              });
+              if roots.is_empty() {
+                *self.last_tracee_exit.lock().unwrap() = Some(event);
+              } else {
+                event.send_if_match(&self.msg_tx, self.filter)?;
+              }
+            }
             if !associated_events.is_empty() {
               self.msg_tx.send(
                 ProcessStateUpdateEvent {
@@ -506,7 +905,10 @@ impl Tracer {
                 .into(),
               )?;
             }
-            if should_exit {
+            self.mark_daemonized_children(&mut store, pid, is_root)?;
+            // The last daemon might exit after the root tracees
+            if (is_root || daemonized) && roots.is_empty() && !self.follows_daemons(&store) {
+              self.send_last_tracee_exit()?;
               return Ok(ControlFlow::Break(()));
             }
           }
@@ -519,6 +921,7 @@ impl Tracer {
             | nix::libc::PTRACE_EVENT_CLONE => {
               let new_child = Pid::from_raw(ptrace::getevent(pid)? as pid_t);
               trace!("ptrace fork event, evt {evt}, pid: {pid}, child: {new_child}");
+              self.refresh_comm(pid);
               if self.filter.intersects(TracerEventDetailsKind::NewChild) {
                 let store = self.store.read().unwrap();
                 let parent = store.get_current(pid).unwrap();
@@ -528,10 +931,15 @@ impl Tracer {
                   pid: new_child,
                 });
                 self.msg_tx.send(event.into())?;
-                self.printer.print_new_child(parent, new_child)?;
+                if !self.unprinted.contains(TracerEventDetailsKind::NewChild) {
+                  self.printer.print_new_child(parent, new_child)?;
+                }
               }
               {
                 let mut store = self.store.write().unwrap();
+                // The new child descends from the same root command as its parent
+                let root_index = store.get_current(pid).and_then(|p| p.root_index);
+                let daemonized = store.get_current(pid).is_some_and(|p| p.daemonized);
                 let mut pid_reuse = false;
                 let mut handled = false;
                 if let Some(state) = store.get_current_mut(new_child) {
@@ -541,11 +949,16 @@ impl Tracer {
                     );
                     state.status = ProcessStatus::Running;
                     state.ppid = Some(pid);
+                    state.root_index = root_index;
+                    state.daemonized = daemonized;
                     self.seccomp_aware_cont(new_child)?;
                     handled = true;
                   } else if state.status == ProcessStatus::Initialized {
                     // Manually inserted process state. (root child)
                     handled = true;
+                  } else if detaching && state.status == ProcessStatus::Detached {
+                    // The new child is detached on its initial SIGSTOP
+                    handled = true;
                   } else {
                     // Pid reuse
                     pid_reuse = true;
@@ -558,6 +971,8 @@ impl Tracer {
                   let mut state = ProcessState::new(new_child, 0)?;
                   state.status = ProcessStatus::PtraceForkEventReceived;
                   state.ppid = Some(pid);
+                  state.root_index = root_index;
+                  state.daemonized = daemonized;
                   store.insert(state);
                   drop(store);
                 }
@@ -568,7 +983,16 @@ impl Tracer {
             nix::libc::PTRACE_EVENT_EXEC => {
               trace!("exec event");
               let mut store = self.store.write().unwrap();
-              let p = store.get_current_mut(pid).unwrap();
+              // The event is reported with the thread id of the thread group leader
+              // even if another thread execs, and the former thread id is the event message.
+              let former_tid = Pid::from_raw(ptrace::getevent(pid)? as pid_t);
+              let p = if former_tid != pid {
+                debug!("thread {former_tid} execs and takes over the thread id {pid}");
+                store.take_over_leader(pid, former_tid)
+              } else {
+                store.get_current_mut(pid)
+              }
+              .unwrap();
               assert!(!p.presyscall);
               // After execve or execveat, in syscall exit event,
               // the registers might be clobbered(e.g. aarch64).
@@ -586,6 +1010,8 @@ impl Tracer {
             }
             nix::libc::PTRACE_EVENT_EXIT => {
               trace!("exit event");
+              // Last chance to read the comm before the process becomes a zombie
+              self.refresh_comm(pid);
               self.seccomp_aware_cont(pid)?;
             }
             nix::libc::PTRACE_EVENT_SECCOMP => {
@@ -603,15 +1029,23 @@ impl Tracer {
           let mut store = self.store.write().unwrap();
           if let Some(state) = store.get_current_mut(pid) {
             state.status = ProcessStatus::Exited(ProcessExit::Signal(sig));
-            if pid == root_child {
-              filterable_event!(TraceeExit {
+            let associated_events = state.associated_events.clone();
+            if !associated_events.is_empty() {
+              self.report_process_exit(state, ProcessExit::Signal(sig))?;
+            }
+            let daemonized = state.daemonized;
+            let is_root = roots.remove(&pid);
+            if is_root {
+              let event = filterable_event!(TraceeExit {
                 signal: Some(sig),
                 exit_code: 128 + (sig as i32),
-              })
-              .send_if_match(&self.msg_tx, self.filter)?;
-              return Ok(ControlFlow::Break(()));
+              });
+              if roots.is_empty() {
+                *self.last_tracee_exit.lock().unwrap() = Some(event);
+              } else {
+                event.send_if_match(&self.msg_tx, self.filter)?;
+              }
             }
-            let associated_events = state.associated_events.clone();
             if !associated_events.is_empty() {
               self.msg_tx.send(
                 ProcessStateUpdateEvent {
@@ -622,6 +1056,12 @@ impl Tracer {
                 .into(),
               )?;
             }
+            self.mark_daemonized_children(&mut store, pid, is_root)?;
+            // The last daemon might exit after the root tracees
+            if (is_root || daemonized) && roots.is_empty() && !self.follows_daemons(&store) {
+              self.send_last_tracee_exit()?;
+              return Ok(ControlFlow::Break(()));
+            }
           }
         }
         WaitStatus::PtraceSyscall(pid) => {
@@ -643,10 +1083,10 @@ impl Tracer {
       if counter > 100 {
         // Give up if we have handled 100 events, so that we have a chance to handle other events
         debug!("yielding after 100 events");
-        break;
+        return Ok(ControlFlow::Continue(true));
       }
     }
-    Ok(ControlFlow::Continue(()))
+    Ok(ControlFlow::Continue(false))
   }
 
   fn on_syscall_enter(&self, pid: Pid) -> color_eyre::Result<()> {
@@ -682,6 +1122,7 @@ impl Tracer {
     };
     let syscallno = info.number;
     let is_32bit = info.is_32bit();
+    let has_32bit_pointers = info.has_32bit_pointers();
     // trace!("pre syscall: {syscallno}");
     if info.is_execveat() {
       p.syscall = Syscall::Execveat;
@@ -692,9 +1133,8 @@ impl Tracer {
       //              int flags);
       let dirfd = regs.syscall_arg(0, is_32bit) as i32;
       let flags = regs.syscall_arg(4, is_32bit) as i32;
-      let filename = match read_string(pid, regs.syscall_arg(1, is_32bit) as AddressType) {
+      let filename = match read_arcstr(pid, regs.syscall_arg(1, is_32bit) as AddressType) {
         Ok(pathname) => {
-          let pathname = cached_string(pathname);
           let pathname_is_empty = pathname.is_empty();
           let filename = match (
             pathname.starts_with('/'),
@@ -721,9 +1161,17 @@ impl Tracer {
       };
       let filename = self.get_filename_for_display(pid, filename)?;
       self.warn_for_filename(&filename, pid)?;
-      let argv = read_output_msg_array(pid, regs.syscall_arg(2, is_32bit) as AddressType, is_32bit);
+      let argv = read_output_msg_array(
+        pid,
+        regs.syscall_arg(2, is_32bit) as AddressType,
+        has_32bit_pointers,
+      );
       self.warn_for_argv(&argv, pid)?;
-      let envp = read_env(pid, regs.syscall_arg(3, is_32bit) as AddressType, is_32bit);
+      let envp = read_env(
+        pid,
+        regs.syscall_arg(3, is_32bit) as AddressType,
+        has_32bit_pointers,
+      );
       self.warn_for_envp(&envp, pid)?;
 
       let interpreters = if self.printer.args.trace_interpreter && filename.is_ok() {
@@ -742,6 +1190,7 @@ impl Tracer {
         OutputMsg::Ok(read_cwd(pid)?),
         Some(interpreters),
         read_fds(pid)?,
+        read_cgroup(pid),
       ));
     } else if info.is_execve() {
       p.syscall = Syscall::Execve;
@@ -749,10 +1198,17 @@ impl Tracer {
       let filename = read_arcstr(pid, regs.syscall_arg(0, is_32bit) as AddressType);
       let filename = self.get_filename_for_display(pid, filename)?;
       self.warn_for_filename(&filename, pid)?;
-      let argv = read_output_msg_array(pid, regs.syscall_arg(1, is_32bit) as AddressType, is_32bit);
+      let argv = read_output_msg_array(
+        pid,
+        regs.syscall_arg(1, is_32bit) as AddressType,
+        has_32bit_pointers,
+      );
       self.warn_for_argv(&argv, pid)?;
-      let envp = read_string_array(pid, regs.syscall_arg(2, is_32bit) as AddressType, is_32bit)
-        .map(parse_envp);
+      let envp = read_envp(
+        pid,
+        regs.syscall_arg(2, is_32bit) as AddressType,
+        has_32bit_pointers,
+      );
       self.warn_for_envp(&envp, pid)?;
       let interpreters = if self.printer.args.trace_interpreter && filename.is_ok() {
         read_interpreter_recursive(filename.as_deref().unwrap())
@@ -770,10 +1226,60 @@ impl Tracer {
         OutputMsg::Ok(read_cwd(pid)?),
         Some(interpreters),
         read_fds(pid)?,
+        read_cgroup(pid),
       ));
+    } else if let Some(syscall) = info
+      .trapped_syscall()
+      .filter(|syscall| self.trapped_syscalls.contains(syscall))
+    {
+      trace!("pre {syscall} {syscallno}");
+      p.syscall = Syscall::Trapped(syscall);
     } else {
       p.syscall = Syscall::Other;
     }
+    if matches!(p.syscall, Syscall::Execve | Syscall::Execveat) {
+      if let Some(exec_data) = p.exec_data.as_mut() {
+        // Compared with the label after the exec to find the transition
+        exec_data.security_label_before = read_security_label(pid);
+      }
+    }
+    if let Some((bid, index, action)) = self.evaluate_rules(p) {
+      match action {
+        RuleAction::Allow => (),
+        RuleAction::Log => self.report_rule_match(p, index, action)?,
+        // Ask the user in the TUI like an ask breakpoint
+        RuleAction::Ask if matches!(self.mode, TracerMode::Tui(_)) => {
+          let associated_events = p.associated_events.clone();
+          let hit = BreakPointHit {
+            bid,
+            pid,
+            stop: BreakPointStop::SyscallEnter,
+          };
+          let event = ProcessStateUpdateEvent {
+            update: ProcessStateUpdate::BreakPointHit(hit),
+            pid,
+            ids: associated_events,
+          };
+          p.status = ProcessStatus::BreakPointHit(hit);
+          self.msg_tx.send(event.into())?;
+          return Ok(()); // Do not continue the syscall
+        }
+        RuleAction::Deny | RuleAction::Ask => {
+          self.report_rule_match(p, index, action)?;
+          match ptrace_skip_syscall(pid) {
+            Err(Errno::ESRCH) => {
+              info!("failed to deny exec: {pid}, ESRCH, child probably gone!");
+              return Ok(());
+            }
+            r => r?,
+          }
+          // The result is changed on syscall exit
+          p.exec_denied = true;
+          self.syscall_enter_cont(pid)?;
+          return Ok(());
+        }
+      }
+    }
     if let Some(exec_data) = &p.exec_data {
       let mut hit = None;
       for (&idx, brk) in self
@@ -793,16 +1299,17 @@ impl Tracer {
       }
       if let Some(bid) = hit {
         let associated_events = p.associated_events.clone();
+        let hit = BreakPointHit {
+          bid,
+          pid,
+          stop: BreakPointStop::SyscallEnter,
+        };
         let event = ProcessStateUpdateEvent {
-          update: ProcessStateUpdate::BreakPointHit(BreakPointHit {
-            bid,
-            pid,
-            stop: BreakPointStop::SyscallEnter,
-          }),
+          update: ProcessStateUpdate::BreakPointHit(hit),
           pid,
           ids: associated_events,
         };
-        p.status = ProcessStatus::BreakPointHit;
+        p.status = ProcessStatus::BreakPointHit(hit);
         self.msg_tx.send(event.into())?;
         return Ok(()); // Do not continue the syscall
       }
@@ -825,29 +1332,75 @@ impl Tracer {
       }
       e => e?,
     };
+    let result = if p.exec_denied {
+      p.exec_denied = false;
+      let denied = -(Errno::EPERM as isize);
+      ptrace_set_syscall_result(pid, denied)?;
+      denied
+    } else {
+      result
+    };
     // If exec is successful, the register value might be clobbered.
     // TODO: would the value in ptrace_syscall_info be clobbered?
     let exec_result = if p.is_exec_successful { 0 } else { result } as i64;
     match p.syscall {
       Syscall::Execve | Syscall::Execveat => {
         trace!("post execve(at) in exec");
-        if self.printer.args.successful_only && !p.is_exec_successful {
+        if let Some(exec_data) = p.exec_data.as_mut() {
+          exec_data.ppid = p.ppid;
+          exec_data.credentials = read_credentials(pid).ok();
+          exec_data.capabilities = read_exec_capabilities(pid, p.ppid);
+          exec_data.security_label = read_security_label(pid);
+          if exec_data.security_label_before == exec_data.security_label {
+            exec_data.security_label_before = None;
+          }
+        }
+        if p.is_exec_successful {
+          if let Some(exec_data) = p.exec_data.as_mut() {
+            exec_data.exe = read_exe(pid).ok().map(OutputMsg::Ok);
+            exec_data.privileges = read_exe_privileges(pid).ok();
+            if let Some(hasher) = self.exe_hasher.as_ref() {
+              exec_data.exe_hash = hasher
+                .hash_exe(pid)
+                .inspect_err(|e| warn!("Failed to hash the executable of {pid}: {e}"))
+                .ok();
+            }
+          }
+        }
+        if self.modifier_args.audit {
+          if let Some(exec_data) = p.exec_data.as_mut() {
+            exec_data.audit = audit_exec(exec_data, &self.baseline.env);
+          }
+        }
+        let privileged = p
+          .exec_data
+          .as_ref()
+          .and_then(|exec_data| exec_data.privileges)
+          .is_some_and(|privileges| privileges.is_privileged());
+        if (self.printer.args.successful_only && !p.is_exec_successful)
+          || (self.modifier_args.privileged_only && !privileged)
+        {
           p.exec_data = None;
           self.seccomp_aware_cont(pid)?;
           return Ok(());
         }
-        if self.filter.intersects(TracerEventDetailsKind::Exec) {
+        if self.filter.intersects(TracerEventDetailsKind::Exec)
+          && !(self.printer.args.failed_only && p.is_exec_successful)
+          && self.count_exec_event()?
+        {
           // TODO: optimize, we don't need to collect exec event for log mode
           let event = TracerEvent::from(TracerEventDetails::Exec(Self::collect_exec_event(
             &self.baseline.env,
             p,
             exec_result,
+            self.process_handle(pid),
           )));
           p.associate_event([event.id]);
           self.msg_tx.send(event.into())?;
           self.printer.print_exec_trace(
             p.pid,
             p.comm.clone(),
+            p.root_index,
             exec_result,
             p.exec_data.as_ref().unwrap(),
             &self.baseline.env,
@@ -875,16 +1428,17 @@ impl Tracer {
           }
           if let Some(bid) = hit {
             let associated_events = p.associated_events.clone();
+            let hit = BreakPointHit {
+              bid,
+              pid,
+              stop: BreakPointStop::SyscallExit,
+            };
             let event = ProcessStateUpdateEvent {
-              update: ProcessStateUpdate::BreakPointHit(BreakPointHit {
-                bid,
-                pid,
-                stop: BreakPointStop::SyscallExit,
-              }),
+              update: ProcessStateUpdate::BreakPointHit(hit),
               pid,
               ids: associated_events,
             };
-            p.status = ProcessStatus::BreakPointHit;
+            p.status = ProcessStatus::BreakPointHit(hit);
             self.msg_tx.send(event.into())?;
             return Ok(()); // Do not continue the syscall
           }
@@ -894,6 +1448,7 @@ impl Tracer {
         // update comm
         p.comm = read_comm(pid)?;
       }
+      Syscall::Trapped(syscall) if result == 0 => self.on_trapped_syscall_exit(p, syscall)?,
       _ => (),
     }
     self.seccomp_aware_cont(pid)?;
@@ -1039,16 +1594,28 @@ impl Tracer {
     env: &BTreeMap<OutputMsg, OutputMsg>,
     state: &ProcessState,
     result: i64,
+    process: Option<Arc<ProcessHandle>>,
   ) -> Box<ExecEvent> {
     let exec_data = state.exec_data.as_ref().unwrap();
     Box::new(ExecEvent {
       pid: state.pid,
+      ppid: exec_data.ppid,
       cwd: exec_data.cwd.clone(),
       comm: state.comm.clone(),
       filename: exec_data.filename.clone(),
       argv: exec_data.argv.clone(),
       envp: exec_data.envp.clone(),
       interpreter: exec_data.interpreters.clone(),
+      container: exec_data.container.clone(),
+      cgroup: exec_data.cgroup.clone(),
+      exe: exec_data.exe.clone(),
+      exe_hash: exec_data.exe_hash.clone(),
+      privileges: exec_data.privileges,
+      credentials: exec_data.credentials,
+      capabilities: exec_data.capabilities,
+      security_label: exec_data.security_label.clone(),
+      security_label_before: exec_data.security_label_before.clone(),
+      audit: exec_data.audit.clone(),
       env_diff: exec_data
         .envp
         .as_ref()
@@ -1057,7 +1624,237 @@ impl Tracer {
         .map_err(|e| *e),
       result,
       fdinfo: exec_data.fdinfo.clone(),
+      process,
+    })
+  }
+
+  /// Open a handle to a tracee that is stopped or not reaped yet,
+  /// so that the TUI can signal it without racing with pid reuse.
+  fn process_handle(&self, pid: Pid) -> Option<Arc<ProcessHandle>> {
+    matches!(self.mode, TracerMode::Tui(_)).then(|| Arc::new(ProcessHandle::new(pid)))
+  }
+
+  /// Report the exit of a process that has been shown in the event list
+  fn report_process_exit(&self, state: &ProcessState, exit: ProcessExit) -> color_eyre::Result<()> {
+    filterable_event!(ProcessExit {
+      pid: state.pid,
+      comm: state.comm.clone(),
+      exit,
+    })
+    .send_if_match(&self.msg_tx, self.filter)?;
+    self
+      .printer
+      .print_exit(state.pid, state.comm.clone(), state.root_index, exit)?;
+    Ok(())
+  }
+
+  /// Outside of an interactive session, the exit of the last root tracee ends the event stream.
+  /// The daemonized tracees are followed until they exit so that their events are not cut off.
+  fn follows_daemons(&self, store: &ProcessStateStore) -> bool {
+    !self.is_interactive() && store.live_daemons().next().is_some()
+  }
+
+  fn send_last_tracee_exit(&self) -> color_eyre::Result<()> {
+    let event = self.last_tracee_exit.lock().unwrap().take();
+    if let Some(event) = event {
+      event.send_if_match(&self.msg_tx, self.filter)?;
+    }
+    Ok(())
+  }
+
+  /// The children of an exiting tracee are orphaned. Those in a session created by a tracee
+  /// other than the root tracees have daemonized, e.g. by a double fork and `setsid`.
+  fn mark_daemonized_children(
+    &self,
+    store: &mut ProcessStateStore,
+    ppid: Pid,
+    parent_is_root: bool,
+  ) -> color_eyre::Result<()> {
+    let orphans = store
+      .live_processes_mut()
+      .filter(|state| state.ppid == Some(ppid) && !state.daemonized)
+      .map(|state| state.pid)
+      .collect::<Vec<_>>();
+    for pid in orphans {
+      let Ok(sid) = getsid(Some(pid)) else {
+        continue;
+      };
+      // The session of a root tracee is created by tracexec when a pseudo terminal is used
+      if sid != pid && (sid != ppid || parent_is_root) {
+        continue;
+      }
+      debug!("{pid} daemonized into session {sid}");
+      let state = store.get_current_mut(pid).unwrap();
+      state.daemonized = true;
+      if self.filter.intersects(TracerEventDetailsKind::Daemonize) {
+        filterable_event!(Daemonize {
+          pid,
+          comm: state.comm.clone(),
+          sid,
+        })
+        .send_if_match(&self.msg_tx, self.filter)?;
+        self
+          .printer
+          .print_daemonize(pid, state.comm.clone(), state.root_index, sid)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// The comm could be changed by prctl(PR_SET_NAME) or writing to /proc/self/comm
+  /// without exec. Re-read it when the tracee is stopped so that events show the current name.
+  fn refresh_comm(&self, pid: Pid) {
+    let Ok(comm) = read_comm(pid) else {
+      return;
+    };
+    let mut store = self.store.write().unwrap();
+    if let Some(state) = store.get_current_mut(pid) {
+      if state.comm != comm {
+        trace!("comm of {pid} changed: {} -> {comm}", state.comm);
+        state.comm = comm;
+      }
+    }
+  }
+
+  /// Find the rule that decides what happens to the pending exec of a process.
+  /// Returns the id, the index and the action of the rule.
+  fn evaluate_rules(&self, state: &ProcessState) -> Option<(u32, usize, RuleAction)> {
+    let rules = self.rules.read().unwrap();
+    if rules.rules.is_empty() {
+      return None;
+    }
+    let exec_data = state.exec_data.as_ref()?;
+    let subject = ExecSubject {
+      filename: &exec_data.filename,
+      argv: exec_data.argv.as_deref().ok(),
+      comm: &state.comm,
+      uid: read_uid(state.pid).ok(),
+    };
+    let (index, rule) = rules.rules.evaluate(&subject)?;
+    Some((rules.first_id + index as u32, index, rule.action))
+  }
+
+  /// Update the state of the process after a successful trapped syscall and report the change.
+  fn on_trapped_syscall_exit(
+    &self,
+    p: &mut ProcessState,
+    syscall: TrappedSyscall,
+  ) -> color_eyre::Result<()> {
+    // The process could be gone already, in which case the change does not matter.
+    let msg = match syscall {
+      TrappedSyscall::Chdir => {
+        let Ok(cwd) = read_cwd(p.pid) else {
+          return Ok(());
+        };
+        let msg = format!("Changed the working directory to {cwd}");
+        p.cwd = Some(cwd);
+        msg
+      }
+      TrappedSyscall::Chroot => {
+        let Ok(root) = read_root(p.pid) else {
+          return Ok(());
+        };
+        let msg = format!("Changed the root directory to {root}");
+        p.root = Some(root);
+        msg
+      }
+      TrappedSyscall::Setuid => {
+        let Ok(credentials) = read_credentials(p.pid) else {
+          return Ok(());
+        };
+        p.credentials = Some(credentials);
+        format!("Changed the user ids: {credentials}")
+      }
+    };
+    filterable_event!(Info(TracerEventMessage {
+      pid: Some(p.pid),
+      msg,
+    }))
+    .send_if_match(&self.msg_tx, self.filter)?;
+    Ok(())
+  }
+
+  fn report_rule_match(
+    &self,
+    state: &ProcessState,
+    index: usize,
+    action: RuleAction,
+  ) -> color_eyre::Result<()> {
+    let Some(exec_data) = &state.exec_data else {
+      return Ok(());
+    };
+    filterable_event!(Info(TracerEventMessage {
+      pid: Some(state.pid),
+      msg: format!(
+        "Exec of {} matched rule #{}: {action}",
+        exec_data.filename,
+        index + 1
+      ),
+    }))
+    .send_if_match(&self.msg_tx, self.filter)?;
+    self.printer.print_rule_match(
+      state.pid,
+      state.comm.clone(),
+      state.root_index,
+      &exec_data.filename,
+      index,
+      action,
+    )?;
+    Ok(())
+  }
+
+  /// Reload the rules from the rules file. The old rules stay in effect if it fails.
+  /// Returns the number of the loaded rules.
+  pub fn reload_rules(&self) -> color_eyre::Result<usize> {
+    let Some(path) = &self.rules_path else {
+      color_eyre::eyre::bail!("No rules file is specified");
+    };
+    match RuleSet::load(path) {
+      Ok(rules) => {
+        let count = rules.len();
+        *self.rules.write().unwrap() = LoadedRules::new(rules);
+        info!("Reloaded {count} rules from {}", path.display());
+        filterable_event!(Info(TracerEventMessage {
+          pid: None,
+          msg: format!("Reloaded {count} rules from {}", path.display()),
+        }))
+        .send_if_match(&self.msg_tx, self.filter)?;
+        Ok(count)
+      }
+      Err(e) => {
+        error!("Failed to reload rules from {}: {e}", path.display());
+        filterable_event!(Error(TracerEventMessage {
+          pid: None,
+          msg: format!("Failed to reload rules from {}: {e}", path.display()),
+        }))
+        .send_if_match(&self.msg_tx, self.filter)?;
+        Err(e.into())
+      }
+    }
+  }
+
+  fn report_signal_delivery(&self, pid: Pid, signal: Signal) -> color_eyre::Result<()> {
+    if !self
+      .filter
+      .intersects(TracerEventDetailsKind::SignalDelivery)
+    {
+      return Ok(());
+    }
+    self.refresh_comm(pid);
+    let store = self.store.read().unwrap();
+    let Some(state) = store.get_current(pid) else {
+      return Ok(());
+    };
+    filterable_event!(SignalDelivery {
+      pid,
+      comm: state.comm.clone(),
+      signal,
     })
+    .send_if_match(&self.msg_tx, self.filter)?;
+    self
+      .printer
+      .print_signal(pid, state.comm.clone(), state.root_index, signal)?;
+    Ok(())
   }
 }
 
@@ -1100,6 +1897,37 @@ impl Tracer {
       .unwrap()
       .get(&id)
       .map(|b| b.pattern.to_editable())
+      .or_else(|| {
+        self
+          .rules
+          .read()
+          .unwrap()
+          .get(id)
+          .map(|(index, rule)| format!("rule #{}: {rule}", index + 1))
+      })
+  }
+
+  /// Returns whether the user should be asked to approve the execs hitting this breakpoint
+  pub fn is_ask_breakpoint(&self, id: u32) -> bool {
+    self
+      .breakpoints
+      .read()
+      .unwrap()
+      .get(&id)
+      .is_some_and(|b| matches!(b.ty, BreakPointType::Ask))
+      || self
+        .rules
+        .read()
+        .unwrap()
+        .get(id)
+        .is_some_and(|(_, rule)| rule.action == RuleAction::Ask)
+  }
+
+  /// Get the filename and argv of the exec that a process stopped on syscall entry is about to do
+  pub fn get_pending_exec(&self, pid: Pid) -> Option<(OutputMsg, Arc<Argv>)> {
+    let store = self.store.read().unwrap();
+    let exec_data = store.get_current(pid)?.exec_data.as_ref()?;
+    Some((exec_data.filename.clone(), exec_data.argv.clone()))
   }
 
   pub fn remove_breakpoint(&self, index: u32) {
@@ -1167,6 +1995,18 @@ impl Tracer {
     Ok(())
   }
 
+  fn deny_exec(
+    &self,
+    state: &mut ProcessState,
+    hit: BreakPointHit,
+  ) -> Result<(), Either<Errno, SendError<TracerMessage>>> {
+    debug_assert_eq!(hit.stop, BreakPointStop::SyscallEnter);
+    ptrace_skip_syscall(state.pid).map_err(Either::Left)?;
+    // The result is changed on syscall exit
+    state.exec_denied = true;
+    self.resume_process(state, hit.stop)
+  }
+
   fn prepare_to_detach_with_signal(
     &self,
     state: &mut ProcessState,
@@ -1217,6 +2057,82 @@ impl Tracer {
     Ok(())
   }
 
+  /// Stop all tracees so that they could be detached on SIGSTOP.
+  ///
+  /// Tracees that are already in a ptrace stop are detached directly.
+  fn stop_all_for_detach(&self) -> color_eyre::Result<()> {
+    let mut store = self.store.write().unwrap();
+    for state in store.live_processes_mut() {
+      let pid = state.pid;
+      match state.status {
+        ProcessStatus::BreakPointHit(_) | ProcessStatus::SigstopReceived => {
+          match ptrace::detach(pid, None) {
+            Ok(_) | Err(Errno::ESRCH) => (),
+            Err(e) => warn!("Failed to detach from {pid}: {e}"),
+          }
+          state.status = ProcessStatus::Detached;
+        }
+        // The new child stops with SIGSTOP by itself
+        ProcessStatus::PtraceForkEventReceived => (),
+        _ => match kill(pid, SENTINEL_SIGNAL) {
+          Ok(_) => (),
+          // The process is gone but not reaped yet
+          Err(Errno::ESRCH) => state.status = ProcessStatus::Detached,
+          Err(e) => Err(e)?,
+        },
+      }
+    }
+    Ok(())
+  }
+
+  fn kill_all(&self) -> color_eyre::Result<()> {
+    let mut store = self.store.write().unwrap();
+    for state in store.live_processes_mut() {
+      match kill(state.pid, Signal::SIGKILL) {
+        Ok(_) | Err(Errno::ESRCH) => (),
+        Err(e) => warn!("Failed to kill {}: {e}", state.pid),
+      }
+    }
+    Ok(())
+  }
+
+  /// Count an exec event towards `--max-events` and stop tracing when the limit is reached.
+  /// Returns false if the limit is already reached and the event should be dropped.
+  fn count_exec_event(&self) -> color_eyre::Result<bool> {
+    let Some(max_events) = self.modifier_args.max_events else {
+      return Ok(true);
+    };
+    let count = self
+      .exec_events
+      .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+      + 1;
+    if count == max_events.get() {
+      self.req_tx.send(match self.modifier_args.on_max_events {
+        MaxEventsAction::Detach => PendingRequest::DetachAllAndWait,
+        MaxEventsAction::Kill => PendingRequest::KillAll,
+      })?;
+    }
+    Ok(count <= max_events.get())
+  }
+
+  /// Detach from a tracee that is in the signal-delivery-stop of SIGSTOP and suppress the signal
+  fn detach_on_exit(&self, pid: Pid) -> color_eyre::Result<()> {
+    match ptrace::detach(pid, None) {
+      Ok(_) | Err(Errno::ESRCH) => (),
+      Err(e) => warn!("Failed to detach from {pid}: {e}"),
+    }
+    trace!("detached on exit: {pid}");
+    let mut store = self.store.write().unwrap();
+    if let Some(state) = store.get_current_mut(pid) {
+      state.status = ProcessStatus::Detached;
+    } else if let Ok(mut state) = ProcessState::new(pid, 0) {
+      // A new child that stops before its parent's fork event
+      state.status = ProcessStatus::Detached;
+      store.insert(state);
+    }
+    Ok(())
+  }
+
   pub fn request_process_detach(
     &self,
     hit: BreakPointHit,
@@ -1234,6 +2150,18 @@ impl Tracer {
     Ok(())
   }
 
+  /// Resume all the processes stopped at the breakpoint.
+  /// Remove or deactivate the breakpoint first so that no more processes stop at it.
+  pub fn request_breakpoint_resume(&self, bid: u32) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::ResumeBreakPoint(bid))?;
+    Ok(())
+  }
+
+  pub fn request_exec_deny(&self, hit: BreakPointHit) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::DenyExec(hit))?;
+    Ok(())
+  }
+
   #[cfg(feature = "seccomp-bpf")]
   fn suspend_seccomp_bpf(&self, pid: Pid) -> Result<(), Errno> {
     use nix::libc::{ptrace, PTRACE_O_SUSPEND_SECCOMP, PTRACE_SETOPTIONS};
@@ -1260,6 +2188,34 @@ impl Tracer {
     Ok(())
   }
 
+  pub fn request_spawn_root(&self, args: Vec<String>) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::SpawnRoot(args))?;
+    Ok(())
+  }
+
+  /// Ask the tracer to exit after all root tracees exit.
+  pub fn request_shutdown(&self) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::Shutdown)?;
+    Ok(())
+  }
+
+  /// Ask the tracer to detach from all tracees and exit, leaving them running.
+  pub fn request_detach_all(&self) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::DetachAll)?;
+    Ok(())
+  }
+
+  /// Ask the tracer to kill all tracees.
+  pub fn request_kill_all(&self) -> color_eyre::Result<()> {
+    self.req_tx.send(PendingRequest::KillAll)?;
+    Ok(())
+  }
+
+  /// Whether new root tracees could be spawned after all root tracees exit.
+  pub fn is_interactive(&self) -> bool {
+    matches!(self.mode, TracerMode::Tui(_))
+  }
+
   pub fn seccomp_bpf(&self) -> bool {
     cfg_if! {
       if #[cfg(feature = "seccomp-bpf")] {
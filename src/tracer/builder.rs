@@ -0,0 +1,324 @@
+use std::{
+  num::NonZeroUsize,
+  path::PathBuf,
+  pin::Pin,
+  sync::{Arc, Mutex, Weak},
+  task::{Context, Poll},
+};
+
+use color_eyre::eyre::eyre;
+use enumflags2::BitFlags;
+use futures::{future, stream::BoxStream, Stream, StreamExt};
+use nix::unistd::User;
+use tokio::{
+  sync::mpsc::{self, UnboundedReceiver},
+  task::JoinHandle,
+};
+
+use crate::{
+  channel::MessageReceiver,
+  cli::{
+    args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+    options::SeccompBpf,
+  },
+  event::{TracerEvent, TracerEventDetailsKind, TracerMessage},
+  proc::BaselineInfo,
+};
+
+use super::{
+  state::{BreakPoint, BreakPointPattern, BreakPointStop, BreakPointType},
+  PendingRequest, Tracer, TracerMode,
+};
+
+/// Builds a [`Tracer`] for programs that embed tracexec.
+///
+/// By default, the tracer sends all the events. The options are a subset of the command line
+/// options of tracexec.
+pub struct TracerBuilder {
+  foreground: bool,
+  tracing_args: LogModeArgs,
+  modifier_args: ModifierArgs,
+  ptrace_args: PtraceArgs,
+  tracer_event_args: TracerEventArgs,
+  baseline: Option<BaselineInfo>,
+  user: Option<User>,
+}
+
+/// A tracer that is built but not started yet
+pub struct BuiltTracer {
+  pub tracer: Arc<Tracer>,
+  /// The messages of the tracer. The channel is closed once the tracer is dropped.
+  pub messages: MessageReceiver,
+  /// Pass this to [`Tracer::spawn`] or [`Tracer::spawn_group`] to start tracing
+  pub requests: UnboundedReceiver<PendingRequest>,
+}
+
+/// The events of a tracer started by [`TracerBuilder::spawn`].
+///
+/// The stream ends when the tracer is done. Await [`TracerEventStream::wait`] afterwards to
+/// find out whether the tracer failed.
+pub struct TracerEventStream {
+  events: BoxStream<'static, TracerEvent>,
+  tracer_thread: JoinHandle<color_eyre::Result<()>>,
+  control: TracerControl,
+}
+
+/// Controls a tracer started by [`TracerBuilder::spawn`] while its events are streamed.
+///
+/// The requests are handled by the tracer in order. They fail once the tracer is done.
+#[derive(Clone)]
+pub struct TracerControl {
+  /// Weak so that the channel of the events is closed when the tracer is done
+  tracer: Weak<Tracer>,
+  /// The breakpoint that pauses the tracees
+  pause: Arc<Mutex<Option<u32>>>,
+}
+
+impl TracerControl {
+  /// Stop the tracees before their next execs until [`TracerControl::resume`] is called
+  pub fn pause(&self) -> color_eyre::Result<()> {
+    let tracer = self.tracer()?;
+    let mut pause = self.pause.lock().unwrap();
+    if pause.is_none() {
+      *pause = Some(tracer.add_breakpoint(BreakPoint {
+        // Every filename contains the empty string
+        pattern: BreakPointPattern::InFilename(String::new()),
+        ty: BreakPointType::Permanent,
+        activated: true,
+        stop: BreakPointStop::SyscallEnter,
+      }));
+    }
+    Ok(())
+  }
+
+  /// Let the tracees stopped by [`TracerControl::pause`] do their execs
+  pub fn resume(&self) -> color_eyre::Result<()> {
+    let tracer = self.tracer()?;
+    let pause = self.pause.lock().unwrap().take();
+    if let Some(bid) = pause {
+      tracer.remove_breakpoint(bid);
+      tracer.request_breakpoint_resume(bid)?;
+    }
+    Ok(())
+  }
+
+  /// Trace another command besides the spawned one
+  pub fn inject(&self, cmd: Vec<String>) -> color_eyre::Result<()> {
+    self.tracer()?.request_spawn_root(cmd)
+  }
+
+  /// Kill all tracees
+  pub fn kill(&self) -> color_eyre::Result<()> {
+    self.tracer()?.request_kill_all()
+  }
+
+  /// Detach from all tracees and stop tracing, leaving the tracees running
+  pub fn detach(&self) -> color_eyre::Result<()> {
+    self.tracer()?.request_detach_all()
+  }
+
+  fn tracer(&self) -> color_eyre::Result<Arc<Tracer>> {
+    self
+      .tracer
+      .upgrade()
+      .ok_or_else(|| eyre!("The tracer is done"))
+  }
+}
+
+impl Stream for TracerEventStream {
+  type Item = TracerEvent;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.events.poll_next_unpin(cx)
+  }
+}
+
+impl TracerEventStream {
+  /// A handle to control the tracer
+  pub fn control(&self) -> TracerControl {
+    self.control.clone()
+  }
+
+  /// Wait for the tracer to finish and return its result
+  pub async fn wait(self) -> color_eyre::Result<()> {
+    self.tracer_thread.await?
+  }
+}
+
+impl Default for TracerBuilder {
+  fn default() -> Self {
+    Self {
+      foreground: false,
+      tracing_args: LogModeArgs::default(),
+      modifier_args: ModifierArgs::default(),
+      ptrace_args: PtraceArgs::default(),
+      tracer_event_args: TracerEventArgs::all(),
+      baseline: None,
+      user: None,
+    }
+  }
+}
+
+impl TracerBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Run the tracees in the foreground process group of the terminal
+  pub fn foreground(mut self, foreground: bool) -> Self {
+    self.foreground = foreground;
+    self
+  }
+
+  /// Detach from the tracees instead of killing them when the tracer is interrupted
+  pub fn detach_on_exit(mut self, detach: bool) -> Self {
+    self.tracing_args.detach_on_exit = detach;
+    self
+  }
+
+  /// Only send these kinds of events
+  pub fn filter(mut self, filter: BitFlags<TracerEventDetailsKind>) -> Self {
+    self.tracer_event_args.show_all_events = false;
+    self.tracer_event_args.filter = filter;
+    self
+  }
+
+  /// Spill the messages to disk once this many messages are queued
+  pub fn queue_size(mut self, size: usize) -> Self {
+    self.tracer_event_args.queue_size = Some(size);
+    self
+  }
+
+  /// The directory to spill the messages to. Defaults to the temporary directory.
+  pub fn spill_dir(mut self, dir: PathBuf) -> Self {
+    self.tracer_event_args.spill_dir = Some(dir);
+    self
+  }
+
+  /// Whether to follow the forks of the tracees. Defaults to `true`.
+  pub fn follow_forks(mut self, follow: bool) -> Self {
+    self.ptrace_args.no_follow_forks = !follow;
+    self
+  }
+
+  /// Whether to use seccomp-bpf to only stop the tracees on execs.
+  /// By default, it is used if the kernel supports it and the other options allow it.
+  pub fn seccomp_bpf(mut self, enabled: bool) -> Self {
+    self.ptrace_args.seccomp_bpf = if enabled {
+      SeccompBpf::On
+    } else {
+      SeccompBpf::Off
+    };
+    self
+  }
+
+  /// Also send the signals delivered to the tracees
+  pub fn trace_signals(mut self, trace: bool) -> Self {
+    self.ptrace_args.trace_signals = trace;
+    self
+  }
+
+  /// Set an environment variable for the command
+  pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+    self.ptrace_args.set_env.push((key.into(), value.into()));
+    self
+  }
+
+  /// Remove an environment variable for the command
+  pub fn env_remove(mut self, key: impl Into<String>) -> Self {
+    self.ptrace_args.unset_env.push(key.into());
+    self
+  }
+
+  /// The working directory of the command
+  pub fn cwd(mut self, cwd: PathBuf) -> Self {
+    self.ptrace_args.tracee_cwd = Some(cwd);
+    self
+  }
+
+  /// Whether to resolve `/proc/self/exe` in the filenames of the execs. Defaults to `true`.
+  pub fn resolve_proc_self_exe(mut self, resolve: bool) -> Self {
+    self.modifier_args.resolve_proc_self_exe = resolve;
+    self.modifier_args.no_resolve_proc_self_exe = !resolve;
+    self
+  }
+
+  /// Stop tracing after this many exec events and let the command run to completion
+  pub fn max_events(mut self, max: NonZeroUsize) -> Self {
+    self.modifier_args.max_events = Some(max);
+    self
+  }
+
+  /// The environment that the exec events are compared against.
+  /// Defaults to the environment of the current process.
+  pub fn baseline(mut self, baseline: BaselineInfo) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+
+  /// Run the tracees as this user, which requires root
+  pub fn user(mut self, user: User) -> Self {
+    self.user = Some(user);
+    self
+  }
+
+  pub fn build(self) -> color_eyre::Result<BuiltTracer> {
+    let baseline = match self.baseline {
+      Some(baseline) => baseline,
+      None => BaselineInfo::new()?,
+    };
+    let (msg_tx, messages) = self.tracer_event_args.channel(Arc::new(baseline.clone()));
+    let (req_tx, requests) = mpsc::unbounded_channel();
+    let tracer = Tracer::new(
+      TracerMode::Log {
+        foreground: self.foreground,
+      },
+      self.tracing_args,
+      self.modifier_args.processed(),
+      self.ptrace_args,
+      self.tracer_event_args,
+      baseline,
+      msg_tx,
+      self.user,
+      req_tx,
+    )?;
+    Ok(BuiltTracer {
+      tracer: Arc::new(tracer),
+      messages,
+      requests,
+    })
+  }
+
+  /// Build the tracer and start tracing the command.
+  ///
+  /// Only the events are streamed. Use [`TracerBuilder::build`] to receive the process state
+  /// updates as well or to send other requests to the tracer.
+  pub fn spawn(self, cmd: Vec<String>) -> color_eyre::Result<TracerEventStream> {
+    let BuiltTracer {
+      tracer,
+      messages,
+      requests,
+    } = self.build()?;
+    let control = TracerControl {
+      tracer: Arc::downgrade(&tracer),
+      pause: Arc::new(Mutex::new(None)),
+    };
+    let tracer_thread = tracer.spawn(cmd, None, requests);
+    let events = messages
+      .into_stream()
+      // The tracer sends a fatal error right before it fails
+      .take_while(|msg| future::ready(!matches!(msg, TracerMessage::FatalError(_))))
+      .filter_map(|msg| {
+        future::ready(match msg {
+          TracerMessage::Event(event) => Some(event),
+          _ => None,
+        })
+      })
+      .boxed();
+    Ok(TracerEventStream {
+      events,
+      tracer_thread,
+      control,
+    })
+  }
+}
@@ -1,8 +1,9 @@
-use std::{collections::BTreeMap, ffi::CString};
+use std::{cell::RefCell, collections::BTreeMap, ffi::CString};
 
 use arcstr::ArcStr;
 use nix::{
   errno::Errno,
+  libc::c_long,
   sys::ptrace::{self, AddressType},
   unistd::Pid,
 };
@@ -10,36 +11,50 @@ use tracing::warn;
 
 use crate::{
   event::OutputMsg,
-  proc::{cached_str, cached_string, parse_env_entry},
+  proc::{cached_str, parse_env_entry},
 };
 
 pub type InspectError = Errno;
 
+/// Buffers larger than this are not kept around for the next read
+const MAX_RETAINED_BUFFER_SIZE: usize = 64 * 1024;
+
+thread_local! {
+  /// The buffer for reading strings from the tracee, which is reused across reads
+  /// so that strings already in the cache could be read without any allocation.
+  static READ_BUFFER: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(256));
+}
+
 pub fn read_generic_string<TString>(
   pid: Pid,
   address: AddressType,
-  ctor: impl Fn(Vec<u8>) -> TString,
+  ctor: impl FnOnce(&[u8]) -> TString,
 ) -> Result<TString, InspectError> {
-  let mut buf = Vec::new();
-  let mut address = address;
-  const WORD_SIZE: usize = 8; // FIXME
-  loop {
-    let word = match ptrace::read(pid, address) {
-      Err(e) => {
-        warn!("Cannot read tracee {pid} memory {address:?}: {e}");
-        return Err(e);
-      }
-      Ok(word) => word,
-    };
-    let word_bytes = word.to_ne_bytes();
-    for &byte in word_bytes.iter() {
-      if byte == 0 {
+  READ_BUFFER.with_borrow_mut(|buf| {
+    buf.clear();
+    if buf.capacity() > MAX_RETAINED_BUFFER_SIZE {
+      buf.shrink_to(MAX_RETAINED_BUFFER_SIZE);
+    }
+    let mut address = address;
+    // ptrace reads a word at a time
+    const WORD_SIZE: usize = size_of::<c_long>();
+    loop {
+      let word = match ptrace::read(pid, address) {
+        Err(e) => {
+          warn!("Cannot read tracee {pid} memory {address:?}: {e}");
+          return Err(e);
+        }
+        Ok(word) => word,
+      };
+      let word_bytes = word.to_ne_bytes();
+      if let Some(nul) = word_bytes.iter().position(|&byte| byte == 0) {
+        buf.extend_from_slice(&word_bytes[..nul]);
         return Ok(ctor(buf));
       }
-      buf.push(byte);
+      buf.extend_from_slice(&word_bytes);
+      address = unsafe { address.add(WORD_SIZE) };
     }
-    address = unsafe { address.add(WORD_SIZE) };
-  }
+  })
 }
 
 #[allow(unused)]
@@ -48,12 +63,13 @@ pub fn read_cstring(pid: Pid, address: AddressType) -> Result<CString, InspectEr
 }
 
 pub fn read_arcstr(pid: Pid, address: AddressType) -> Result<ArcStr, InspectError> {
-  read_generic_string(pid, address, |x| cached_str(&String::from_utf8_lossy(&x)))
+  // from_utf8_lossy only allocates for invalid UTF-8 and the cache only allocates for new strings
+  read_generic_string(pid, address, |x| cached_str(&String::from_utf8_lossy(x)))
 }
 
 pub fn read_string(pid: Pid, address: AddressType) -> Result<String, InspectError> {
   // Waiting on https://github.com/rust-lang/libs-team/issues/116
-  read_generic_string(pid, address, |x| String::from_utf8_lossy(&x).into_owned())
+  read_generic_string(pid, address, |x| String::from_utf8_lossy(x).into_owned())
 }
 
 pub fn read_null_ended_array<TItem>(
@@ -64,7 +80,7 @@ pub fn read_null_ended_array<TItem>(
 ) -> Result<Vec<TItem>, InspectError> {
   let mut res = Vec::new();
   // FIXME: alignment
-  let word_size = if is_32bit { 4 } else { 8 };
+  let word_size = if is_32bit { 4 } else { size_of::<usize>() };
   loop {
     let ptr = match ptrace::read(pid, address) {
       Err(e) => {
@@ -73,7 +89,7 @@ pub fn read_null_ended_array<TItem>(
       }
       Ok(ptr) => {
         if is_32bit {
-          ptr as u32 as i64
+          ptr as u32 as c_long
         } else {
           ptr
         }
@@ -97,6 +113,7 @@ pub fn read_cstring_array(
   read_null_ended_array(pid, address, is_32bit, read_cstring)
 }
 
+#[allow(unused)]
 pub fn read_string_array(
   pid: Pid,
   address: AddressType,
@@ -111,9 +128,7 @@ pub fn read_arcstr_array(
   address: AddressType,
   is_32bit: bool,
 ) -> Result<Vec<ArcStr>, InspectError> {
-  read_null_ended_array(pid, address, is_32bit, |pid, address| {
-    read_string(pid, address).map(cached_string)
-  })
+  read_null_ended_array(pid, address, is_32bit, read_arcstr)
 }
 
 pub fn read_output_msg_array(
@@ -122,15 +137,29 @@ pub fn read_output_msg_array(
   is_32bit: bool,
 ) -> Result<Vec<OutputMsg>, InspectError> {
   read_null_ended_array(pid, address, is_32bit, |pid, address| {
-    read_string(pid, address)
-      .map(cached_string)
-      .map(OutputMsg::Ok)
+    read_arcstr(pid, address).map(OutputMsg::Ok)
+  })
+}
+
+/// Read envp without the intermediate strings. Fails if any of the entries could not be read.
+pub fn read_envp(
+  pid: Pid,
+  address: AddressType,
+  is_32bit: bool,
+) -> Result<BTreeMap<OutputMsg, OutputMsg>, InspectError> {
+  read_null_ended_array(pid, address, is_32bit, |pid, address| {
+    read_generic_string(pid, address, |bytes| {
+      let utf8 = String::from_utf8_lossy(bytes);
+      let (k, v) = parse_env_entry(&utf8);
+      (OutputMsg::Ok(cached_str(k)), OutputMsg::Ok(cached_str(v)))
+    })
   })
+  .map(|entries| entries.into_iter().collect())
 }
 
 fn read_single_env_entry(pid: Pid, address: AddressType) -> (OutputMsg, OutputMsg) {
   let result = read_generic_string(pid, address, |bytes| {
-    let utf8 = String::from_utf8_lossy(&bytes);
+    let utf8 = String::from_utf8_lossy(bytes);
     let (k, v) = parse_env_entry(&utf8);
     let k = cached_str(k);
     let v = cached_str(v);
@@ -152,7 +181,7 @@ pub fn read_env(
 ) -> Result<BTreeMap<OutputMsg, OutputMsg>, InspectError> {
   let mut res = BTreeMap::new();
   // FIXME: alignment
-  let word_size = if is_32bit { 4 } else { 8 };
+  let word_size = if is_32bit { 4 } else { size_of::<usize>() };
   loop {
     let ptr = match ptrace::read(pid, address) {
       Err(e) => {
@@ -161,7 +190,7 @@ pub fn read_env(
       }
       Ok(ptr) => {
         if is_32bit {
-          ptr as u32 as i64
+          ptr as u32 as c_long
         } else {
           ptr
         }
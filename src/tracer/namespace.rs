@@ -0,0 +1,80 @@
+//! Start the root tracee in new namespaces. Everything here runs in the forked child before exec.
+
+use std::process::exit;
+
+use nix::{
+  mount::{mount, MsFlags},
+  sched::{unshare, CloneFlags},
+  sys::wait::{waitpid, WaitStatus},
+  unistd::{fork, ForkResult, Gid, Uid},
+};
+
+use crate::cli::options::Namespace;
+
+pub fn clone_flags(namespaces: &[Namespace]) -> CloneFlags {
+  namespaces
+    .iter()
+    .map(|ns| match ns {
+      Namespace::Cgroup => CloneFlags::CLONE_NEWCGROUP,
+      Namespace::Ipc => CloneFlags::CLONE_NEWIPC,
+      Namespace::Mount => CloneFlags::CLONE_NEWNS,
+      Namespace::Net => CloneFlags::CLONE_NEWNET,
+      Namespace::Pid => CloneFlags::CLONE_NEWPID,
+      Namespace::User => CloneFlags::CLONE_NEWUSER,
+      Namespace::Uts => CloneFlags::CLONE_NEWUTS,
+    })
+    .fold(CloneFlags::empty(), |acc, flag| acc | flag)
+}
+
+/// Unshare the namespaces. `uid` and `gid` are mapped to themselves in the new user namespace
+/// so that the files created by the tracee are still owned by the user.
+pub fn unshare_namespaces(flags: CloneFlags, uid: Uid, gid: Gid) -> color_eyre::Result<()> {
+  unshare(flags)?;
+  if flags.contains(CloneFlags::CLONE_NEWUSER) {
+    // Unprivileged processes could only write the gid map after disabling setgroups
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+  }
+  if flags.contains(CloneFlags::CLONE_NEWNS) {
+    // Don't propagate the mounts of the tracee back to the host
+    mount(
+      None::<&str>,
+      "/",
+      None::<&str>,
+      MsFlags::MS_REC | MsFlags::MS_PRIVATE,
+      None::<&str>,
+    )?;
+  }
+  Ok(())
+}
+
+/// Only the children enter the new pid namespace. Fork so that the command becomes the init
+/// process of the namespace. The parent waits for it and exits with the same status.
+///
+/// The tracer follows the fork so the command is still traced.
+pub fn enter_pid_namespace(mount_proc: bool) -> color_eyre::Result<()> {
+  match unsafe { fork()? } {
+    ForkResult::Child => {
+      if mount_proc {
+        // Otherwise /proc still shows the processes of the host
+        mount(
+          Some("proc"),
+          "/proc",
+          Some("proc"),
+          MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+          None::<&str>,
+        )?;
+      }
+      Ok(())
+    }
+    ForkResult::Parent { child } => {
+      let code = match waitpid(child, None) {
+        Ok(WaitStatus::Exited(_, code)) => code,
+        Ok(WaitStatus::Signaled(_, sig, _)) => 128 + sig as i32,
+        _ => 1,
+      };
+      exit(code);
+    }
+  }
+}
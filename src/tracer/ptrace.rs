@@ -1,4 +1,7 @@
-use std::{mem::MaybeUninit, ptr::addr_of_mut};
+use std::{
+  mem::MaybeUninit,
+  ptr::{addr_of, addr_of_mut},
+};
 
 use cfg_if::cfg_if;
 use nix::{
@@ -12,7 +15,10 @@ use nix::{
 };
 use tracing::info;
 
-use crate::arch::{Regs, RegsPayload, RegsRepr, HAS_32BIT, NATIVE_AUDIT_ARCH};
+use crate::{
+  arch::{Regs, RegsExt, RegsPayload, RegsRepr, HAS_32BIT, NATIVE_AUDIT_ARCH},
+  cli::options::TrappedSyscall,
+};
 
 pub use nix::sys::ptrace::*;
 
@@ -49,21 +55,39 @@ impl SyscallInfo {
   /// resulting in X64 ptregs but with 32bit semantics
   pub fn is_32bit(&self) -> bool {
     if HAS_32BIT {
-      // FIXME: x32 ABI
       NATIVE_AUDIT_ARCH != self.arch
     } else {
       false
     }
   }
 
+  /// Returns true if this syscall uses the x32 ABI.
+  ///
+  /// x32 syscalls use the 64bit registers but the pointers are 32bit.
+  pub fn is_x32(&self) -> bool {
+    cfg_if! {
+      if #[cfg(target_arch = "x86_64")] {
+        self.arch == crate::arch::AUDIT_ARCH_X86_64 && self.number & crate::arch::X32_SYSCALL_BIT != 0
+      } else {
+        false
+      }
+    }
+  }
+
+  /// Returns true if the pointers passed to this syscall are 32bit.
+  pub fn has_32bit_pointers(&self) -> bool {
+    self.is_32bit() || self.is_x32()
+  }
+
   pub fn is_execve(&self) -> bool {
     cfg_if! {
       if #[cfg(target_arch = "x86_64")] {
         use crate::arch;
         (self.arch == arch::AUDIT_ARCH_X86_64 && self.number == SYS_execve) ||
+        (self.arch == arch::AUDIT_ARCH_X86_64 && self.number == arch::SYS_EXECVE_X32) ||
         (self.arch == arch::AUDIT_ARCH_I386 && self.number == arch::SYS_EXECVE_32 as i64)
       } else {
-        self.arch == NATIVE_AUDIT_ARCH && self.number == SYS_execve
+        self.arch == NATIVE_AUDIT_ARCH && self.number == SYS_execve as i64
       }
     }
   }
@@ -73,10 +97,28 @@ impl SyscallInfo {
       if #[cfg(target_arch = "x86_64")] {
         use crate::arch;
         (self.arch == arch::AUDIT_ARCH_X86_64 && self.number == SYS_execveat) ||
+        (self.arch == arch::AUDIT_ARCH_X86_64 && self.number == arch::SYS_EXECVEAT_X32) ||
         (self.arch == arch::AUDIT_ARCH_I386 && self.number == arch::SYS_EXECVEAT_32 as i64)
       } else {
-        self.arch == NATIVE_AUDIT_ARCH && self.number == SYS_execveat
+        self.arch == NATIVE_AUDIT_ARCH && self.number == SYS_execveat as i64
+      }
+    }
+  }
+
+  /// Returns the [`TrappedSyscall`] that this syscall belongs to.
+  ///
+  /// Only the syscalls of the native ABI are recognized.
+  pub fn trapped_syscall(&self) -> Option<TrappedSyscall> {
+    if self.arch != NATIVE_AUDIT_ARCH {
+      return None;
+    }
+    match self.number {
+      nix::libc::SYS_chdir | nix::libc::SYS_fchdir => Some(TrappedSyscall::Chdir),
+      nix::libc::SYS_chroot => Some(TrappedSyscall::Chroot),
+      nix::libc::SYS_setuid | nix::libc::SYS_setreuid | nix::libc::SYS_setresuid => {
+        Some(TrappedSyscall::Setuid)
       }
+      _ => None,
     }
   }
 }
@@ -174,7 +216,12 @@ pub fn ptrace_getregs(pid: Pid) -> Result<Regs, Errno> {
           SIZE_OF_REGS64 => unsafe { addr_of_mut!((*dest).tag).write(crate::arch::RegsTag::X64); }
           size => panic!("Invalid length {size} of user_regs_struct!")
         }
-      } else if #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))] {
+      } else if #[cfg(any(
+        target_arch = "riscv64",
+        target_arch = "aarch64",
+        target_arch = "arm",
+        target_arch = "s390x"
+      ))] {
         assert_eq!(iovec.iov_len, std::mem::size_of::<RegsPayload>());
       } else {
         compile_error!("Please update the code for your architecture!");
@@ -184,3 +231,159 @@ pub fn ptrace_getregs(pid: Pid) -> Result<Regs, Errno> {
   };
   Ok(regs)
 }
+
+pub fn ptrace_setregs(pid: Pid, regs: &Regs) -> Result<(), Errno> {
+  const NT_PRSTATUS: std::ffi::c_int = 1;
+
+  use nix::sys::ptrace::AddressType;
+
+  let src: *const RegsRepr = (regs as *const Regs).cast();
+  #[cfg(target_arch = "x86_64")]
+  let iov_len = match regs {
+    Regs::X86(_) => size_of::<crate::arch::PtraceRegisters32>(),
+    Regs::X64(_) => size_of::<crate::arch::PtraceRegisters64>(),
+  };
+  #[cfg(not(target_arch = "x86_64"))]
+  let iov_len = size_of::<RegsPayload>();
+  let mut iovec = nix::libc::iovec {
+    iov_base: unsafe { addr_of!((*src).payload) }.cast_mut() as AddressType,
+    iov_len,
+  };
+  let ptrace_result = unsafe {
+    nix::libc::ptrace(
+      nix::libc::PTRACE_SETREGSET,
+      pid.as_raw(),
+      NT_PRSTATUS,
+      &mut iovec,
+    )
+  };
+  Errno::result(ptrace_result).map(drop)
+}
+
+/// Skip the syscall on ptrace syscall entry/seccomp stop.
+///
+/// The skipped syscall returns -ENOSYS unless its result is changed on the syscall exit stop.
+pub fn ptrace_skip_syscall(pid: Pid) -> Result<(), Errno> {
+  cfg_if! {
+    if #[cfg(target_arch = "aarch64")] {
+      // https://github.com/torvalds/linux/blob/v6.9/include/uapi/linux/elf.h
+      const NT_ARM_SYSTEM_CALL: std::ffi::c_int = 0x404;
+      let mut nr: std::ffi::c_int = -1;
+      let mut iovec = nix::libc::iovec {
+        iov_base: addr_of_mut!(nr).cast(),
+        iov_len: size_of::<std::ffi::c_int>(),
+      };
+      let ptrace_result = unsafe {
+        nix::libc::ptrace(
+          nix::libc::PTRACE_SETREGSET,
+          pid.as_raw(),
+          NT_ARM_SYSTEM_CALL,
+          &mut iovec,
+        )
+      };
+      Errno::result(ptrace_result).map(drop)
+    } else if #[cfg(target_arch = "arm")] {
+      const PTRACE_SET_SYSCALL: std::ffi::c_int = 23;
+      let ptrace_result = unsafe {
+        nix::libc::ptrace(PTRACE_SET_SYSCALL as _, pid.as_raw(), 0, -1 as std::ffi::c_long)
+      };
+      Errno::result(ptrace_result).map(drop)
+    } else {
+      let mut regs = ptrace_getregs(pid)?;
+      regs.set_syscall_nr(-1);
+      ptrace_setregs(pid, &regs)
+    }
+  }
+}
+
+/// Change the result of the syscall on ptrace syscall exit stop
+pub fn ptrace_set_syscall_result(pid: Pid, result: isize) -> Result<(), Errno> {
+  let mut regs = ptrace_getregs(pid)?;
+  regs.set_syscall_result(result);
+  ptrace_setregs(pid, &regs)
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+  use super::*;
+  use crate::arch::{
+    RegsExt, AUDIT_ARCH_I386, AUDIT_ARCH_X86_64, SYS_EXECVEAT_32, SYS_EXECVE_32, SYS_EXECVE_X32,
+  };
+
+  #[test]
+  fn native_execve() {
+    let info = SyscallInfo {
+      arch: AUDIT_ARCH_X86_64,
+      number: SYS_execve,
+    };
+    assert!(info.is_execve());
+    assert!(!info.is_32bit());
+    assert!(!info.has_32bit_pointers());
+    assert_eq!(info.trapped_syscall(), None);
+  }
+
+  #[test]
+  fn native_trapped_syscalls() {
+    let chdir = SyscallInfo {
+      arch: AUDIT_ARCH_X86_64,
+      number: nix::libc::SYS_chdir,
+    };
+    assert_eq!(chdir.trapped_syscall(), Some(TrappedSyscall::Chdir));
+    let setresuid = SyscallInfo {
+      arch: AUDIT_ARCH_X86_64,
+      number: nix::libc::SYS_setresuid,
+    };
+    assert_eq!(setresuid.trapped_syscall(), Some(TrappedSyscall::Setuid));
+    // The same number is another syscall in the ia32 ABI
+    let ia32 = SyscallInfo {
+      arch: AUDIT_ARCH_I386,
+      number: nix::libc::SYS_chdir,
+    };
+    assert_eq!(ia32.trapped_syscall(), None);
+  }
+
+  #[test]
+  fn ia32_execs() {
+    let execve = SyscallInfo {
+      arch: AUDIT_ARCH_I386,
+      number: SYS_EXECVE_32 as i64,
+    };
+    assert!(execve.is_execve());
+    assert!(execve.is_32bit());
+    assert!(execve.has_32bit_pointers());
+    let execveat = SyscallInfo {
+      arch: AUDIT_ARCH_I386,
+      number: SYS_EXECVEAT_32 as i64,
+    };
+    assert!(execveat.is_execveat());
+    // 11 is munmap in the native ABI
+    let munmap = SyscallInfo {
+      arch: AUDIT_ARCH_X86_64,
+      number: SYS_EXECVE_32 as i64,
+    };
+    assert!(!munmap.is_execve());
+  }
+
+  #[test]
+  fn x32_execve() {
+    let info = SyscallInfo {
+      arch: AUDIT_ARCH_X86_64,
+      number: SYS_EXECVE_X32,
+    };
+    assert!(info.is_execve());
+    // x32 uses the 64bit registers
+    assert!(!info.is_32bit());
+    assert!(info.has_32bit_pointers());
+  }
+
+  #[test]
+  fn compat_syscall_args_of_64bit_process() {
+    // A 64bit process could make ia32 syscalls by `int 0x80`
+    let mut regs: crate::arch::PtraceRegisters64 = unsafe { std::mem::zeroed() };
+    regs.rbx = 0xffff_ffff_0000_1000;
+    regs.rdi = 0x7fff_0000_2000;
+    let regs = Regs::X64(regs);
+    assert_eq!(regs.syscall_arg(0, true), 0x1000);
+    assert_eq!(regs.syscall_arg(0, false), 0x7fff_0000_2000);
+  }
+}
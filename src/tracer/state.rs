@@ -11,8 +11,15 @@ use regex_cursor::engines::pikevm::{self, PikeVM};
 use strum::IntoStaticStr;
 
 use crate::{
+  audit::AuditRule,
+  caps::Capabilities,
+  cli::options::TrappedSyscall,
+  container::{Container, ProcessCgroup},
   event::OutputMsg,
-  proc::{read_comm, FileDescriptorInfoCollection, Interpreter},
+  proc::{
+    cached_argv, cached_envp, read_comm, Credentials, ExePrivileges, FileDescriptorInfoCollection,
+    Interpreter,
+  },
   regex::{ArgvCursor, SPACE},
   tracer::InspectError,
 };
@@ -34,6 +41,7 @@ pub struct PendingDetach {
 pub enum Syscall {
   Execve,
   Execveat,
+  Trapped(TrappedSyscall),
   Other,
 }
 
@@ -51,6 +59,19 @@ pub struct ProcessState {
   pub associated_events: Vec<u64>,
   /// A pending detach request with a signal to send to the process
   pub pending_detach: Option<PendingDetach>,
+  /// Whether the exec is skipped on syscall entry and should fail with EPERM
+  pub exec_denied: bool,
+  /// Index of the root command that this process descends from when tracing a group of commands
+  pub root_index: Option<usize>,
+  /// Whether the process has daemonized or descends from a daemonized process.
+  /// Such processes are followed after the root tracees exit.
+  pub daemonized: bool,
+  /// The working directory, known after the process changes it in a trapped syscall
+  pub cwd: Option<ArcStr>,
+  /// The root directory, known after the process changes it in a trapped syscall
+  pub root: Option<ArcStr>,
+  /// The user and group ids, known after the process changes them in a trapped syscall
+  pub credentials: Option<Credentials>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -66,7 +87,8 @@ pub enum ProcessStatus {
   PtraceForkEventReceived,
   Running,
   Exited(ProcessExit),
-  BreakPointHit,
+  /// Stopped at a breakpoint until it is resumed or detached
+  BreakPointHit(BreakPointHit),
   Detached,
 }
 
@@ -78,6 +100,27 @@ pub struct ExecData {
   pub cwd: OutputMsg,
   pub interpreters: Option<Vec<Interpreter>>,
   pub fdinfo: Arc<FileDescriptorInfoCollection>,
+  pub container: Option<Container>,
+  /// The cgroup path of the process
+  pub cgroup: Option<ArcStr>,
+  /// The parent of the process that execs
+  pub ppid: Option<Pid>,
+  /// The resolved path of the executable, read from /proc/<pid>/exe after a successful exec
+  pub exe: Option<OutputMsg>,
+  /// The hash of the executable if `--hash-exe` is used
+  pub exe_hash: Option<ArcStr>,
+  /// Whether the executable is setuid/setgid or has file capabilities
+  pub privileges: Option<ExePrivileges>,
+  /// The uid and gid of the process after the exec
+  pub credentials: Option<Credentials>,
+  /// The capabilities of the process after the exec
+  pub capabilities: Option<Capabilities>,
+  /// The SELinux context or the AppArmor profile of the process after the exec
+  pub security_label: Option<ArcStr>,
+  /// The security label before the exec if the exec changes it
+  pub security_label_before: Option<ArcStr>,
+  /// The suspicious patterns found in `--audit` mode
+  pub audit: Vec<AuditRule>,
 }
 
 impl ExecData {
@@ -88,14 +131,26 @@ impl ExecData {
     cwd: OutputMsg,
     interpreters: Option<Vec<Interpreter>>,
     fdinfo: FileDescriptorInfoCollection,
+    cgroup: ProcessCgroup,
   ) -> Self {
     Self {
       filename,
-      argv: Arc::new(argv),
-      envp: Arc::new(envp),
+      argv: cached_argv(argv),
+      envp: cached_envp(envp),
       cwd,
       interpreters,
       fdinfo: Arc::new(fdinfo),
+      container: cgroup.container,
+      cgroup: cgroup.path,
+      ppid: None,
+      exe: None,
+      exe_hash: None,
+      privileges: None,
+      credentials: None,
+      capabilities: None,
+      security_label: None,
+      security_label_before: None,
+      audit: Vec::new(),
     }
   }
 }
@@ -122,6 +177,77 @@ impl ProcessStateStore {
     // The last process in the vector is the current process
     self.processes.get(&pid)?.last()
   }
+
+  /// A thread other than the thread group leader execs and takes over the thread id of the leader.
+  ///
+  /// The state of the execing thread, which holds the exec data, replaces the state of the leader
+  /// and the former thread id is forgotten. The events of both are kept so that their exit is reported.
+  pub fn take_over_leader(&mut self, leader: Pid, former_tid: Pid) -> Option<&mut ProcessState> {
+    let states = self.processes.get_mut(&former_tid)?;
+    let mut state = states.pop()?;
+    if states.is_empty() {
+      self.processes.remove(&former_tid);
+    }
+    state.pid = leader;
+    match self.get_current_mut(leader) {
+      Some(leader_state) => {
+        // These are the properties of the thread group, which the leader has
+        state.ppid = leader_state.ppid;
+        state.start_time = leader_state.start_time;
+        state.root_index = leader_state.root_index;
+        state.daemonized = leader_state.daemonized;
+        state
+          .associated_events
+          .splice(0..0, leader_state.associated_events.drain(..));
+        *leader_state = state;
+      }
+      None => self.insert(state),
+    }
+    self.get_current_mut(leader)
+  }
+
+  /// The current processes that have neither exited nor been detached
+  pub fn live_processes_mut(&mut self) -> impl Iterator<Item = &mut ProcessState> {
+    self
+      .processes
+      .values_mut()
+      .filter_map(|states| states.last_mut())
+      .filter(|state| {
+        !matches!(
+          state.status,
+          ProcessStatus::Exited(_) | ProcessStatus::Detached
+        )
+      })
+  }
+
+  /// The daemonized processes that have neither exited nor been detached
+  pub fn live_daemons(&self) -> impl Iterator<Item = Pid> + '_ {
+    self
+      .processes
+      .values()
+      .filter_map(|states| states.last())
+      .filter(|state| {
+        state.daemonized
+          && !matches!(
+            state.status,
+            ProcessStatus::Exited(_) | ProcessStatus::Detached
+          )
+      })
+      .map(|state| state.pid)
+  }
+
+  pub fn has_live_processes(&self) -> bool {
+    self
+      .processes
+      .values()
+      .filter_map(|states| states.last())
+      .any(|state| {
+        !matches!(
+          state.status,
+          ProcessStatus::Exited(_) | ProcessStatus::Detached
+        )
+      })
+  }
 }
 
 impl ProcessState {
@@ -138,6 +264,12 @@ impl ProcessState {
       exec_data: None,
       associated_events: Vec::new(),
       pending_detach: None,
+      exec_denied: false,
+      root_index: None,
+      daemonized: false,
+      cwd: None,
+      root: None,
+      credentials: None,
     })
   }
 
@@ -183,6 +315,8 @@ pub enum BreakPointType {
   Once,
   /// The breakpoint will be hit every time it is encountered.
   Permanent,
+  /// The user will be asked to approve or deny the exec every time it is encountered.
+  Ask,
 }
 
 #[derive(Debug, Clone)]
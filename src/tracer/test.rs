@@ -1,5 +1,7 @@
 use std::{env, path::PathBuf, sync::Arc};
 
+use futures::StreamExt;
+use nix::sys::signal::Signal;
 use rstest::{fixture, rstest};
 use serial_test::file_serial;
 use tokio::sync::mpsc::UnboundedReceiver;
@@ -7,13 +9,23 @@ use tracing::info;
 use tracing_test::traced_test;
 
 use crate::{
-  cli::args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
-  event::{OutputMsg, TracerEvent, TracerEventDetails, TracerMessage},
+  channel::MessageReceiver,
+  cli::{
+    args::{LogModeArgs, ModifierArgs, PtraceArgs, TracerEventArgs},
+    options::{Redirect, TrappedSyscall},
+  },
+  event::{
+    OutputMsg, ProcessStateUpdate, ProcessStateUpdateEvent, TracerEvent, TracerEventDetails,
+    TracerEventDetailsKind, TracerEventMessage, TracerMessage,
+  },
   proc::{BaselineInfo, Interpreter},
-  tracer::Tracer,
+  tracer::{Tracer, TracerBuilder},
 };
 
-use super::{PendingRequest, TracerMode};
+use super::{
+  state::{BreakPoint, BreakPointPattern, BreakPointStop, BreakPointType, ProcessExit},
+  PendingRequest, TracerMode,
+};
 
 #[fixture]
 fn true_executable() -> PathBuf {
@@ -36,17 +48,18 @@ fn true_executable() -> PathBuf {
 #[fixture]
 fn tracer(
   #[default(Default::default())] modifier_args: ModifierArgs,
+  #[default(Default::default())] ptrace_args: PtraceArgs,
+  #[default(Default::default())] tracing_args: LogModeArgs,
 ) -> (
   Arc<Tracer>,
-  UnboundedReceiver<TracerMessage>,
+  MessageReceiver,
   UnboundedReceiver<PendingRequest>,
 ) {
   let tracer_mod = TracerMode::Log { foreground: false };
-  let tracing_args = LogModeArgs::default();
   let tracer_event_args = TracerEventArgs::all();
-  let (msg_tx, msg_rx) = tokio::sync::mpsc::unbounded_channel();
-  let (req_tx, req_rx) = tokio::sync::mpsc::unbounded_channel();
   let baseline = BaselineInfo::new().unwrap();
+  let (msg_tx, msg_rx) = tracer_event_args.channel(Arc::new(baseline.clone()));
+  let (req_tx, req_rx) = tokio::sync::mpsc::unbounded_channel();
 
   (
     Arc::new(
@@ -54,7 +67,7 @@ fn tracer(
         tracer_mod,
         tracing_args,
         modifier_args,
-        PtraceArgs::default(),
+        ptrace_args,
         tracer_event_args,
         baseline,
         msg_tx,
@@ -70,7 +83,7 @@ fn tracer(
 
 async fn run_exe_and_collect_msgs(
   tracer: Arc<Tracer>,
-  mut rx: UnboundedReceiver<TracerMessage>,
+  mut rx: MessageReceiver,
   req_rx: UnboundedReceiver<PendingRequest>,
   argv: Vec<String>,
 ) -> Vec<TracerMessage> {
@@ -89,7 +102,7 @@ async fn run_exe_and_collect_msgs(
 
 type TracerFixture = (
   Arc<Tracer>,
-  UnboundedReceiver<TracerMessage>,
+  MessageReceiver,
   UnboundedReceiver<PendingRequest>,
 );
 
@@ -204,3 +217,643 @@ async fn tracer_emits_exec_event(tracer: TracerFixture, true_executable: PathBuf
   }
   panic!("Corresponding exec event not found")
 }
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_records_exe_ppid_and_credentials_of_exec(
+  tracer: TracerFixture,
+  true_executable: PathBuf,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let events = run_exe_and_collect_msgs(tracer, rx, req_rx, vec![true_executable.clone()]).await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      ..
+    }) = event
+    {
+      // The executable is resolved from /proc/<pid>/exe, which has the symlinks resolved
+      let exe = std::fs::canonicalize(&true_executable).unwrap();
+      assert_eq!(
+        exec.exe,
+        Some(OutputMsg::Ok(exe.to_string_lossy().as_ref().into()))
+      );
+      // The root tracee is spawned by the tracer
+      assert_eq!(exec.ppid, Some(nix::unistd::getpid()));
+      // true is not setuid so the credentials are the same as ours
+      let creds = exec.credentials.unwrap();
+      assert_eq!(creds.uid, nix::unistd::getuid().as_raw());
+      assert!(!creds.is_escalated());
+      return;
+    }
+  }
+  panic!("Corresponding exec event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_emits_process_exit_event(tracer: TracerFixture) {
+  let (tracer, rx, req_rx) = tracer;
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec!["sh".to_string(), "-c".to_string(), "exit 42".to_string()],
+  )
+  .await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: details @ TracerEventDetails::ProcessExit { .. },
+      ..
+    }) = event
+    {
+      assert_eq!(details.kind(), TracerEventDetailsKind::ProcessExit);
+      let TracerEventDetails::ProcessExit { comm, exit, .. } = details else {
+        unreachable!()
+      };
+      assert_eq!(comm, "sh");
+      assert_eq!(exit, ProcessExit::Code(42));
+      return;
+    }
+  }
+  panic!("Corresponding process exit event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_reports_renamed_comm_on_exit(tracer: TracerFixture) {
+  let (tracer, rx, req_rx) = tracer;
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec![
+      "sh".to_string(),
+      "-c".to_string(),
+      "printf renamed > /proc/self/comm; exit 3".to_string(),
+    ],
+  )
+  .await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::ProcessExit { comm, exit, .. },
+      ..
+    }) = event
+    {
+      assert_eq!(comm, "renamed");
+      assert_eq!(exit, ProcessExit::Code(3));
+      return;
+    }
+  }
+  panic!("Corresponding process exit event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_emits_signal_delivery_event(
+  #[with(Default::default(), PtraceArgs {
+    trace_signals: true,
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec![
+      "sh".to_string(),
+      "-c".to_string(),
+      "kill -USR1 $$".to_string(),
+    ],
+  )
+  .await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::SignalDelivery { comm, signal, .. },
+      ..
+    }) = event
+    {
+      assert_eq!(comm, "sh");
+      assert_eq!(signal, Signal::SIGUSR1);
+      return;
+    }
+  }
+  panic!("Corresponding signal delivery event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_does_not_follow_forks(
+  #[with(Default::default(), PtraceArgs {
+    no_follow_forks: true,
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec![
+      "sh".to_string(),
+      "-c".to_string(),
+      "env true; exec env true".to_string(),
+    ],
+  )
+  .await;
+  let execs = events
+    .into_iter()
+    .filter_map(|event| match event {
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::Exec(exec),
+        ..
+      }) if exec.result == 0 => Some(exec),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  // Only the successful execs of the root process: sh, env and true.
+  // The failed ones depend on how many directories in PATH are searched.
+  assert_eq!(execs.len(), 3);
+  assert!(execs.iter().all(|exec| exec.pid == execs[0].pid));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_modifies_environment_of_root_command(
+  #[with(Default::default(), PtraceArgs {
+    set_env: vec![("TRACEXEC_TEST_ENV".to_string(), "1".to_string())],
+    unset_env: vec!["PATH".to_string()],
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+  true_executable: PathBuf,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let events = run_exe_and_collect_msgs(tracer, rx, req_rx, vec![true_executable]).await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      ..
+    }) = event
+    {
+      let env_diff = exec.env_diff.as_ref().unwrap();
+      assert_eq!(
+        env_diff
+          .added
+          .get(&OutputMsg::Ok("TRACEXEC_TEST_ENV".into())),
+        Some(&OutputMsg::Ok("1".into()))
+      );
+      if env::var_os("PATH").is_some() {
+        assert!(env_diff.removed.contains(&OutputMsg::Ok("PATH".into())));
+      }
+      return;
+    }
+  }
+  panic!("Corresponding exec event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_starts_root_command_in_tracee_cwd(
+  #[with(Default::default(), PtraceArgs {
+    tracee_cwd: Some(env::temp_dir()),
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+  true_executable: PathBuf,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let events = run_exe_and_collect_msgs(tracer, rx, req_rx, vec![true_executable]).await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      ..
+    }) = event
+    {
+      let cwd = env::temp_dir().canonicalize().unwrap();
+      assert_eq!(
+        exec.cwd,
+        OutputMsg::Ok(cwd.to_string_lossy().as_ref().into())
+      );
+      return;
+    }
+  }
+  panic!("Corresponding exec event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[case(vec![])]
+#[case(vec![TrappedSyscall::Chdir])]
+#[file_serial]
+#[tokio::test]
+async fn tracer_reports_cwd_changed_between_execs(
+  #[case] trap_syscalls: Vec<TrappedSyscall>,
+  true_executable: PathBuf,
+) {
+  let (tracer, rx, req_rx) = tracer(
+    Default::default(),
+    PtraceArgs {
+      trap_syscalls,
+      ..Default::default()
+    },
+    Default::default(),
+  );
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec![
+      "sh".to_string(),
+      "-c".to_string(),
+      format!("cd / && {true_executable}"),
+    ],
+  )
+  .await;
+  let cwds = events
+    .into_iter()
+    .filter_map(|event| match event {
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::Exec(exec),
+        ..
+      }) => Some((exec.filename.clone(), exec.cwd.clone())),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  // The shell starts in the current directory and runs true after changing it
+  assert_eq!(cwds.len(), 2, "execs: {cwds:?}");
+  assert_eq!(cwds[0].1, BaselineInfo::new().unwrap().cwd);
+  assert_eq!(cwds[1].0, OutputMsg::Ok(true_executable.as_str().into()));
+  assert_eq!(cwds[1].1, OutputMsg::Ok("/".into()));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_reports_trapped_chdir(
+  #[with(Default::default(), PtraceArgs {
+    trap_syscalls: vec![TrappedSyscall::Chdir],
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec!["sh".to_string(), "-c".to_string(), "cd /; true".to_string()],
+  )
+  .await;
+  for event in events {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Info(TracerEventMessage { msg, .. }),
+      ..
+    }) = event
+    {
+      if msg.starts_with("Changed the working directory") {
+        assert_eq!(msg, "Changed the working directory to /");
+        return;
+      }
+    }
+  }
+  panic!("Corresponding chdir info event not found")
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_redirects_stdout_of_root_command(
+  #[with(Default::default(), Default::default(), LogModeArgs {
+    stdout: Redirect::File(env::temp_dir().join("tracexec-test-stdout")),
+    ..Default::default()
+  })]
+  tracer: TracerFixture,
+) {
+  let (tracer, rx, req_rx) = tracer;
+  run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec!["sh".to_string(), "-c".to_string(), "echo hello".to_string()],
+  )
+  .await;
+  let path = env::temp_dir().join("tracexec-test-stdout");
+  assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\n");
+  std::fs::remove_file(path).unwrap();
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_runs_multiple_root_commands(true_executable: PathBuf) {
+  let baseline = BaselineInfo::new().unwrap();
+  let (msg_tx, msg_rx) = TracerEventArgs::all().channel(Arc::new(baseline.clone()));
+  let (req_tx, req_rx) = tokio::sync::mpsc::unbounded_channel();
+  let tracer = Arc::new(
+    Tracer::new(
+      TracerMode::Tui(None),
+      LogModeArgs::default(),
+      ModifierArgs::default(),
+      PtraceArgs::default(),
+      TracerEventArgs::all(),
+      baseline,
+      msg_tx,
+      None,
+      req_tx,
+    )
+    .unwrap(),
+  );
+  let true_executable = true_executable.to_string_lossy().to_string();
+  tracer
+    .request_spawn_root(vec![true_executable.clone()])
+    .unwrap();
+  tracer.request_spawn_root(vec![true_executable]).unwrap();
+  tracer.request_shutdown().unwrap();
+  // Start an empty session and let the requests spawn the root tracees
+  let events = run_exe_and_collect_msgs(tracer, msg_rx, req_rx, vec![]).await;
+  let spawns = events
+    .iter()
+    .filter(|e| {
+      matches!(
+        e,
+        TracerMessage::Event(TracerEvent {
+          details: TracerEventDetails::TraceeSpawn { .. },
+          ..
+        })
+      )
+    })
+    .count();
+  let exits = events
+    .iter()
+    .filter(|e| {
+      matches!(
+        e,
+        TracerMessage::Event(TracerEvent {
+          details: TracerEventDetails::TraceeExit { exit_code: 0, .. },
+          ..
+        })
+      )
+    })
+    .count();
+  assert_eq!(spawns, 2);
+  assert_eq!(exits, 2);
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_runs_group_of_commands(tracer: TracerFixture) {
+  let (tracer, mut rx, req_rx) = tracer;
+  let cmds = vec![
+    vec!["sh".to_string(), "-c".to_string(), "exit 1".to_string()],
+    vec!["sh".to_string(), "-c".to_string(), "exit 2".to_string()],
+  ];
+  tracer
+    .spawn_group(cmds, None, req_rx)
+    .await
+    .unwrap()
+    .unwrap();
+  let mut exit_codes = vec![];
+  while let Some(msg) = rx.recv().await {
+    if let TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::TraceeExit { exit_code, .. },
+      ..
+    }) = msg
+    {
+      exit_codes.push(exit_code);
+    }
+  }
+  exit_codes.sort();
+  assert_eq!(exit_codes, vec![1, 2]);
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_denies_exec_at_ask_breakpoint(tracer: TracerFixture, true_executable: PathBuf) {
+  let (tracer, mut rx, req_rx) = tracer;
+  let true_executable = true_executable.to_string_lossy().to_string();
+  tracer.add_breakpoint(BreakPoint {
+    pattern: BreakPointPattern::from_editable(&format!("exact-filename:{true_executable}"))
+      .unwrap(),
+    ty: BreakPointType::Ask,
+    activated: true,
+    stop: BreakPointStop::SyscallEnter,
+  });
+  let tracer_thread = tracer.clone().spawn(
+    vec!["sh".to_string(), "-c".to_string(), true_executable.clone()],
+    None,
+    req_rx,
+  );
+  let filename = OutputMsg::Ok(true_executable.as_str().into());
+  let mut result = None;
+  while let Some(msg) = rx.recv().await {
+    match msg {
+      TracerMessage::StateUpdate(ProcessStateUpdateEvent {
+        update: ProcessStateUpdate::BreakPointHit(hit),
+        ..
+      }) => {
+        tracer.request_exec_deny(hit).unwrap();
+      }
+      TracerMessage::Event(TracerEvent {
+        details: TracerEventDetails::Exec(exec),
+        ..
+      }) if exec.filename == filename => {
+        result = Some(exec.result);
+        break;
+      }
+      _ => (),
+    }
+  }
+  tracer_thread.await.unwrap().unwrap();
+  assert_eq!(result, Some(-(nix::libc::EPERM as i64)));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_denies_exec_by_reloaded_rules(true_executable: PathBuf) {
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let rules = env::temp_dir().join("tracexec-test-rules.toml");
+  std::fs::write(
+    &rules,
+    format!("[[rule]]\naction = \"Log\"\nfilename = \"{true_executable}\"\n"),
+  )
+  .unwrap();
+  let baseline = BaselineInfo::new().unwrap();
+  let (msg_tx, rx) = TracerEventArgs::all().channel(Arc::new(baseline.clone()));
+  let (req_tx, req_rx) = tokio::sync::mpsc::unbounded_channel();
+  let tracer = Arc::new(
+    Tracer::new(
+      TracerMode::Log { foreground: false },
+      LogModeArgs::default(),
+      ModifierArgs::default(),
+      PtraceArgs {
+        rules: Some(rules.clone()),
+        ..Default::default()
+      },
+      TracerEventArgs::all(),
+      baseline,
+      msg_tx,
+      None,
+      req_tx,
+    )
+    .unwrap(),
+  );
+  std::fs::write(
+    &rules,
+    format!("[[rule]]\naction = \"Deny\"\nfilename = \"{true_executable}\"\n"),
+  )
+  .unwrap();
+  assert_eq!(tracer.reload_rules().unwrap(), 1);
+  std::fs::remove_file(&rules).unwrap();
+  let events = run_exe_and_collect_msgs(
+    tracer,
+    rx,
+    req_rx,
+    vec!["sh".to_string(), "-c".to_string(), true_executable.clone()],
+  )
+  .await;
+  let filename = OutputMsg::Ok(true_executable.as_str().into());
+  let result = events.iter().find_map(|msg| match msg {
+    TracerMessage::Event(TracerEvent {
+      details: TracerEventDetails::Exec(exec),
+      ..
+    }) if exec.filename == filename => Some(exec.result),
+    _ => None,
+  });
+  assert_eq!(result, Some(-(nix::libc::EPERM as i64)));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_streams_events_of_spawned_command(true_executable: PathBuf) {
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let mut stream = TracerBuilder::new()
+    .spawn(vec![true_executable.clone()])
+    .unwrap();
+  let mut events = vec![];
+  while let Some(event) = stream.next().await {
+    events.push(event);
+  }
+  stream.wait().await.unwrap();
+  let filename = OutputMsg::Ok(true_executable.as_str().into());
+  assert!(events.iter().any(|e| matches!(
+    &e.details,
+    TracerEventDetails::Exec(exec) if exec.filename == filename
+  )));
+  assert!(events.iter().any(|e| matches!(
+    e.details,
+    TracerEventDetails::TraceeExit { exit_code: 0, .. }
+  )));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_control_pauses_and_resumes_execs(true_executable: PathBuf) {
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let mut stream = TracerBuilder::new()
+    .spawn(vec![
+      "/bin/sh".to_string(),
+      "-c".to_string(),
+      true_executable.clone(),
+    ])
+    .unwrap();
+  let control = stream.control();
+  control.pause().unwrap();
+  let filename = OutputMsg::Ok(true_executable.as_str().into());
+  let is_true_exec = |e: &TracerEvent| {
+    matches!(
+      &e.details,
+      TracerEventDetails::Exec(exec) if exec.filename == filename
+    )
+  };
+  let mut events = vec![];
+  // The exec is stopped on syscall entry and reported on syscall exit
+  while let Ok(Some(event)) =
+    tokio::time::timeout(std::time::Duration::from_millis(500), stream.next()).await
+  {
+    events.push(event);
+  }
+  assert!(!events.iter().any(is_true_exec));
+  control.resume().unwrap();
+  while let Some(event) = stream.next().await {
+    events.push(event);
+  }
+  stream.wait().await.unwrap();
+  assert!(events.iter().any(is_true_exec));
+  assert!(events.iter().any(|e| matches!(
+    e.details,
+    TracerEventDetails::TraceeExit { exit_code: 0, .. }
+  )));
+}
+
+#[traced_test]
+#[rstest]
+#[file_serial]
+#[tokio::test]
+async fn tracer_control_injects_commands_and_kills_tracees(true_executable: PathBuf) {
+  let true_executable = true_executable.to_string_lossy().to_string();
+  let mut stream = TracerBuilder::new()
+    .spawn(vec!["/bin/sleep".to_string(), "10".to_string()])
+    .unwrap();
+  let control = stream.control();
+  control.inject(vec![true_executable.clone()]).unwrap();
+  let filename = OutputMsg::Ok(true_executable.as_str().into());
+  let mut killed = false;
+  let mut events = vec![];
+  while let Some(event) = stream.next().await {
+    if !killed
+      && matches!(
+        &event.details,
+        TracerEventDetails::Exec(exec) if exec.filename == filename
+      )
+    {
+      control.kill().unwrap();
+      killed = true;
+    }
+    events.push(event);
+  }
+  stream.wait().await.unwrap();
+  assert!(killed);
+  assert!(events.iter().any(|e| matches!(
+    e.details,
+    TracerEventDetails::TraceeExit {
+      signal: Some(Signal::SIGKILL),
+      ..
+    }
+  )));
+  assert!(control.kill().is_err());
+}
@@ -29,37 +29,47 @@ use crossterm::{
 };
 use futures::{FutureExt, StreamExt};
 use ratatui::{backend::CrosstermBackend as Backend, layout::Size};
-use tokio::{
-  sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
-  task::JoinHandle,
-};
+use tokio::{sync::mpsc, task::JoinHandle};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, trace};
 
-use crate::event::{Event, TracerMessage};
+use crate::{channel::MessageReceiver, event::Event};
 
 pub mod app;
 mod breakpoint_manager;
+mod build_progress;
+mod command_prompt;
+pub mod confirm_popup;
 pub mod copy_popup;
 pub mod details_popup;
 pub mod error_popup;
 pub mod event_line;
 mod event_list;
+pub mod export_prompt;
 pub mod help;
 mod hit_manager;
+pub mod keybindings_popup;
+pub mod keymap;
 mod partial_line;
+mod pause;
 mod pseudo_term;
 pub mod query;
+pub mod session;
+pub mod signal_popup;
 mod sized_paragraph;
+mod statistics;
 pub mod theme;
 mod ui;
 
+/// The number of events queued for the app
+const EVENT_QUEUE_SIZE: usize = 1024;
+
 pub struct Tui {
   pub terminal: ratatui::Terminal<Backend<std::io::Stderr>>,
   pub task: JoinHandle<()>,
   pub cancellation_token: CancellationToken,
-  pub event_rx: UnboundedReceiver<Event>,
-  pub event_tx: UnboundedSender<Event>,
+  pub event_rx: mpsc::Receiver<Event>,
+  pub event_tx: mpsc::Sender<Event>,
   pub frame_rate: f64,
 }
 
@@ -79,7 +89,9 @@ impl Tui {
   pub fn new() -> Result<Self> {
     let frame_rate = 30.0;
     let terminal = ratatui::Terminal::new(Backend::new(std::io::stderr()))?;
-    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    // Bounded so that the tracer messages queue up in the tracer channel and spill
+    // when the TUI falls behind.
+    let (event_tx, event_rx) = mpsc::channel(EVENT_QUEUE_SIZE);
     let cancellation_token = CancellationToken::new();
     let task = tokio::spawn(async {});
     Ok(Self {
@@ -97,7 +109,7 @@ impl Tui {
     self
   }
 
-  pub fn start(&mut self, mut tracer_rx: UnboundedReceiver<TracerMessage>) {
+  pub fn start(&mut self, mut tracer_rx: MessageReceiver) {
     let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
     self.cancel();
     self.cancellation_token = CancellationToken::new();
@@ -106,7 +118,7 @@ impl Tui {
     self.task = tokio::spawn(async move {
       let mut reader = crossterm::event::EventStream::new();
       let mut render_interval = tokio::time::interval(render_delay);
-      event_tx.send(Event::Init).unwrap();
+      event_tx.send(Event::Init).await.unwrap();
       loop {
         let render_delay = render_interval.tick();
         let crossterm_event = reader.next().fuse();
@@ -117,7 +129,7 @@ impl Tui {
           }
           Some(tracer_event) = tracer_event => {
             trace!("TUI event: tracer message!");
-            event_tx.send(Event::Tracer(tracer_event)).unwrap();
+            event_tx.send(Event::Tracer(tracer_event)).await.unwrap();
           }
           Some(event) = crossterm_event => {
             #[cfg(debug_assertions)]
@@ -127,26 +139,26 @@ impl Tui {
                 match evt {
                   CrosstermEvent::Key(key) => {
                       if key.kind == KeyEventKind::Press {
-                          event_tx.send(Event::Key(key)).unwrap();
+                          event_tx.send(Event::Key(key)).await.unwrap();
                       }
                   },
                   CrosstermEvent::Resize(cols, rows) => {
                       event_tx.send(Event::Resize(Size {
                           width: cols,
                           height: rows,
-                      })).unwrap();
+                      })).await.unwrap();
                   },
                   _ => {},
                 }
               }
               Err(_) => {
-                event_tx.send(Event::Error).unwrap();
+                event_tx.send(Event::Error).await.unwrap();
               }
             }
           },
           _ = render_delay => {
             // log::trace!("TUI event: Render!");
-            event_tx.send(Event::Render).unwrap();
+            event_tx.send(Event::Render).await.unwrap();
           },
         }
       }
@@ -170,7 +182,7 @@ impl Tui {
     Ok(())
   }
 
-  pub fn enter(&mut self, tracer_rx: UnboundedReceiver<TracerMessage>) -> Result<()> {
+  pub fn enter(&mut self, tracer_rx: MessageReceiver) -> Result<()> {
     init_tui()?;
     self.start(tracer_rx);
     Ok(())
@@ -16,52 +16,85 @@
 // OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use std::{ops::ControlFlow, sync::Arc};
+use std::{
+  collections::HashMap,
+  fs::{File, Permissions},
+  io::{BufWriter, Write},
+  ops::ControlFlow,
+  os::unix::fs::PermissionsExt,
+  path::Path,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+  },
+};
 
 use arboard::Clipboard;
 use clap::ValueEnum;
+use color_eyre::eyre::Context;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use itertools::chain;
-use nix::{errno::Errno, sys::signal::Signal, unistd::Pid};
+use itertools::{chain, Itertools};
+use nix::{
+  errno::Errno,
+  sys::signal::{killpg, Signal},
+  unistd::Pid,
+};
 use ratatui::{
   buffer::Buffer,
   layout::{Constraint, Layout, Position, Rect},
   style::Stylize,
   text::{Line, Span},
-  widgets::{Block, Paragraph, StatefulWidget, StatefulWidgetRef, Widget, Wrap},
+  widgets::{Block, LineGauge, Paragraph, StatefulWidget, StatefulWidgetRef, Widget, Wrap},
 };
 use serde::{Deserialize, Serialize};
 use strum::Display;
 use tokio::sync::mpsc;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 use tui_popup::Popup;
 
 use crate::{
-  action::{Action, ActivePopup},
+  action::{Action, ActivePopup, CopyTarget},
+  channel::OverflowStats,
   cli::{
     args::{DebuggerArgs, LogModeArgs, ModifierArgs, TuiModeArgs},
     config::ExitHandling,
-    options::ActivePane,
+    options::{ActivePane, PauseMode},
   },
-  event::{Event, ProcessStateUpdate, ProcessStateUpdateEvent, TracerEventDetails, TracerMessage},
+  event::{
+    Event, ProcessStateUpdate, ProcessStateUpdateEvent, TracerEvent, TracerEventDetails,
+    TracerMessage,
+  },
+  export::{JsonMetaData, RecordEntry, TraceNotes},
+  hook::ExecHook,
   printer::PrinterArgs,
-  proc::BaselineInfo,
+  proc::{BaselineInfo, ProcessHandle},
   pty::{PtySize, UnixMasterPty},
-  tracer::Tracer,
+  script::ShellScriptBuilder,
+  serialize_json_to_output,
+  tracer::{state::BreakPointStop, BreakPointHit, Tracer},
   tui::{error_popup::InfoPopupState, query::QueryKind},
 };
 
 use super::{
   breakpoint_manager::{BreakPointManager, BreakPointManagerState},
+  command_prompt::CommandPrompt,
+  confirm_popup::{ConfirmPopup, ConfirmPopupState},
   copy_popup::{CopyPopup, CopyPopupState},
   details_popup::{DetailsPopup, DetailsPopupState},
   error_popup::InfoPopup,
-  event_list::EventList,
+  event_list::{EventList, ResultFilter},
+  export_prompt::{ExportFormat, ExportPrompt, ExportScope},
   help::{fancy_help_desc, help, help_item, help_key},
   hit_manager::{HitManager, HitManagerState},
-  pseudo_term::PseudoTerminalPane,
+  keybindings_popup::{KeyBindingsPopup, KeyBindingsPopupState},
+  keymap::{self, KeyCommand},
+  pause::{HeldMessage, Pause},
+  pseudo_term::{PseudoTerminalPane, TerminalActivity, DEFAULT_SCROLLBACK},
   query::QueryBuilder,
+  session::SessionWriter,
+  signal_popup::{SignalPopup, SignalPopupState},
+  statistics::Statistics,
   theme::THEME,
   ui::render_title,
   Tui,
@@ -81,19 +114,41 @@ pub struct App {
   pub event_list: EventList,
   pub printer_args: PrinterArgs,
   pub term: Option<PseudoTerminalPane>,
-  pub root_pid: Option<Pid>,
+  pub root_processes: Vec<Arc<ProcessHandle>>,
+  /// Handles of the processes that have exec events and have not exited
+  live_processes: HashMap<Pid, Arc<ProcessHandle>>,
   pub active_pane: ActivePane,
   pub clipboard: Option<Clipboard>,
   pub split_percentage: u16,
+  /// Show only the active pane
+  pub zoomed: bool,
   pub layout: AppLayout,
   pub should_handle_internal_resize: bool,
   pub popup: Option<ActivePopup>,
   pub active_experiments: Vec<&'static str>,
+  /// Notes of the loaded trace
+  pub trace_notes: TraceNotes,
+  /// Counters of the tracer messages that overflowed the in-memory queue
+  pub overflow_stats: Option<Arc<OverflowStats>>,
+  /// Counter of the events that the eBPF program failed to send through the ring buffer
+  pub lost_events: Option<Arc<AtomicU64>>,
+  /// The command to run for every exec event
+  pub exec_hook: Option<ExecHook>,
+  /// Where the session is saved
+  pub session: Option<SessionWriter>,
   tracer: Option<Arc<Tracer>>,
   query_builder: Option<QueryBuilder>,
   breakpoint_manager: Option<BreakPointManagerState>,
   hit_manager_state: Option<HitManagerState>,
+  command_prompt: Option<CommandPrompt>,
+  export_prompt: Option<ExportPrompt>,
+  last_copy_target: Option<CopyTarget>,
   exit_handling: ExitHandling,
+  statistics: Statistics,
+  auto_switch_pane: bool,
+  /// The activity of the terminal when it was last checked
+  terminal_activity: TerminalActivity,
+  pause: Pause,
 }
 
 pub struct PTracer {
@@ -120,11 +175,20 @@ impl App {
       for bp in tracer.debugger_args.breakpoints.drain(..) {
         tracer.tracer.add_breakpoint(bp);
       }
+      for bp in tracer.debugger_args.ask.drain(..) {
+        tracer.tracer.add_breakpoint(bp);
+      }
     }
     Ok(Self {
-      event_list: EventList::new(baseline, tui_args.follow, modifier_args.to_owned()),
+      event_list: EventList::new(
+        baseline,
+        tui_args.follow,
+        modifier_args.to_owned(),
+        tui_args.color_by.unwrap_or_default(),
+      ),
       printer_args: PrinterArgs::from_cli(tracing_args, modifier_args),
       split_percentage: if pty_master.is_some() { 50 } else { 100 },
+      zoomed: false,
       term: if let Some(pty_master) = pty_master {
         let mut term = PseudoTerminalPane::new(
           PtySize {
@@ -134,6 +198,7 @@ impl App {
             pixel_height: 0,
           },
           pty_master,
+          tui_args.scrollback.unwrap_or(DEFAULT_SCROLLBACK),
         )?;
         if active_pane == ActivePane::Terminal {
           term.focus(true);
@@ -142,28 +207,33 @@ impl App {
       } else {
         None
       },
-      root_pid: None,
+      root_processes: vec![],
+      live_processes: HashMap::new(),
       active_pane,
       clipboard: Clipboard::new().ok(),
       layout: tui_args.layout.unwrap_or_default(),
       should_handle_internal_resize: true,
       popup: None,
+      statistics: Statistics::default(),
       query_builder: None,
       breakpoint_manager: None,
+      command_prompt: None,
+      export_prompt: None,
+      last_copy_target: None,
       active_experiments: vec![],
+      trace_notes: TraceNotes::default(),
+      overflow_stats: None,
+      lost_events: None,
+      exec_hook: None,
+      session: None,
+      auto_switch_pane: tui_args.auto_switch_pane,
+      terminal_activity: TerminalActivity::Busy,
       tracer: tracer.as_ref().map(|t| t.tracer.clone()),
       hit_manager_state: tracer
         .map(|t| HitManagerState::new(t.tracer, t.debugger_args.default_external_command))
         .transpose()?,
-      exit_handling: {
-        if tui_args.kill_on_exit {
-          ExitHandling::Kill
-        } else if tui_args.terminate_on_exit {
-          ExitHandling::Terminate
-        } else {
-          ExitHandling::Wait
-        }
-      },
+      exit_handling: tui_args.exit_handling(),
+      pause: Pause::new(tui_args.pause_mode.unwrap_or_default()),
     })
   }
 
@@ -183,6 +253,33 @@ impl App {
     }
   }
 
+  /// Whether to switch the active pane because the tracee started or stopped waiting for input.
+  /// Only changes of the terminal activity are followed, so that switching manually still works.
+  fn should_switch_pane(&mut self) -> bool {
+    let Some(term) = self.term.as_ref() else {
+      return false;
+    };
+    let activity = term.activity();
+    if std::mem::replace(&mut self.terminal_activity, activity) == activity {
+      return false;
+    }
+    // Do not take the focus away from an unfinished interaction with the events pane
+    if self.popup.is_some()
+      || self.breakpoint_manager.is_some()
+      || self.command_prompt.is_some()
+      || self.export_prompt.is_some()
+      || self.query_builder.as_ref().is_some_and(|b| b.editing())
+      || self.hit_manager_state.as_ref().is_some_and(|h| h.visible)
+    {
+      return false;
+    }
+    matches!(
+      (activity, self.active_pane),
+      (TerminalActivity::WaitingForInput, ActivePane::Events)
+        | (TerminalActivity::Quiet, ActivePane::Terminal)
+    )
+  }
+
   pub async fn run(&mut self, tui: &mut Tui) -> color_eyre::Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
 
@@ -197,7 +294,8 @@ impl App {
             action_tx.send(Action::Quit)?;
           }
           Event::Key(ke) => {
-            if ke.code == KeyCode::Char('s') && ke.modifiers.contains(KeyModifiers::CONTROL) {
+            let global_command = keymap::GLOBAL.lookup(&ke);
+            if global_command == Some(KeyCommand::SwitchPane) {
               action_tx.send(Action::SwitchActivePane)?;
               // Cancel all popups
               self.popup = None;
@@ -210,6 +308,9 @@ impl App {
               if self.breakpoint_manager.is_some() {
                 self.breakpoint_manager = None;
               }
+              // Cancel command prompt
+              self.command_prompt = None;
+              self.export_prompt = None;
               // Cancel hit manager
               if let Some(h) = self.hit_manager_state.as_mut() {
                 if h.visible {
@@ -217,6 +318,8 @@ impl App {
                 }
               }
               // action_tx.send(Action::Render)?;
+            } else if global_command == Some(KeyCommand::ToggleZoom) && self.term.is_some() {
+              action_tx.send(Action::ToggleZoom)?;
             } else {
               trace!("TUI: Active pane: {}", self.active_pane);
               if self.active_pane == ActivePane::Events {
@@ -224,7 +327,7 @@ impl App {
                 // TODO: do this in a separate function
                 if let Some(popup) = &mut self.popup {
                   match popup {
-                    ActivePopup::Help => {
+                    ActivePopup::Help | ActivePopup::Statistics => {
                       self.popup = None;
                     }
                     ActivePopup::ViewDetails(state) => {
@@ -244,6 +347,21 @@ impl App {
                         action_tx.send(action)?;
                       }
                     }
+                    ActivePopup::Confirm(state) => {
+                      for action in state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::SignalSelection(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::KeyBindings(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
                   }
                   continue;
                 }
@@ -266,6 +384,22 @@ impl App {
                   continue;
                 }
 
+                // Handle command prompt
+                if let Some(command_prompt) = self.command_prompt.as_mut() {
+                  if let Some(action) = command_prompt.handle_key_event(ke) {
+                    action_tx.send(action)?;
+                  }
+                  continue;
+                }
+
+                // Handle export prompt
+                if let Some(export_prompt) = self.export_prompt.as_mut() {
+                  if let Some(action) = export_prompt.handle_key_event(ke) {
+                    action_tx.send(action)?;
+                  }
+                  continue;
+                }
+
                 // Handle query builder
                 if let Some(query_builder) = self.query_builder.as_mut() {
                   if query_builder.editing() {
@@ -283,148 +417,246 @@ impl App {
                     }
                     continue;
                   } else {
-                    match (ke.code, ke.modifiers) {
-                      (KeyCode::Char('n'), KeyModifiers::NONE) => {
+                    match keymap::SEARCH.lookup(&ke) {
+                      Some(KeyCommand::NextMatch) => {
                         trace!("Query: Next match");
                         action_tx.send(Action::NextMatch)?;
                         continue;
                       }
-                      (KeyCode::Char('p'), KeyModifiers::NONE) => {
+                      Some(KeyCommand::PrevMatch) => {
                         trace!("Query: Prev match");
                         action_tx.send(Action::PrevMatch)?;
                         continue;
                       }
+                      Some(KeyCommand::ClearSearch) => {
+                        action_tx.send(Action::EndSearch)?;
+                        continue;
+                      }
                       _ => {}
                     }
                   }
                 }
 
-                match ke.code {
-                  KeyCode::Char('q') if ke.modifiers == KeyModifiers::NONE => {
+                match keymap::EVENT_LIST
+                  .lookup(&ke)
+                  .or_else(|| keymap::SEARCH.lookup(&ke))
+                {
+                  Some(KeyCommand::Quit) => {
                     if self.popup.is_some() {
                       self.popup = None;
                     } else {
                       action_tx.send(Action::Quit)?;
                     }
                   }
-                  KeyCode::Down | KeyCode::Char('j') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageDown)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::NextItem)?;
-                    }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Up | KeyCode::Char('k') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::PageUp)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::PrevItem)?;
-                    }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Left | KeyCode::Char('h') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageLeft)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollLeft)?;
-                    }
-                    // action_tx.send(Action::Render)?;
+                  Some(KeyCommand::NextItem) => {
+                    action_tx.send(Action::NextItem)?;
                   }
-                  KeyCode::Right | KeyCode::Char('l') if ke.modifiers != KeyModifiers::ALT => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageRight)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollRight)?;
-                    }
-                    // action_tx.send(Action::Render)?;
+                  Some(KeyCommand::PrevItem) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::PrevItem)?;
                   }
-                  KeyCode::PageDown if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::PageDown) => {
                     action_tx.send(Action::PageDown)?;
-                    // action_tx.send(Action::Render)?;
                   }
-                  KeyCode::PageUp if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::PageUp) => {
                     action_tx.send(Action::StopFollow)?;
                     action_tx.send(Action::PageUp)?;
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Home => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::ScrollToTop)?;
-                    } else if ke.modifiers == KeyModifiers::SHIFT {
-                      action_tx.send(Action::ScrollToStart)?;
-                    }
-                    // action_tx.send(Action::Render)?;
                   }
-                  KeyCode::End => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollToBottom)?;
-                    } else if ke.modifiers == KeyModifiers::SHIFT {
-                      action_tx.send(Action::ScrollToEnd)?;
-                    }
-                    // action_tx.send(Action::Render)?;
+                  Some(KeyCommand::ScrollLeft) => {
+                    action_tx.send(Action::ScrollLeft)?;
                   }
-                  KeyCode::Char('g') if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::ScrollRight) => {
+                    action_tx.send(Action::ScrollRight)?;
+                  }
+                  Some(KeyCommand::PageLeft) => {
+                    action_tx.send(Action::PageLeft)?;
+                  }
+                  Some(KeyCommand::PageRight) => {
+                    action_tx.send(Action::PageRight)?;
+                  }
+                  Some(KeyCommand::ScrollToTop) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ScrollToTop)?;
+                  }
+                  Some(KeyCommand::ScrollToBottom) => {
+                    action_tx.send(Action::ScrollToBottom)?;
+                  }
+                  Some(KeyCommand::ScrollToStart) => {
+                    action_tx.send(Action::ScrollToStart)?;
+                  }
+                  Some(KeyCommand::ScrollToEnd) => {
+                    action_tx.send(Action::ScrollToEnd)?;
+                  }
+                  Some(KeyCommand::GrowPane) => {
                     action_tx.send(Action::GrowPane)?;
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Char('s') => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ShrinkPane)?;
-                    } else if ke.modifiers == KeyModifiers::ALT {
-                      action_tx.send(Action::HandleTerminalKeyPress(KeyEvent::new(
-                        KeyCode::Char('s'),
-                        KeyModifiers::CONTROL,
-                      )))?;
-                    }
-                    // action_tx.send(Action::Render)?;
                   }
-                  KeyCode::Char('c')
-                    if ke.modifiers == KeyModifiers::NONE && self.clipboard.is_some() =>
-                  {
+                  Some(KeyCommand::ShrinkPane) => {
+                    action_tx.send(Action::ShrinkPane)?;
+                  }
+                  Some(KeyCommand::SendCtrlS) => {
+                    action_tx.send(Action::HandleTerminalKeyPress(KeyEvent::new(
+                      KeyCode::Char('s'),
+                      KeyModifiers::CONTROL,
+                    )))?;
+                  }
+                  Some(KeyCommand::Copy) if self.clipboard.is_some() => {
                     if let Some(selected) = self.event_list.selection() {
                       action_tx.send(Action::ShowCopyDialog(selected.details.clone()))?;
                     }
                   }
-                  KeyCode::Char('l') if ke.modifiers == KeyModifiers::ALT => {
+                  Some(KeyCommand::CopyAgain) if self.clipboard.is_some() => {
+                    if let Some(selected) = self.event_list.selection() {
+                      let event = selected.details.clone();
+                      // Other targets are only available for exec events
+                      match self.last_copy_target {
+                        Some(target)
+                          if target == CopyTarget::Line
+                            || matches!(event.as_ref(), TracerEventDetails::Exec(_)) =>
+                        {
+                          action_tx.send(Action::CopyToClipboard { event, target })?;
+                        }
+                        _ => action_tx.send(Action::ShowCopyDialog(event))?,
+                      }
+                    }
+                  }
+                  Some(KeyCommand::SwitchLayout) => {
                     action_tx.send(Action::SwitchLayout)?;
                   }
-                  KeyCode::Char('f') => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ToggleFollow)?;
-                    } else if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::BeginSearch)?;
-                    }
+                  Some(KeyCommand::Export) => {
+                    action_tx.send(Action::ShowExportPrompt)?;
                   }
-                  KeyCode::Char('e') if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::ToggleWrap) => {
+                    action_tx.send(Action::ToggleWrap)?;
+                  }
+                  Some(KeyCommand::ToggleBookmark) => {
+                    action_tx.send(Action::ToggleBookmark)?;
+                  }
+                  Some(KeyCommand::NextBookmark) => {
+                    action_tx.send(Action::NextBookmark)?;
+                  }
+                  Some(KeyCommand::PrevBookmark) => {
+                    action_tx.send(Action::PrevBookmark)?;
+                  }
+                  Some(KeyCommand::ToggleFollow) => {
+                    action_tx.send(Action::ToggleFollow)?;
+                  }
+                  Some(KeyCommand::Search) => {
+                    action_tx.send(Action::BeginSearch)?;
+                  }
+                  Some(KeyCommand::ToggleEnvDisplay) => {
                     action_tx.send(Action::ToggleEnvDisplay)?;
                   }
-                  KeyCode::Char('w') if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::ToggleCwdDisplay) => {
                     action_tx.send(Action::ToggleCwdDisplay)?;
                   }
-                  KeyCode::F(1) if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::ToggleArgvDisplay) => {
+                    action_tx.send(Action::ToggleArgvDisplay)?;
+                  }
+                  Some(KeyCommand::ToggleFilenameDisplay) => {
+                    action_tx.send(Action::ToggleFilenameDisplay)?;
+                  }
+                  Some(KeyCommand::ToggleCommDisplay) => {
+                    action_tx.send(Action::ToggleCommDisplay)?;
+                  }
+                  Some(KeyCommand::Help) => {
                     action_tx.send(Action::SetActivePopup(ActivePopup::Help))?;
                   }
-                  KeyCode::Char('v') if ke.modifiers == KeyModifiers::NONE => {
+                  Some(KeyCommand::KeyBindings) => {
+                    action_tx.send(Action::SetActivePopup(ActivePopup::KeyBindings(
+                      KeyBindingsPopupState::default(),
+                    )))?;
+                  }
+                  Some(KeyCommand::JumpToParent) => {
+                    action_tx.send(Action::JumpToParent)?;
+                  }
+                  Some(KeyCommand::JumpBack) => {
+                    action_tx.send(Action::JumpBack)?;
+                  }
+                  Some(KeyCommand::ToggleGrouping) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ToggleGrouping)?;
+                  }
+                  Some(KeyCommand::ToggleGroupCollapse) if self.event_list.is_grouped() => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ToggleGroupCollapse)?;
+                  }
+                  Some(KeyCommand::TogglePause) => {
+                    action_tx.send(Action::TogglePause)?;
+                  }
+                  Some(KeyCommand::CycleResultFilter) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::CycleResultFilter)?;
+                  }
+                  Some(KeyCommand::ToggleDedup) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ToggleDedup)?;
+                  }
+                  Some(KeyCommand::ToggleTable) => {
+                    action_tx.send(Action::ToggleTable)?;
+                  }
+                  Some(KeyCommand::CycleColorBy) => {
+                    action_tx.send(Action::CycleColorBy)?;
+                  }
+                  Some(KeyCommand::ToggleScope) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ToggleScope)?;
+                  }
+                  Some(KeyCommand::CycleSortColumn) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::CycleSortColumn)?;
+                  }
+                  Some(KeyCommand::ReverseSortOrder) => {
+                    action_tx.send(Action::StopFollow)?;
+                    action_tx.send(Action::ReverseSortOrder)?;
+                  }
+                  Some(KeyCommand::Statistics) => {
+                    action_tx.send(Action::SetActivePopup(ActivePopup::Statistics))?;
+                  }
+                  Some(KeyCommand::ViewDetails) => {
                     if let Some(selected) = self.event_list.selection() {
                       action_tx.send(Action::SetActivePopup(ActivePopup::ViewDetails(
-                        DetailsPopupState::new(selected, self.event_list.baseline.clone()),
+                        DetailsPopupState::new(
+                          selected,
+                          self.event_list.baseline.clone(),
+                          self.printer_args.escape_style,
+                          self.printer_args.env_filter.clone(),
+                        ),
                       )))?;
                     }
                   }
-                  KeyCode::Char('b')
-                    if ke.modifiers == KeyModifiers::NONE && self.tracer.is_some() =>
-                  {
+                  Some(KeyCommand::Breakpoints) if self.tracer.is_some() => {
                     action_tx.send(Action::ShowBreakpointManager)?;
                   }
-                  KeyCode::Char('z')
-                    if ke.modifiers == KeyModifiers::NONE && self.tracer.is_some() =>
-                  {
+                  Some(KeyCommand::Hits) if self.tracer.is_some() => {
                     action_tx.send(Action::ShowHitManager)?;
                   }
+                  Some(KeyCommand::RunCommand) if self.tracer.is_some() => {
+                    action_tx.send(Action::ShowCommandPrompt)?;
+                  }
+                  Some(KeyCommand::TerminateRoot) if self.tracer.is_some() => {
+                    action_tx.send(Action::SetActivePopup(ActivePopup::Confirm(
+                      Self::signal_confirmation(Signal::SIGTERM),
+                    )))?;
+                  }
+                  Some(KeyCommand::KillRoot) if self.tracer.is_some() => {
+                    action_tx.send(Action::SetActivePopup(ActivePopup::Confirm(
+                      Self::signal_confirmation(Signal::SIGKILL),
+                    )))?;
+                  }
+                  Some(KeyCommand::SendSignal) if self.tracer.is_some() => {
+                    if let Some(pid) = self.event_list.selection().and_then(|e| e.details.pid()) {
+                      if self.live_processes.contains_key(&pid) {
+                        action_tx.send(Action::SetActivePopup(ActivePopup::SignalSelection(
+                          SignalPopupState::new(pid),
+                        )))?;
+                      } else {
+                        action_tx.send(Action::show_error_popup(
+                          "Process Not Running".to_owned(),
+                          format!("Process {pid} has exited or has not executed any program."),
+                        ))?;
+                      }
+                    }
+                  }
                   _ => {}
                 }
               } else {
@@ -434,17 +666,39 @@ impl App {
             }
           }
           Event::Tracer(msg) => {
+            if let Some(session) = self.session.as_mut() {
+              if let Err(e) = session.write(&msg) {
+                warn!("Failed to save the session, stop saving: {e}");
+                self.session = None;
+              }
+            }
             match msg {
               TracerMessage::Event(e) => {
-                if let TracerEventDetails::TraceeSpawn(pid) = &e.details {
+                if let TracerEventDetails::TraceeSpawn { pid, process } = &e.details {
                   // FIXME: we should not rely on TracerMessage, which might be filtered.
                   debug!("Received tracee spawn event: {pid}");
-                  self.root_pid = Some(*pid);
+                  self.root_processes.push(
+                    process
+                      .clone()
+                      .unwrap_or_else(|| Arc::new(ProcessHandle::new(*pid))),
+                  );
+                }
+                // The exit of processes with exec events is reported in state updates
+                if let TracerEventDetails::Exec(exec) = &e.details {
+                  // The handle from the tracer is opened before the process can be reaped.
+                  // The handle opened here is only a fallback for the eBPF tracer.
+                  self.live_processes.entry(exec.pid).or_insert_with(|| {
+                    exec
+                      .process
+                      .clone()
+                      .unwrap_or_else(|| Arc::new(ProcessHandle::new(exec.pid)))
+                  });
+                  if let Some(hook) = &self.exec_hook {
+                    hook.run(e.id, exec);
+                  }
                 }
-                debug_assert_eq!(e.id, self.event_list.len() as u64);
-                self.event_list.push(e.details);
-                if self.event_list.is_following() {
-                  action_tx.send(Action::ScrollToBottom)?;
+                if let Some(e) = self.pause.hold_event(e) {
+                  self.push_event(e, &action_tx)?;
                 }
               }
               TracerMessage::StateUpdate(update) => {
@@ -455,9 +709,16 @@ impl App {
                     update: ProcessStateUpdate::BreakPointHit(hit),
                     ..
                   } => {
-                    self
-                      .hit_manager_state
-                      .access_some_mut(|h| _ = h.add_hit(*hit));
+                    let hid = self.hit_manager_state.as_mut().map(|h| h.add_hit(*hit));
+                    if let (Some(hid), Some(tracer)) = (hid, self.tracer.as_ref()) {
+                      if hit.stop == BreakPointStop::SyscallEnter
+                        && tracer.is_ask_breakpoint(hit.bid)
+                      {
+                        action_tx.send(Action::SetActivePopup(ActivePopup::Confirm(
+                          Self::exec_approval(tracer, hid, hit),
+                        )))?;
+                      }
+                    }
                     // Warn: This grants CAP_SYS_ADMIN to not only the tracer but also the tracees
                     // sudo -E env RUST_LOG=debug setpriv --reuid=$(id -u) --regid=$(id -g) --init-groups --inh-caps=+sys_admin --ambient-caps +sys_admin -- target/debug/tracexec tui -t --
                   }
@@ -536,8 +797,13 @@ impl App {
                   }
                   _ => (),
                 }
+                if let ProcessStateUpdate::Exit(_) = update.update {
+                  self.live_processes.remove(&update.pid);
+                }
                 if !handled {
-                  self.event_list.update(update);
+                  if let Some(update) = self.pause.hold_update(update) {
+                    self.apply_update(update);
+                  }
                 }
               }
               TracerMessage::FatalError(e) => {
@@ -555,6 +821,9 @@ impl App {
             // action_tx.send(Action::Render)?;
           }
           Event::Render => {
+            if self.auto_switch_pane && self.should_switch_pane() {
+              action_tx.send(Action::SwitchActivePane)?;
+            }
             action_tx.send(Action::Render)?;
           }
           Event::Resize(size) => {
@@ -600,6 +869,17 @@ impl App {
               if let Some((x, y)) = self.hit_manager_state.as_ref().and_then(|x| x.cursor()) {
                 f.set_cursor_position(Position::new(x, y));
               }
+              if let Some((x, y)) = self.command_prompt.as_ref().map(|x| x.cursor()) {
+                f.set_cursor_position(Position::new(x, y));
+              }
+              if let Some((x, y)) = self.export_prompt.as_ref().map(|x| x.cursor()) {
+                f.set_cursor_position(Position::new(x, y));
+              }
+              if let Some(ActivePopup::ViewDetails(state)) = self.popup.as_ref() {
+                if let Some((x, y)) = state.cursor() {
+                  f.set_cursor_position(Position::new(x, y));
+                }
+              }
             })?;
           }
           Action::NextItem => {
@@ -622,7 +902,19 @@ impl App {
           }
           Action::HandleTerminalKeyPress(ke) => {
             if let Some(term) = self.term.as_mut() {
-              term.handle_key_event(&ke).await;
+              if term.is_selecting() {
+                if let Some(text) = term.handle_selection_key_event(&ke) {
+                  if let Some(clipboard) = self.clipboard.as_mut() {
+                    clipboard.set_text(text)?;
+                  }
+                }
+              } else if keymap::TERMINAL.lookup(&ke) == Some(KeyCommand::StartSelection)
+                && self.clipboard.is_some()
+              {
+                term.start_selection();
+              } else {
+                term.handle_key_event(&ke).await;
+              }
             }
           }
           Action::Resize(_size) => {
@@ -658,9 +950,75 @@ impl App {
           Action::ToggleCwdDisplay => {
             self.event_list.toggle_cwd_display();
           }
+          Action::ToggleArgvDisplay => {
+            self.event_list.toggle_argv_display();
+          }
+          Action::ToggleFilenameDisplay => {
+            self.event_list.toggle_filename_display();
+          }
+          Action::ToggleCommDisplay => {
+            self.event_list.toggle_comm_display();
+          }
           Action::StopFollow => {
             self.event_list.stop_follow();
           }
+          Action::JumpToParent => {
+            self.event_list.jump_to_parent();
+          }
+          Action::JumpBack => {
+            self.event_list.jump_back();
+          }
+          Action::ToggleGrouping => {
+            self.event_list.toggle_grouping();
+          }
+          Action::ToggleGroupCollapse => {
+            self.event_list.toggle_group_collapse();
+          }
+          Action::TogglePause => {
+            if self.pause.is_paused() {
+              for msg in self.pause.resume() {
+                match msg {
+                  HeldMessage::Event(e) => self.push_event(e, &action_tx)?,
+                  HeldMessage::StateUpdate(update) => self.apply_update(update),
+                }
+              }
+            } else {
+              self.pause.pause();
+            }
+          }
+          Action::CycleResultFilter => {
+            self.event_list.cycle_result_filter();
+          }
+          Action::ToggleDedup => {
+            self.event_list.toggle_dedup();
+          }
+          Action::ToggleTable => {
+            self.event_list.toggle_table();
+          }
+          Action::CycleColorBy => {
+            self.event_list.cycle_color_by();
+          }
+          Action::ToggleScope => {
+            self.event_list.toggle_scope();
+          }
+          Action::CycleSortColumn => {
+            self.event_list.cycle_sort_column();
+          }
+          Action::ReverseSortOrder => {
+            self.event_list.reverse_sort_order();
+          }
+          Action::ToggleWrap => {
+            self.event_list.toggle_wrap();
+          }
+          Action::ToggleBookmark => {
+            self.event_list.toggle_bookmark();
+          }
+          Action::NextBookmark => {
+            self.event_list.next_bookmark();
+          }
+          Action::PrevBookmark => {
+            self.event_list.prev_bookmark();
+          }
           Action::ShrinkPane => {
             self.shrink_pane();
             self.should_handle_internal_resize = true;
@@ -676,7 +1034,13 @@ impl App {
             };
             self.should_handle_internal_resize = true;
           }
+          Action::ToggleZoom => {
+            self.zoomed = !self.zoomed;
+            self.should_handle_internal_resize = true;
+          }
           Action::SwitchActivePane => {
+            // The other pane is zoomed after switching
+            self.should_handle_internal_resize |= self.zoomed;
             self.active_pane = match self.active_pane {
               ActivePane::Events => {
                 if let Some(term) = self.term.as_mut() {
@@ -698,9 +1062,13 @@ impl App {
             }
           }
           Action::ShowCopyDialog(e) => {
-            self.popup = Some(ActivePopup::CopyTargetSelection(CopyPopupState::new(e)));
+            self.popup = Some(ActivePopup::CopyTargetSelection(CopyPopupState::new(
+              e,
+              self.last_copy_target,
+            )));
           }
           Action::CopyToClipboard { event, target } => {
+            self.last_copy_target = Some(target);
             let text = event.text_for_copy(
               &self.event_list.baseline,
               target,
@@ -720,6 +1088,41 @@ impl App {
           Action::CancelCurrentPopup => {
             self.popup = None;
           }
+          Action::SignalProcess { pid, signal } => {
+            self.popup = None;
+            let result = match self.live_processes.get(&pid) {
+              Some(process) => process.signal(signal),
+              None => Err(Errno::ESRCH),
+            };
+            match result {
+              Ok(_) => (),
+              Err(Errno::ESRCH) => {
+                action_tx.send(Action::show_error_popup(
+                  "Process Not Running".to_owned(),
+                  format!("Process {pid} has exited."),
+                ))?;
+              }
+              Err(e) => {
+                action_tx.send(Action::show_error_popup(
+                  format!("Failed to send {signal} to {pid}"),
+                  e,
+                ))?;
+              }
+            }
+          }
+          Action::SignalRootProcesses { signal, group } => {
+            let result = if group {
+              self.signal_root_process_groups(signal)
+            } else {
+              self.signal_root_processes(signal)
+            };
+            if let Err(e) = result {
+              action_tx.send(Action::show_error_popup(
+                format!("Failed to send {signal}"),
+                e,
+              ))?;
+            }
+          }
           Action::BeginSearch => {
             if let Some(query_builder) = self.query_builder.as_mut() {
               // action_tx.send(query_builder.edit())?;
@@ -766,29 +1169,270 @@ impl App {
               .hit_manager_state
               .access_some_mut(|h| h.visible = false);
           }
+          Action::AnswerExecApproval { hid, approved } => {
+            if let Some(h) = self.hit_manager_state.as_mut() {
+              let result = if approved { h.resume(hid) } else { h.deny(hid) };
+              if let Err(e) = result {
+                action_tx.send(Action::show_error_popup(
+                  if approved {
+                    "Resume failed"
+                  } else {
+                    "Deny failed"
+                  }
+                  .to_string(),
+                  e,
+                ))?;
+              }
+            }
+          }
+          Action::ShowCommandPrompt => {
+            self.command_prompt = Some(CommandPrompt::new());
+          }
+          Action::CloseCommandPrompt => {
+            self.command_prompt = None;
+          }
+          Action::RunCommand(argv) => {
+            self.command_prompt = None;
+            if let Some(tracer) = self.tracer.as_ref() {
+              tracer.request_spawn_root(argv)?;
+            }
+          }
+          Action::ShowExportPrompt => {
+            self.export_prompt = Some(ExportPrompt::new());
+          }
+          Action::CloseExportPrompt => {
+            self.export_prompt = None;
+          }
+          Action::ExportEvents {
+            path,
+            scope,
+            format,
+          } => {
+            self.export_prompt = None;
+            match self.export_events(&path, scope, format) {
+              Ok(0) => action_tx.send(Action::show_error_popup(
+                "Export".to_string(),
+                format!("There is nothing to export in {scope}"),
+              ))?,
+              Ok(count) => action_tx.send(Action::SetActivePopup(ActivePopup::InfoPopup(
+                InfoPopupState::info(
+                  "Export".to_string(),
+                  vec![Line::raw(format!(
+                    "Exported {count} event{} to {}",
+                    if count == 1 { "" } else { "s" },
+                    path.display()
+                  ))],
+                ),
+              )))?,
+              Err(e) => action_tx.send(Action::show_error_popup("Export Failed".to_string(), e))?,
+            }
+          }
         }
       }
     }
   }
 
-  pub fn exit(&self) -> color_eyre::Result<()> {
+  pub fn exit(&mut self) -> color_eyre::Result<()> {
+    if let Some(session) = self.session.take() {
+      session.finish(self.event_list.bookmarks().map(|i| self.pause.id_of(i)))?;
+    }
     // Close pty master
     self.term.as_ref().inspect(|t| t.exit());
-    // Terminate root process
+    // Terminate root processes
     match self.exit_handling {
-      ExitHandling::Kill => self.signal_root_process(Signal::SIGKILL)?,
-      ExitHandling::Terminate => self.signal_root_process(Signal::SIGTERM)?,
-      ExitHandling::Wait => (),
+      ExitHandling::Kill => self.signal_root_processes(Signal::SIGKILL)?,
+      ExitHandling::Terminate => self.signal_root_processes(Signal::SIGTERM)?,
+      ExitHandling::Wait | ExitHandling::Detach => (),
+    }
+    if let Some(tracer) = self.tracer.as_ref() {
+      if self.exit_handling == ExitHandling::Detach {
+        tracer.request_detach_all()?;
+      } else {
+        // Let the tracer exit after all root processes exit
+        tracer.request_shutdown()?;
+      }
     }
     Ok(())
   }
 
-  pub fn signal_root_process(&self, sig: Signal) -> color_eyre::Result<()> {
-    if let Some(root_pid) = self.root_pid {
-      nix::sys::signal::kill(root_pid, sig)?;
+  /// Write the events to the file and return how many events are written
+  fn export_events(
+    &self,
+    path: &Path,
+    scope: ExportScope,
+    format: ExportFormat,
+  ) -> color_eyre::Result<usize> {
+    let indices = self.event_list.export_indices(scope);
+    if indices.is_empty() {
+      return Ok(0);
+    }
+    let mut output = BufWriter::new(
+      File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+    );
+    match format {
+      ExportFormat::Text => {
+        for &i in indices.iter() {
+          writeln!(output, "{}", self.event_list.event_text(i))?;
+        }
+      }
+      ExportFormat::Json => {
+        let meta = JsonMetaData::new(self.event_list.baseline.as_ref().clone())
+          .with_notes(self.trace_notes.clone());
+        serialize_json_to_output(&mut output, &meta, false)?;
+        output.write_all(b"\n")?;
+        let mut bookmarks = vec![];
+        for &i in indices.iter() {
+          let id = self.pause.id_of(i);
+          let msg = TracerMessage::Event(TracerEvent {
+            details: self.event_list.event_details(i).as_ref().clone(),
+            id,
+          });
+          if let Some(entry) = RecordEntry::from_message(msg) {
+            serialize_json_to_output(&mut output, &entry, false)?;
+            output.write_all(b"\n")?;
+          }
+          if self.event_list.is_bookmarked(i) {
+            bookmarks.push(id);
+          }
+        }
+        for id in bookmarks {
+          serialize_json_to_output(&mut output, &RecordEntry::Bookmark { id }, false)?;
+          output.write_all(b"\n")?;
+        }
+      }
+      ExportFormat::ShellScript => {
+        let mut script = ShellScriptBuilder::default();
+        for &i in indices.iter() {
+          script.record(&self.event_list.event_details(i));
+        }
+        let meta = JsonMetaData::new(self.event_list.baseline.as_ref().clone())
+          .with_notes(self.trace_notes.clone());
+        output.write_all(script.finish(&meta).as_bytes())?;
+        std::fs::set_permissions(path, Permissions::from_mode(0o755))?;
+      }
+    }
+    output.flush()?;
+    Ok(indices.len())
+  }
+
+  fn push_event(
+    &mut self,
+    e: TracerEvent,
+    action_tx: &mpsc::UnboundedSender<Action>,
+  ) -> color_eyre::Result<()> {
+    debug_assert_eq!(self.pause.index_of(e.id), Some(self.event_list.len()));
+    let build_progress = self.term.as_ref().and_then(|t| t.build_progress());
+    self.statistics.record(&e.details);
+    self.event_list.push(e.details, build_progress);
+    if self.event_list.is_following() {
+      action_tx.send(Action::ScrollToBottom)?;
+    }
+    Ok(())
+  }
+
+  fn apply_update(&mut self, mut update: ProcessStateUpdateEvent) {
+    // The ids of the events are their indices in the event list unless events were discarded
+    update.ids = update
+      .ids
+      .into_iter()
+      .filter_map(|id| self.pause.index_of(id).map(|i| i as u64))
+      .collect();
+    self.event_list.update(update);
+  }
+
+  pub fn signal_root_processes(&self, sig: Signal) -> color_eyre::Result<()> {
+    for root_process in self.root_processes.iter() {
+      debug!("Sending {sig} to root process {}", root_process.pid());
+      match root_process.signal(sig) {
+        // The root process has already exited
+        Ok(_) | Err(Errno::ESRCH) => (),
+        Err(e) => Err(e)?,
+      }
     }
     Ok(())
   }
+
+  /// Root processes are process group leaders, so this signals the process trees
+  /// except for the descendants that moved to other process groups.
+  pub fn signal_root_process_groups(&self, sig: Signal) -> color_eyre::Result<()> {
+    for root_process in self.root_processes.iter() {
+      debug!("Sending {sig} to process group {}", root_process.pid());
+      match killpg(root_process.pid(), sig) {
+        // All processes in the group have exited
+        Ok(_) | Err(Errno::ESRCH) => (),
+        Err(e) => Err(e)?,
+      }
+    }
+    Ok(())
+  }
+
+  /// The popup that asks the user to approve or deny an exec.
+  /// Cancelling it leaves the process stopped in the hit manager.
+  fn exec_approval(tracer: &Tracer, hid: u64, hit: &BreakPointHit) -> ConfirmPopupState {
+    let mut message = vec![Line::default().spans(vec![
+      "Process ".into(),
+      hit.pid.to_string().bold(),
+      " is about to exec".into(),
+    ])];
+    if let Some((filename, argv)) = tracer.get_pending_exec(hit.pid) {
+      message.push(Line::default().spans(vec!["Filename: ".bold(), filename.to_string().into()]));
+      if let Ok(argv) = argv.as_ref() {
+        message.push(Line::default().spans(vec!["Argv: ".bold(), argv.iter().join(" ").into()]));
+      }
+    }
+    if let Some(pattern) = tracer.get_breakpoint_pattern_string(hit.bid) {
+      message.push(Line::default().spans(vec!["Rule: ".bold(), pattern.into()]));
+    }
+    ConfirmPopupState::new(
+      "Approve Exec".to_owned(),
+      message,
+      vec![
+        (
+          'a',
+          "Approve",
+          Action::AnswerExecApproval {
+            hid,
+            approved: true,
+          },
+        ),
+        (
+          'd',
+          "Deny",
+          Action::AnswerExecApproval {
+            hid,
+            approved: false,
+          },
+        ),
+      ],
+    )
+  }
+
+  fn signal_confirmation(signal: Signal) -> ConfirmPopupState {
+    ConfirmPopupState::new(
+      format!("Send {signal}"),
+      vec![Line::raw(format!(
+        "Send {signal} to the root process or its whole process group?"
+      ))],
+      vec![
+        (
+          'y',
+          "Root process",
+          Action::SignalRootProcesses {
+            signal,
+            group: false,
+          },
+        ),
+        (
+          'g',
+          "Process group",
+          Action::SignalRootProcesses {
+            signal,
+            group: true,
+          },
+        ),
+      ],
+    )
+  }
 }
 
 impl Widget for &mut App {
@@ -805,12 +1449,22 @@ impl Widget for &mut App {
       Constraint::Percentage(self.split_percentage),
       Constraint::Percentage(100 - self.split_percentage),
     ];
-    let [event_area, term_area] = (if self.layout == AppLayout::Horizontal {
+    let [mut event_area, mut term_area] = (if self.layout == AppLayout::Horizontal {
       Layout::horizontal
     } else {
       Layout::vertical
     })(horizontal_constraints)
     .areas(rest_area);
+    let zoomed_pane = self
+      .zoomed
+      .then_some(self.active_pane)
+      .filter(|_| self.term.is_some());
+    // The terminal keeps its size when hidden so that the tracee is not disturbed.
+    match zoomed_pane {
+      Some(ActivePane::Events) => event_area = rest_area,
+      Some(ActivePane::Terminal) => term_area = rest_area,
+      None => (),
+    }
     let mut title = vec![Span::from(" tracexec "), env!("CARGO_PKG_VERSION").into()];
     if !self.active_experiments.is_empty() {
       title.push(Span::from(" with "));
@@ -823,9 +1477,42 @@ impl Widget for &mut App {
       }
       title.push(Span::from(" feature(s) active"));
     }
-    render_title(header_area, buf, Line::from(title));
-    if let Some(query_builder) = self.query_builder.as_mut() {
+    if !self.trace_notes.is_empty() {
+      title.push(Span::from(" | "));
+      title.push(Span::styled(self.trace_notes.to_string(), THEME.sublabel));
+    }
+    let build_progress = self.term.as_ref().and_then(|t| t.build_progress());
+    if let Some(progress) = build_progress {
+      let [title_area, progress_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(40)]).areas(header_area);
+      render_title(title_area, buf, Line::from(title));
+      LineGauge::default()
+        .ratio(progress.ratio())
+        .label(progress.to_string())
+        .filled_style(THEME.build_progress_filled)
+        .unfilled_style(THEME.build_progress_unfilled)
+        .render(progress_area, buf);
+    } else {
+      render_title(header_area, buf, Line::from(title));
+    }
+    if let Some(command_prompt) = self.command_prompt.as_mut() {
+      command_prompt.render(search_bar_area, buf);
+    } else if let Some(export_prompt) = self.export_prompt.as_mut() {
+      export_prompt.render(search_bar_area, buf);
+    } else if let Some(query_builder) = self.query_builder.as_mut() {
       query_builder.render(search_bar_area, buf);
+      if let Some(query_result) = self.event_list.query_result() {
+        let statistics = query_result.statistics();
+        let statistics_len = statistics.width() as u16;
+        if statistics_len < search_bar_area.width {
+          let statistics_area = Rect {
+            x: search_bar_area.right() - statistics_len,
+            width: statistics_len,
+            ..search_bar_area
+          };
+          statistics.render(statistics_area, buf);
+        }
+      }
     }
 
     self.render_help(footer_area, buf);
@@ -861,8 +1548,37 @@ impl Widget for &mut App {
       }
     }
 
+    let mut title = if self.pause.is_paused() {
+      format!(
+        "Events (paused, {} {})",
+        self.pause.held_events(),
+        match self.pause.mode() {
+          PauseMode::Buffer => "buffered",
+          PauseMode::Discard => "discarded",
+        }
+      )
+    } else {
+      "Events".to_owned()
+    };
+    if let Some(scope) = self.event_list.scope() {
+      title.push_str(&format!(" [subtree of {scope}]"));
+    }
+    if let Some(stats) = self.overflow_stats.as_ref() {
+      let (spilled, kept_in_memory) = (stats.spilled(), stats.kept_in_memory());
+      if spilled + kept_in_memory > 0 {
+        title.push_str(&format!(
+          " [overflow: {spilled} spilled, {kept_in_memory} in memory]"
+        ));
+      }
+    }
+    if let Some(lost) = self.lost_events.as_ref() {
+      let lost = lost.load(Ordering::Relaxed);
+      if lost > 0 {
+        title.push_str(&format!(" [{lost} events lost]"));
+      }
+    }
     let block = Block::default()
-      .title("Events")
+      .title(title)
       .borders(ratatui::widgets::Borders::ALL)
       .border_style(if self.active_pane == ActivePane::Events {
         THEME.active_border
@@ -870,12 +1586,29 @@ impl Widget for &mut App {
         THEME.inactive_border
       })
       .title(self.event_list.statistics());
+    let block = match self.event_list.breadcrumb() {
+      Some(breadcrumb) => block.title(breadcrumb.centered()),
+      None => block,
+    };
     let inner = block.inner(event_area);
-    block.render(event_area, buf);
-    self.event_list.render(inner, buf);
-    if let Some(term) = self.term.as_mut() {
+    if zoomed_pane != Some(ActivePane::Terminal) {
+      block.render(event_area, buf);
+      self.event_list.render(inner, buf);
+    }
+    if let Some(term) = self
+      .term
+      .as_mut()
+      .filter(|_| zoomed_pane != Some(ActivePane::Events))
+    {
+      let scrollback_offset = term.scrollback_offset();
       let block = Block::default()
-        .title("Terminal")
+        .title(if term.is_selecting() {
+          "Terminal (selecting)".to_owned()
+        } else if scrollback_offset > 0 {
+          format!("Terminal (scrolled up {scrollback_offset} lines)")
+        } else {
+          "Terminal".to_owned()
+        })
         .borders(ratatui::widgets::Borders::ALL)
         .border_style(if self.active_pane == ActivePane::Terminal {
           THEME.active_border
@@ -908,9 +1641,24 @@ impl Widget for &mut App {
         ActivePopup::CopyTargetSelection(state) => {
           CopyPopup.render_ref(area, buf, state);
         }
+        ActivePopup::Statistics => {
+          let popup = Popup::new(self.statistics.paragraph(rest_area))
+            .title("Statistics")
+            .style(THEME.help_popup);
+          popup.render(area, buf);
+        }
         ActivePopup::InfoPopup(state) => {
           InfoPopup.render(area, buf, state);
         }
+        ActivePopup::Confirm(state) => {
+          ConfirmPopup.render(area, buf, state);
+        }
+        ActivePopup::SignalSelection(state) => {
+          SignalPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::KeyBindings(state) => {
+          KeyBindingsPopup.render_ref(area, buf, state);
+        }
         _ => {}
       }
     }
@@ -939,30 +1687,66 @@ impl App {
         .into_iter()
         .flatten(),
     );
+    if self.term.is_some() {
+      items.extend(help_item!(
+        "Alt+Z",
+        if self.zoomed { "Unzoom" } else { "Zoom" }
+      ));
+    }
 
-    if let Some(popup) = &self.popup {
+    if self.popup.as_ref().is_some_and(
+      |popup| matches!(popup, ActivePopup::ViewDetails(state) if state.is_searching_env()),
+    ) {
+      items.extend(help_item!("Esc", "Cancel\u{00a0}Search"));
+      items.extend(help_item!("Enter", "Done"));
+      items.extend(help_item!("Ctrl+U", "Clear"));
+    } else if let Some(popup) = &self.popup {
       items.extend(help_item!("Q", "Close\u{00a0}Popup"));
       match popup {
         ActivePopup::ViewDetails(state) => {
           if state.active_tab() == "Info" {
             items.extend(help_item!("W/S", "Move\u{00a0}Focus"));
+          } else if state.active_tab() == "Env Diff" {
+            items.extend(help_item!("↑/↓", "Move\u{00a0}Focus"));
+            if self.clipboard.is_some() {
+              items.extend(help_item!("C", "Copy"));
+            }
+          } else if state.active_tab() == "Environment" {
+            items.extend(help_item!("/", "Search"));
+            if state.has_env_search() {
+              items.extend(help_item!("Esc", "Clear\u{00a0}Search"));
+            }
           }
           items.extend(help_item!("←/Tab/→", "Switch\u{00a0}Tab"));
+          if matches!(state.event(), TracerEventDetails::Exec(_)) {
+            items.extend(help_item!(
+              "X",
+              format!("Escape:\u{00a0}{}", state.escape_style())
+            ));
+          }
         }
         ActivePopup::CopyTargetSelection(state) => {
           items.extend(help_item!("Enter", "Choose"));
           items.extend(state.help_items())
         }
+        ActivePopup::SignalSelection(_) => {
+          items.extend(help_item!("Enter", "Send"));
+        }
         _ => {}
       }
     } else if let Some(breakpoint_manager) = self.breakpoint_manager.as_ref() {
       items.extend(breakpoint_manager.help());
     } else if self.hit_manager_state.as_ref().is_some_and(|x| x.visible) {
       items.extend(self.hit_manager_state.as_ref().unwrap().help());
+    } else if let Some(command_prompt) = self.command_prompt.as_ref() {
+      items.extend(command_prompt.help());
+    } else if let Some(export_prompt) = self.export_prompt.as_ref() {
+      items.extend(export_prompt.help());
     } else if let Some(query_builder) = self.query_builder.as_ref().filter(|q| q.editing()) {
       items.extend(query_builder.help());
     } else if self.active_pane == ActivePane::Events {
       items.extend(help_item!("F1", "Help"));
+      items.extend(help_item!("?", "Keys"));
       if self.term.is_some() {
         items.extend(help_item!("G/S", "Grow/Shrink\u{00a0}Pane"));
         items.extend(help_item!("Alt+L", "Layout"));
@@ -992,10 +1776,79 @@ impl App {
             "Show\u{00a0}CWD"
           }
         ),
+        help_item!(
+          "Alt+W",
+          if self.event_list.is_wrapping() {
+            "Unwrap"
+          } else {
+            "Wrap"
+          }
+        ),
         help_item!("V", "View"),
+        help_item!("T", "Stats"),
+        help_item!("P", "Parent"),
+        help_item!(
+          "O",
+          if self.event_list.is_grouped() {
+            "Ungroup"
+          } else {
+            "Group"
+          }
+        ),
+        help_item!(
+          "X",
+          match self.event_list.result_filter() {
+            ResultFilter::All => "Successful",
+            ResultFilter::Successful => "Failed",
+            ResultFilter::Failed => "All",
+          }
+        ),
+        help_item!(
+          "D",
+          if self.event_list.scope().is_some() {
+            "All\u{00a0}Processes"
+          } else {
+            "Subtree"
+          }
+        ),
+        help_item!(
+          "U",
+          if self.event_list.is_deduplicating() {
+            "Show\u{00a0}Repeats"
+          } else {
+            "Hide\u{00a0}Repeats"
+          }
+        ),
+        help_item!(
+          "Alt+T",
+          if self.event_list.is_table() {
+            "List"
+          } else {
+            "Table"
+          }
+        ),
+        help_item!(
+          "Alt+P",
+          format!(
+            "Color\u{00a0}by\u{00a0}{}",
+            self.event_list.color_by().next()
+          )
+        ),
         help_item!("Ctrl+F", "Search"),
+        help_item!(
+          "Shift+P",
+          if self.pause.is_paused() {
+            "Resume"
+          } else {
+            "Pause"
+          }
+        ),
       ));
       if let Some(h) = self.hit_manager_state.as_ref() {
+        items.extend(help_item!("R", "Run"));
+        items.extend(help_item!("Shift+T", "Terminate"));
+        items.extend(help_item!("Shift+K", "Kill"));
+        items.extend(help_item!("Shift+S", "Signal"));
         items.extend(help_item!("B", "Breakpoints"));
         if h.count() > 0 {
           items.extend([
@@ -1007,6 +1860,21 @@ impl App {
           items.extend(help_item!("Z", "Hits"));
         }
       }
+      if self.event_list.is_grouped() {
+        items.extend(help_item!("Space", "Fold"));
+      }
+      if self.event_list.is_table() {
+        items.extend(help_item!(
+          ">",
+          match self.event_list.sort() {
+            Some((column, _)) => format!("Sort:\u{00a0}{}", column.name()),
+            None => "Sort".to_owned(),
+          }
+        ));
+        if self.event_list.sort().is_some() {
+          items.extend(help_item!("<", "Reverse"));
+        }
+      }
       if self.clipboard.is_some() {
         items.extend(help_item!("C", "Copy"));
       }
@@ -1016,6 +1884,17 @@ impl App {
       items.extend(help_item!("Q", "Quit"));
     } else {
       // Terminal
+      if self.term.as_ref().is_some_and(|t| t.is_selecting()) {
+        items.extend(help_item!("←↓↑→/HJKL", "Move"));
+        items.extend(help_item!("V", "Mark"));
+        items.extend(help_item!("Y", "Copy"));
+        items.extend(help_item!("Esc", "Cancel"));
+      } else {
+        items.extend(help_item!("Shift+PgUp/PgDn", "Scroll"));
+        if self.clipboard.is_some() {
+          items.extend(help_item!("Alt+V", "Select"));
+        }
+      }
       if let Some(h) = self.hit_manager_state.as_ref() {
         if h.count() > 0 {
           items.extend([
@@ -69,6 +69,9 @@ impl BreakPointEntry {
         match self.breakpoint.ty {
           BreakPointType::Once => "\u{00a0}\u{00a0}One-Time\u{00a0}\u{200b}",
           BreakPointType::Permanent => "\u{00a0}Permanent\u{00a0}\u{200b}",
+          BreakPointType::Ask => {
+            "\u{00a0}\u{00a0}\u{00a0}\u{00a0}Ask\u{00a0}\u{00a0}\u{00a0}\u{00a0}\u{200b}"
+          }
         },
         THEME.breakpoint_info_value,
       ),
@@ -220,7 +223,11 @@ impl BreakPointManagerState {
           };
           let new = BreakPoint {
             pattern,
-            ty: BreakPointType::Permanent,
+            // Keep the type of the breakpoint being edited
+            ty: self
+              .editing
+              .and_then(|id| self.breakpoints.get(&id))
+              .map_or(BreakPointType::Permanent, |b| b.ty.clone()),
             activated: self.active,
             stop: self.stop,
           };
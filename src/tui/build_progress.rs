@@ -0,0 +1,194 @@
+//! Best-effort progress estimation for well-known build tools.
+//!
+//! The progress reports printed by the build tools to the pseudo terminal
+//! are parsed to figure out how far the build has gone.
+
+use std::fmt::Display;
+
+use strum::Display;
+
+/// Build tools whose progress reports are understood
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum BuildTool {
+  /// `[12/345] ...`
+  Ninja,
+  /// `    Building [=====>    ] 12/345: ...`
+  Cargo,
+  /// `[ 42%] ...`, printed by makefiles generated by CMake
+  Make,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildProgress {
+  pub tool: BuildTool,
+  pub done: u64,
+  pub total: u64,
+}
+
+impl BuildProgress {
+  pub fn ratio(&self) -> f64 {
+    if self.total == 0 {
+      0.0
+    } else {
+      (self.done as f64 / self.total as f64).clamp(0.0, 1.0)
+    }
+  }
+}
+
+impl Display for BuildProgress {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.tool {
+      BuildTool::Make => write!(f, "{} {}%", self.tool, self.done),
+      _ => write!(f, "{} {}/{}", self.tool, self.done, self.total),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EscapeState {
+  #[default]
+  None,
+  Escape,
+  Csi,
+}
+
+/// Incrementally parses the output of the pty to find progress reports
+#[derive(Debug, Default)]
+pub struct BuildProgressParser {
+  line: Vec<u8>,
+  escape: EscapeState,
+  progress: Option<BuildProgress>,
+}
+
+impl BuildProgressParser {
+  /// Progress reports are short. Longer lines are truncated.
+  const MAX_LINE_LEN: usize = 256;
+
+  pub fn progress(&self) -> Option<BuildProgress> {
+    self.progress
+  }
+
+  /// Feed the output of the pty to the parser.
+  ///
+  /// Returns true if the progress is updated.
+  pub fn feed(&mut self, data: &[u8]) -> bool {
+    let mut updated = false;
+    for &byte in data {
+      match (self.escape, byte) {
+        (EscapeState::None, 0x1b) => self.escape = EscapeState::Escape,
+        (EscapeState::None, b'\r' | b'\n') => {
+          if let Some(progress) = std::str::from_utf8(&self.line)
+            .ok()
+            .and_then(parse_progress)
+          {
+            updated |= self.progress != Some(progress);
+            self.progress = Some(progress);
+          }
+          self.line.clear();
+        }
+        (EscapeState::None, byte) => {
+          if self.line.len() < Self::MAX_LINE_LEN {
+            self.line.push(byte);
+          }
+        }
+        (EscapeState::Escape, b'[') => self.escape = EscapeState::Csi,
+        // Two-byte escape sequences
+        (EscapeState::Escape, _) => self.escape = EscapeState::None,
+        (EscapeState::Csi, 0x40..=0x7e) => self.escape = EscapeState::None,
+        (EscapeState::Csi, _) => (),
+      }
+    }
+    updated
+  }
+}
+
+fn parse_progress(line: &str) -> Option<BuildProgress> {
+  let line = line.trim_start();
+  if let Some(rest) = line.strip_prefix("Building [") {
+    // cargo: Building [=====>    ] 12/345: foo, bar
+    let (_, rest) = rest.split_once("] ")?;
+    let (counts, _) = rest.split_once(':').unwrap_or((rest, ""));
+    let (done, total) = parse_fraction(counts)?;
+    return Some(BuildProgress {
+      tool: BuildTool::Cargo,
+      done,
+      total,
+    });
+  }
+  let (inner, _) = line.strip_prefix('[')?.split_once(']')?;
+  if let Some(percentage) = inner.strip_suffix('%') {
+    // make(CMake): [ 42%] Building C object ...
+    let done = percentage.trim_start().parse().ok()?;
+    return Some(BuildProgress {
+      tool: BuildTool::Make,
+      done,
+      total: 100,
+    });
+  }
+  // ninja: [12/345] Building C object ...
+  let (done, total) = parse_fraction(inner)?;
+  Some(BuildProgress {
+    tool: BuildTool::Ninja,
+    done,
+    total,
+  })
+}
+
+fn parse_fraction(s: &str) -> Option<(u64, u64)> {
+  let (done, total) = s.split_once('/')?;
+  let done = done.parse().ok()?;
+  let total = total.parse().ok()?;
+  (done <= total).then_some((done, total))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_ninja_progress() {
+    let mut parser = BuildProgressParser::default();
+    assert!(parser.feed(b"\r[12/345] Building C object foo.o\x1b[K\r"));
+    assert!(!parser.feed(b"[12/345] Building C object bar.o\r"));
+    assert_eq!(
+      parser.progress(),
+      Some(BuildProgress {
+        tool: BuildTool::Ninja,
+        done: 12,
+        total: 345
+      })
+    );
+  }
+
+  #[test]
+  fn parses_cargo_progress() {
+    let mut parser = BuildProgressParser::default();
+    parser.feed(b"\x1b[1m\x1b[36m    Building\x1b[0m [=====>     ] 50/100: tracexec(bin)\r");
+    assert_eq!(
+      parser.progress(),
+      Some(BuildProgress {
+        tool: BuildTool::Cargo,
+        done: 50,
+        total: 100
+      })
+    );
+  }
+
+  #[test]
+  fn parses_cmake_make_progress() {
+    let mut parser = BuildProgressParser::default();
+    parser.feed(b"[  7%] Building C object CMakeFiles/foo.dir/foo.c.o\n");
+    let progress = parser.progress().unwrap();
+    assert_eq!(progress.tool, BuildTool::Make);
+    assert_eq!(progress.done, 7);
+    assert_eq!(progress.to_string(), "make 7%");
+  }
+
+  #[test]
+  fn ignores_unrelated_output() {
+    let mut parser = BuildProgressParser::default();
+    assert!(!parser.feed(b"[info] hello\n[a/b]\nBuilding [ok]\n"));
+    assert_eq!(parser.progress(), None);
+  }
+}
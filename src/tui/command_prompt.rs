@@ -0,0 +1,76 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  text::Span,
+  widgets::{StatefulWidget, Widget},
+};
+use tui_prompts::{State, TextPrompt, TextState};
+
+use crate::action::Action;
+
+use super::help::help_item;
+
+/// A prompt for launching a new root command in the current session
+pub struct CommandPrompt {
+  state: TextState<'static>,
+}
+
+impl Default for CommandPrompt {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl CommandPrompt {
+  pub fn new() -> Self {
+    let mut state = TextState::new();
+    state.focus();
+    Self { state }
+  }
+
+  /// Get the current cursor position,
+  /// this should be called after render is called
+  pub fn cursor(&self) -> (u16, u16) {
+    self.state.cursor()
+  }
+
+  pub fn handle_key_event(&mut self, key: KeyEvent) -> Option<Action> {
+    match (key.code, key.modifiers) {
+      (KeyCode::Enter, _) => {
+        let text = self.state.value();
+        if text.trim().is_empty() {
+          return Some(Action::CloseCommandPrompt);
+        }
+        match shell_words::split(text) {
+          Ok(argv) => Some(Action::RunCommand(argv)),
+          Err(e) => Some(Action::show_error_popup("Invalid Command".to_string(), e)),
+        }
+      }
+      (KeyCode::Esc, KeyModifiers::NONE) => Some(Action::CloseCommandPrompt),
+      _ => {
+        self.state.handle_key_event(key);
+        None
+      }
+    }
+  }
+
+  pub fn help(&self) -> impl Iterator<Item = Span<'_>> {
+    [
+      help_item!("Esc", "Cancel"),
+      help_item!("Enter", "Run\u{00a0}Command"),
+      help_item!("Ctrl+U", "Clear"),
+    ]
+    .into_iter()
+    .flatten()
+  }
+}
+
+impl Widget for &mut CommandPrompt {
+  fn render(self, area: Rect, buf: &mut Buffer)
+  where
+    Self: Sized,
+  {
+    TextPrompt::new("Run".into()).render(area, buf, &mut self.state);
+  }
+}
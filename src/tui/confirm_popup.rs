@@ -0,0 +1,79 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  style::Stylize,
+  text::{Line, Span},
+  widgets::{Paragraph, StatefulWidget, WidgetRef, Wrap},
+};
+use tui_popup::Popup;
+
+use crate::action::Action;
+
+use super::{sized_paragraph::SizedParagraph, theme::THEME};
+
+/// A popup that asks the user to confirm an action by pressing the key of a choice
+#[derive(Debug, Clone)]
+pub struct ConfirmPopupState {
+  pub title: String,
+  pub message: Vec<Line<'static>>,
+  /// The key, the description and the action of each choice
+  pub choices: Vec<(char, &'static str, Action)>,
+}
+
+impl ConfirmPopupState {
+  pub fn new(
+    title: String,
+    message: Vec<Line<'static>>,
+    choices: Vec<(char, &'static str, Action)>,
+  ) -> Self {
+    Self {
+      title,
+      message,
+      choices,
+    }
+  }
+
+  /// Any key other than the choices cancels the popup
+  pub fn handle_key_event(&self, key: KeyEvent) -> Vec<Action> {
+    let mut actions = vec![Action::CancelCurrentPopup];
+    if let KeyCode::Char(c) = key.code {
+      if let Some((_, _, action)) = self
+        .choices
+        .iter()
+        .find(|(k, _, _)| k.eq_ignore_ascii_case(&c))
+      {
+        actions.push(action.clone());
+      }
+    }
+    actions
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmPopup;
+
+impl StatefulWidget for ConfirmPopup {
+  type State = ConfirmPopupState;
+
+  fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+    let mut message = state.message.clone();
+    message.push("".into());
+    for (key, description, _) in state.choices.iter() {
+      message.push(Line::from(vec![
+        Span::raw(format!(" {} ", key.to_ascii_uppercase())).bold(),
+        Span::raw(*description),
+      ]));
+    }
+    message.push("".into());
+    message.push(Line::raw("Press any other key to cancel").centered().bold());
+    let paragraph = Paragraph::new(message).wrap(Wrap { trim: false });
+    let popup = Popup::new(SizedParagraph::new(
+      paragraph,
+      (area.width as f32 * 0.7) as usize,
+    ))
+    .title(Line::raw(state.title.as_str()).centered())
+    .style(THEME.error_popup);
+    popup.render_ref(area, buf);
+  }
+}
@@ -11,7 +11,7 @@ use ratatui::{
 };
 
 use crate::{
-  action::{Action, CopyTarget, SupportedShell::Bash},
+  action::{Action, CopyTarget, SupportedShell},
   event::TracerEventDetails,
 };
 
@@ -25,6 +25,8 @@ pub struct CopyPopupState {
   pub event: Arc<TracerEventDetails>,
   pub state: ListState,
   pub available_targets: Vec<char>,
+  /// The shell of the command line targets
+  pub shell: SupportedShell,
 }
 
 lazy_static! {
@@ -38,8 +40,10 @@ lazy_static! {
     ('e', ("(E)nvironment variables", "Env")),
     ('d', ("(D)iff of environment variables", "Diff of Env")),
     ('a', ("(A)rguments", "Argv")),
+    ('j', ("Arguments as (J)SON", "Argv JSON")),
     ('n', ("File(N)ame", "Filename")),
     ('r', ("Syscall (R)esult", "Result")),
+    ('p', ("(P)id", "Pid")),
     ('l', ("Current (L)ine", "Line")),
   ]
   .into_iter()
@@ -47,7 +51,8 @@ lazy_static! {
 }
 
 impl CopyPopupState {
-  pub fn new(event: Arc<TracerEventDetails>) -> Self {
+  /// The last target is selected if it is available for the event
+  pub fn new(event: Arc<TracerEventDetails>, last_target: Option<CopyTarget>) -> Self {
     let mut state = ListState::default();
     state.select(Some(0));
     let available_targets = if let TracerEventDetails::Exec(_) = &event.as_ref() {
@@ -55,10 +60,37 @@ impl CopyPopupState {
     } else {
       vec!['l']
     };
-    Self {
+    let mut this = Self {
       event,
       state,
       available_targets,
+      shell: SupportedShell::Bash,
+    };
+    if let Some(target) = last_target {
+      if let CopyTarget::Commandline(shell) = target {
+        this.shell = shell;
+      }
+      let key = Self::key_of(target);
+      if let Some(id) = this.available_targets.iter().position(|&k| k == key) {
+        this.state.select(Some(id));
+      }
+    }
+    this
+  }
+
+  fn key_of(target: CopyTarget) -> char {
+    match target {
+      CopyTarget::Commandline(_) => 'c',
+      CopyTarget::CommandlineWithStdio(_) => 's',
+      CopyTarget::CommandlineWithFds(_) => 'f',
+      CopyTarget::Env => 'e',
+      CopyTarget::EnvDiff => 'd',
+      CopyTarget::Argv => 'a',
+      CopyTarget::ArgvJson => 'j',
+      CopyTarget::Filename => 'n',
+      CopyTarget::SyscallResult => 'r',
+      CopyTarget::Pid => 'p',
+      CopyTarget::Line => 'l',
     }
   }
 
@@ -78,14 +110,17 @@ impl CopyPopupState {
     let id = self.state.selected().unwrap_or(0);
     let key = self.available_targets[id];
     match key {
-      'c' => CopyTarget::Commandline(Bash),
-      's' => CopyTarget::CommandlineWithStdio(Bash),
-      'f' => CopyTarget::CommandlineWithFds(Bash),
+      'c' => CopyTarget::Commandline(self.shell),
+      // Only the command line without file descriptors could be quoted for other shells
+      's' => CopyTarget::CommandlineWithStdio(SupportedShell::Bash),
+      'f' => CopyTarget::CommandlineWithFds(SupportedShell::Bash),
       'e' => CopyTarget::Env,
       'd' => CopyTarget::EnvDiff,
       'a' => CopyTarget::Argv,
+      'j' => CopyTarget::ArgvJson,
       'n' => CopyTarget::Filename,
       'r' => CopyTarget::SyscallResult,
+      'p' => CopyTarget::Pid,
       'l' => CopyTarget::Line,
       _ => unreachable!(),
     }
@@ -101,12 +136,20 @@ impl CopyPopupState {
   }
 
   pub fn help_items(&self) -> impl Iterator<Item = Span> {
-    self.available_targets.iter().flat_map(|&key| {
-      help_item!(
-        key.to_ascii_uppercase().to_string(),
-        KEY_MAP.get(&key).unwrap().1
-      )
-    })
+    let shell = (self.available_targets.len() > 1)
+      .then(|| help_item!("Tab", "Shell"))
+      .into_iter()
+      .flatten();
+    self
+      .available_targets
+      .iter()
+      .flat_map(|&key| {
+        help_item!(
+          key.to_ascii_uppercase().to_string(),
+          KEY_MAP.get(&key).unwrap().1
+        )
+      })
+      .chain(shell)
   }
 
   pub fn handle_key_event(&mut self, ke: KeyEvent) -> color_eyre::Result<Option<Action>> {
@@ -121,6 +164,9 @@ impl CopyPopupState {
         KeyCode::Up | KeyCode::Char('k') => {
           self.prev();
         }
+        KeyCode::Tab => {
+          self.shell = self.shell.next();
+        }
         KeyCode::Enter => {
           return Ok(Some(Action::CopyToClipboard {
             event: self.event.clone(),
@@ -152,7 +198,11 @@ impl StatefulWidgetRef for CopyPopup {
     )
     .block(
       Block::default()
-        .title("Copy")
+        .title(if state.available_targets.len() > 1 {
+          format!("Copy ({})", state.shell)
+        } else {
+          "Copy".to_string()
+        })
         .title_alignment(Center)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::LightGreen)),
@@ -198,7 +248,7 @@ impl StatefulWidgetRef for CopyPopup {
 // SOFTWARE.
 
 /// Create a rectangle centered in the given area.
-fn centered_popup_rect(width: u16, height: u16, area: Rect) -> Rect {
+pub(super) fn centered_popup_rect(width: u16, height: u16, area: Rect) -> Rect {
   let height = height.saturating_add(2).min(area.height);
   let width = width.saturating_add(2).min(area.width);
   Rect {
@@ -1,4 +1,5 @@
 use std::{
+  collections::BTreeMap,
   ops::{ControlFlow, Deref, DerefMut},
   sync::Arc,
 };
@@ -10,18 +11,22 @@ use nix::{errno::Errno, fcntl::OFlag};
 use ratatui::{
   buffer::Buffer,
   layout::{Alignment::Center, Rect, Size},
-  style::Styled,
+  style::{Style, Styled},
   text::{Line, Span},
   widgets::{
-    Block, Borders, Clear, Paragraph, StatefulWidget, StatefulWidgetRef, Tabs, Widget, WidgetRef,
-    Wrap,
+    Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget,
+    StatefulWidgetRef, Tabs, Widget, WidgetRef, Wrap,
   },
 };
+use tui_prompts::{State, TextPrompt, TextState};
 use tui_scrollview::{ScrollView, ScrollViewState};
 
 use crate::{
-  event::{EventStatus, TracerEventDetails},
-  proc::BaselineInfo,
+  cli::{args::EnvFilterArgs, options::EscapeStyle},
+  event::{EventStatus, ExecEvent, OutputMsg, TracerEventDetails, TracerEventDetailsKind},
+  i18n, path_map,
+  proc::{ArgSizeReport, BaselineInfo},
+  redact,
 };
 
 use super::{
@@ -46,17 +51,33 @@ pub struct DetailsPopupState {
   active_index: usize,
   scroll: ScrollViewState,
   env: Option<Vec<Line<'static>>>,
+  /// The incremental search over the names and values of the environment
+  env_search: TextState<'static>,
+  env_search_editing: bool,
+  env_diff: Option<Vec<Line<'static>>>,
+  env_diff_state: ListState,
+  /// The height of the env diff pane in the last render, used for paging
+  env_diff_page_size: u16,
   fdinfo: Option<Vec<Line<'static>>>,
   available_tabs: Vec<&'static str>,
   tab_index: usize,
+  event: Arc<TracerEventDetails>,
+  baseline: Arc<BaselineInfo>,
+  escape_style: EscapeStyle,
+  env_filter: EnvFilterArgs,
 }
 
 impl DetailsPopupState {
-  pub fn new(event: &Event, baseline: Arc<BaselineInfo>) -> Self {
+  pub fn new(
+    event: &Event,
+    baseline: Arc<BaselineInfo>,
+    escape_style: EscapeStyle,
+    env_filter: EnvFilterArgs,
+  ) -> Self {
     let mut modifier_args = Default::default();
     let rt_modifier = Default::default();
     let mut details = vec![(
-      if matches!(event.details.as_ref(), TracerEventDetails::Exec(_)) {
+      if event.details.kind() == TracerEventDetailsKind::Exec {
         " Cmdline "
       } else {
         " Details "
@@ -65,7 +86,7 @@ impl DetailsPopupState {
         .details
         .to_tui_line(&baseline, true, &modifier_args, rt_modifier, None),
     )];
-    let (env, fdinfo, available_tabs) = if let TracerEventDetails::Exec(exec) =
+    let (env, env_diff, fdinfo, available_tabs) = if let TracerEventDetails::Exec(exec) =
       event.details.as_ref()
     {
       details.extend([
@@ -86,9 +107,13 @@ impl DetailsPopupState {
           if exec.result == 0 {
             "0 (Success)".set_style(THEME.exec_result_success).into()
           } else {
-            format!("{} ({})", exec.result, Errno::from_raw(-exec.result as i32))
-              .set_style(THEME.exec_result_failure)
-              .into()
+            format!(
+              "{} ({})",
+              exec.result,
+              i18n::describe_errno(Errno::from_raw(-exec.result as i32))
+            )
+            .set_style(THEME.exec_result_failure)
+            .into()
           }
         }),
         (" Process Status ", {
@@ -128,14 +153,110 @@ impl DetailsPopupState {
           }
         }),
         (" Cwd ", Span::from(exec.cwd.as_ref().to_owned()).into()),
+        (
+          " Parent Pid ",
+          exec
+            .ppid
+            .map(|ppid| ppid.to_string().into())
+            .unwrap_or_else(|| "Unknown".set_style(THEME.value_unknown).into()),
+        ),
         (" Comm (Before exec) ", exec.comm.to_string().into()),
+        (
+          " Container ",
+          exec
+            .container
+            .as_ref()
+            .map(|c| format!("{} {}", c.runtime, c.id).into())
+            .unwrap_or_else(|| "None".set_style(THEME.value_unknown).into()),
+        ),
+        (
+          " Cgroup ",
+          exec
+            .cgroup
+            .as_ref()
+            .map(|cgroup| cgroup.to_string().into())
+            .unwrap_or_else(|| "Unknown".set_style(THEME.value_unknown).into()),
+        ),
         (
           " Filename ",
-          Span::from(exec.filename.as_ref().to_owned()).into(),
+          Span::from(path_map::map(&exec.filename).as_ref().to_owned()).into(),
+        ),
+        (
+          " Executable ",
+          exec
+            .exe
+            .as_ref()
+            .map(|exe| Span::from(path_map::map(exe).as_ref().to_owned()).into())
+            .unwrap_or_else(|| "Unknown".set_style(THEME.value_unknown).into()),
+        ),
+        (
+          " Privileges ",
+          match exec.privileges {
+            Some(privileges) if privileges.is_privileged() => {
+              privileges.to_string().set_style(THEME.privileged).into()
+            }
+            Some(_) => "None".into(),
+            None => "Unknown".set_style(THEME.value_unknown).into(),
+          },
+        ),
+        (
+          " Credentials ",
+          match exec.credentials {
+            Some(creds) if creds.is_escalated() => {
+              creds.to_string().set_style(THEME.privileged).into()
+            }
+            Some(creds) => creds.to_string().into(),
+            None => "Unknown".set_style(THEME.value_unknown).into(),
+          },
+        ),
+        (
+          " Capabilities ",
+          match exec.capabilities {
+            Some(caps) if !caps.gained.is_empty() => {
+              format!("{} (gained {})", caps.effective, caps.gained)
+                .set_style(THEME.privileged)
+                .into()
+            }
+            Some(caps) if !caps.is_empty() => caps.effective.to_string().into(),
+            Some(_) => "None".into(),
+            None => "Unknown".set_style(THEME.value_unknown).into(),
+          },
+        ),
+        (
+          " Security Label ",
+          match (&exec.security_label_before, &exec.security_label) {
+            (Some(before), Some(label)) => format!("{before} => {label}")
+              .set_style(THEME.privileged)
+              .into(),
+            (None, Some(label)) => label.to_string().into(),
+            _ => "Unknown".set_style(THEME.value_unknown).into(),
+          },
+        ),
+        (
+          " Audit ",
+          if exec.audit.is_empty() {
+            "None".into()
+          } else {
+            exec
+              .audit
+              .iter()
+              .map(|rule| format!("{rule} ({})", rule.description()))
+              .join(", ")
+              .set_style(THEME.audit_finding)
+              .into()
+          },
+        ),
+        (
+          " Executable Hash ",
+          exec
+            .exe_hash
+            .as_ref()
+            .map(|hash| hash.to_string().into())
+            .unwrap_or_else(|| "Unknown".set_style(THEME.value_unknown).into()),
         ),
         (
           " Argv ",
-          TracerEventDetails::argv_to_string(&exec.argv).into(),
+          TracerEventDetails::argv_to_string(&exec.argv, escape_style).into(),
         ),
         (
           " Interpreters ",
@@ -172,84 +293,22 @@ impl DetailsPopupState {
           },
         ),
       ]);
-      let env = match exec.env_diff.as_ref() {
-        Ok(env_diff) => {
-          let mut env = env_diff
-            .added
-            .iter()
-            .map(|(key, value)| {
-              let spans = vec![
-                "+".set_style(THEME.plus_sign),
-                key.to_string().set_style(THEME.added_env_key),
-                "=".set_style(THEME.equal_sign),
-                value.to_string().set_style(THEME.added_env_val),
-              ];
-              Line::default().spans(spans)
-            })
-            .collect_vec();
-          env.extend(
-            env_diff
-              .removed
-              .iter()
-              .map(|key| {
-                let value = baseline.env.get(key).unwrap();
-                let spans = vec![
-                  "-".set_style(THEME.minus_sign),
-                  key.to_string().set_style(THEME.removed_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  value.to_string().set_style(THEME.removed_env_val),
-                ];
-                Line::default().spans(spans)
-              })
-              .collect_vec(),
-          );
-          env.extend(
-            env_diff
-              .modified
-              .iter()
-              .flat_map(|(key, new)| {
-                let old = baseline.env.get(key).unwrap();
-                let spans_old = vec![
-                  "-".set_style(THEME.minus_sign),
-                  key.to_string().set_style(THEME.removed_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  old.to_string().set_style(THEME.removed_env_val),
-                ];
-                let spans_new = vec![
-                  "+".set_style(THEME.plus_sign),
-                  key.to_string().set_style(THEME.added_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  new.to_string().set_style(THEME.added_env_val),
-                ];
-                vec![
-                  Line::default().spans(spans_old),
-                  Line::default().spans(spans_new),
-                ]
-              })
-              .collect_vec(),
-          );
-          env.extend(
-            // Unchanged env
-            baseline
-              .env
-              .iter()
-              .filter(|(key, _)| !env_diff.is_modified_or_removed(key))
-              .map(|(key, value)| {
-                let spans = vec![
-                  " ".into(),
-                  key.to_string().set_style(THEME.unchanged_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  value.to_string().set_style(THEME.unchanged_env_val),
-                ];
-                Line::default().spans(spans)
-              }),
-          );
-          env
-        }
-        Err(e) => {
-          vec![Line::from(format!("Failed to read envp: {}", e))]
+      if let Some(progress) = event.build_progress {
+        details.push((" Build Progress ", progress.to_string().into()));
+      }
+      if exec.result == -(Errno::E2BIG as i64) {
+        if let (Ok(argv), Ok(envp)) = (exec.argv.as_ref(), exec.envp.as_ref()) {
+          details.push((
+            " Argument Size ",
+            ArgSizeReport::new(argv, envp)
+              .to_string()
+              .set_style(THEME.exec_result_failure)
+              .into(),
+          ));
         }
-      };
+      }
+      let env = Self::env_lines(exec, escape_style, &env_filter);
+      let env_diff = Self::env_diff_lines(exec, &baseline, escape_style, &env_filter);
       let mut fdinfo = vec![];
       for (&fd, info) in exec.fdinfo.fdinfo.iter() {
         fdinfo.push(
@@ -351,11 +410,12 @@ impl DetailsPopupState {
 
       (
         Some(env),
+        Some(env_diff),
         Some(fdinfo),
-        vec!["Info", "Environment", "FdInfo"],
+        vec!["Info", "Env Diff", "Environment", "FdInfo"],
       )
     } else {
-      (None, None, vec!["Info"])
+      (None, None, None, vec!["Info"])
     };
     Self {
       details,
@@ -363,11 +423,162 @@ impl DetailsPopupState {
       active_index: 0,
       scroll: Default::default(),
       env,
+      env_search: TextState::new(),
+      env_search_editing: false,
+      env_diff,
+      env_diff_state: ListState::default().with_selected(Some(0)),
+      env_diff_page_size: 0,
       available_tabs,
       tab_index: 0,
+      event: event.details.clone(),
+      baseline,
+      escape_style,
+      env_filter,
     }
   }
 
+  /// The complete environment of the exec, with the added and modified variables marked
+  fn env_lines(
+    exec: &ExecEvent,
+    escape_style: EscapeStyle,
+    filter: &EnvFilterArgs,
+  ) -> Vec<Line<'static>> {
+    let (envp, env_diff) = match (exec.envp.as_ref(), exec.env_diff.as_ref()) {
+      (Ok(envp), Ok(env_diff)) => (envp, env_diff),
+      (Err(e), _) | (_, Err(e)) => {
+        return vec![Line::from(format!("Failed to read envp: {}", e))];
+      }
+    };
+    envp
+      .iter()
+      .filter(|(key, _)| filter.is_shown(key.as_ref()))
+      .map(|(key, value)| {
+        let (marker, key_style, val_style) = if env_diff.added.contains_key(key) {
+          (
+            "+".set_style(THEME.plus_sign),
+            THEME.added_env_key,
+            THEME.added_env_val,
+          )
+        } else if env_diff.modified.contains_key(key) {
+          (
+            "M".set_style(THEME.modified_sign),
+            THEME.modified_env_key,
+            THEME.added_env_val,
+          )
+        } else {
+          (" ".into(), THEME.unchanged_env_key, THEME.unchanged_env_val)
+        };
+        Line::default().spans(vec![
+          marker,
+          " ".into(),
+          Span::styled(key.escaped_if_unprintable(escape_style), key_style),
+          "=".set_style(THEME.equal_sign),
+          Span::styled(
+            redact::env(key, value).escaped_if_unprintable(escape_style),
+            val_style,
+          ),
+        ])
+      })
+      .collect_vec()
+  }
+
+  /// One line per changed variable, sorted by key and marked with `+`, `-` or `M`
+  fn env_diff_lines(
+    exec: &ExecEvent,
+    baseline: &BaselineInfo,
+    escape_style: EscapeStyle,
+    filter: &EnvFilterArgs,
+  ) -> Vec<Line<'static>> {
+    let env_diff = match exec.env_diff.as_ref() {
+      Ok(env_diff) => env_diff,
+      Err(e) => return vec![Line::from(format!("Failed to read envp: {}", e))],
+    };
+    let var = |marker: &'static str, marker_style: Style, key: &OutputMsg, key_style: Style| {
+      vec![
+        marker.set_style(marker_style),
+        " ".into(),
+        Span::styled(key.escaped_if_unprintable(escape_style), key_style),
+        "=".set_style(THEME.equal_sign),
+      ]
+    };
+    let mut changes = BTreeMap::new();
+    for (key, value) in env_diff.added.iter() {
+      let mut spans = var("+", THEME.plus_sign, key, THEME.added_env_key);
+      spans.push(Span::styled(
+        redact::env(key, value).escaped_if_unprintable(escape_style),
+        THEME.added_env_val,
+      ));
+      changes.insert(key, spans);
+    }
+    for key in env_diff.removed.iter() {
+      let value = baseline.env.get(key).unwrap();
+      let mut spans = var("-", THEME.minus_sign, key, THEME.removed_env_key);
+      spans.push(Span::styled(
+        redact::env(key, value).escaped_if_unprintable(escape_style),
+        THEME.removed_env_val,
+      ));
+      changes.insert(key, spans);
+    }
+    for (key, new) in env_diff.modified.iter() {
+      let old = baseline.env.get(key).unwrap();
+      let mut spans = var("M", THEME.modified_sign, key, THEME.modified_env_key);
+      spans.extend([
+        Span::styled(
+          redact::env(key, old).escaped_if_unprintable(escape_style),
+          THEME.removed_env_val,
+        ),
+        " → ".set_style(THEME.visual_separator),
+        Span::styled(
+          redact::env(key, new).escaped_if_unprintable(escape_style),
+          THEME.added_env_val,
+        ),
+      ]);
+      changes.insert(key, spans);
+    }
+    let lines = changes
+      .into_iter()
+      .filter(|(key, _)| filter.is_shown(key.as_ref()))
+      .map(|(_, spans)| Line::default().spans(spans))
+      .collect_vec();
+    if lines.is_empty() {
+      vec!["No changes to the environment"
+        .set_style(THEME.value_unknown)
+        .into()]
+    } else {
+      lines
+    }
+  }
+
+  /// Switch to the next escape style and rebuild the contents affected by it
+  pub fn cycle_escape_style(&mut self) {
+    let TracerEventDetails::Exec(exec) = self.event.as_ref() else {
+      return;
+    };
+    self.escape_style = self.escape_style.next();
+    if let Some((_, argv)) = self
+      .details
+      .iter_mut()
+      .find(|(label, _)| *label == " Argv ")
+    {
+      *argv = TracerEventDetails::argv_to_string(&exec.argv, self.escape_style).into();
+    }
+    self.env = Some(Self::env_lines(exec, self.escape_style, &self.env_filter));
+    self.env_diff = Some(Self::env_diff_lines(
+      exec,
+      &self.baseline,
+      self.escape_style,
+      &self.env_filter,
+    ));
+  }
+
+  pub fn event(&self) -> &TracerEventDetails {
+    &self.event
+  }
+
+  pub fn escape_style(&self) -> EscapeStyle {
+    self.escape_style
+  }
+
   pub fn next(&mut self) {
     self.active_index = (self.active_index + 1).min(self.details.len() - 1);
   }
@@ -380,6 +591,88 @@ impl DetailsPopupState {
     self.details[self.active_index].1.to_string()
   }
 
+  /// The selected line of the env diff without its marker
+  pub fn selected_env_diff(&self) -> Option<String> {
+    let line = self
+      .env_diff
+      .as_ref()?
+      .get(self.env_diff_state.selected()?)?;
+    Some(
+      line
+        .spans
+        .iter()
+        .skip(2)
+        .map(|s| s.content.as_ref())
+        .collect(),
+    )
+  }
+
+  pub fn is_searching_env(&self) -> bool {
+    self.env_search_editing
+  }
+
+  pub fn has_env_search(&self) -> bool {
+    !self.env_search.value().is_empty()
+  }
+
+  /// Get the cursor position of the env search prompt,
+  /// this should be called after render is called
+  pub fn cursor(&self) -> Option<(u16, u16)> {
+    self.env_search_editing.then(|| self.env_search.cursor())
+  }
+
+  fn clear_env_search(&mut self) {
+    self.env_search = TextState::new();
+    self.env_search_editing = false;
+    self.scroll.scroll_to_top();
+  }
+
+  /// The lines of the environment that match the search
+  fn searched_env(&self) -> Vec<Line<'static>> {
+    let env = self.env.clone().unwrap_or_default();
+    let query = self.env_search.value().to_lowercase();
+    if query.is_empty() {
+      return env;
+    }
+    let matched = env
+      .into_iter()
+      .filter(|line| {
+        // Skip the marker
+        let text: String = line
+          .spans
+          .iter()
+          .skip(2)
+          .map(|s| s.content.as_ref())
+          .collect();
+        text.to_lowercase().contains(&query)
+      })
+      .collect_vec();
+    if matched.is_empty() {
+      vec!["No match".set_style(THEME.query_no_match).into()]
+    } else {
+      matched
+    }
+  }
+
+  fn env_diff_len(&self) -> usize {
+    self.env_diff.as_ref().map_or(0, |lines| lines.len())
+  }
+
+  fn env_diff_select(&mut self, index: usize) {
+    let last = self.env_diff_len().saturating_sub(1);
+    self.env_diff_state.select(Some(index.min(last)));
+  }
+
+  fn env_diff_move_down(&mut self, lines: usize) {
+    let current = self.env_diff_state.selected().unwrap_or(0);
+    self.env_diff_select(current.saturating_add(lines));
+  }
+
+  fn env_diff_move_up(&mut self, lines: usize) {
+    let current = self.env_diff_state.selected().unwrap_or(0);
+    self.env_diff_select(current.saturating_sub(lines));
+  }
+
   pub fn next_tab(&mut self) {
     let old = self.tab_index;
     self.tab_index = (self.tab_index + 1).min(self.available_tabs.len() - 1);
@@ -413,6 +706,52 @@ impl DetailsPopupState {
     ke: KeyEvent,
     clipboard: Option<&mut Clipboard>,
   ) -> color_eyre::Result<ControlFlow<()>> {
+    if self.active_tab() == "Environment" {
+      if self.env_search_editing {
+        match (ke.code, ke.modifiers) {
+          (KeyCode::Enter, _) => {
+            self.env_search_editing = false;
+            self.env_search.blur();
+          }
+          (KeyCode::Esc, KeyModifiers::NONE) => self.clear_env_search(),
+          _ => {
+            self.env_search.handle_key_event(ke);
+            self.scroll.scroll_to_top();
+          }
+        }
+        return Ok(ControlFlow::Continue(()));
+      }
+      match (ke.code, ke.modifiers) {
+        (KeyCode::Char('/'), KeyModifiers::NONE) => {
+          self.env_search_editing = true;
+          self.env_search.focus();
+          return Ok(ControlFlow::Continue(()));
+        }
+        (KeyCode::Esc, KeyModifiers::NONE) if self.has_env_search() => {
+          self.clear_env_search();
+          return Ok(ControlFlow::Continue(()));
+        }
+        _ => (),
+      }
+    }
+    if ke.modifiers == KeyModifiers::NONE && self.active_tab() == "Env Diff" {
+      let page = (self.env_diff_page_size as usize).max(1);
+      match ke.code {
+        KeyCode::Down | KeyCode::Char('j') => self.env_diff_move_down(1),
+        KeyCode::Up | KeyCode::Char('k') => self.env_diff_move_up(1),
+        KeyCode::PageDown => self.env_diff_move_down(page),
+        KeyCode::PageUp => self.env_diff_move_up(page),
+        KeyCode::Home => self.env_diff_select(0),
+        KeyCode::End => self.env_diff_select(usize::MAX),
+        KeyCode::Char('c') => {
+          if let Some((clipboard, line)) = clipboard.zip(self.selected_env_diff()) {
+            clipboard.set_text(line)?;
+          }
+        }
+        _ => return self.handle_common_key_event(ke),
+      }
+      return Ok(ControlFlow::Continue(()));
+    }
     if ke.modifiers == KeyModifiers::NONE {
       match ke.code {
         KeyCode::Down | KeyCode::Char('j') => {
@@ -441,12 +780,6 @@ impl DetailsPopupState {
         KeyCode::End => {
           self.scroll_to_bottom();
         }
-        KeyCode::Right | KeyCode::Char('l') => {
-          self.next_tab();
-        }
-        KeyCode::Left | KeyCode::Char('h') => {
-          self.prev_tab();
-        }
         KeyCode::Char('w') => {
           if self.active_tab() == "Info" {
             self.prev();
@@ -457,9 +790,6 @@ impl DetailsPopupState {
             self.next();
           }
         }
-        KeyCode::Char('q') => {
-          return Ok(ControlFlow::Break(()));
-        }
         KeyCode::Char('c') => {
           if self.active_tab() == "Info" {
             if let Some(clipboard) = clipboard {
@@ -467,9 +797,31 @@ impl DetailsPopupState {
             }
           }
         }
+        _ => return self.handle_common_key_event(ke),
+      }
+    }
+    Ok(ControlFlow::Continue(()))
+  }
+
+  /// Handle the keys shared by all the tabs
+  fn handle_common_key_event(&mut self, ke: KeyEvent) -> color_eyre::Result<ControlFlow<()>> {
+    if ke.modifiers == KeyModifiers::NONE {
+      match ke.code {
+        KeyCode::Right | KeyCode::Char('l') => {
+          self.next_tab();
+        }
+        KeyCode::Left | KeyCode::Char('h') => {
+          self.prev_tab();
+        }
+        KeyCode::Char('q') => {
+          return Ok(ControlFlow::Break(()));
+        }
         KeyCode::Tab => {
           self.circle_tab();
         }
+        KeyCode::Char('x') => {
+          self.cycle_escape_style();
+        }
         _ => {}
       }
     }
@@ -517,11 +869,35 @@ impl StatefulWidgetRef for DetailsPopup {
     let start = screen.right().saturating_sub(tabs_width);
     tabs.render_ref(Rect::new(start, 0, tabs_width, 1), buf);
 
+    // The env diff has its own pane with per-line navigation
+    if state.active_tab() == "Env Diff" {
+      state.env_diff_page_size = inner.height;
+      let list = List::new(state.env_diff.clone().unwrap())
+        .highlight_style(THEME.selected_env_diff)
+        .highlight_spacing(HighlightSpacing::Never);
+      StatefulWidget::render(list, inner, buf, &mut state.env_diff_state);
+      return;
+    }
+
+    // The env search prompt takes the last line
+    let inner = if state.active_tab() == "Environment"
+      && (state.env_search_editing || state.has_env_search())
+    {
+      let prompt_area = Rect::new(inner.x, inner.bottom().saturating_sub(1), inner.width, 1);
+      TextPrompt::new("🔍".into()).render(prompt_area, buf, &mut state.env_search);
+      Rect {
+        height: inner.height.saturating_sub(1),
+        ..inner
+      }
+    } else {
+      inner
+    };
+
     // Tab Info
-    let paragraph = match state.tab_index {
-      0 => self.info_paragraph(state),
-      1 => self.env_paragraph(state),
-      2 => self.fd_paragraph(state),
+    let paragraph = match state.active_tab() {
+      "Info" => self.info_paragraph(state),
+      "Environment" => self.env_paragraph(state),
+      "FdInfo" => self.fd_paragraph(state),
       _ => unreachable!(),
     };
 
@@ -572,7 +948,7 @@ impl DetailsPopup {
   }
 
   fn env_paragraph(&self, state: &DetailsPopupState) -> Paragraph {
-    let text = state.env.clone().unwrap();
+    let text = state.searched_env();
     Paragraph::new(text).wrap(Wrap { trim: false })
   }
 
@@ -1,50 +1,18 @@
-use std::{borrow::Cow, fmt::Display, mem, ops::Range};
+use std::fmt::Display;
 
 use ratatui::text::{Line, Span};
 use regex_cursor::{Cursor, IntoCursor};
 
 use crate::regex::{BidirectionalIter, BidirectionalIterator, IntoBidirectionalIterator};
 
-#[derive(Debug, Clone)]
-pub struct Mask {
-  /// The range of the spans to mask
-  pub range: Range<usize>,
-  /// The value of the spans to mask
-  pub values: Vec<Cow<'static, str>>,
-}
-
-impl Mask {
-  pub fn new(range: Range<usize>) -> Self {
-    Self {
-      values: vec![Default::default(); range.len()],
-      range,
-    }
-  }
-
-  pub fn toggle(&mut self, line: &mut Line<'static>) {
-    for (span, value) in line.spans[self.range.clone()]
-      .iter_mut()
-      .zip(self.values.iter_mut())
-    {
-      mem::swap(&mut span.content, value);
-    }
-  }
-}
-
 #[derive(Debug, Clone)]
 pub struct EventLine {
   pub line: Line<'static>,
-  pub cwd_mask: Option<Mask>,
-  pub env_mask: Option<Mask>,
 }
 
 impl From<Line<'static>> for EventLine {
   fn from(line: Line<'static>) -> Self {
-    Self {
-      line,
-      cwd_mask: None,
-      env_mask: None,
-    }
+    Self { line }
   }
 }
 
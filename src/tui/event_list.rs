@@ -16,32 +16,41 @@
 // OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use std::sync::Arc;
+use std::{
+  cmp::Ordering,
+  collections::{BTreeSet, HashMap},
+  sync::Arc,
+  time::{Duration, Instant},
+};
 
+use arcstr::ArcStr;
 use indexmap::IndexMap;
-use nix::sys::signal::Signal;
+use itertools::Itertools;
+use nix::{errno::Errno, unistd::Pid};
 use ratatui::{
   layout::Alignment::Right,
   prelude::{Buffer, Rect},
   style::{Color, Modifier, Style},
-  text::Line,
+  text::{Line, Span},
   widgets::{
-    HighlightSpacing, List, ListItem, ListState, Scrollbar, ScrollbarOrientation,
-    ScrollbarState, StatefulWidget, StatefulWidgetRef, Widget,
+    HighlightSpacing, List, ListItem, ListState, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    StatefulWidget, StatefulWidgetRef, Widget,
   },
 };
 
 use crate::{
-  cli::args::ModifierArgs,
+  cli::{args::ModifierArgs, options::ColorBy},
   event::{
-    EventStatus, ProcessStateUpdate, ProcessStateUpdateEvent, RuntimeModifier, TracerEventDetails,
+    EventStatus, OutputMsg, ProcessStateUpdate, ProcessStateUpdateEvent, RuntimeModifier,
+    TracerEventDetails,
   },
   proc::BaselineInfo,
-  tracer::state::ProcessExit,
 };
 
 use super::{
+  build_progress::BuildProgress,
   event_line::EventLine,
+  export_prompt::ExportScope,
   partial_line::PartialLine,
   query::{Query, QueryResult},
   theme::THEME,
@@ -50,26 +59,177 @@ use super::{
 pub struct Event {
   pub details: Arc<TracerEventDetails>,
   pub status: Option<EventStatus>,
+  /// The progress of the build tool when this event happened
+  pub build_progress: Option<BuildProgress>,
+  /// The key of exec events to find identical execs
+  pub dedup_key: Option<u64>,
+  /// Whether an identical exec happened before this one
+  pub duplicate: bool,
+  /// When the event is received, relative to the creation of the event list
+  pub time: Duration,
 }
 
 impl Event {
   fn to_event_line(&self, list: &EventList) -> EventLine {
+    self.to_line(list, false)
+  }
+
+  fn to_line(&self, list: &EventList, cmdline_only: bool) -> EventLine {
     self.details.to_event_line(
       &list.baseline,
-      false,
+      cmdline_only,
       &list.modifier_args,
       list.runtime_modifier(),
       self.status,
-      true,
     )
   }
+
+  /// The cells of the event in the table layout, except the command
+  fn table_cells(&self) -> [Span<'static>; 4] {
+    let result = match self.details.as_ref() {
+      TracerEventDetails::Exec(exec) if exec.result != 0 => format!(
+        "{} {:?}",
+        self.status.map(<&'static str>::from).unwrap_or_default(),
+        Errno::from_raw(-exec.result as i32)
+      ),
+      _ => self
+        .status
+        .map(<&'static str>::from)
+        .unwrap_or_default()
+        .to_owned(),
+    };
+    [
+      Span::styled(
+        format!("+{:.3}s", self.time.as_secs_f64()),
+        THEME.table_time,
+      ),
+      Span::styled(
+        self
+          .details
+          .pid()
+          .map(|pid| pid.to_string())
+          .unwrap_or_default(),
+        THEME.pid_in_msg,
+      ),
+      Span::styled(
+        self
+          .details
+          .comm()
+          .map(|comm| comm.to_string())
+          .unwrap_or_default(),
+        THEME.comm,
+      ),
+      Span::styled(
+        result,
+        if self.details.is_failed_exec() {
+          THEME.exec_result_failure
+        } else {
+          Style::default()
+        },
+      ),
+    ]
+  }
+}
+
+/// A column of the table layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableColumn {
+  Time,
+  Pid,
+  Comm,
+  Result,
+  Command,
+}
+
+impl TableColumn {
+  const ALL: [Self; 5] = [
+    Self::Time,
+    Self::Pid,
+    Self::Comm,
+    Self::Result,
+    Self::Command,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Time => "Time",
+      Self::Pid => "Pid",
+      Self::Comm => "Comm",
+      Self::Result => "Result",
+      Self::Command => "Command",
+    }
+  }
+
+  /// The next column to sort by, `None` after the last one
+  fn next(self) -> Option<Self> {
+    Self::ALL
+      .iter()
+      .position(|&c| c == self)
+      .and_then(|i| Self::ALL.get(i + 1).copied())
+  }
+}
+
+/// Pad the cell to the width of its column, plus a space between the columns
+fn table_cell(mut cell: Span<'static>, width: usize) -> Span<'static> {
+  let padding = width.saturating_sub(cell.width()) + 1;
+  cell.content.to_mut().push_str(&" ".repeat(padding));
+  cell
+}
+
+/// A run of consecutive events about the same process
+struct EventGroup {
+  pid: Option<Pid>,
+  comm: Option<ArcStr>,
+  /// Index of the first event in the group
+  start: usize,
+  len: usize,
+  collapsed: bool,
+}
+
+/// A row of the event list when events are grouped or filtered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+  /// Index of the group
+  Header(usize),
+  /// Index of the event
+  Event(usize),
+}
+
+/// Quick filter of the exec events by their result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultFilter {
+  #[default]
+  All,
+  Successful,
+  Failed,
+}
+
+impl ResultFilter {
+  fn next(self) -> Self {
+    match self {
+      Self::All => Self::Successful,
+      Self::Successful => Self::Failed,
+      Self::Failed => Self::All,
+    }
+  }
+
+  /// Other events are hidden when only successful or failed execs are shown
+  fn is_shown(self, event: &Event) -> bool {
+    match self {
+      Self::All => true,
+      Self::Successful => {
+        matches!(&*event.details, TracerEventDetails::Exec(exec) if exec.is_successful())
+      }
+      Self::Failed => event.details.is_failed_exec(),
+    }
+  }
 }
 
 pub struct EventList {
   state: ListState,
+  /// The lines of the events are rendered on demand instead of being kept for every event
+  /// so that long sessions fit in memory.
   events: Vec<Event>,
-  /// The string representation of the events, used for searching
-  event_lines: Vec<EventLine>,
   /// Current window of the event list, [start, end)
   window: (usize, usize),
   /// Cache of the list items in the view
@@ -89,14 +249,46 @@ pub struct EventList {
   rt_modifier: RuntimeModifier,
   query: Option<Query>,
   query_result: Option<QueryResult>,
+  /// pid -> ppid, collected from the fork events
+  parents: HashMap<Pid, Pid>,
+  /// Selections before jumping to parents
+  jump_history: Vec<usize>,
+  grouped: bool,
+  groups: Vec<EventGroup>,
+  result_filter: ResultFilter,
+  /// Rows in grouping or filtering mode. The window indexes rows instead of events in these modes.
+  rows: Vec<Row>,
+  /// Wrap long lines instead of scrolling horizontally
+  wrap: bool,
+  /// Indices of the bookmarked events
+  bookmarks: BTreeSet<usize>,
+  /// Hide the execs that are identical to an earlier one
+  dedup: bool,
+  /// dedup key -> (index of the first exec, number of identical execs)
+  exec_counts: HashMap<u64, (usize, usize)>,
+  /// Render the events as a table with aligned columns
+  table: bool,
+  /// Widths of the columns before the command, negotiated from the rows in the window
+  column_widths: [usize; 4],
+  /// The column to sort the table by and whether the order is descending
+  sort: Option<(TableColumn, bool)>,
+  /// The time of the events is relative to this
+  start: Instant,
+  color_by: ColorBy,
+  /// Only show the events of this process and its descendants
+  scope: Option<Pid>,
 }
 
 impl EventList {
-  pub fn new(baseline: Arc<BaselineInfo>, follow: bool, modifier_args: ModifierArgs) -> Self {
+  pub fn new(
+    baseline: Arc<BaselineInfo>,
+    follow: bool,
+    modifier_args: ModifierArgs,
+    color_by: ColorBy,
+  ) -> Self {
     Self {
       state: ListState::default(),
       events: vec![],
-      event_lines: vec![],
       window: (0, 0),
       nr_items_in_window: 0,
       horizontal_offset: 0,
@@ -111,6 +303,22 @@ impl EventList {
       rt_modifier: Default::default(),
       query: None,
       query_result: None,
+      parents: HashMap::new(),
+      jump_history: Vec::new(),
+      grouped: false,
+      groups: Vec::new(),
+      result_filter: ResultFilter::All,
+      rows: Vec::new(),
+      wrap: false,
+      bookmarks: BTreeSet::new(),
+      dedup: false,
+      exec_counts: HashMap::new(),
+      table: false,
+      column_widths: [0; 4],
+      sort: None,
+      start: Instant::now(),
+      color_by,
+      scope: None,
     }
   }
 
@@ -140,29 +348,55 @@ impl EventList {
 
   pub fn toggle_env_display(&mut self) {
     self.rt_modifier.show_env = !self.rt_modifier.show_env;
-    for line in &mut self.event_lines {
-      if let Some(mask) = &mut line.env_mask {
-        mask.toggle(&mut line.line);
-      }
-    }
     self.should_refresh_list_cache = true;
     self.search();
   }
 
   pub fn toggle_cwd_display(&mut self) {
     self.rt_modifier.show_cwd = !self.rt_modifier.show_cwd;
-    for line in &mut self.event_lines {
-      if let Some(mask) = &mut line.cwd_mask {
-        mask.toggle(&mut line.line);
-      }
-    }
     self.should_refresh_list_cache = true;
     self.search();
   }
 
-  /// returns the index of the selected item if there is any
+  pub fn toggle_argv_display(&mut self) {
+    self.rt_modifier.show_argv = !self.rt_modifier.show_argv;
+    self.should_refresh_list_cache = true;
+    self.search();
+  }
+
+  pub fn toggle_filename_display(&mut self) {
+    self.rt_modifier.show_filename = !self.rt_modifier.show_filename;
+    self.should_refresh_list_cache = true;
+    self.search();
+  }
+
+  pub fn toggle_comm_display(&mut self) {
+    self.rt_modifier.show_comm = !self.rt_modifier.show_comm;
+    self.should_refresh_list_cache = true;
+    self.search();
+  }
+
+  pub fn is_wrapping(&self) -> bool {
+    self.wrap
+  }
+
+  pub fn toggle_wrap(&mut self) {
+    self.wrap = !self.wrap;
+    self.horizontal_offset = 0;
+    // Restore the full window. It will be shrunk to fit the wrapped rows when rendering.
+    self.window.1 = self.window.0 + self.max_window_len;
+    self.should_refresh_list_cache = true;
+  }
+
+  /// returns the index of the selected event if there is any.
+  /// The first event of the group is returned if a group header is selected.
   pub fn selection_index(&self) -> Option<usize> {
-    self.state.selected().map(|i| self.window.0 + i)
+    self
+      .state
+      .selected()
+      .map(|i| self.window.0 + i)
+      .filter(|&row| row < self.row_count())
+      .map(|row| self.row_to_event(row))
   }
 
   /// returns the selected item if there is any
@@ -180,9 +414,70 @@ impl EventList {
     self.window
   }
 
-  // TODO: this is ugly due to borrow checking.
-  pub fn window(items: &[EventLine], window: (usize, usize)) -> &[EventLine] {
-    &items[window.0..window.1.min(items.len())]
+  /// Whether the window indexes rows instead of events
+  fn uses_rows(&self) -> bool {
+    self.grouped || self.filters_rows() || self.sort.is_some()
+  }
+
+  /// Sorting the table takes precedence over grouping
+  fn shows_groups(&self) -> bool {
+    self.grouped && self.sort.is_none()
+  }
+
+  /// Whether some events are hidden
+  fn filters_rows(&self) -> bool {
+    self.result_filter != ResultFilter::All || self.dedup || self.scope.is_some()
+  }
+
+  /// Number of rows in the list
+  fn row_count(&self) -> usize {
+    if self.uses_rows() {
+      self.rows.len()
+    } else {
+      self.events.len()
+    }
+  }
+
+  fn row_to_event(&self, row: usize) -> usize {
+    if !self.uses_rows() {
+      return row;
+    }
+    match self.rows[row] {
+      Row::Header(group) => self.groups[group].start,
+      Row::Event(index) => index,
+    }
+  }
+
+  /// Returns the row of the event, expanding its group if it is collapsed.
+  /// The next shown row is returned if the event is filtered out.
+  fn event_to_row(&mut self, index: usize) -> usize {
+    if !self.uses_rows() {
+      return index;
+    }
+    if self.shows_groups() {
+      let group = self
+        .groups
+        .partition_point(|g| g.start <= index)
+        .saturating_sub(1);
+      if self.groups[group].collapsed {
+        self.groups[group].collapsed = false;
+        self.rebuild_rows();
+      }
+    }
+    if self.sort.is_some() {
+      return self
+        .rows
+        .iter()
+        .position(|row| *row == Row::Event(index))
+        .unwrap_or(0);
+    }
+    self
+      .rows
+      .partition_point(|row| match *row {
+        Row::Header(g) => self.groups[g].start <= index,
+        Row::Event(i) => i < index,
+      })
+      .min(self.rows.len().saturating_sub(1))
   }
 
   pub fn statistics(&self) -> Line {
@@ -198,6 +493,127 @@ impl EventList {
   pub fn len(&self) -> usize {
     self.events.len()
   }
+
+  /// Returns the full line of the row and the index of the event if the row is not a group header
+  fn row_line(&self, row: usize) -> (Option<usize>, Line<'static>) {
+    let (index, mut line) = if !self.uses_rows() {
+      (Some(row), self.event_line(row))
+    } else {
+      match self.rows[row] {
+        Row::Header(group) => (None, self.group_header(&self.groups[group])),
+        Row::Event(i) => {
+          let mut line = self.event_line(i);
+          if self.shows_groups() {
+            line.spans.insert(0, Span::raw("  "));
+          }
+          (Some(i), line)
+        }
+      }
+    };
+    if let Some(i) = index {
+      if self.bookmarks.contains(&i) {
+        line.spans.insert(0, Span::styled("★ ", THEME.bookmark));
+      } else if self.table {
+        // Keep the columns aligned
+        line.spans.insert(0, Span::raw("  "));
+      }
+      if let Some(style) = self.event_color(i) {
+        for span in line.spans.iter_mut() {
+          span.style = span.style.patch(style);
+        }
+      }
+      if self.dedup {
+        if let Some(count) = self.repeat_count(i) {
+          line
+            .spans
+            .push(Span::styled(format!(" ×{count}"), THEME.dedup_count));
+        }
+      }
+    }
+    (index, line)
+  }
+
+  /// The line of the event in the current layout
+  fn event_line(&self, index: usize) -> Line<'static> {
+    let event = &self.events[index];
+    if !self.table {
+      return event.to_event_line(self).line;
+    }
+    let mut spans = event
+      .table_cells()
+      .into_iter()
+      .zip(self.column_widths)
+      .map(|(cell, width)| table_cell(cell, width))
+      .collect_vec();
+    spans.extend(event.to_line(self, true).line.spans);
+    Line::from(spans)
+  }
+
+  /// Number of lines the row takes in wrap mode
+  fn row_height(&self, row: usize) -> usize {
+    let width = self.row_line(row).1.width();
+    width.div_ceil(self.inner_width.max(1) as usize).max(1)
+  }
+
+  /// In wrap mode, rows have different heights so the window contains fewer rows
+  /// than `max_window_len`. Slide the window down until the selected row is visible,
+  /// then shrink it to the rows that fit in `height` lines.
+  /// Returns true if the window is changed.
+  fn fit_window(&mut self, height: usize) -> bool {
+    let old_window = self.window;
+    let row_count = self.row_count();
+    let mut start = self.window.0.min(row_count);
+    let selected = self
+      .state
+      .selected()
+      .filter(|_| row_count > 0)
+      .map(|i| (self.window.0 + i).min(row_count - 1));
+    if let Some(selected) = selected {
+      let mut used: usize = (start..=selected).map(|row| self.row_height(row)).sum();
+      while used > height && start < selected {
+        used -= self.row_height(start);
+        start += 1;
+      }
+    }
+    let mut end = start;
+    let mut used = 0;
+    while end < row_count {
+      let row_height = self.row_height(end);
+      // Always show at least one row even if it does not fit
+      if end > start && used + row_height > height {
+        break;
+      }
+      used += row_height;
+      end += 1;
+    }
+    // Leave room for new rows if the end of the list is reached
+    if end == row_count && used < height {
+      end += height - used;
+    }
+    self.window = (start, end);
+    if let Some(selected) = selected {
+      self.state.select(Some(selected - start));
+    }
+    old_window != self.window
+  }
+}
+
+/// The file name of the executable
+fn program_name(filename: &OutputMsg) -> String {
+  let filename: &str = filename.as_ref();
+  filename
+    .rsplit_once('/')
+    .map_or(filename, |(_, name)| name)
+    .to_owned()
+}
+
+/// Split the line into lines that are no wider than `width`
+fn wrap_line(line: Line<'static>, width: u16) -> Vec<Line<'static>> {
+  let width = width.max(1);
+  let height = line.width().div_ceil(width as usize).max(1);
+  (0..height)
+    .map(|i| line.clone().substring(i * width as usize, width))
+    .collect()
 }
 
 impl Widget for &mut EventList {
@@ -205,11 +621,35 @@ impl Widget for &mut EventList {
   where
     Self: Sized,
   {
+    // The header of the table takes the first line
+    let area = if self.table {
+      let header = self
+        .table_header()
+        .substring(self.horizontal_offset, area.width - 2);
+      header.render(Rect::new(area.x + 2, area.y, area.width - 2, 1), buf);
+      Rect {
+        y: area.y + 1,
+        height: area.height - 1,
+        ..area
+      }
+    } else {
+      area
+    };
     self.inner_width = area.width - 2; // for the selection indicator
-    let mut max_len = area.width as usize - 1;
-    // Iterate through all elements in the `items` and stylize them.
-    let events_in_window = EventList::window(&self.event_lines, self.window);
-    self.nr_items_in_window = events_in_window.len();
+    if self.wrap && self.fit_window(area.height as usize) {
+      self.should_refresh_list_cache = true;
+    }
+    // Lines never exceed the inner width in wrap mode so there is nothing to scroll horizontally
+    let mut max_len = if self.wrap {
+      self.inner_width as usize
+    } else {
+      area.width as usize - 1
+    };
+    self.nr_items_in_window = self
+      .window
+      .1
+      .min(self.row_count())
+      .saturating_sub(self.window.0);
     // tracing::debug!(
     //   "Should refresh list cache: {}",
     //   self.should_refresh_list_cache
@@ -217,27 +657,33 @@ impl Widget for &mut EventList {
     if self.should_refresh_list_cache {
       self.should_refresh_list_cache = false;
       tracing::debug!("Refreshing list cache");
-      let items = self
-        .event_lines
-        .iter()
-        .enumerate()
-        .skip(self.window.0)
-        .take(self.window.1 - self.window.0)
-        .map(|(i, full_line)| {
-          max_len = max_len.max(full_line.line.width());
-          let highlighted = self
+      if self.table {
+        self.column_widths = self.negotiate_column_widths();
+      }
+      let items = (self.window.0..self.window.1.min(self.row_count())).map(|row| {
+        let (i, full_line) = self.row_line(row);
+        if !self.wrap {
+          max_len = max_len.max(full_line.width());
+        }
+        let highlighted = i.is_some_and(|i| {
+          self
             .query_result
             .as_ref()
-            .map_or(false, |query_result| query_result.indices.contains_key(&i));
-          let mut base = full_line
-            .line
-            .clone()
-            .substring(self.horizontal_offset, area.width);
-          if highlighted {
-            base = base.style(THEME.search_match);
-          }
-          ListItem::from(base)
+            .is_some_and(|query_result| query_result.indices.contains_key(&i))
         });
+        let mut lines = if self.wrap {
+          wrap_line(full_line, self.inner_width)
+        } else {
+          vec![full_line.substring(self.horizontal_offset, area.width)]
+        };
+        if highlighted {
+          lines = lines
+            .into_iter()
+            .map(|line| line.style(THEME.search_match))
+            .collect();
+        }
+        ListItem::new(lines)
+      });
       // Create a List from all list items and highlight the currently selected one
       let list = List::new(items)
         .highlight_style(
@@ -275,7 +721,7 @@ impl Widget for &mut EventList {
           .position(self.horizontal_offset),
       );
     }
-    if self.events.len() > area.height as usize {
+    if self.row_count() > area.height as usize {
       // Render vertical scrollbar
       let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
       let scrollbar_area = Rect {
@@ -287,30 +733,19 @@ impl Widget for &mut EventList {
       scrollbar.render(
         scrollbar_area,
         buf,
-        &mut ScrollbarState::new(self.events.len() - area.height as usize)
+        &mut ScrollbarState::new(self.row_count() - area.height as usize)
           .position(self.window.0 + self.state.selected().unwrap_or(0)),
       );
     }
-
-    if let Some(query_result) = self.query_result.as_ref() {
-      let statistics = query_result.statistics();
-      let statistics_len = statistics.width();
-      if statistics_len > buf.area().width as usize {
-        return;
-      }
-      let statistics_area = Rect {
-        x: buf.area().right().saturating_sub(statistics_len as u16),
-        y: 1,
-        width: statistics_len as u16,
-        height: 1,
-      };
-      statistics.render(statistics_area, buf);
-    }
   }
 }
 
 /// Query Management
 impl EventList {
+  pub fn query_result(&self) -> Option<&QueryResult> {
+    self.query_result.as_ref()
+  }
+
   pub fn set_query(&mut self, query: Option<Query>) {
     if query.is_some() {
       self.query = query;
@@ -333,8 +768,8 @@ impl EventList {
     // Events won't change during the search because this is Rust and we already have a reference to it.
     // Rust really makes the code more easier to reason about.
     let searched_len = self.events.len();
-    for (i, evt) in self.event_lines.iter().enumerate() {
-      if query.matches(evt) {
+    for (i, evt) in self.events.iter().enumerate() {
+      if query.matches(&evt.to_event_line(self)) {
         indices.insert(i, 0);
       }
     }
@@ -355,23 +790,24 @@ impl EventList {
     let Some(query) = self.query.as_ref() else {
       return;
     };
-    let Some(existing_result) = self.query_result.as_mut() else {
+    let Some(existing_result) = self.query_result.as_ref() else {
       self.search();
       return;
     };
-    let mut modified = false;
-    for (i, evt) in self
-      .event_lines
+    let matched = self
+      .events
       .iter()
       .enumerate()
       .skip(existing_result.searched_len)
-    {
-      if query.matches(evt) {
-        existing_result.indices.insert(i, 0);
-        modified = true;
-      }
+      .filter(|(_, evt)| query.matches(&evt.to_event_line(self)))
+      .map(|(i, _)| i)
+      .collect::<Vec<_>>();
+    let existing_result = self.query_result.as_mut().unwrap();
+    existing_result.searched_len = self.events.len();
+    let modified = !matched.is_empty();
+    for i in matched {
+      existing_result.indices.insert(i, 0);
     }
-    existing_result.searched_len = self.event_lines.len();
     if modified {
       self.should_refresh_list_cache = true;
     }
@@ -396,13 +832,138 @@ impl EventList {
   }
 }
 
+/// Bookmarks
+impl EventList {
+  /// Bookmark the selected event or remove its bookmark
+  pub fn toggle_bookmark(&mut self) {
+    if let Some(index) = self.selection_index() {
+      if !self.bookmarks.remove(&index) {
+        self.bookmarks.insert(index);
+      }
+      self.should_refresh_list_cache = true;
+    }
+  }
+
+  /// Select the next bookmarked event, wrapping around at the end
+  pub fn next_bookmark(&mut self) {
+    let marks = self.bookmarks.range(..self.events.len());
+    let next = match self.selection_index() {
+      Some(current) => marks
+        .clone()
+        .find(|&&i| i > current)
+        .or_else(|| marks.clone().next()),
+      None => marks.clone().next(),
+    };
+    self.jump_to_bookmark(next.copied());
+  }
+
+  /// Select the previous bookmarked event, wrapping around at the start
+  pub fn prev_bookmark(&mut self) {
+    let mut marks = self.bookmarks.range(..self.events.len());
+    let prev = match self.selection_index() {
+      Some(current) => marks
+        .clone()
+        .rev()
+        .find(|&&i| i < current)
+        .or_else(|| marks.next_back()),
+      None => marks.next_back(),
+    };
+    self.jump_to_bookmark(prev.copied());
+  }
+
+  fn jump_to_bookmark(&mut self, index: Option<usize>) {
+    if index.is_some() {
+      self.scroll_to(index);
+      self.stop_follow();
+    }
+  }
+
+  /// Indices of the bookmarked events in ascending order
+  pub fn bookmarks(&self) -> impl Iterator<Item = usize> + '_ {
+    self.bookmarks.iter().copied()
+  }
+
+  /// Replace the bookmarks, e.g. with the ones of a loaded session.
+  /// The events they refer to may be pushed later.
+  pub fn set_bookmarks(&mut self, bookmarks: impl IntoIterator<Item = usize>) {
+    self.bookmarks = bookmarks.into_iter().collect();
+    self.should_refresh_list_cache = true;
+  }
+}
+
+/// Export
+impl EventList {
+  /// Indices of the events to export in ascending order
+  pub fn export_indices(&self, scope: ExportScope) -> Vec<usize> {
+    match scope {
+      ExportScope::Selected => self.selection_index().into_iter().collect(),
+      ExportScope::Subtree => {
+        let Some(root) = self.selection().and_then(|e| e.details.pid()) else {
+          return vec![];
+        };
+        (0..self.events.len())
+          .filter(|&i| {
+            self.events[i]
+              .details
+              .pid()
+              .is_some_and(|pid| self.is_descendant_or_self(pid, root))
+          })
+          .collect()
+      }
+      ExportScope::View => (0..self.events.len())
+        .filter(|&i| self.is_shown(&self.events[i]))
+        .collect(),
+      ExportScope::Bookmarks => self.bookmarks.range(..self.events.len()).copied().collect(),
+    }
+  }
+
+  /// The line of the event as shown in the list, without styles
+  pub fn event_text(&self, index: usize) -> String {
+    self.events[index].to_event_line(self).line.to_string()
+  }
+
+  pub fn event_details(&self, index: usize) -> Arc<TracerEventDetails> {
+    self.events[index].details.clone()
+  }
+
+  pub fn is_bookmarked(&self, index: usize) -> bool {
+    self.bookmarks.contains(&index)
+  }
+
+  fn is_descendant_or_self(&self, mut pid: Pid, root: Pid) -> bool {
+    // Bounded by the number of processes in case the pids are reused
+    for _ in 0..=self.parents.len() {
+      if pid == root {
+        return true;
+      }
+      match self.parents.get(&pid) {
+        Some(&ppid) => pid = ppid,
+        None => return false,
+      }
+    }
+    false
+  }
+}
+
 /// Event Management
 impl EventList {
-  pub fn push(&mut self, event: impl Into<Arc<TracerEventDetails>>) {
+  pub fn push(
+    &mut self,
+    event: impl Into<Arc<TracerEventDetails>>,
+    build_progress: Option<BuildProgress>,
+  ) {
     let event = event.into();
-    let event = Event {
+    if let TracerEventDetails::NewChild { ppid, pid, .. } = event.as_ref() {
+      self.parents.insert(*pid, *ppid);
+    }
+    let mut event = Event {
+      dedup_key: match event.as_ref() {
+        TracerEventDetails::Exec(exec) => Some(exec.dedup_key()),
+        _ => None,
+      },
       status: match event.as_ref() {
         TracerEventDetails::NewChild { .. } => Some(EventStatus::ProcessRunning),
+        TracerEventDetails::ProcessExit { exit, .. } => Some((*exit).into()),
         TracerEventDetails::Exec(exec) => {
           match exec.result {
             0 => Some(EventStatus::ProcessRunning),
@@ -413,11 +974,78 @@ impl EventList {
         _ => None,
       },
       details: event,
+      build_progress,
+      duplicate: false,
+      time: self.start.elapsed(),
     };
-    self.event_lines.push(event.to_event_line(self));
+    let index = self.events.len();
+    if let Some(key) = event.dedup_key {
+      let (first, count) = self.exec_counts.entry(key).or_insert((index, 0));
+      *count += 1;
+      event.duplicate = *first != index;
+      // The count shown on the first exec changes
+      if event.duplicate && self.dedup {
+        self.should_refresh_list_cache = true;
+      }
+    }
+    let pid = event.details.pid();
+    let comm = event.details.comm();
+    let shown = self.is_shown(&event);
     self.events.push(event);
+    let same_process = self.groups.last().is_some_and(|group| group.pid == pid);
+    if same_process {
+      let shows_groups = self.shows_groups();
+      let group = self.groups.last_mut().unwrap();
+      group.len += 1;
+      if comm.is_some() {
+        group.comm = comm;
+      }
+      if shows_groups && !group.collapsed && shown {
+        self.rows.push(Row::Event(index));
+      }
+    } else {
+      self.groups.push(EventGroup {
+        pid,
+        comm,
+        start: index,
+        len: 1,
+        collapsed: false,
+      });
+      if self.shows_groups() {
+        self.rows.push(Row::Header(self.groups.len() - 1));
+        if shown {
+          self.rows.push(Row::Event(index));
+        }
+      }
+    }
+    let sorted_row = (self.sort.is_some() && shown).then(|| {
+      let row = self.rows.partition_point(|row| match *row {
+        Row::Event(i) => self.compare_events(i, index) == Ordering::Less,
+        Row::Header(_) => true,
+      });
+      self.rows.insert(row, Row::Event(index));
+      row
+    });
+    if !self.grouped && self.sort.is_none() && self.filters_rows() && shown {
+      self.rows.push(Row::Event(index));
+    }
+    // The first row that needs to be redrawn. The header shows the number of events.
+    let first_changed_row = if self.sort.is_some() {
+      sorted_row.unwrap_or(usize::MAX)
+    } else if self.grouped {
+      let header = Row::Header(self.groups.len() - 1);
+      self.rows.iter().rposition(|row| *row == header).unwrap()
+    } else if self.uses_rows() {
+      if shown {
+        self.rows.len() - 1
+      } else {
+        usize::MAX
+      }
+    } else {
+      index
+    };
     self.incremental_search();
-    if (self.window.0..self.window.1).contains(&(self.events.len() - 1)) {
+    if first_changed_row < self.window.1 {
       self.should_refresh_list_cache = true;
     }
   }
@@ -425,57 +1053,454 @@ impl EventList {
   pub fn update(&mut self, update: ProcessStateUpdateEvent) {
     for i in update.ids {
       let i = i as usize;
-      if let TracerEventDetails::Exec(exec) = self.events[i].details.as_ref() {
-        if exec.result != 0 {
-          // Don't update the status for failed exec events
-          continue;
-        }
+      if self.events[i].details.is_failed_exec() {
+        // Don't update the status for failed exec events
+        continue;
       }
       self.events[i].status = match update.update {
-        ProcessStateUpdate::Exit(ProcessExit::Code(0)) => Some(EventStatus::ProcessExitedNormally),
-        ProcessStateUpdate::Exit(ProcessExit::Code(c)) => {
-          Some(EventStatus::ProcessExitedAbnormally(c))
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGTERM)) => {
-          Some(EventStatus::ProcessTerminated)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGKILL)) => {
-          Some(EventStatus::ProcessKilled)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGINT)) => {
-          Some(EventStatus::ProcessInterrupted)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGSEGV)) => {
-          Some(EventStatus::ProcessSegfault)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGABRT)) => {
-          Some(EventStatus::ProcessAborted)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(Signal::SIGILL)) => {
-          Some(EventStatus::ProcessIllegalInstruction)
-        }
-        ProcessStateUpdate::Exit(ProcessExit::Signal(s)) => Some(EventStatus::ProcessSignaled(s)),
+        ProcessStateUpdate::Exit(exit) => Some(exit.into()),
         ProcessStateUpdate::BreakPointHit { .. } => Some(EventStatus::ProcessPaused),
         ProcessStateUpdate::Resumed => Some(EventStatus::ProcessRunning),
         ProcessStateUpdate::Detached { .. } => Some(EventStatus::ProcessDetached),
         _ => unimplemented!(),
       };
-      self.event_lines[i] = self.events[i].to_event_line(self);
-      if self.window.0 <= i && i < self.window.1 {
+      // Finding the row of the event is not worth it in grouping or filtering mode
+      if self.uses_rows() || (self.window.0 <= i && i < self.window.1) {
         self.should_refresh_list_cache = true;
       }
     }
   }
 
   pub fn rebuild_lines(&mut self) {
-    // TODO: only update spans that are affected by the change
-    self.event_lines = self
-      .events
+    // The lines are rendered on demand so only the view needs to be refreshed
+    self.should_refresh_list_cache = true;
+  }
+}
+
+/// Process tree navigation
+impl EventList {
+  /// Find the last successful exec event of the closest ancestor that happens before
+  /// the exec event at `index`.
+  fn find_parent_exec(&self, index: usize) -> Option<usize> {
+    let TracerEventDetails::Exec(exec) = self.events[index].details.as_ref() else {
+      return None;
+    };
+    self.find_ancestor_exec(exec.pid, index)
+  }
+
+  /// Find the last successful exec event of the closest ancestor of `pid` that happens before
+  /// the event at `index`.
+  fn find_ancestor_exec(&self, mut pid: Pid, index: usize) -> Option<usize> {
+    // Bound the walk in case pid reuse forms a cycle
+    for _ in 0..self.parents.len() {
+      let ppid = *self.parents.get(&pid)?;
+      let parent_exec = self.events[..index].iter().rposition(|e| {
+        matches!(e.details.as_ref(), TracerEventDetails::Exec(exec) if exec.pid == ppid && exec.result == 0)
+      });
+      if parent_exec.is_some() {
+        return parent_exec;
+      }
+      // The parent process did not exec. Try its parent.
+      pid = ppid;
+    }
+    None
+  }
+
+  /// The chain of programs that lead to the selected event, e.g. `make → sh → cc1`
+  pub fn breadcrumb(&self) -> Option<Line<'static>> {
+    /// Only the closest ancestors are shown
+    const MAX_DEPTH: usize = 8;
+    let index = self.selection_index()?;
+    let event = &self.events[index];
+    let (pid, name) = match event.details.as_ref() {
+      TracerEventDetails::Exec(exec) => (exec.pid, program_name(&exec.filename)),
+      details => (details.pid()?, details.comm()?.to_string()),
+    };
+    let mut names = vec![name];
+    let mut ancestor = self.find_ancestor_exec(pid, index);
+    while let Some(i) = ancestor {
+      if names.len() == MAX_DEPTH {
+        names.push("…".to_owned());
+        break;
+      }
+      let TracerEventDetails::Exec(exec) = self.events[i].details.as_ref() else {
+        unreachable!()
+      };
+      names.push(program_name(&exec.filename));
+      ancestor = self.find_ancestor_exec(exec.pid, i);
+    }
+    let last = names.len() - 1;
+    let spans = names.into_iter().rev().enumerate().flat_map(|(i, name)| {
+      let separator = (i > 0).then(|| Span::styled(" → ", THEME.breadcrumb_separator));
+      let style = if i == last {
+        THEME.breadcrumb_current
+      } else {
+        THEME.breadcrumb
+      };
+      separator.into_iter().chain([Span::styled(name, style)])
+    });
+    Some(Line::from_iter(spans))
+  }
+
+  /// Select the exec event of the parent process. Returns false if it is not found.
+  pub fn jump_to_parent(&mut self) -> bool {
+    let Some(index) = self.selection_index() else {
+      return false;
+    };
+    let Some(parent) = self.find_parent_exec(index) else {
+      return false;
+    };
+    self.jump_history.push(index);
+    self.stop_follow();
+    self.scroll_to(Some(parent));
+    true
+  }
+
+  /// Select the event before the last jump to parent. Returns false if there is none.
+  pub fn jump_back(&mut self) -> bool {
+    let Some(index) = self.jump_history.pop() else {
+      return false;
+    };
+    self.stop_follow();
+    self.scroll_to(Some(index));
+    true
+  }
+}
+
+/// Grouping of events by process
+impl EventList {
+  pub fn is_grouped(&self) -> bool {
+    self.grouped
+  }
+
+  pub fn toggle_grouping(&mut self) {
+    let selection = self.selection_index();
+    self.grouped = !self.grouped;
+    self.rebuild_rows();
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    self.scroll_to(selection);
+  }
+
+  /// Collapse or expand the group of the selected event
+  pub fn toggle_group_collapse(&mut self) {
+    if !self.grouped {
+      return;
+    }
+    let Some(index) = self.selection_index() else {
+      return;
+    };
+    let group = self
+      .groups
+      .partition_point(|g| g.start <= index)
+      .saturating_sub(1);
+    self.groups[group].collapsed = !self.groups[group].collapsed;
+    self.rebuild_rows();
+    let header_row = self
+      .rows
       .iter()
-      .map(|evt| evt.to_event_line(self))
-      .collect();
+      .position(|row| *row == Row::Header(group))
+      .unwrap();
+    self.scroll_to_row(Some(header_row));
+  }
+
+  fn rebuild_rows(&mut self) {
+    let mut rows = Vec::new();
+    if let Some((column, descending)) = self.sort {
+      let mut indices = (0..self.events.len())
+        .filter(|&i| self.is_shown(&self.events[i]))
+        .collect_vec();
+      indices.sort_by_cached_key(|&i| (self.sort_key(i, column), i));
+      if descending {
+        indices.reverse();
+      }
+      rows.extend(indices.into_iter().map(Row::Event));
+    } else if self.grouped {
+      for (i, group) in self.groups.iter().enumerate() {
+        rows.push(Row::Header(i));
+        if !group.collapsed {
+          rows.extend(
+            (group.start..group.start + group.len)
+              .filter(|&i| self.is_shown(&self.events[i]))
+              .map(Row::Event),
+          );
+        }
+      }
+    } else if self.filters_rows() {
+      rows.extend(
+        (0..self.events.len())
+          .filter(|&i| self.is_shown(&self.events[i]))
+          .map(Row::Event),
+      );
+    }
+    self.rows = rows;
+    self.should_refresh_list_cache = true;
+  }
+
+  fn group_header(&self, group: &EventGroup) -> Line<'static> {
+    let mut spans = vec![Span::raw(if group.collapsed { "▶ " } else { "▼ " })];
+    match group.pid {
+      Some(pid) => {
+        spans.push(Span::styled(pid.to_string(), THEME.pid_in_msg));
+        if let Some(comm) = group.comm.as_ref() {
+          spans.push(Span::styled(format!("<{comm}>"), THEME.comm));
+        }
+      }
+      None => spans.push(Span::styled("tracexec", THEME.tracer_event)),
+    }
+    spans.push(Span::styled(
+      format!(
+        " ({} event{})",
+        group.len,
+        if group.len == 1 { "" } else { "s" }
+      ),
+      THEME.group_header,
+    ));
+    Line::from(spans)
+  }
+}
+
+/// Table layout
+impl EventList {
+  pub fn is_table(&self) -> bool {
+    self.table
+  }
+
+  pub fn sort(&self) -> Option<(TableColumn, bool)> {
+    self.sort
+  }
+
+  /// Switch between the table layout and the single-line layout
+  pub fn toggle_table(&mut self) {
+    self.table = !self.table;
+    self.horizontal_offset = 0;
+    if !self.table && self.sort.is_some() {
+      self.sort = None;
+      self.relayout_rows();
+    }
     self.should_refresh_list_cache = true;
   }
+
+  /// Sort the table by the next column, or stop sorting after the last column
+  pub fn cycle_sort_column(&mut self) {
+    if !self.table {
+      return;
+    }
+    self.sort = match self.sort {
+      None => Some((TableColumn::Time, false)),
+      Some((column, descending)) => column.next().map(|column| (column, descending)),
+    };
+    self.relayout_rows();
+  }
+
+  pub fn reverse_sort_order(&mut self) {
+    if let Some((_, descending)) = self.sort.as_mut() {
+      *descending = !*descending;
+      self.relayout_rows();
+    }
+  }
+
+  /// Rebuild the rows and keep the selected event selected
+  fn relayout_rows(&mut self) {
+    let selection = self.selection_index();
+    self.rebuild_rows();
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    self.scroll_to(selection);
+  }
+
+  fn sort_key(&self, index: usize, column: TableColumn) -> (i64, String) {
+    let event = &self.events[index];
+    match column {
+      // The events are received in order
+      TableColumn::Time => (index as i64, String::new()),
+      TableColumn::Pid => (
+        event.details.pid().map_or(0, |pid| pid.as_raw().into()),
+        String::new(),
+      ),
+      TableColumn::Comm => (
+        0,
+        event
+          .details
+          .comm()
+          .map(|comm| comm.to_string())
+          .unwrap_or_default(),
+      ),
+      TableColumn::Result => match event.details.as_ref() {
+        TracerEventDetails::Exec(exec) => (-exec.result, String::new()),
+        _ => (i64::MAX, String::new()),
+      },
+      TableColumn::Command => (0, event.to_line(self, true).line.to_string()),
+    }
+  }
+
+  /// Compare two events in the sort order of the table
+  fn compare_events(&self, a: usize, b: usize) -> Ordering {
+    let Some((column, descending)) = self.sort else {
+      return a.cmp(&b);
+    };
+    let ordering = (self.sort_key(a, column), a).cmp(&(self.sort_key(b, column), b));
+    if descending {
+      ordering.reverse()
+    } else {
+      ordering
+    }
+  }
+
+  fn header_cells(&self) -> [Span<'static>; 5] {
+    TableColumn::ALL.map(|column| {
+      let indicator = match self.sort {
+        Some((sorted, false)) if sorted == column => " ▲",
+        Some((sorted, true)) if sorted == column => " ▼",
+        _ => "",
+      };
+      Span::styled(format!("{}{indicator}", column.name()), THEME.table_header)
+    })
+  }
+
+  /// Each column is as wide as its widest cell in the window
+  fn negotiate_column_widths(&self) -> [usize; 4] {
+    let header = self.header_cells();
+    let mut widths = [0; 4];
+    for (width, cell) in widths.iter_mut().zip(header.iter()) {
+      *width = cell.width();
+    }
+    for row in self.window.0..self.window.1.min(self.row_count()) {
+      let index = if self.uses_rows() {
+        match self.rows[row] {
+          Row::Event(i) => i,
+          Row::Header(_) => continue,
+        }
+      } else {
+        row
+      };
+      for (width, cell) in widths.iter_mut().zip(self.events[index].table_cells()) {
+        *width = (*width).max(cell.width());
+      }
+    }
+    widths
+  }
+
+  fn table_header(&self) -> Line<'static> {
+    let [time, pid, comm, result, command] = self.header_cells();
+    // The gutter of the bookmarks and the indentation of the groups
+    let mut spans = vec![Span::raw(if self.shows_groups() { "    " } else { "  " })];
+    spans.extend(
+      [time, pid, comm, result]
+        .into_iter()
+        .zip(self.column_widths)
+        .map(|(cell, width)| table_cell(cell, width)),
+    );
+    spans.push(command);
+    Line::from(spans)
+  }
+}
+
+/// Coloring of events
+impl EventList {
+  pub fn color_by(&self) -> ColorBy {
+    self.color_by
+  }
+
+  pub fn cycle_color_by(&mut self) {
+    self.color_by = self.color_by.next();
+    self.should_refresh_list_cache = true;
+  }
+
+  /// The style that overrides the colors of the theme for the event
+  fn event_color(&self, index: usize) -> Option<Style> {
+    let event = &self.events[index];
+    match self.color_by {
+      ColorBy::Theme => None,
+      ColorBy::Status => event
+        .details
+        .is_failed_exec()
+        .then_some(THEME.failed_exec_line),
+      ColorBy::Process => event.details.pid().map(|pid| {
+        let palette = &THEME.process_palette;
+        Style::default().fg(palette[pid.as_raw().unsigned_abs() as usize % palette.len()])
+      }),
+    }
+  }
+}
+
+/// Filtering of events by exec result
+impl EventList {
+  pub fn result_filter(&self) -> ResultFilter {
+    self.result_filter
+  }
+
+  /// Cycle between showing all events, only successful execs and only failed execs
+  pub fn cycle_result_filter(&mut self) {
+    let selection = self.selection_index();
+    self.result_filter = self.result_filter.next();
+    self.rebuild_rows();
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    self.scroll_to(selection);
+  }
+
+  fn is_shown(&self, event: &Event) -> bool {
+    self.result_filter.is_shown(event)
+      && !(self.dedup && event.duplicate)
+      && self.scope.is_none_or(|root| {
+        event
+          .details
+          .pid()
+          .is_some_and(|pid| self.is_descendant_or_self(pid, root))
+      })
+  }
+}
+
+/// Filtering of events by process subtree
+impl EventList {
+  pub fn scope(&self) -> Option<Pid> {
+    self.scope
+  }
+
+  /// Only show the process of the selected event and its descendants,
+  /// or show all the events again if the list is already scoped
+  pub fn toggle_scope(&mut self) {
+    let scope = match self.scope {
+      Some(_) => None,
+      None => match self.selection().and_then(|e| e.details.pid()) {
+        Some(pid) => Some(pid),
+        None => return,
+      },
+    };
+    let selection = self.selection_index();
+    self.scope = scope;
+    self.rebuild_rows();
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    self.scroll_to(selection);
+  }
+}
+
+/// Deduplication of identical execs
+impl EventList {
+  pub fn is_deduplicating(&self) -> bool {
+    self.dedup
+  }
+
+  /// Show identical execs only once
+  pub fn toggle_dedup(&mut self) {
+    let selection = self.selection_index();
+    self.dedup = !self.dedup;
+    self.rebuild_rows();
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    self.scroll_to(selection);
+  }
+
+  /// The number of identical execs if the event is the first of them and it is repeated
+  fn repeat_count(&self, index: usize) -> Option<usize> {
+    self.events[index]
+      .dedup_key
+      .and_then(|key| self.exec_counts.get(&key))
+      .filter(|&&(first, count)| first == index && count > 1)
+      .map(|&(_, count)| count)
+  }
 }
 
 /// Scrolling implementation for the EventList
@@ -486,6 +1511,11 @@ impl EventList {
   /// no scrolling will be done,
   /// And if the item is in the last window, we won't scroll past it.
   fn scroll_to(&mut self, index: Option<usize>) {
+    let row = index.map(|i| self.event_to_row(i));
+    self.scroll_to_row(row);
+  }
+
+  fn scroll_to_row(&mut self, index: Option<usize>) {
     let Some(index) = index else {
       return;
     };
@@ -497,7 +1527,7 @@ impl EventList {
       self.state.select(Some(0));
     } else if index >= self.window.1 {
       // Scroll down
-      self.window.0 = index.min(self.events.len().saturating_sub(self.max_window_len));
+      self.window.0 = index.min(self.row_count().saturating_sub(self.max_window_len));
       self.window.1 = self.window.0 + self.max_window_len;
       self.should_refresh_list_cache = true;
       self.state.select(Some(index - self.window.0));
@@ -517,7 +1547,7 @@ impl EventList {
         .window
         .1
         .saturating_sub(1)
-        .min(self.events.len().saturating_sub(1)),
+        .min(self.row_count().saturating_sub(1)),
     )
   }
 
@@ -528,7 +1558,7 @@ impl EventList {
         self
           .window
           .1
-          .min(self.events.len())
+          .min(self.row_count())
           .saturating_sub(self.window.0)
           .saturating_sub(1),
       )
@@ -555,7 +1585,7 @@ impl EventList {
     if self.events.is_empty() {
       return false;
     }
-    if self.window.1 < self.events.len() {
+    if self.window.1 < self.row_count() {
       self.window.0 += 1;
       self.window.1 += 1;
       self.should_refresh_list_cache = true;
@@ -619,7 +1649,7 @@ impl EventList {
   }
 
   pub fn page_down(&mut self) {
-    if self.window.1 + self.max_window_len <= self.events.len() {
+    if self.window.1 + self.max_window_len <= self.row_count() {
       self.window.0 += self.max_window_len;
       self.window.1 += self.max_window_len;
       self.should_refresh_list_cache = true;
@@ -627,7 +1657,7 @@ impl EventList {
       // If we can't slide down the window by the number of items in the window
       // just set the window to the last items
       let old_window = self.window;
-      self.window.0 = self.events.len().saturating_sub(self.max_window_len);
+      self.window.0 = self.row_count().saturating_sub(self.max_window_len);
       self.window.1 = self.window.0 + self.max_window_len;
       self.should_refresh_list_cache = old_window != self.window;
     }
@@ -707,7 +1737,7 @@ impl EventList {
       return;
     }
     let old_window = self.window;
-    self.window.0 = self.events.len().saturating_sub(self.max_window_len);
+    self.window.0 = self.row_count().saturating_sub(self.max_window_len);
     self.window.1 = self.window.0 + self.max_window_len;
     self.select_last();
     self.should_refresh_list_cache = old_window != self.window;
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  text::Span,
+  widgets::{StatefulWidget, Widget},
+};
+use strum::Display;
+use tui_prompts::{State, TextPrompt, TextState};
+
+use crate::action::Action;
+
+use super::help::help_item;
+
+/// Which events to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ExportScope {
+  #[strum(serialize = "the selected event")]
+  Selected,
+  /// The events of the process of the selected event and its descendants
+  #[strum(serialize = "the process tree of the selected event")]
+  Subtree,
+  /// The events that pass the current filter
+  #[strum(serialize = "the events in view")]
+  View,
+  #[strum(serialize = "the bookmarked events")]
+  Bookmarks,
+}
+
+impl ExportScope {
+  fn next(self) -> Self {
+    match self {
+      Self::Selected => Self::Subtree,
+      Self::Subtree => Self::View,
+      Self::View => Self::Bookmarks,
+      Self::Bookmarks => Self::Selected,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ExportFormat {
+  /// The lines as shown in the event list
+  #[strum(serialize = "text")]
+  Text,
+  /// The record format of `tracexec collect`, which could be loaded with `--load`
+  #[strum(serialize = "JSON")]
+  Json,
+  /// A shell script that runs the successful execs again
+  #[strum(serialize = "a shell script")]
+  ShellScript,
+}
+
+impl ExportFormat {
+  fn next(self) -> Self {
+    match self {
+      Self::Text => Self::Json,
+      Self::Json => Self::ShellScript,
+      Self::ShellScript => Self::Text,
+    }
+  }
+}
+
+/// A prompt for the file that the events are exported to
+pub struct ExportPrompt {
+  state: TextState<'static>,
+  scope: ExportScope,
+  format: ExportFormat,
+}
+
+impl Default for ExportPrompt {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ExportPrompt {
+  pub fn new() -> Self {
+    let mut state = TextState::new();
+    state.focus();
+    Self {
+      state,
+      scope: ExportScope::Selected,
+      format: ExportFormat::Text,
+    }
+  }
+
+  /// Get the current cursor position,
+  /// this should be called after render is called
+  pub fn cursor(&self) -> (u16, u16) {
+    self.state.cursor()
+  }
+
+  pub fn handle_key_event(&mut self, key: KeyEvent) -> Option<Action> {
+    match (key.code, key.modifiers) {
+      (KeyCode::Enter, _) => {
+        let path = self.state.value().trim();
+        if path.is_empty() {
+          return Some(Action::CloseExportPrompt);
+        }
+        Some(Action::ExportEvents {
+          path: PathBuf::from(path),
+          scope: self.scope,
+          format: self.format,
+        })
+      }
+      (KeyCode::Esc, KeyModifiers::NONE) => Some(Action::CloseExportPrompt),
+      (KeyCode::Tab, KeyModifiers::NONE) => {
+        self.scope = self.scope.next();
+        None
+      }
+      (KeyCode::BackTab, _) => {
+        self.format = self.format.next();
+        None
+      }
+      _ => {
+        self.state.handle_key_event(key);
+        None
+      }
+    }
+  }
+
+  pub fn help(&self) -> impl Iterator<Item = Span<'_>> {
+    [
+      help_item!("Esc", "Cancel"),
+      help_item!("Enter", "Export"),
+      help_item!("Tab", "Switch\u{00a0}Events"),
+      help_item!("Shift+Tab", "Switch\u{00a0}Format"),
+      help_item!("Ctrl+U", "Clear"),
+    ]
+    .into_iter()
+    .flatten()
+  }
+}
+
+impl Widget for &mut ExportPrompt {
+  fn render(self, area: Rect, buf: &mut Buffer)
+  where
+    Self: Sized,
+  {
+    let title = format!("Export {} as {} to", self.scope, self.format);
+    TextPrompt::new(title.into()).render(area, buf, &mut self.state);
+  }
+}
@@ -91,21 +91,61 @@ pub fn help<'a>(area: Rect) -> SizedParagraph<'a> {
     " to toggle follow mode, which will keep the list scrolled to bottom. ".into(),
     "To change pane size, press ".into(),
     help_key("G/S"),
-    " when the active pane is event list. ".into(),
+    " when the active pane is event list. To show only the active pane, press ".into(),
+    help_key("Alt+Z"),
+    " and press it again to restore. ".into(),
     "To switch between horizontal and vertical layout, press ".into(),
     help_key("Alt+L"),
+    ". To wrap long lines instead of scrolling horizontally, press ".into(),
+    help_key("Alt+W"),
     ". To view the details of the selected event, press ".into(),
     help_key("V"),
+    ". To show the statistics of the events, press ".into(),
+    help_key("T"),
+    ". To jump to the exec event of the parent process, press ".into(),
+    help_key("P"),
+    " and press ".into(),
+    help_key("Backspace"),
+    " to jump back. Press ".into(),
+    help_key("O"),
+    " to group consecutive events by process and ".into(),
+    help_key("Space"),
+    " to fold the group of the selected event".into(),
+    ". To show only successful or failed execs, press ".into(),
+    help_key("X"),
+    " repeatedly".into(),
     ". To copy the selected event to the clipboard, press ".into(),
     help_key("C"),
-    " then select what to copy. To quit, press ".into(),
+    " then select what to copy. To launch another command in this session, press ".into(),
+    help_key("R"),
+    ". To terminate or kill the root process, press ".into(),
+    help_key("Shift+T"),
+    " or ".into(),
+    help_key("Shift+K"),
+    " and confirm. To pause the event list while the tracee keeps running, press ".into(),
+    help_key("Shift+P"),
+    ". Press it again to resume. To send a signal to the process of the selected event, press "
+      .into(),
+    help_key("Shift+S"),
+    ". To quit, press ".into(),
     help_key("Q"),
     " while the event list is active.".into(),
   ]);
   let line3 = Line::default().spans(vec![
     "W".bold(),
-    "hen the pseudo terminal is active, you can interact with the terminal using the keyboard."
+    "hen the pseudo terminal is active, you can interact with the terminal using the keyboard. \
+    To review the output that scrolled past, press "
       .into(),
+    help_key("Shift+PageUp"),
+    " and ".into(),
+    help_key("Shift+PageDown"),
+    ". Typing into the terminal scrolls it back to the bottom. To copy text from the terminal, press ".into(),
+    help_key("Alt+V"),
+    " to start selecting, move with arrow keys, press ".into(),
+    help_key("V"),
+    " to mark the start of the selection and ".into(),
+    help_key("Y"),
+    " to copy it.".into(),
   ]);
   let line4 =
     Line::default().spans(vec![
@@ -163,7 +203,9 @@ pub fn help<'a>(area: Rect) -> SizedParagraph<'a> {
       "P".bold().black(),
       "ress ".into(),
       help_key("Any Key"),
-      " to close this help popup.".into(),
+      " to close this help popup. To list all key bindings, press ".into(),
+      help_key("?"),
+      " in the event list.".into(),
     ])
     .centered();
   let paragraph =
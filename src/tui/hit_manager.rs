@@ -4,7 +4,10 @@ use std::{
   sync::Arc,
 };
 
-use color_eyre::{eyre::eyre, Section};
+use color_eyre::{
+  eyre::{bail, eyre},
+  Section,
+};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use either::Either;
 use itertools::{chain, Itertools};
@@ -140,6 +143,7 @@ impl HitManagerState {
           help_item!("Q", "Back"),
           help_item!("R", "Resume\u{00a0}Process"),
           help_item!("D", "Detach\u{00a0}Process"),
+          help_item!("X", "Deny\u{00a0}Exec"),
           help_item!("E", "Edit\u{00a0}Default\u{00a0}Command")
         ],
         if self.default_external_command.is_some() {
@@ -268,6 +272,16 @@ impl HitManagerState {
             return self.close_when_empty();
           }
         }
+        KeyCode::Char('x') => {
+          if let Some(selected) = self.list_state.selected {
+            let hid = *self.hits.keys().nth(selected).unwrap();
+            if let Err(e) = self.deny(hid) {
+              return Some(Action::show_error_popup("Deny failed".to_string(), e));
+            }
+            self.select_near_by(selected);
+            return self.close_when_empty();
+          }
+        }
         KeyCode::Char('r') => {
           if let Some(selected) = self.list_state.selected {
             debug!("selected: {}", selected);
@@ -331,6 +345,18 @@ impl HitManagerState {
     Ok(())
   }
 
+  /// Make the exec of a process stopped at syscall-enter fail with EPERM
+  pub fn deny(&mut self, hid: u64) -> color_eyre::Result<()> {
+    if let Some(hit) = self.hits.get(&hid) {
+      if hit.stop != BreakPointStop::SyscallEnter {
+        bail!("The exec has already been done at syscall-exit");
+      }
+      self.tracer.request_exec_deny(hit.hit())?;
+      self.hits.remove(&hid);
+    }
+    Ok(())
+  }
+
   pub fn detach_pause_and_launch_external(
     &mut self,
     hid: u64,
@@ -514,7 +540,8 @@ impl HitManager {
           " flag. ".into(),
         ]),
         Line::default().spans(vec![
-          "You can detach, resume or detach and launch external commands for the stopped processes. The ".into(),
+          "You can detach, resume or detach and launch external commands for the stopped processes. \
+          The exec of a process stopped at syscall-enter could also be denied, which makes it fail with EPERM. The ".into(),
           "{{PID}}".cyan().bold(),
           " parameter in the external command will be replaced with the PID of the detached and stopped process. ".into(),
           "For example, you can detach a process and launch a debugger to attach to it. \
@@ -0,0 +1,94 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  style::{Color, Style, Stylize},
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidgetRef, Widget},
+};
+
+use crate::action::Action;
+
+use super::{copy_popup::centered_popup_rect, keymap::KEYMAP, theme::THEME};
+
+/// Lists all the key bindings in the keymap, grouped by pane or mode
+#[derive(Debug, Clone)]
+pub struct KeyBindingsPopup;
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyBindingsPopupState {
+  scroll: u16,
+  /// The maximum scroll offset, updated when rendering
+  max_scroll: u16,
+}
+
+impl KeyBindingsPopupState {
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if ke.modifiers != KeyModifiers::NONE && ke.code != KeyCode::Char('?') {
+      return None;
+    }
+    match ke.code {
+      KeyCode::Char('q') | KeyCode::Char('?') | KeyCode::Esc => {
+        return Some(Action::CancelCurrentPopup);
+      }
+      KeyCode::Down | KeyCode::Char('j') => {
+        self.scroll = (self.scroll + 1).min(self.max_scroll);
+      }
+      KeyCode::Up | KeyCode::Char('k') => {
+        self.scroll = self.scroll.saturating_sub(1);
+      }
+      KeyCode::PageDown => {
+        self.scroll = (self.scroll + 10).min(self.max_scroll);
+      }
+      KeyCode::PageUp => {
+        self.scroll = self.scroll.saturating_sub(10);
+      }
+      _ => {}
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for KeyBindingsPopup {
+  type State = KeyBindingsPopupState;
+
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+    let key_width = KEYMAP
+      .iter()
+      .flat_map(|group| group.bindings.iter())
+      .map(|binding| binding.key_names().chars().count())
+      .max()
+      .unwrap_or(0);
+    let mut lines = Vec::new();
+    for group in KEYMAP {
+      if !lines.is_empty() {
+        lines.push(Line::default());
+      }
+      lines.push(Line::from(group.name).bold().centered());
+      for binding in group.bindings {
+        lines.push(Line::from(vec![
+          Span::styled(
+            format!(" {:<key_width$} ", binding.key_names()),
+            THEME.help_key,
+          ),
+          Span::raw(" "),
+          Span::raw(binding.description),
+        ]));
+      }
+    }
+    let width = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+    let popup_area = centered_popup_rect(width, lines.len() as u16, area);
+    state.max_scroll = (lines.len() as u16).saturating_sub(popup_area.height.saturating_sub(2));
+    state.scroll = state.scroll.min(state.max_scroll);
+    let paragraph = Paragraph::new(lines).scroll((state.scroll, 0)).block(
+      Block::default()
+        .title("Key Bindings")
+        .title_alignment(Center)
+        .title_bottom(Line::from("j/k to scroll, q to close").centered())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan)),
+    );
+    Clear.render(popup_area, buf);
+    paragraph.render(popup_area, buf);
+  }
+}
@@ -0,0 +1,440 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key binding does. The meaning depends on the pane or mode of the binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCommand {
+  // Global
+  SwitchPane,
+  ToggleZoom,
+  // Event list
+  NextItem,
+  PrevItem,
+  PageDown,
+  PageUp,
+  ScrollLeft,
+  ScrollRight,
+  PageLeft,
+  PageRight,
+  ScrollToTop,
+  ScrollToBottom,
+  ScrollToStart,
+  ScrollToEnd,
+  JumpToParent,
+  JumpBack,
+  Search,
+  NextMatch,
+  PrevMatch,
+  ClearSearch,
+  ToggleFollow,
+  ToggleEnvDisplay,
+  ToggleCwdDisplay,
+  ToggleArgvDisplay,
+  ToggleFilenameDisplay,
+  ToggleCommDisplay,
+  ToggleWrap,
+  ToggleGrouping,
+  ToggleGroupCollapse,
+  CycleResultFilter,
+  ToggleDedup,
+  ToggleTable,
+  CycleColorBy,
+  ToggleScope,
+  CycleSortColumn,
+  ReverseSortOrder,
+  ToggleBookmark,
+  NextBookmark,
+  PrevBookmark,
+  TogglePause,
+  GrowPane,
+  ShrinkPane,
+  SwitchLayout,
+  ViewDetails,
+  Copy,
+  CopyAgain,
+  Export,
+  Statistics,
+  Help,
+  KeyBindings,
+  Breakpoints,
+  Hits,
+  RunCommand,
+  TerminateRoot,
+  KillRoot,
+  SendSignal,
+  SendCtrlS,
+  Quit,
+  // Terminal
+  ScrollTerminalUp,
+  ScrollTerminalDown,
+  StartSelection,
+  // Terminal selection
+  SelectionLeft,
+  SelectionRight,
+  SelectionUp,
+  SelectionDown,
+  SelectionLineStart,
+  SelectionLineEnd,
+  SelectionMark,
+  SelectionCopy,
+  SelectionCancel,
+}
+
+#[derive(Debug)]
+pub struct KeyBinding {
+  /// The key combinations that trigger the command
+  pub keys: &'static [(KeyCode, KeyModifiers)],
+  pub description: &'static str,
+  pub command: KeyCommand,
+}
+
+/// The key bindings of a pane or a mode
+#[derive(Debug)]
+pub struct KeyBindingGroup {
+  pub name: &'static str,
+  pub bindings: &'static [KeyBinding],
+}
+
+macro_rules! bind {
+  ([$(($code: expr, $mods: ident)),+ $(,)?], $desc: expr, $command: ident) => {
+    KeyBinding {
+      keys: &[$(($code, KeyModifiers::$mods)),+],
+      description: $desc,
+      command: KeyCommand::$command,
+    }
+  };
+}
+
+use KeyCode::*;
+
+pub const GLOBAL: KeyBindingGroup = KeyBindingGroup {
+  name: "Global",
+  bindings: &[
+    bind!([(Char('s'), CONTROL)], "Switch active pane", SwitchPane),
+    bind!(
+      [(Char('z'), ALT)],
+      "Zoom/unzoom the active pane",
+      ToggleZoom
+    ),
+  ],
+};
+
+pub const EVENT_LIST: KeyBindingGroup = KeyBindingGroup {
+  name: "Event List",
+  bindings: &[
+    bind!([(Down, NONE), (Char('j'), NONE)], "Next event", NextItem),
+    bind!([(Up, NONE), (Char('k'), NONE)], "Previous event", PrevItem),
+    bind!(
+      [(PageDown, NONE), (Down, CONTROL), (Char('j'), CONTROL)],
+      "Page down",
+      PageDown
+    ),
+    bind!(
+      [(PageUp, NONE), (Up, CONTROL), (Char('k'), CONTROL)],
+      "Page up",
+      PageUp
+    ),
+    bind!([(Left, NONE), (Char('h'), NONE)], "Scroll left", ScrollLeft),
+    bind!(
+      [(Right, NONE), (Char('l'), NONE)],
+      "Scroll right",
+      ScrollRight
+    ),
+    bind!(
+      [(Left, CONTROL), (Char('h'), CONTROL)],
+      "Page left",
+      PageLeft
+    ),
+    bind!(
+      [(Right, CONTROL), (Char('l'), CONTROL)],
+      "Page right",
+      PageRight
+    ),
+    bind!([(Home, NONE)], "Scroll to top", ScrollToTop),
+    bind!([(End, NONE)], "Scroll to bottom", ScrollToBottom),
+    bind!([(Home, SHIFT)], "Scroll to line start", ScrollToStart),
+    bind!([(End, SHIFT)], "Scroll to line end", ScrollToEnd),
+    bind!([(Char('p'), NONE)], "Jump to parent", JumpToParent),
+    bind!([(Backspace, NONE)], "Jump back", JumpBack),
+    bind!([(Char('f'), NONE)], "Toggle follow mode", ToggleFollow),
+    bind!(
+      [(Char('e'), NONE)],
+      "Toggle environment display",
+      ToggleEnvDisplay
+    ),
+    bind!([(Char('w'), NONE)], "Toggle cwd display", ToggleCwdDisplay),
+    bind!([(Char('a'), ALT)], "Toggle argv display", ToggleArgvDisplay),
+    bind!(
+      [(Char('f'), ALT)],
+      "Toggle filename display",
+      ToggleFilenameDisplay
+    ),
+    bind!([(Char('c'), ALT)], "Toggle comm display", ToggleCommDisplay),
+    bind!([(Char('w'), ALT)], "Toggle line wrapping", ToggleWrap),
+    bind!(
+      [(Char('o'), NONE)],
+      "Toggle grouping by process",
+      ToggleGrouping
+    ),
+    bind!(
+      [(Char(' '), NONE)],
+      "Fold/unfold the group",
+      ToggleGroupCollapse
+    ),
+    bind!(
+      [(Char('x'), NONE)],
+      "Cycle the exec result filter",
+      CycleResultFilter
+    ),
+    bind!(
+      [(Char('u'), NONE)],
+      "Show identical execs only once",
+      ToggleDedup
+    ),
+    bind!([(Char('t'), ALT)], "Toggle table layout", ToggleTable),
+    bind!(
+      [(Char('>'), NONE)],
+      "Sort the table by the next column",
+      CycleSortColumn
+    ),
+    bind!(
+      [(Char('<'), NONE)],
+      "Reverse the sort order",
+      ReverseSortOrder
+    ),
+    bind!(
+      [(Char('p'), ALT)],
+      "Color the events by theme, status or process",
+      CycleColorBy
+    ),
+    bind!(
+      [(Char('d'), NONE)],
+      "Only show the process of the event and its descendants, or show all processes",
+      ToggleScope
+    ),
+    bind!([(Char('m'), NONE)], "Toggle bookmark", ToggleBookmark),
+    bind!([(Char(']'), NONE)], "Next bookmark", NextBookmark),
+    bind!([(Char('['), NONE)], "Previous bookmark", PrevBookmark),
+    bind!(
+      [(Char('P'), SHIFT)],
+      "Pause/resume the event list",
+      TogglePause
+    ),
+    bind!([(Char('g'), NONE)], "Grow the event list", GrowPane),
+    bind!([(Char('s'), NONE)], "Shrink the event list", ShrinkPane),
+    bind!([(Char('l'), ALT)], "Switch layout", SwitchLayout),
+    bind!(
+      [(Char('v'), NONE)],
+      "View details of the event",
+      ViewDetails
+    ),
+    bind!([(Char('c'), NONE)], "Copy the event", Copy),
+    bind!(
+      [(Char('C'), SHIFT)],
+      "Copy the event like last time",
+      CopyAgain
+    ),
+    bind!([(Char('E'), SHIFT)], "Export events to a file", Export),
+    bind!([(Char('t'), NONE)], "Show statistics", Statistics),
+    bind!([(Char('b'), NONE)], "Manage breakpoints", Breakpoints),
+    bind!([(Char('z'), NONE)], "Show breakpoint hits", Hits),
+    bind!([(Char('r'), NONE)], "Run a command", RunCommand),
+    bind!(
+      [(Char('T'), SHIFT)],
+      "Terminate the root process",
+      TerminateRoot
+    ),
+    bind!([(Char('K'), SHIFT)], "Kill the root process", KillRoot),
+    bind!(
+      [(Char('S'), SHIFT)],
+      "Send a signal to the process of the event",
+      SendSignal
+    ),
+    bind!([(Char('s'), ALT)], "Send Ctrl+S to the terminal", SendCtrlS),
+    bind!([(F(1), NONE)], "Help", Help),
+    bind!([(Char('?'), NONE)], "List key bindings", KeyBindings),
+    bind!([(Char('q'), NONE)], "Quit", Quit),
+  ],
+};
+
+pub const SEARCH: KeyBindingGroup = KeyBindingGroup {
+  name: "Search",
+  bindings: &[
+    bind!([(Char('f'), CONTROL)], "Search", Search),
+    bind!([(Char('n'), NONE)], "Next match", NextMatch),
+    bind!(
+      [(Char('N'), SHIFT), (Char('p'), NONE)],
+      "Previous match",
+      PrevMatch
+    ),
+    bind!([(Esc, NONE)], "Clear search", ClearSearch),
+  ],
+};
+
+pub const TERMINAL: KeyBindingGroup = KeyBindingGroup {
+  name: "Terminal",
+  bindings: &[
+    bind!([(PageUp, SHIFT)], "Scroll up", ScrollTerminalUp),
+    bind!([(PageDown, SHIFT)], "Scroll down", ScrollTerminalDown),
+    bind!([(Char('v'), ALT)], "Select text to copy", StartSelection),
+  ],
+};
+
+pub const TERMINAL_SELECTION: KeyBindingGroup = KeyBindingGroup {
+  name: "Terminal Selection",
+  bindings: &[
+    bind!(
+      [(Left, NONE), (Char('h'), NONE)],
+      "Move left",
+      SelectionLeft
+    ),
+    bind!(
+      [(Right, NONE), (Char('l'), NONE)],
+      "Move right",
+      SelectionRight
+    ),
+    bind!([(Up, NONE), (Char('k'), NONE)], "Move up", SelectionUp),
+    bind!(
+      [(Down, NONE), (Char('j'), NONE)],
+      "Move down",
+      SelectionDown
+    ),
+    bind!(
+      [(Home, NONE), (Char('0'), NONE)],
+      "Line start",
+      SelectionLineStart
+    ),
+    bind!(
+      [(End, NONE), (Char('$'), NONE)],
+      "Line end",
+      SelectionLineEnd
+    ),
+    bind!(
+      [(Char('v'), NONE), (Char(' '), NONE)],
+      "Mark/unmark the start",
+      SelectionMark
+    ),
+    bind!(
+      [(Char('y'), NONE), (Enter, NONE)],
+      "Copy the selection or the line",
+      SelectionCopy
+    ),
+    bind!([(Esc, NONE), (Char('q'), NONE)], "Cancel", SelectionCancel),
+  ],
+};
+
+/// All the key bindings, in the order they are listed to the user
+pub const KEYMAP: &[&KeyBindingGroup] = &[
+  &GLOBAL,
+  &EVENT_LIST,
+  &SEARCH,
+  &TERMINAL,
+  &TERMINAL_SELECTION,
+];
+
+/// Terminals report shifted symbols with or without the shift modifier
+/// and uppercase letters with it. Normalize so that bindings only need one form.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> KeyModifiers {
+  match code {
+    Char(c) if c.is_ascii_uppercase() => modifiers | KeyModifiers::SHIFT,
+    Char(c) if !c.is_alphabetic() => modifiers - KeyModifiers::SHIFT,
+    _ => modifiers,
+  }
+}
+
+impl KeyBindingGroup {
+  /// Find the command bound to the key event in this group
+  pub fn lookup(&self, ke: &KeyEvent) -> Option<KeyCommand> {
+    let modifiers = normalize(ke.code, ke.modifiers);
+    self
+      .bindings
+      .iter()
+      .find(|b| {
+        b.keys
+          .iter()
+          .any(|&(code, mods)| code == ke.code && normalize(code, mods) == modifiers)
+      })
+      .map(|b| b.command)
+  }
+}
+
+fn key_name(code: KeyCode, modifiers: KeyModifiers) -> String {
+  let mut name = String::new();
+  if modifiers.contains(KeyModifiers::CONTROL) {
+    name.push_str("Ctrl+");
+  }
+  if modifiers.contains(KeyModifiers::ALT) {
+    name.push_str("Alt+");
+  }
+  if modifiers.contains(KeyModifiers::SHIFT) {
+    name.push_str("Shift+");
+  }
+  match code {
+    Char(' ') => name.push_str("Space"),
+    Char(c) => name.push(c.to_ascii_uppercase()),
+    Up => name.push('↑'),
+    Down => name.push('↓'),
+    Left => name.push('←'),
+    Right => name.push('→'),
+    PageUp => name.push_str("PgUp"),
+    PageDown => name.push_str("PgDn"),
+    F(n) => name.push_str(&format!("F{n}")),
+    code => name.push_str(&format!("{code:?}")),
+  }
+  name
+}
+
+impl KeyBinding {
+  /// How the keys are shown to the user, e.g. `↓/J`
+  pub fn key_names(&self) -> String {
+    self
+      .keys
+      .iter()
+      .map(|&(code, mods)| key_name(code, mods))
+      .collect::<Vec<_>>()
+      .join("/")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn lookup_normalizes_shift() {
+    let upper_t = KeyEvent::new(Char('T'), KeyModifiers::SHIFT);
+    assert_eq!(EVENT_LIST.lookup(&upper_t), Some(KeyCommand::TerminateRoot));
+    let upper_t = KeyEvent::new(Char('T'), KeyModifiers::NONE);
+    assert_eq!(EVENT_LIST.lookup(&upper_t), Some(KeyCommand::TerminateRoot));
+    let question = KeyEvent::new(Char('?'), KeyModifiers::SHIFT);
+    assert_eq!(EVENT_LIST.lookup(&question), Some(KeyCommand::KeyBindings));
+    let ctrl_t = KeyEvent::new(Char('t'), KeyModifiers::CONTROL);
+    assert_eq!(EVENT_LIST.lookup(&ctrl_t), None);
+    let upper_n = KeyEvent::new(Char('N'), KeyModifiers::SHIFT);
+    assert_eq!(SEARCH.lookup(&upper_n), Some(KeyCommand::PrevMatch));
+  }
+
+  #[test]
+  fn no_conflicting_bindings() {
+    for group in KEYMAP {
+      let mut seen = Vec::new();
+      for binding in group.bindings {
+        for &(code, mods) in binding.keys {
+          let key = (code, normalize(code, mods));
+          assert!(
+            !seen.contains(&key),
+            "{} is bound twice in {}",
+            key_name(code, mods),
+            group.name
+          );
+          seen.push(key);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn key_names() {
+    assert_eq!(EVENT_LIST.bindings[0].key_names(), "↓/J");
+    assert_eq!(GLOBAL.bindings[0].key_names(), "Ctrl+S");
+  }
+}
@@ -0,0 +1,174 @@
+use std::{collections::VecDeque, ops::Range};
+
+use crate::{
+  cli::options::PauseMode,
+  event::{ProcessStateUpdateEvent, TracerEvent},
+};
+
+/// A tracer message held back while the event list is paused
+#[derive(Debug)]
+pub enum HeldMessage {
+  Event(TracerEvent),
+  StateUpdate(ProcessStateUpdateEvent),
+}
+
+/// Pausing of the event list.
+///
+/// The tracer keeps running while paused.
+/// New events are either buffered and shown on resume or discarded, depending on the mode.
+#[derive(Debug, Default)]
+pub struct Pause {
+  mode: PauseMode,
+  paused: bool,
+  held: VecDeque<HeldMessage>,
+  /// Number of events buffered or discarded during the current pause
+  held_events: usize,
+  /// Ids of the discarded events, in ascending order.
+  /// The events after them are shifted down in the event list.
+  discarded: Vec<Range<u64>>,
+}
+
+impl Pause {
+  pub fn new(mode: PauseMode) -> Self {
+    Self {
+      mode,
+      ..Default::default()
+    }
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused
+  }
+
+  pub fn mode(&self) -> PauseMode {
+    self.mode
+  }
+
+  pub fn pause(&mut self) {
+    self.paused = true;
+    self.held_events = 0;
+  }
+
+  /// Resume and return the buffered messages in the order they were received
+  pub fn resume(&mut self) -> VecDeque<HeldMessage> {
+    self.paused = false;
+    std::mem::take(&mut self.held)
+  }
+
+  pub fn held_events(&self) -> usize {
+    self.held_events
+  }
+
+  /// Returns the event if it should be shown now
+  pub fn hold_event(&mut self, event: TracerEvent) -> Option<TracerEvent> {
+    if !self.paused {
+      return Some(event);
+    }
+    self.held_events += 1;
+    match self.mode {
+      PauseMode::Buffer => self.held.push_back(HeldMessage::Event(event)),
+      PauseMode::Discard => match self.discarded.last_mut() {
+        Some(range) if range.end == event.id => range.end += 1,
+        _ => self.discarded.push(event.id..event.id + 1),
+      },
+    }
+    None
+  }
+
+  /// Returns the update if it should be applied now.
+  ///
+  /// In discard mode, updates still apply to the events that are already shown.
+  pub fn hold_update(
+    &mut self,
+    update: ProcessStateUpdateEvent,
+  ) -> Option<ProcessStateUpdateEvent> {
+    if self.paused && self.mode == PauseMode::Buffer {
+      self.held.push_back(HeldMessage::StateUpdate(update));
+      return None;
+    }
+    Some(update)
+  }
+
+  /// The index in the event list of the event with the id, if it is not discarded
+  pub fn index_of(&self, id: u64) -> Option<usize> {
+    let mut shift = 0;
+    for range in self.discarded.iter() {
+      if range.contains(&id) {
+        return None;
+      } else if range.end <= id {
+        shift += range.end - range.start;
+      } else {
+        break;
+      }
+    }
+    Some((id - shift) as usize)
+  }
+
+  /// The id of the event at the index in the event list
+  pub fn id_of(&self, index: usize) -> u64 {
+    let mut id = index as u64;
+    for range in self.discarded.iter() {
+      if range.start <= id {
+        id += range.end - range.start;
+      } else {
+        break;
+      }
+    }
+    id
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use nix::unistd::Pid;
+
+  use crate::event::TracerEventDetails;
+
+  fn event(id: u64) -> TracerEvent {
+    TracerEvent {
+      details: TracerEventDetails::TraceeSpawn {
+        pid: Pid::from_raw(1),
+        process: None,
+      },
+      id,
+    }
+  }
+
+  #[test]
+  fn discarded_events_shift_later_events() {
+    let mut pause = Pause::new(PauseMode::Discard);
+    assert!(pause.hold_event(event(0)).is_some());
+    pause.pause();
+    assert!(pause.hold_event(event(1)).is_none());
+    assert!(pause.hold_event(event(2)).is_none());
+    assert!(pause.resume().is_empty());
+    assert!(pause.hold_event(event(3)).is_some());
+    pause.pause();
+    assert!(pause.hold_event(event(4)).is_none());
+    pause.resume();
+    assert_eq!(pause.index_of(0), Some(0));
+    assert_eq!(pause.index_of(1), None);
+    assert_eq!(pause.index_of(2), None);
+    assert_eq!(pause.index_of(3), Some(1));
+    assert_eq!(pause.index_of(4), None);
+    assert_eq!(pause.index_of(5), Some(2));
+    assert_eq!(pause.id_of(0), 0);
+    assert_eq!(pause.id_of(1), 3);
+    assert_eq!(pause.id_of(2), 5);
+  }
+
+  #[test]
+  fn buffered_events_are_returned_on_resume() {
+    let mut pause = Pause::new(PauseMode::Buffer);
+    pause.pause();
+    assert!(pause.hold_event(event(0)).is_none());
+    assert!(pause.hold_event(event(1)).is_none());
+    assert_eq!(pause.held_events(), 2);
+    let held = pause.resume();
+    assert_eq!(held.len(), 2);
+    assert_eq!(pause.index_of(1), Some(1));
+    assert!(pause.hold_event(event(2)).is_some());
+  }
+}
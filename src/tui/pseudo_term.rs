@@ -23,11 +23,14 @@
 
 use bytes::Bytes;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use nix::sys::termios::LocalFlags;
 use ratatui::prelude::{Buffer, Rect};
+use ratatui::style::{Modifier, Style};
 
 use ratatui::widgets::Widget;
 use std::io::{BufWriter, Write};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::channel;
 use tracing::{trace, warn};
 use tui_term::widget::{Cursor, PseudoTerminal};
@@ -38,10 +41,17 @@ use std::sync::RwLock;
 
 use crate::pty::{MasterPty, PtySize, UnixMasterPty};
 
+use super::{
+  build_progress::{BuildProgress, BuildProgressParser},
+  keymap::{self, KeyCommand},
+};
+
 pub struct PseudoTerminalPane {
   // cannot move out of `parser` because it is borrowed
   // term: PseudoTerminal<'a, Screen>,
   pub parser: Arc<RwLock<vt100::Parser>>,
+  build_progress: Arc<RwLock<Option<BuildProgress>>>,
+  last_output: Arc<RwLock<LastOutput>>,
   pty_master: UnixMasterPty,
   #[allow(unused)]
   reader_task: tokio::task::JoinHandle<color_eyre::Result<()>>,
@@ -51,21 +61,86 @@ pub struct PseudoTerminalPane {
   master_cancellation_token: CancellationToken,
   size: PtySize,
   focus: bool,
+  selection: Option<Selection>,
+}
+
+/// A region of the visible terminal contents being selected with the keyboard.
+///
+/// Positions are (row, col) on the visible screen.
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+  cursor: (u16, u16),
+  /// The other end of the selection, if it has been marked
+  anchor: Option<(u16, u16)>,
+}
+
+impl Selection {
+  /// The start and the end (inclusive) of the selected region
+  fn range(&self) -> ((u16, u16), (u16, u16)) {
+    let anchor = self.anchor.unwrap_or(self.cursor);
+    if anchor <= self.cursor {
+      (anchor, self.cursor)
+    } else {
+      (self.cursor, anchor)
+    }
+  }
+
+  fn contains(&self, pos: (u16, u16)) -> bool {
+    let (start, end) = self.range();
+    self.anchor.is_some() && start <= pos && pos <= end
+  }
 }
 
 const ESCAPE: u8 = 27;
 
+/// The number of scrollback lines kept by default
+pub const DEFAULT_SCROLLBACK: usize = 1000;
+
+/// A prompt that has not been answered for this long is considered to be waiting for input
+const PROMPT_DELAY: Duration = Duration::from_millis(300);
+/// The terminal is considered quiet after producing no output for this long
+const QUIET_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalActivity {
+  /// The program in the terminal seems to be waiting for input
+  WaitingForInput,
+  /// The program in the terminal is producing output
+  Busy,
+  /// No output for a while
+  Quiet,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastOutput {
+  at: Instant,
+  /// The output does not end with a newline, like a prompt
+  unterminated: bool,
+}
+
 impl PseudoTerminalPane {
-  pub fn new(size: PtySize, pty_master: UnixMasterPty) -> color_eyre::Result<Self> {
-    let parser = vt100::Parser::new(size.rows, size.cols, 0);
+  pub fn new(
+    size: PtySize,
+    pty_master: UnixMasterPty,
+    scrollback: usize,
+  ) -> color_eyre::Result<Self> {
+    let parser = vt100::Parser::new(size.rows, size.cols, scrollback);
     // let screen = parser.screen();
     let parser = Arc::new(RwLock::new(parser));
     // let term = PseudoTerminal::new(screen);
+    let build_progress = Arc::new(RwLock::new(None));
+    let last_output = Arc::new(RwLock::new(LastOutput {
+      at: Instant::now(),
+      unterminated: false,
+    }));
 
     let reader_task = {
       let mut reader = pty_master.try_clone_reader()?;
       let parser = parser.clone();
+      let build_progress = build_progress.clone();
+      let last_output = last_output.clone();
       tokio::spawn(async move {
+        let mut progress_parser = BuildProgressParser::default();
         let mut processed_buf = Vec::new();
         let mut buf = [0u8; 8192];
 
@@ -77,6 +152,13 @@ impl PseudoTerminalPane {
           if size > 0 {
             processed_buf.extend_from_slice(&buf[..size]);
             parser.write().unwrap().process(&processed_buf);
+            if progress_parser.feed(&processed_buf) {
+              *build_progress.write().unwrap() = progress_parser.progress();
+            }
+            *last_output.write().unwrap() = LastOutput {
+              at: Instant::now(),
+              unterminated: !processed_buf.ends_with(b"\n"),
+            };
 
             // Clear the processed portion of the buffer
             processed_buf.clear();
@@ -112,16 +194,32 @@ impl PseudoTerminalPane {
       // term,
       size,
       parser,
+      build_progress,
+      last_output,
       pty_master,
       reader_task,
       writer_task,
       master_tx: tx,
       master_cancellation_token,
       focus: false,
+      selection: None,
     })
   }
 
   pub async fn handle_key_event(&self, key: &KeyEvent) -> bool {
+    match keymap::TERMINAL.lookup(key) {
+      Some(KeyCommand::ScrollTerminalUp) => {
+        self.scroll_up(self.size.rows as usize / 2);
+        return true;
+      }
+      Some(KeyCommand::ScrollTerminalDown) => {
+        self.scroll_down(self.size.rows as usize / 2);
+        return true;
+      }
+      _ => {}
+    }
+    // Typing into the terminal brings the view back to the live screen
+    self.scroll_to_bottom();
     let input_bytes = match key.code {
       KeyCode::Char(ch) => {
         let mut send = vec![ch as u8];
@@ -195,11 +293,127 @@ impl PseudoTerminalPane {
       return Ok(());
     }
     self.size = size;
+    // The selected positions are no longer meaningful after resizing
+    self.selection = None;
     self.parser.write().unwrap().set_size(size.rows, size.cols);
     self.pty_master.resize(size)?;
     Ok(())
   }
 
+  /// Scroll the view up into the scrollback buffer by the given number of lines
+  pub fn scroll_up(&self, lines: usize) {
+    let mut parser = self.parser.write().unwrap();
+    let offset = parser.screen().scrollback();
+    // vt100 clamps the offset to the number of lines in the scrollback buffer
+    parser.set_scrollback(offset.saturating_add(lines));
+  }
+
+  /// Scroll the view down towards the live screen by the given number of lines
+  pub fn scroll_down(&self, lines: usize) {
+    let mut parser = self.parser.write().unwrap();
+    let offset = parser.screen().scrollback();
+    parser.set_scrollback(offset.saturating_sub(lines));
+  }
+
+  pub fn scroll_to_bottom(&self) {
+    self.parser.write().unwrap().set_scrollback(0);
+  }
+
+  /// How many lines the view is scrolled up from the live screen
+  pub fn scrollback_offset(&self) -> usize {
+    self.parser.read().unwrap().screen().scrollback()
+  }
+
+  pub fn is_selecting(&self) -> bool {
+    self.selection.is_some()
+  }
+
+  /// Enter selection mode with the cursor placed at the terminal cursor
+  pub fn start_selection(&mut self) {
+    let cursor = {
+      let parser = self.parser.read().unwrap();
+      let screen = parser.screen();
+      if screen.scrollback() > 0 {
+        (0, 0)
+      } else {
+        screen.cursor_position()
+      }
+    };
+    self.selection = Some(Selection {
+      cursor,
+      anchor: None,
+    });
+  }
+
+  /// Handle a key event in selection mode.
+  ///
+  /// Returns the selected text when the selection is copied.
+  /// Without a marked region, the line under the cursor is copied.
+  pub fn handle_selection_key_event(&mut self, key: &KeyEvent) -> Option<String> {
+    let selection = self.selection.as_mut()?;
+    let (max_row, max_col) = (
+      self.size.rows.saturating_sub(1),
+      self.size.cols.saturating_sub(1),
+    );
+    let (row, col) = &mut selection.cursor;
+    match keymap::TERMINAL_SELECTION.lookup(key)? {
+      KeyCommand::SelectionLeft => *col = col.saturating_sub(1),
+      KeyCommand::SelectionRight => *col = (*col + 1).min(max_col),
+      KeyCommand::SelectionUp => *row = row.saturating_sub(1),
+      KeyCommand::SelectionDown => *row = (*row + 1).min(max_row),
+      KeyCommand::SelectionLineStart => *col = 0,
+      KeyCommand::SelectionLineEnd => *col = max_col,
+      KeyCommand::SelectionMark => {
+        selection.anchor = match selection.anchor {
+          Some(_) => None,
+          None => Some(selection.cursor),
+        };
+      }
+      KeyCommand::SelectionCopy => {
+        let ((start_row, start_col), (end_row, end_col)) = match selection.anchor {
+          Some(_) => selection.range(),
+          None => ((*row, 0), (*row, max_col)),
+        };
+        self.selection = None;
+        let parser = self.parser.read().unwrap();
+        // The end column is exclusive
+        return Some(
+          parser
+            .screen()
+            .contents_between(start_row, start_col, end_row, end_col + 1),
+        );
+      }
+      KeyCommand::SelectionCancel => self.selection = None,
+      _ => {}
+    }
+    None
+  }
+
+  /// The latest progress reported by a recognized build tool running in the terminal
+  pub fn build_progress(&self) -> Option<BuildProgress> {
+    *self.build_progress.read().unwrap()
+  }
+
+  /// Guess what the program in the terminal is doing.
+  ///
+  /// Programs that read keys one by one, e.g. shells and editors, turn off canonical mode.
+  /// Programs that read lines usually print a prompt without a trailing newline and then wait.
+  pub fn activity(&self) -> TerminalActivity {
+    let canonical = self
+      .pty_master
+      .get_termios()
+      .is_none_or(|termios| termios.local_flags.contains(LocalFlags::ICANON));
+    let last_output = *self.last_output.read().unwrap();
+    let elapsed = last_output.at.elapsed();
+    if !canonical || (last_output.unterminated && elapsed >= PROMPT_DELAY) {
+      TerminalActivity::WaitingForInput
+    } else if elapsed >= QUIET_PERIOD {
+      TerminalActivity::Quiet
+    } else {
+      TerminalActivity::Busy
+    }
+  }
+
   pub fn focus(&mut self, focus: bool) {
     self.focus = focus;
   }
@@ -217,10 +431,27 @@ impl Widget for &PseudoTerminalPane {
   {
     let parser = self.parser.read().unwrap();
     let mut cursor = Cursor::default();
-    if !self.focus {
+    // The cursor is on the live screen, which is not (fully) shown while scrolled up
+    if !self.focus || parser.screen().scrollback() > 0 || self.selection.is_some() {
       cursor.hide();
     }
     let pseudo_term = PseudoTerminal::new(parser.screen()).cursor(cursor);
     pseudo_term.render(area, buf);
+    if let Some(selection) = self.selection {
+      for row in 0..area.height.min(self.size.rows) {
+        for col in 0..area.width.min(self.size.cols) {
+          let style = if (row, col) == selection.cursor {
+            Style::default().add_modifier(Modifier::REVERSED | Modifier::UNDERLINED)
+          } else if selection.contains((row, col)) {
+            Style::default().add_modifier(Modifier::REVERSED)
+          } else {
+            continue;
+          };
+          if let Some(cell) = buf.cell_mut((area.x + col, area.y + row)) {
+            cell.set_style(style);
+          }
+        }
+      }
+    }
   }
 }
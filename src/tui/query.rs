@@ -128,7 +128,7 @@ impl QueryResult {
         .unwrap_or(0)
         .to_string()
         .set_style(THEME.query_match_current_no);
-      Line::default().spans(vec![selected, "/".into(), total])
+      Line::default().spans(vec!["Match ".into(), selected, "/".into(), total])
     }
   }
 }
@@ -245,7 +245,8 @@ impl QueryBuilder {
     } else {
       [
         help_item!("N", "Next\u{00a0}Match"),
-        help_item!("P", "Previous\u{00a0}Match"),
+        help_item!("Shift+N/P", "Previous\u{00a0}Match"),
+        help_item!("Esc", "Clear\u{00a0}Search"),
       ]
       .into_iter()
       .flatten()
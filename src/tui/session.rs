@@ -0,0 +1,59 @@
+//! Save the session of the TUI in the record format so that it could be loaded again.
+
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+use color_eyre::eyre::Context;
+
+use crate::{
+  event::TracerMessage,
+  export::{JsonMetaData, RecordEntry, TraceNotes},
+  proc::BaselineInfo,
+  serialize_json_to_output,
+};
+
+pub struct SessionWriter {
+  output: BufWriter<File>,
+}
+
+impl SessionWriter {
+  pub fn create(
+    path: &Path,
+    baseline: &BaselineInfo,
+    notes: &TraceNotes,
+  ) -> color_eyre::Result<Self> {
+    let file =
+      File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = Self {
+      output: BufWriter::new(file),
+    };
+    writer.write_line(&JsonMetaData::new(baseline.clone()).with_notes(notes.clone()))?;
+    Ok(writer)
+  }
+
+  /// Record a message received by the TUI
+  pub fn write(&mut self, msg: &TracerMessage) -> color_eyre::Result<()> {
+    match RecordEntry::from_message(msg.clone()) {
+      Some(entry) => self.write_line(&entry),
+      None => Ok(()),
+    }
+  }
+
+  /// Append the bookmarks, which are only known when the TUI exits
+  pub fn finish(mut self, bookmarks: impl IntoIterator<Item = u64>) -> color_eyre::Result<()> {
+    for id in bookmarks {
+      self.write_line(&RecordEntry::Bookmark { id })?;
+    }
+    self.output.flush()?;
+    Ok(())
+  }
+
+  fn write_line(&mut self, value: &impl serde::Serialize) -> color_eyre::Result<()> {
+    serialize_json_to_output(&mut self.output, value, false)?;
+    self.output.write_all(b"\n")?;
+    Ok(())
+  }
+}
@@ -0,0 +1,113 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use nix::{sys::signal::Signal, unistd::Pid};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  style::{Color, Modifier, Style},
+  widgets::{Block, Borders, Clear, HighlightSpacing, List, ListState, StatefulWidgetRef, Widget},
+};
+
+use crate::action::Action;
+
+use super::copy_popup::centered_popup_rect;
+
+/// The signals that could be picked, along with a short description
+const SIGNALS: &[(Signal, &str)] = &[
+  (Signal::SIGTERM, "Terminate"),
+  (Signal::SIGINT, "Interrupt"),
+  (Signal::SIGHUP, "Hang up"),
+  (Signal::SIGQUIT, "Quit and dump core"),
+  (Signal::SIGKILL, "Kill"),
+  (Signal::SIGSTOP, "Stop"),
+  (Signal::SIGCONT, "Continue"),
+  (Signal::SIGUSR1, "User defined signal 1"),
+  (Signal::SIGUSR2, "User defined signal 2"),
+];
+
+#[derive(Debug, Clone)]
+pub struct SignalPopup;
+
+#[derive(Debug, Clone)]
+pub struct SignalPopupState {
+  /// The process to send the signal to
+  pub pid: Pid,
+  pub state: ListState,
+}
+
+impl SignalPopupState {
+  pub fn new(pid: Pid) -> Self {
+    let mut state = ListState::default();
+    state.select(Some(0));
+    Self { pid, state }
+  }
+
+  pub fn next(&mut self) {
+    self.state.select(Some(
+      (self.state.selected().unwrap() + 1).min(SIGNALS.len() - 1),
+    ))
+  }
+
+  pub fn prev(&mut self) {
+    self
+      .state
+      .select(Some(self.state.selected().unwrap().saturating_sub(1)))
+  }
+
+  pub fn selected(&self) -> Signal {
+    SIGNALS[self.state.selected().unwrap_or(0)].0
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if ke.modifiers == KeyModifiers::NONE {
+      match ke.code {
+        KeyCode::Char('q') => {
+          return Some(Action::CancelCurrentPopup);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+          self.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+          self.prev();
+        }
+        KeyCode::Enter => {
+          return Some(Action::SignalProcess {
+            pid: self.pid,
+            signal: self.selected(),
+          });
+        }
+        _ => {}
+      }
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for SignalPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut SignalPopupState) {
+    let list = List::from_iter(
+      SIGNALS
+        .iter()
+        .map(|(signal, description)| format!("{:<8} {description}", signal.as_str())),
+    )
+    .block(
+      Block::default()
+        .title(format!("Send Signal to {}", state.pid))
+        .title_alignment(Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightRed)),
+    )
+    .highlight_style(
+      Style::default()
+        .add_modifier(Modifier::BOLD)
+        .add_modifier(Modifier::REVERSED)
+        .fg(Color::Cyan),
+    )
+    .highlight_symbol(">")
+    .highlight_spacing(HighlightSpacing::Always);
+    let popup_area = centered_popup_rect(34, list.len() as u16, area);
+    Clear.render(popup_area, buf);
+    StatefulWidgetRef::render_ref(&list, popup_area, buf, &mut state.state);
+  }
+
+  type State = SignalPopupState;
+}
@@ -0,0 +1,161 @@
+use std::{
+  collections::{HashMap, HashSet, VecDeque},
+  time::{Duration, Instant},
+};
+
+use arcstr::ArcStr;
+use itertools::Itertools;
+use nix::{errno::Errno, unistd::Pid};
+use ratatui::{
+  layout::Rect,
+  style::Styled,
+  text::{Line, Text},
+  widgets::{Paragraph, Wrap},
+};
+
+use crate::{event::TracerEventDetails, i18n};
+
+use super::{sized_paragraph::SizedParagraph, theme::THEME};
+
+/// How many entries to show in each table
+const TOP_N: usize = 10;
+/// The window for calculating the event rate
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Aggregate statistics of the events, updated as events arrive
+pub struct Statistics {
+  start: Instant,
+  total_events: u64,
+  successful_execs: u64,
+  failed_execs: u64,
+  processes: HashSet<Pid>,
+  /// comm -> number of successful execs
+  execs_per_comm: HashMap<ArcStr, u64>,
+  /// errno -> number of failed execs
+  failures_per_errno: HashMap<i64, u64>,
+  /// Arrival time of the events in the rate window
+  recent_events: VecDeque<Instant>,
+}
+
+impl Default for Statistics {
+  fn default() -> Self {
+    Self {
+      start: Instant::now(),
+      total_events: 0,
+      successful_execs: 0,
+      failed_execs: 0,
+      processes: HashSet::new(),
+      execs_per_comm: HashMap::new(),
+      failures_per_errno: HashMap::new(),
+      recent_events: VecDeque::new(),
+    }
+  }
+}
+
+impl Statistics {
+  pub fn record(&mut self, details: &TracerEventDetails) {
+    let now = Instant::now();
+    self.total_events += 1;
+    while self
+      .recent_events
+      .front()
+      .is_some_and(|&t| now.duration_since(t) > RATE_WINDOW)
+    {
+      self.recent_events.pop_front();
+    }
+    self.recent_events.push_back(now);
+    if let Some(pid) = details.pid() {
+      self.processes.insert(pid);
+    }
+    match details {
+      TracerEventDetails::NewChild { pid, .. } => {
+        self.processes.insert(*pid);
+      }
+      TracerEventDetails::Exec(exec) if exec.result == 0 => {
+        self.successful_execs += 1;
+        if let Some(comm) = details.comm() {
+          *self.execs_per_comm.entry(comm).or_default() += 1;
+        }
+      }
+      TracerEventDetails::Exec(exec) => {
+        self.failed_execs += 1;
+        *self.failures_per_errno.entry(exec.result).or_default() += 1;
+      }
+      _ => (),
+    }
+  }
+
+  /// Events per second in the last second
+  fn event_rate(&self) -> usize {
+    let now = Instant::now();
+    self
+      .recent_events
+      .iter()
+      .rev()
+      .take_while(|&&t| now.duration_since(t) <= RATE_WINDOW)
+      .count()
+  }
+
+  pub fn paragraph<'a>(&self, area: Rect) -> SizedParagraph<'a> {
+    let elapsed = self.start.elapsed().as_secs_f64();
+    let mut lines = vec![
+      entry("Total events", self.total_events.to_string()),
+      entry("Total processes", self.processes.len().to_string()),
+      entry("Successful execs", self.successful_execs.to_string()),
+      entry("Failed execs", self.failed_execs.to_string()),
+      entry("Events/second (now)", self.event_rate().to_string()),
+      entry(
+        "Events/second (average)",
+        format!(
+          "{:.1}",
+          if elapsed > 0. {
+            self.total_events as f64 / elapsed
+          } else {
+            0.
+          }
+        ),
+      ),
+      Line::default(),
+      "Execs per comm".set_style(THEME.sublabel).into(),
+    ];
+    lines.extend(
+      top_n(&self.execs_per_comm).map(|(comm, count)| entry_item(comm.to_string(), *count)),
+    );
+    lines.push(Line::default());
+    lines.push("Failures per errno".set_style(THEME.sublabel).into());
+    lines.extend(top_n(&self.failures_per_errno).map(|(&result, count)| {
+      entry_item(
+        i18n::describe_errno(Errno::from_raw(-result as i32)),
+        *count,
+      )
+    }));
+    let paragraph = Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false });
+    SizedParagraph::new(paragraph, area.width.saturating_sub(6).min(80) as usize)
+  }
+}
+
+/// The entries with the largest counts, in descending order
+fn top_n<K: Ord>(map: &HashMap<K, u64>) -> impl Iterator<Item = (&K, &u64)> {
+  map
+    .iter()
+    .sorted_by(|(k1, c1), (k2, c2)| c2.cmp(c1).then_with(|| k1.cmp(k2)))
+    .take(TOP_N)
+}
+
+fn entry(label: &'static str, value: String) -> Line<'static> {
+  Line::default().spans(vec![
+    label.set_style(THEME.sublabel),
+    ": ".into(),
+    value.into(),
+  ])
+}
+
+fn entry_item(key: String, count: u64) -> Line<'static> {
+  Line::default().spans(vec![
+    "•".set_style(THEME.visual_separator),
+    " ".into(),
+    key.into(),
+    ": ".into(),
+    count.to_string().into(),
+  ])
+}
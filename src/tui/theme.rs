@@ -1,5 +1,5 @@
 use lazy_static::lazy_static;
-use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::style::{Color, Modifier, Style, Stylize};
 
 pub struct Theme {
   // Color for UI Elements
@@ -8,6 +8,8 @@ pub struct Theme {
   #[allow(unused)]
   pub popup_border: Style,
   pub app_title: Style,
+  pub build_progress_filled: Style,
+  pub build_progress_unfilled: Style,
   pub help_popup: Style,
   // Color for help items
   pub cli_flag: Style,
@@ -18,8 +20,23 @@ pub struct Theme {
   pub pid_success: Style,
   pub pid_failure: Style,
   pub pid_enoent: Style,
+  pub signal: Style,
   pub pid_in_msg: Style,
   pub comm: Style,
+  pub container: Style,
+  pub privileged: Style,
+  pub audit_finding: Style,
+  pub group_header: Style,
+  pub bookmark: Style,
+  pub dedup_count: Style,
+  pub table_header: Style,
+  pub table_time: Style,
+  pub failed_exec_line: Style,
+  pub breadcrumb: Style,
+  pub breadcrumb_current: Style,
+  pub breadcrumb_separator: Style,
+  /// The colors of the processes when the events are colored by process
+  pub process_palette: Vec<Color>,
   pub tracer_info: Style,
   pub tracer_warning: Style,
   pub tracer_error: Style,
@@ -49,17 +66,20 @@ pub struct Theme {
   pub fd_closed: Style,
   pub plus_sign: Style,
   pub minus_sign: Style,
+  pub modified_sign: Style,
   pub equal_sign: Style,
   pub added_env_key: Style,
   pub added_env_val: Style,
   pub removed_env_key: Style,
   pub removed_env_val: Style,
+  pub modified_env_key: Style,
   pub unchanged_env_key: Style,
   pub unchanged_env_val: Style,
   pub fd_label: Style,
   pub fd_number_label: Style,
   pub sublabel: Style,
   pub selected_label: Style,
+  pub selected_env_diff: Style,
   pub label: Style,
   pub selection_indicator: Style,
   pub open_flag_cloexec: Style,
@@ -126,6 +146,8 @@ impl Default for Theme {
       active_border: Style::default().cyan(),
       popup_border: Style::default(),
       app_title: Style::default().bold(),
+      build_progress_filled: Style::default().light_green(),
+      build_progress_unfilled: Style::default().dark_gray(),
       help_popup: Style::default().black().on_gray(),
       // -- Help Items --
       cli_flag: Style::default().yellow().on_dark_gray().bold(),
@@ -140,8 +162,35 @@ impl Default for Theme {
       pid_success: Style::default().light_green(),
       pid_failure: Style::default().light_red(),
       pid_enoent: Style::default().light_yellow(),
+      signal: Style::default().light_magenta().bold(),
       pid_in_msg: Style::default().light_magenta(),
       comm: Style::default().cyan(),
+      container: Style::default().light_cyan(),
+      privileged: Style::default().white().on_red().bold(),
+      audit_finding: Style::default().black().on_light_yellow().bold(),
+      group_header: Style::default().dark_gray().italic(),
+      bookmark: Style::default().light_yellow().bold(),
+      dedup_count: Style::default().light_cyan().bold(),
+      table_header: Style::default().bold().underlined(),
+      table_time: Style::default().dark_gray(),
+      failed_exec_line: Style::default().light_red(),
+      breadcrumb: Style::default().light_cyan(),
+      breadcrumb_current: Style::default().light_cyan().bold(),
+      breadcrumb_separator: Style::default().dark_gray(),
+      process_palette: vec![
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+      ],
       tracer_info: Style::default().light_blue().bold(),
       tracer_warning: Style::default().light_yellow().bold(),
       tracer_error: Style::default().light_red().bold(),
@@ -171,11 +220,13 @@ impl Default for Theme {
       value_unknown: Style::default().light_red().italic(),
       plus_sign: Style::default().light_green(),
       minus_sign: Style::default().light_red(),
+      modified_sign: Style::default().light_yellow(),
       equal_sign: Style::default().yellow().bold(),
       added_env_key: Style::default().light_green().bold(),
       added_env_val: Style::default().light_green(),
       removed_env_key: Style::default().light_red().bold(),
       removed_env_val: Style::default().light_red(),
+      modified_env_key: Style::default().light_yellow().bold(),
       unchanged_env_key: Style::default().white().bold(),
       unchanged_env_val: Style::default().white(),
       fd_label: Style::default().black().on_light_green().bold(),
@@ -183,6 +234,7 @@ impl Default for Theme {
       sublabel: Style::default().white().bold(),
       label: Style::default().black().on_light_green().bold(),
       selected_label: Style::default().white().on_light_magenta().bold(),
+      selected_env_diff: Style::default().on_dark_gray(),
       selection_indicator: Style::default().light_green().bold(),
       open_flag_cloexec: Style::default().light_green().bold(),
       open_flag_access_mode: Style::default().light_blue().bold(),